@@ -1,8 +1,9 @@
-use crate::metric_collector::{MetricDataPoint, MetricCollector};
+use crate::metric_collector::{MetricDataPoint, MetricCollector, MetricType};
 use std::collections::HashMap;
 use log::{debug, info};
 
 // Aggregation result
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct AggregatedMetric {
     pub metric_name: String,
@@ -19,14 +20,66 @@ pub struct AggregatedMetric {
     pub labels: HashMap<String, String>,
 }
 
+// How percentiles are derived from a sorted sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PercentileMethod {
+    // Smallest value with at least p% of the sample at or below it
+    #[default]
+    NearestRank,
+    // Linear interpolation between the two closest ranks
+    Interpolated,
+}
+
+impl std::str::FromStr for PercentileMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest-rank" => Ok(Self::NearestRank),
+            "interpolated" => Ok(Self::Interpolated),
+            other => Err(format!(
+                "unknown percentile method {:?} (expected nearest-rank or interpolated)",
+                other
+            )),
+        }
+    }
+}
+
 // Aggregator for computing statistics
 pub struct MetricAggregator {
     collector: std::sync::Arc<MetricCollector>,
+    percentile_method: PercentileMethod,
+    // Worker threads used by aggregate_batch; 1 aggregates on the caller
+    parallelism: usize,
 }
 
 impl MetricAggregator {
     pub fn new(collector: std::sync::Arc<MetricCollector>) -> Self {
-        Self { collector }
+        let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            collector,
+            percentile_method: PercentileMethod::default(),
+            parallelism,
+        }
+    }
+
+    pub fn with_percentile_method(mut self, method: PercentileMethod) -> Self {
+        self.percentile_method = method;
+        self
+    }
+
+    pub fn percentile_method(&self) -> PercentileMethod {
+        self.percentile_method
+    }
+
+    // Cap the threads aggregate_batch spreads metrics over (0 is taken as 1)
+    pub fn with_parallelism(mut self, threads: usize) -> Self {
+        self.parallelism = threads.max(1);
+        self
+    }
+
+    pub fn parallelism(&self) -> usize {
+        self.parallelism
     }
 
     // Aggregate a metric over a time range
@@ -70,9 +123,9 @@ impl MetricAggregator {
         let max = *values.last().unwrap();
 
         // Calculate percentiles
-        let p50 = Self::percentile(&values, 50.0);
-        let p95 = Self::percentile(&values, 95.0);
-        let p99 = Self::percentile(&values, 99.0);
+        let p50 = Self::percentile_with(&values, 50.0, self.percentile_method);
+        let p95 = Self::percentile_with(&values, 95.0, self.percentile_method);
+        let p99 = Self::percentile_with(&values, 99.0, self.percentile_method);
 
         // Use labels from first matching point
         let labels = filtered_points[0].labels.clone();
@@ -99,16 +152,63 @@ impl MetricAggregator {
     }
 
     // Aggregate multiple metrics at once
+    //
+    // Metrics are independent, so they are split into contiguous chunks and
+    // aggregated on up to `parallelism` scoped threads. Results keep the order
+    // of `metric_names`, matching a sequential run.
     pub fn aggregate_batch(
         &self,
         metric_names: &[&str],
         start_time: u64,
         end_time: u64,
     ) -> Vec<AggregatedMetric> {
-        metric_names
-            .iter()
-            .filter_map(|name| self.aggregate(name, start_time, end_time, None))
-            .collect()
+        let aggregate_chunk = |names: &[&str]| -> Vec<AggregatedMetric> {
+            names
+                .iter()
+                .filter_map(|name| {
+                    if self.collector.get_histogram(name).is_some() {
+                        self.aggregate_histogram(name, start_time, end_time)
+                    } else {
+                        self.aggregate(name, start_time, end_time, None)
+                    }
+                })
+                .collect()
+        };
+
+        let threads = self.parallelism.min(metric_names.len());
+        if threads <= 1 {
+            return aggregate_chunk(metric_names);
+        }
+
+        let chunk_size = metric_names.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = metric_names
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || aggregate_chunk(chunk)))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("aggregation worker panicked"))
+                .collect()
+        })
+    }
+
+    // aggregate_batch for async callers
+    //
+    // aggregate_batch joins its worker threads before returning, so it runs on
+    // the blocking pool here instead of holding a runtime worker meanwhile.
+    pub async fn aggregate_batch_async(
+        self: std::sync::Arc<Self>,
+        metric_names: Vec<String>,
+        start_time: u64,
+        end_time: u64,
+    ) -> Vec<AggregatedMetric> {
+        tokio::task::spawn_blocking(move || {
+            let names: Vec<&str> = metric_names.iter().map(String::as_str).collect();
+            self.aggregate_batch(&names, start_time, end_time)
+        })
+        .await
+        .expect("aggregation task panicked")
     }
 
     // Aggregate by label (e.g., per node_id or deployment_id)
@@ -127,7 +227,7 @@ impl MetricAggregator {
             if let Some(label_value) = point.labels.get(label_key) {
                 grouped
                     .entry(label_value.clone())
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(point);
             }
         }
@@ -135,7 +235,7 @@ impl MetricAggregator {
         // Aggregate each group
         let mut results = HashMap::new();
         for (label_value, points) in grouped {
-            if let Some(aggregated) = Self::aggregate_points(&points, metric_name, start_time, end_time) {
+            if let Some(aggregated) = self.aggregate_points(&points, metric_name, start_time, end_time) {
                 results.insert(label_value, aggregated);
             }
         }
@@ -143,7 +243,55 @@ impl MetricAggregator {
         results
     }
 
+    // Aggregate a Histogram metric over a time range from its bucketed histogram
+    //
+    // When the range holds every recorded observation the incremental
+    // histogram answers directly; otherwise the range's points are binned into
+    // the same bucket layout. Either way percentiles walk buckets, never sort.
+    pub fn aggregate_histogram(
+        &self,
+        metric_name: &str,
+        start_time: u64,
+        end_time: u64,
+    ) -> Option<AggregatedMetric> {
+        let cumulative = self.collector.get_histogram(metric_name)?;
+        let points = self.collector.get_time_series(metric_name, start_time, end_time);
+        let histogram = if points.len() as u64 == cumulative.count() {
+            cumulative
+        } else {
+            let mut windowed = cumulative.cleared();
+            for point in &points {
+                windowed.observe(point.value);
+            }
+            windowed
+        };
+        if histogram.count() == 0 {
+            debug!("No observations recorded for histogram {} in time range", metric_name);
+            return None;
+        }
+
+        let count = histogram.count() as usize;
+        let sum = histogram.sum();
+        let labels = points.first().map(|p| p.labels.clone()).unwrap_or_default();
+
+        Some(AggregatedMetric {
+            metric_name: metric_name.to_string(),
+            start_time,
+            end_time,
+            count,
+            avg: sum / count as f64,
+            min: histogram.min()?,
+            max: histogram.max()?,
+            sum,
+            p50: histogram.percentile(50.0)?,
+            p95: histogram.percentile(95.0)?,
+            p99: histogram.percentile(99.0)?,
+            labels,
+        })
+    }
+
     // Rolling window aggregation (e.g., last 5 minutes, last hour, last day)
+    #[allow(dead_code)]
     pub fn rolling_window(
         &self,
         metric_name: &str,
@@ -170,7 +318,21 @@ impl MetricAggregator {
             .as_secs();
 
         let start_time = now.saturating_sub(window_seconds);
-        let data_points = self.collector.get_time_series(metric_name, start_time, now);
+        self.calculate_rate_between(metric_name, start_time, now)
+    }
+
+    // Calculate rate of change over a time range
+    //
+    // For Counter metrics a drop in value is taken as a reset (e.g. a node
+    // restart): the drop is skipped and a new segment starts at the post-reset
+    // value, so the rate is the sum of positive deltas over the elapsed time.
+    pub fn calculate_rate_between(
+        &self,
+        metric_name: &str,
+        start_time: u64,
+        end_time: u64,
+    ) -> Option<f64> {
+        let data_points = self.collector.get_time_series(metric_name, start_time, end_time);
 
         if data_points.len() < 2 {
             return None;
@@ -179,7 +341,17 @@ impl MetricAggregator {
         let first = data_points.first().unwrap();
         let last = data_points.last().unwrap();
 
-        let value_delta = last.value - first.value;
+        let is_counter = self
+            .collector
+            .get_metadata(metric_name)
+            .map(|metadata| metadata.metric_type == MetricType::Counter)
+            .unwrap_or(false);
+
+        let value_delta = if is_counter {
+            Self::counter_increase(&data_points)
+        } else {
+            last.value - first.value
+        };
         let time_delta = (last.timestamp - first.timestamp) as f64;
 
         if time_delta > 0.0 {
@@ -192,46 +364,77 @@ impl MetricAggregator {
     // Export aggregated metrics for monitoring systems
     pub fn export_prometheus_format(&self, aggregated: &AggregatedMetric) -> String {
         let mut output = String::new();
-        let labels_str = Self::format_labels(&aggregated.labels);
 
-        // Export multiple statistics as separate metrics
-        output.push_str(&format!(
-            "{}{}{{stat=\"avg\"}} {}\n",
-            aggregated.metric_name, labels_str, aggregated.avg
-        ));
-        output.push_str(&format!(
-            "{}{}{{stat=\"min\"}} {}\n",
-            aggregated.metric_name, labels_str, aggregated.min
-        ));
-        output.push_str(&format!(
-            "{}{}{{stat=\"max\"}} {}\n",
-            aggregated.metric_name, labels_str, aggregated.max
-        ));
-        output.push_str(&format!(
-            "{}{}{{stat=\"p50\"}} {}\n",
-            aggregated.metric_name, labels_str, aggregated.p50
-        ));
-        output.push_str(&format!(
-            "{}{}{{stat=\"p95\"}} {}\n",
-            aggregated.metric_name, labels_str, aggregated.p95
-        ));
-        output.push_str(&format!(
-            "{}{}{{stat=\"p99\"}} {}\n",
-            aggregated.metric_name, labels_str, aggregated.p99
-        ));
+        // Export multiple statistics as separate metrics, the stat joining
+        // the series' own labels in a single label set
+        let stats = [
+            ("avg", aggregated.avg),
+            ("min", aggregated.min),
+            ("max", aggregated.max),
+            ("p50", aggregated.p50),
+            ("p95", aggregated.p95),
+            ("p99", aggregated.p99),
+        ];
+        for (stat, value) in stats {
+            let mut labels = aggregated.labels.clone();
+            labels.insert("stat".to_string(), stat.to_string());
+            output.push_str(&format!(
+                "{}{} {}\n",
+                aggregated.metric_name,
+                Self::format_labels(&labels),
+                value
+            ));
+        }
 
         output
     }
 
-    // Helper: Calculate percentile
+    // Helper: Calculate percentile (nearest-rank)
+    #[cfg(test)]
     fn percentile(sorted_values: &[f64], p: f64) -> f64 {
+        Self::percentile_with(sorted_values, p, PercentileMethod::NearestRank)
+    }
+
+    // Helper: Calculate percentile with the given method
+    fn percentile_with(sorted_values: &[f64], p: f64, method: PercentileMethod) -> f64 {
         if sorted_values.is_empty() {
             return 0.0;
         }
 
         let n = sorted_values.len();
-        let index = (p / 100.0 * (n - 1) as f64).ceil() as usize;
-        sorted_values[index.min(n - 1)]
+        let p = p.clamp(0.0, 100.0) / 100.0;
+        match method {
+            PercentileMethod::NearestRank => {
+                let rank = (p * n as f64).ceil() as usize;
+                sorted_values[rank.clamp(1, n) - 1]
+            }
+            PercentileMethod::Interpolated => {
+                let rank = p * (n - 1) as f64;
+                let lower = rank.floor() as usize;
+                let upper = rank.ceil() as usize;
+                let fraction = rank - lower as f64;
+                sorted_values[lower] + (sorted_values[upper] - sorted_values[lower]) * fraction
+            }
+        }
+    }
+
+    // Helper: Total counter increase, skipping drops caused by resets
+    fn counter_increase(points: &[MetricDataPoint]) -> f64 {
+        let mut resets = 0;
+        let increase = points
+            .windows(2)
+            .map(|pair| pair[1].value - pair[0].value)
+            .filter(|delta| {
+                if *delta < 0.0 {
+                    resets += 1;
+                }
+                *delta > 0.0
+            })
+            .sum();
+        if resets > 0 {
+            debug!("Skipped {} counter reset(s) in rate calculation", resets);
+        }
+        increase
     }
 
     // Helper: Check if labels match filter
@@ -241,6 +444,7 @@ impl MetricAggregator {
 
     // Helper: Aggregate a set of data points
     fn aggregate_points(
+        &self,
         points: &[MetricDataPoint],
         metric_name: &str,
         start_time: u64,
@@ -259,9 +463,9 @@ impl MetricAggregator {
         let min = *values.first().unwrap();
         let max = *values.last().unwrap();
 
-        let p50 = Self::percentile(&values, 50.0);
-        let p95 = Self::percentile(&values, 95.0);
-        let p99 = Self::percentile(&values, 99.0);
+        let p50 = Self::percentile_with(&values, 50.0, self.percentile_method);
+        let p95 = Self::percentile_with(&values, 95.0, self.percentile_method);
+        let p99 = Self::percentile_with(&values, 99.0, self.percentile_method);
 
         Some(AggregatedMetric {
             metric_name: metric_name.to_string(),
@@ -285,18 +489,29 @@ impl MetricAggregator {
             return String::new();
         }
 
-        let label_pairs: Vec<String> = labels
+        // Sorted so a series always renders the same way
+        let mut label_pairs: Vec<String> = labels
             .iter()
-            .map(|(k, v)| format!("{}=\"{}\"", k, v))
+            .map(|(k, v)| format!("{}=\"{}\"", k, Self::escape_label_value(v)))
             .collect();
+        label_pairs.sort();
 
         format!("{{{}}}", label_pairs.join(","))
     }
+
+    // Escape a label value per the Prometheus text exposition format
+    fn escape_label_value(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    }
 }
 
 // Pre-defined aggregation windows
 pub struct AggregationWindows;
 
+#[allow(dead_code)]
 impl AggregationWindows {
     pub const LAST_5_MINUTES: u64 = 300;
     pub const LAST_15_MINUTES: u64 = 900;
@@ -308,7 +523,7 @@ impl AggregationWindows {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::metric_collector::{MetricCollector, MetricType};
+    use crate::metric_collector::MetricCollector;
 
     #[test]
     fn test_percentile_calculation() {
@@ -319,6 +534,28 @@ mod tests {
         assert_eq!(MetricAggregator::percentile(&values, 99.0), 10.0);
     }
 
+    #[test]
+    fn test_percentile_methods() {
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+        let nearest = |p| MetricAggregator::percentile_with(&values, p, PercentileMethod::NearestRank);
+        let interpolated =
+            |p| MetricAggregator::percentile_with(&values, p, PercentileMethod::Interpolated);
+
+        assert_eq!(nearest(25.0), 3.0);
+        assert_eq!(nearest(99.0), 10.0);
+
+        for (p, expected) in [(0.0, 1.0), (25.0, 3.25), (50.0, 5.5), (95.0, 9.55), (99.0, 9.91), (100.0, 10.0)] {
+            let actual = interpolated(p);
+            assert!((actual - expected).abs() < 1e-9, "p{}: {} != {}", p, actual, expected);
+        }
+
+        // Small samples no longer snap p99 to the max
+        let tiny = vec![10.0, 20.0, 30.0];
+        assert_eq!(MetricAggregator::percentile_with(&tiny, 99.0, PercentileMethod::NearestRank), 30.0);
+        let p99 = MetricAggregator::percentile_with(&tiny, 99.0, PercentileMethod::Interpolated);
+        assert!((p99 - 29.8).abs() < 1e-9);
+    }
+
     #[test]
     fn test_aggregation() {
         let collector = std::sync::Arc::new(MetricCollector::new(100, 15));
@@ -349,6 +586,188 @@ mod tests {
         assert_eq!(agg.avg, 45.0);
     }
 
+    #[test]
+    fn test_percentile_method_parsing() {
+        assert_eq!("interpolated".parse(), Ok(PercentileMethod::Interpolated));
+        assert_eq!("nearest-rank".parse(), Ok(PercentileMethod::NearestRank));
+        assert!("median".parse::<PercentileMethod>().is_err());
+    }
+
+    fn populated_collector(metrics: usize, points: usize) -> std::sync::Arc<MetricCollector> {
+        let collector = std::sync::Arc::new(MetricCollector::new(points, 15));
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        for m in 0..metrics {
+            let name = format!("metric_{}", m);
+            collector.register_metric(name.clone(), "Bench".to_string(), MetricType::Gauge, None);
+            for t in 0..points {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let value = (state >> 11) as f64 / (1u64 << 53) as f64 * 100.0;
+                collector.record_metric_at(&name, value, HashMap::new(), t as u64);
+            }
+        }
+        collector
+    }
+
+    #[test]
+    fn test_parallel_batch_matches_sequential() {
+        let collector = populated_collector(37, 200);
+        let mut names: Vec<String> = (0..37).map(|m| format!("metric_{}", m)).collect();
+        names.insert(5, "missing_metric".to_string());
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        let sequential = MetricAggregator::new(collector.clone())
+            .with_parallelism(1)
+            .aggregate_batch(&names, 0, u64::MAX);
+        let parallel = MetricAggregator::new(collector)
+            .with_parallelism(4)
+            .aggregate_batch(&names, 0, u64::MAX);
+
+        assert_eq!(sequential.len(), 37);
+        assert_eq!(parallel.len(), sequential.len());
+        for (p, s) in parallel.iter().zip(&sequential) {
+            assert_eq!(p.metric_name, s.metric_name);
+            assert_eq!(p.count, s.count);
+            assert_eq!((p.min, p.max, p.sum), (s.min, s.max, s.sum));
+            assert_eq!((p.p50, p.p95, p.p99), (s.p50, s.p95, s.p99));
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_async_batch_leaves_runtime_free() {
+        let collector = populated_collector(16, 2_000);
+        let names: Vec<String> = (0..16).map(|m| format!("metric_{}", m)).collect();
+        let aggregator = std::sync::Arc::new(MetricAggregator::new(collector).with_parallelism(4));
+
+        // The only runtime thread keeps ticking while the batch aggregates
+        let ticks = tokio::spawn(async {
+            let mut ticks = 0;
+            loop {
+                tokio::task::yield_now().await;
+                ticks += 1;
+                if ticks == 1_000 {
+                    return ticks;
+                }
+            }
+        });
+        let expected = aggregator.aggregate_batch(
+            &names.iter().map(String::as_str).collect::<Vec<_>>(),
+            0,
+            u64::MAX,
+        );
+        let batch = aggregator.clone().aggregate_batch_async(names, 0, u64::MAX).await;
+        assert_eq!(ticks.await.unwrap(), 1_000);
+
+        assert_eq!(batch.len(), expected.len());
+        for (a, e) in batch.iter().zip(&expected) {
+            assert_eq!(a.metric_name, e.metric_name);
+            assert_eq!((a.p50, a.p99), (e.p50, e.p99));
+        }
+    }
+
+    // cargo test --release bench_parallel_batch -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_parallel_batch() {
+        let collector = populated_collector(64, 20_000);
+        let names: Vec<String> = (0..64).map(|m| format!("metric_{}", m)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        let time = |threads: usize| {
+            let aggregator = MetricAggregator::new(collector.clone()).with_parallelism(threads);
+            let started = std::time::Instant::now();
+            assert_eq!(aggregator.aggregate_batch(&names, 0, u64::MAX).len(), names.len());
+            started.elapsed()
+        };
+        let sequential = time(1);
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let parallel = time(threads);
+        println!(
+            "aggregate_batch over {} metrics: sequential {:?}, {} threads {:?} ({:.1}x)",
+            names.len(),
+            sequential,
+            threads,
+            parallel,
+            sequential.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+
+    #[test]
+    fn test_incremental_histogram_matches_sorted_percentiles() {
+        let collector = std::sync::Arc::new(MetricCollector::new(5000, 15));
+
+        // Deterministic spread of latencies between 1ms and ~500ms
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let unit = (state >> 11) as f64 / (1u64 << 53) as f64;
+            collector.record_metric("betanet_latency", 1.0 + unit * unit * 500.0, HashMap::new());
+        }
+
+        let aggregator = MetricAggregator::new(collector);
+        let exact = aggregator.aggregate("betanet_latency", 0, u64::MAX, None).unwrap();
+        let incremental = aggregator.aggregate_histogram("betanet_latency", 0, u64::MAX).unwrap();
+
+        assert_eq!(incremental.count, exact.count);
+        assert_eq!(incremental.min, exact.min);
+        assert_eq!(incremental.max, exact.max);
+        assert!((incremental.sum - exact.sum).abs() < 1e-6);
+        for (estimate, actual) in [
+            (incremental.p50, exact.p50),
+            (incremental.p95, exact.p95),
+            (incremental.p99, exact.p99),
+        ] {
+            let error = (estimate - actual).abs() / actual;
+            assert!(error < 0.05, "estimate {} vs exact {}", estimate, actual);
+        }
+    }
+
+    #[test]
+    fn test_batch_aggregates_histograms_over_the_window() {
+        let collector = std::sync::Arc::new(MetricCollector::new(100, 15));
+        for t in 0..40 {
+            let latency = if t < 20 { 500.0 } else { 10.0 + t as f64 };
+            collector.record_metric_at("betanet_latency", latency, HashMap::new(), t);
+        }
+
+        let aggregator = MetricAggregator::new(collector);
+        let batch = aggregator.aggregate_batch(&["betanet_latency"], 20, 39);
+        assert_eq!(batch.len(), 1);
+        let windowed = &batch[0];
+        let exact = aggregator.aggregate("betanet_latency", 20, 39, None).unwrap();
+
+        // The earlier 500ms observations fall outside the window
+        assert_eq!(windowed.count, 20);
+        assert_eq!((windowed.min, windowed.max), (30.0, 49.0));
+        assert!((windowed.sum - exact.sum).abs() < 1e-9);
+        assert!((windowed.p99 - exact.p99).abs() / exact.p99 < 0.05);
+
+        // The full range is served by the cumulative histogram as-is
+        let full = aggregator.aggregate_histogram("betanet_latency", 0, u64::MAX).unwrap();
+        assert_eq!(full.count, 40);
+        assert_eq!(full.max, 500.0);
+    }
+
+    #[test]
+    fn test_counter_rate_ignores_reset() {
+        let collector = std::sync::Arc::new(MetricCollector::new(100, 15));
+
+        // Counter climbs 10/s, the node restarts at t=30, then it climbs again
+        for (timestamp, value) in [(0, 100.0), (10, 200.0), (20, 300.0), (30, 5.0), (40, 105.0)] {
+            collector.record_metric_at("betanet_packets_processed", value, HashMap::new(), timestamp);
+            collector.record_metric_at("node_cpu_usage", value, HashMap::new(), timestamp);
+        }
+
+        let aggregator = MetricAggregator::new(collector);
+        let rate = aggregator
+            .calculate_rate_between("betanet_packets_processed", 0, 40)
+            .unwrap();
+        assert!((rate - 7.5).abs() < 1e-9, "rate {}", rate);
+
+        // Gauges keep plain first-to-last semantics
+        let gauge_rate = aggregator.calculate_rate_between("node_cpu_usage", 0, 40).unwrap();
+        assert!((gauge_rate - 0.125).abs() < 1e-9);
+    }
+
     #[test]
     fn test_label_filtering() {
         let mut labels1 = HashMap::new();
@@ -390,4 +809,35 @@ mod tests {
         assert!(output.contains("test_metric{stat=\"avg\"} 50"));
         assert!(output.contains("test_metric{stat=\"p95\"} 85"));
     }
+
+    #[test]
+    fn test_prometheus_export_merges_series_labels() {
+        let mut labels = HashMap::new();
+        labels.insert("relay".to_string(), "10.0.0.2:9000".to_string());
+        labels.insert("note".to_string(), "say \"hi\"".to_string());
+        let agg = AggregatedMetric {
+            metric_name: "relay_reputation".to_string(),
+            start_time: 0,
+            end_time: 100,
+            count: 1,
+            avg: 0.9,
+            min: 0.9,
+            max: 0.9,
+            sum: 0.9,
+            p50: 0.9,
+            p95: 0.9,
+            p99: 0.9,
+            labels,
+        };
+
+        let collector = std::sync::Arc::new(MetricCollector::new(100, 15));
+        let output = MetricAggregator::new(collector).export_prometheus_format(&agg);
+
+        assert_eq!(output.lines().count(), 6);
+        assert!(output.contains(
+            "relay_reputation{note=\"say \\\"hi\\\"\",relay=\"10.0.0.2:9000\",stat=\"avg\"} 0.9\n"
+        ));
+        // One label block per line
+        assert!(output.lines().all(|line| line.matches('{').count() == 1));
+    }
 }
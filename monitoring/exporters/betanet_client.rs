@@ -5,6 +5,8 @@ use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use log::{debug, error, warn, info};
 
+use crate::metric_collector::RelayReputationSample;
+
 // Betanet API response structures
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BetanetMetricsResponse {
@@ -16,6 +18,8 @@ pub struct BetanetMetricsResponse {
     pub packets_dropped: u64,
 }
 
+// Mirrors the API response; not every field is exported yet
+#[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 pub struct BetanetNodeStatus {
     pub active_nodes: u64,
@@ -23,6 +27,7 @@ pub struct BetanetNodeStatus {
     pub total_nodes: u64,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 pub struct BetanetNetworkStats {
     pub bytes_transmitted: u64,
@@ -39,8 +44,8 @@ enum CircuitState {
     HalfOpen, // Testing if service recovered
 }
 
-// Circuit breaker implementation
-struct CircuitBreaker {
+// Circuit breaker implementation (shared with the OTLP exporter)
+pub(crate) struct CircuitBreaker {
     state: CircuitState,
     failure_count: u32,
     last_failure_time: Option<Instant>,
@@ -49,7 +54,7 @@ struct CircuitBreaker {
 }
 
 impl CircuitBreaker {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             state: CircuitState::Closed,
             failure_count: 0,
@@ -59,7 +64,7 @@ impl CircuitBreaker {
         }
     }
 
-    fn record_success(&mut self) {
+    pub(crate) fn record_success(&mut self) {
         if self.state == CircuitState::HalfOpen {
             info!("Circuit breaker: Service recovered, closing circuit");
             self.state = CircuitState::Closed;
@@ -68,19 +73,17 @@ impl CircuitBreaker {
         self.last_failure_time = None;
     }
 
-    fn record_failure(&mut self) {
+    pub(crate) fn record_failure(&mut self) {
         self.failure_count += 1;
         self.last_failure_time = Some(Instant::now());
 
-        if self.failure_count >= self.failure_threshold {
-            if self.state == CircuitState::Closed {
-                warn!("Circuit breaker: Opening circuit after {} failures", self.failure_count);
-                self.state = CircuitState::Open;
-            }
+        if self.failure_count >= self.failure_threshold && self.state == CircuitState::Closed {
+            warn!("Circuit breaker: Opening circuit after {} failures", self.failure_count);
+            self.state = CircuitState::Open;
         }
     }
 
-    fn can_attempt(&mut self) -> bool {
+    pub(crate) fn can_attempt(&mut self) -> bool {
         match self.state {
             CircuitState::Closed => true,
             CircuitState::Open => {
@@ -100,16 +103,21 @@ impl CircuitBreaker {
         }
     }
 
-    fn is_open(&self) -> bool {
+    pub(crate) fn is_open(&self) -> bool {
         self.state == CircuitState::Open
     }
 }
 
+// Default age past which stale data is refused instead of served
+const DEFAULT_MAX_STALE_AGE: Duration = Duration::from_secs(300);
+
 // Metrics cache with TTL
 struct MetricsCache {
     data: Option<BetanetMetricsResponse>,
     cached_at: Option<Instant>,
     ttl: Duration,
+    // Expired data is still served as a fallback until it is this old
+    max_stale_age: Duration,
 }
 
 impl MetricsCache {
@@ -118,6 +126,7 @@ impl MetricsCache {
             data: None,
             cached_at: None,
             ttl: Duration::from_secs(ttl_secs),
+            max_stale_age: DEFAULT_MAX_STALE_AGE,
         }
     }
 
@@ -138,12 +147,28 @@ impl MetricsCache {
         debug!("Cached new metrics");
     }
 
+    // Whether get_stale would serve data right now
     fn has_stale_data(&self) -> bool {
-        self.data.is_some()
+        self.get_stale().is_ok()
     }
 
-    fn get_stale(&self) -> Option<BetanetMetricsResponse> {
-        self.data.clone()
+    // Fallback data, refused once it is older than max_stale_age
+    fn get_stale(&self) -> Result<BetanetMetricsResponse, String> {
+        match (&self.data, self.cached_at) {
+            (Some(data), Some(cached_at)) => {
+                let age = cached_at.elapsed();
+                if age <= self.max_stale_age {
+                    Ok(data.clone())
+                } else {
+                    Err(format!(
+                        "Cached data is {}s old, beyond the {}s stale limit",
+                        age.as_secs(),
+                        self.max_stale_age.as_secs()
+                    ))
+                }
+            }
+            _ => Err("no cached data available".to_string()),
+        }
     }
 }
 
@@ -172,6 +197,12 @@ impl BetanetClient {
         }
     }
 
+    // Refuse stale cached data older than this when Betanet is unreachable
+    pub fn with_max_stale_age(self, max_stale_age: Duration) -> Self {
+        self.cache.lock().unwrap().max_stale_age = max_stale_age;
+        self
+    }
+
     // Fetch aggregated metrics from Betanet
     pub async fn fetch_metrics(&self) -> Result<BetanetMetricsResponse, String> {
         // Check cache first
@@ -183,11 +214,15 @@ impl BetanetClient {
         {
             let mut breaker = self.circuit_breaker.lock().unwrap();
             if !breaker.can_attempt() {
-                warn!("Circuit breaker is open, using stale data if available");
-                if let Some(stale) = self.cache.lock().unwrap().get_stale() {
-                    return Ok(stale);
+                let cache = self.cache.lock().unwrap();
+                if cache.has_stale_data() {
+                    warn!("Circuit breaker is open, using stale data");
+                } else {
+                    warn!("Circuit breaker is open and no usable stale data is cached");
                 }
-                return Err("Circuit breaker open and no cached data available".to_string());
+                return cache
+                    .get_stale()
+                    .map_err(|e| format!("Circuit breaker open and {}", e));
             }
         }
 
@@ -202,12 +237,16 @@ impl BetanetClient {
                 error!("Failed to fetch metrics: {}", e);
                 self.circuit_breaker.lock().unwrap().record_failure();
 
-                // Return stale data if available
-                if let Some(stale) = self.cache.lock().unwrap().get_stale() {
-                    warn!("Using stale cached data due to fetch failure");
-                    Ok(stale)
-                } else {
-                    Err(e)
+                // Return stale data if available and recent enough
+                match self.cache.lock().unwrap().get_stale() {
+                    Ok(stale) => {
+                        warn!("Using stale cached data due to fetch failure");
+                        Ok(stale)
+                    }
+                    Err(stale_err) => {
+                        debug!("Not serving stale data: {}", stale_err);
+                        Err(e)
+                    }
                 }
             }
         }
@@ -215,7 +254,7 @@ impl BetanetClient {
 
     // Fetch with exponential backoff retry
     async fn fetch_with_retry(&self) -> Result<BetanetMetricsResponse, String> {
-        let retry_delays = vec![
+        let retry_delays = [
             Duration::from_secs(1),
             Duration::from_secs(2),
             Duration::from_secs(4),
@@ -325,6 +364,24 @@ impl BetanetClient {
             .map_err(|e| format!("Failed to parse response: {}", e))
     }
 
+    // Per-relay reputation from the mixnode's ReputationManager
+    //
+    // Not cached: the reputation source keeps the last good snapshot itself.
+    pub async fn fetch_relay_reputation(&self) -> Result<Vec<RelayReputationSample>, String> {
+        let url = format!("{}/api/v1/relays/reputation", self.base_url);
+        debug!("Fetching from {}", url);
+
+        self.client
+            .get(&url)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|e| format!("HTTP request failed: {}", e))?
+            .json::<Vec<RelayReputationSample>>()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
     pub fn is_circuit_open(&self) -> bool {
         self.circuit_breaker.lock().unwrap().is_open()
     }
@@ -373,4 +430,65 @@ mod tests {
         assert!(cache.get().is_none());
         assert!(cache.has_stale_data());
     }
+
+    #[tokio::test]
+    async fn test_stale_data_refused_beyond_max_age() {
+        // Nothing listens here, and the open circuit keeps requests from going out
+        let client = BetanetClient::new("http://127.0.0.1:9".to_string())
+            .with_max_stale_age(Duration::from_secs(60));
+        {
+            let mut breaker = client.circuit_breaker.lock().unwrap();
+            for _ in 0..5 {
+                breaker.record_failure();
+            }
+        }
+        let metrics = BetanetMetricsResponse {
+            node_count: 3,
+            active_connections: 2,
+            throughput_bytes: 100,
+            latency_ms: 5.0,
+            packets_processed: 10,
+            packets_dropped: 0,
+        };
+
+        // Expired but within the window: served
+        {
+            let mut cache = client.cache.lock().unwrap();
+            cache.set(metrics.clone());
+            cache.cached_at = Instant::now().checked_sub(Duration::from_secs(30));
+        }
+        assert_eq!(client.fetch_metrics().await.unwrap().node_count, 3);
+
+        // Beyond the window: refused
+        client.cache.lock().unwrap().cached_at = Instant::now().checked_sub(Duration::from_secs(61));
+        let err = client.fetch_metrics().await.unwrap_err();
+        assert!(err.contains("stale limit"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_relay_reputation_fetched_from_api() {
+        use warp::Filter;
+
+        // Shaped like the mixnode's RelayReputationMetric, extra field included
+        let route = warp::path!("api" / "v1" / "relays" / "reputation").map(|| {
+            warp::reply::json(&serde_json::json!([
+                {"relay": "10.0.0.1:9000", "reputation": 0.4, "reputation_points": 40,
+                 "cost_of_forgery": 2.5},
+                {"relay": "10.0.0.2:9000", "reputation": 0.9, "reputation_points": 90,
+                 "cost_of_forgery": 42.0}
+            ]))
+        });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let client = BetanetClient::new(format!("http://{}", addr));
+        let samples = client.fetch_relay_reputation().await.unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].relay, "10.0.0.2:9000");
+        assert_eq!(samples[1].cost_of_forgery, 42.0);
+
+        let unreachable = BetanetClient::new("http://127.0.0.1:9".to_string());
+        assert!(unreachable.fetch_relay_reputation().await.is_err());
+    }
+
 }
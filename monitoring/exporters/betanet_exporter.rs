@@ -1,8 +1,8 @@
 use prometheus::{
     Histogram, HistogramOpts, IntCounter, IntGauge, Opts, Registry,
 };
-use std::sync::Arc;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::time::{self, Duration};
 use warp::Filter;
 use log::{info, error, warn};
@@ -11,10 +11,15 @@ mod betanet_client;
 use betanet_client::BetanetClient;
 
 mod metric_collector;
-use metric_collector::{MetricCollector, NodeMetricSource, DeploymentMetricSource, MetricSource};
+use metric_collector::{
+    DeploymentMetricSource, MetricCollector, MetricSource, NodeMetricSource, ReputationMetricSource,
+};
 
 mod aggregator;
-use aggregator::{MetricAggregator, AggregationWindows};
+use aggregator::{MetricAggregator, AggregationWindows, PercentileMethod};
+
+#[cfg(feature = "otlp")]
+mod otlp_exporter;
 
 #[derive(Clone)]
 pub struct BetanetMetrics {
@@ -149,37 +154,87 @@ async fn main() {
     let metrics_clone = metrics.clone();
 
     // Initialize Betanet client
-    let betanet_client = Arc::new(BetanetClient::new("http://localhost:9000".to_string()));
+    let mut betanet_client = BetanetClient::new("http://localhost:9000".to_string());
+    if let Some(secs) = env_u64("BETANET_MAX_STALE_SECS") {
+        betanet_client = betanet_client.with_max_stale_age(Duration::from_secs(secs));
+    }
+    let betanet_client = Arc::new(betanet_client);
     let client_clone = betanet_client.clone();
 
-    // Initialize metric collector (5760 data points = 24h at 15s intervals)
-    let collector = Arc::new(MetricCollector::new(5760, 15));
+    // Initialize metric collector (5760 data points = 24h at 15s intervals),
+    // snapping recordings to the collection interval
+    let collector = Arc::new(MetricCollector::new(5760, 15).with_interval_alignment(true));
+    let collection_interval = Duration::from_secs(collector.collection_interval_secs());
     let collector_clone = collector.clone();
     let collector_clone2 = collector.clone();
+    let collector_clone3 = collector.clone();
 
-    // Initialize aggregator
-    let aggregator = Arc::new(MetricAggregator::new(collector.clone()));
+    // Initialize aggregator, optionally capping its batch worker threads
+    let mut aggregator = MetricAggregator::new(collector.clone());
+    if let Some(threads) = env_u64("AGGREGATION_THREADS") {
+        aggregator = aggregator.with_parallelism(threads as usize);
+    }
+    if let Ok(method) = std::env::var("PERCENTILE_METHOD") {
+        match method.parse::<PercentileMethod>() {
+            Ok(method) => aggregator = aggregator.with_percentile_method(method),
+            Err(e) => warn!("Ignoring PERCENTILE_METHOD: {}", e),
+        }
+    }
+    info!(
+        "Aggregating batches on up to {} threads with {:?} percentiles",
+        aggregator.parallelism(),
+        aggregator.percentile_method()
+    );
+    let aggregator = Arc::new(aggregator);
     let aggregator_clone = aggregator.clone();
 
+    // Latest per-relay reputation, read by the reputation metric source
+    let relay_reputation = Arc::new(std::sync::RwLock::new(Vec::new()));
+    let relay_reputation_clone = relay_reputation.clone();
+
     // Spawn Betanet metrics collection task
     tokio::spawn(async move {
-        let mut interval = time::interval(Duration::from_secs(15));
+        let mut interval = time::interval(collection_interval);
 
         loop {
             interval.tick().await;
-            collect_betanet_metrics(&metrics_clone, &client_clone).await;
+            collect_betanet_metrics(&metrics_clone, &client_clone, &collector_clone3).await;
+            match client_clone.fetch_relay_reputation().await {
+                Ok(samples) => *relay_reputation_clone.write().unwrap() = samples,
+                // Keep the last snapshot rather than blanking the series
+                Err(e) => warn!("Failed to fetch relay reputation: {}", e),
+            }
         }
     });
 
+    // Push the mixnode's own stats to an OpenTelemetry collector when one is configured
+    #[cfg(feature = "otlp")]
+    if let Ok(endpoint) = std::env::var("OTLP_ENDPOINT") {
+        let mixnode_addr: std::net::SocketAddr = std::env::var("MIXNODE_ADDR")
+            .unwrap_or_else(|_| "127.0.0.1:9001".to_string())
+            .parse()
+            .expect("MIXNODE_ADDR must be a socket address");
+        info!("Pushing stats of mixnode {} to OTLP collector at {}", mixnode_addr, endpoint);
+        let exporter = Arc::new(otlp_exporter::OtlpExporter::new(
+            otlp_exporter::OtlpConfig::new(endpoint),
+        ));
+        tokio::spawn(exporter.run(move || {
+            otlp_exporter::query_mixnode_stats(mixnode_addr, Duration::from_secs(5))
+        }));
+    }
+
     // Spawn additional metrics collection task (node & deployment metrics)
     tokio::spawn(async move {
-        let mut interval = time::interval(Duration::from_secs(15));
+        let mut interval = time::interval(collection_interval);
 
         // Create metric sources
         let sources: Vec<Box<dyn MetricSource>> = vec![
             Box::new(NodeMetricSource::new("node-1".to_string())),
             Box::new(NodeMetricSource::new("node-2".to_string())),
             Box::new(DeploymentMetricSource::new("deployment-1".to_string())),
+            Box::new(ReputationMetricSource::new(move || {
+                relay_reputation.read().unwrap().clone()
+            })),
         ];
 
         loop {
@@ -206,26 +261,49 @@ async fn main() {
     // Aggregated metrics endpoint
     let agg_metrics_route = warp::path("metrics")
         .and(warp::path("aggregated"))
-        .map(move || {
-            let mut output = String::new();
-
-            // Aggregate last 5 minutes of key metrics
-            let metric_names = vec![
-                "node_cpu_usage",
-                "node_memory_usage",
-                "deployment_latency",
-                "betanet_latency",
-            ];
-
-            for metric_name in metric_names {
-                if let Some(agg) = aggregator_clone.rolling_window(metric_name, AggregationWindows::LAST_5_MINUTES) {
-                    output.push_str(&aggregator_clone.export_prometheus_format(&agg));
+        .and_then(move || {
+            let aggregator = aggregator_clone.clone();
+            async move {
+                let mut output = String::new();
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                let start = now.saturating_sub(AggregationWindows::LAST_5_MINUTES);
+
+                // Aggregate last 5 minutes of key metrics
+                let metric_names = vec![
+                    "node_cpu_usage".to_string(),
+                    "node_memory_usage".to_string(),
+                    "deployment_latency".to_string(),
+                    "betanet_latency".to_string(),
+                ];
+                for agg in aggregator.clone().aggregate_batch_async(metric_names, start, now).await {
+                    output.push_str(&aggregator.export_prometheus_format(&agg));
                 }
-            }
 
-            warp::http::Response::builder()
-                .header("Content-Type", "text/plain; version=0.0.4")
-                .body(output)
+                // Counter rates, with node restarts skipped rather than read as drops
+                for metric_name in ["betanet_packets_processed", "betanet_packets_dropped"] {
+                    if let Some(rate) =
+                        aggregator.calculate_rate(metric_name, AggregationWindows::LAST_5_MINUTES)
+                    {
+                        output.push_str(&format!("{}_rate {}\n", metric_name, rate));
+                    }
+                }
+
+                // Per-relay reputation, one series per relay address
+                for metric_name in ["relay_reputation", "relay_cost_of_forgery"] {
+                    for agg in aggregator.aggregate_by_label(metric_name, start, now, "relay").values() {
+                        output.push_str(&aggregator.export_prometheus_format(agg));
+                    }
+                }
+
+                Ok::<_, warp::Rejection>(
+                    warp::http::Response::builder()
+                        .header("Content-Type", "text/plain; version=0.0.4")
+                        .body(output),
+                )
+            }
         });
 
     // Stats endpoint (JSON format with buffer statistics)
@@ -259,9 +337,22 @@ async fn main() {
     warp::serve(routes).run(([0, 0, 0, 0], 9200)).await;
 }
 
+// Numeric setting from the environment; unset or unparsable means the default
+fn env_u64(name: &str) -> Option<u64> {
+    let value = std::env::var(name).ok()?;
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            warn!("Ignoring {}={:?}: not a whole number", name, value);
+            None
+        }
+    }
+}
+
 async fn collect_betanet_metrics(
     metrics: &Arc<BetanetMetrics>,
     client: &Arc<BetanetClient>,
+    collector: &Arc<MetricCollector>,
 ) {
     info!("Collecting Betanet metrics...");
 
@@ -276,6 +367,18 @@ async fn collect_betanet_metrics(
             // Record latency histogram
             metrics.message_latency.observe(betanet_metrics.latency_ms / 1000.0);
 
+            // Keep the node's cumulative counters for rate calculation
+            collector.record_metric(
+                "betanet_packets_processed",
+                betanet_metrics.packets_processed as f64,
+                HashMap::new(),
+            );
+            collector.record_metric(
+                "betanet_packets_dropped",
+                betanet_metrics.packets_dropped as f64,
+                HashMap::new(),
+            );
+
             info!(
                 "Updated metrics: nodes={}, connections={}, throughput={} bytes, latency={}ms",
                 betanet_metrics.node_count,
@@ -13,6 +13,7 @@ pub struct MetricDataPoint {
 }
 
 // Metric metadata
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct MetricMetadata {
     pub name: String,
@@ -33,6 +34,8 @@ pub struct TimeSeriesBuffer {
     data: VecDeque<MetricDataPoint>,
     max_size: usize,
     name: String,
+    // Number of recordings averaged into each point, aligned with data
+    samples: VecDeque<usize>,
 }
 
 impl TimeSeriesBuffer {
@@ -41,15 +44,41 @@ impl TimeSeriesBuffer {
             data: VecDeque::with_capacity(max_size),
             max_size,
             name,
+            samples: VecDeque::with_capacity(max_size),
         }
     }
 
     pub fn push(&mut self, point: MetricDataPoint) {
         if self.data.len() >= self.max_size {
             self.data.pop_front();
+            self.samples.pop_front();
             debug!("Ring buffer for {} full, removed oldest data point", self.name);
         }
         self.data.push_back(point);
+        self.samples.push_back(1);
+    }
+
+    // Push a point whose timestamp is already snapped to a bucket boundary,
+    // averaging it into the point with the same bucket and labels. Series with
+    // different label sets interleave, so every point of the bucket is checked.
+    pub fn push_bucketed(&mut self, point: MetricDataPoint) {
+        let existing = self
+            .data
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, p)| p.timestamp >= point.timestamp)
+            .find(|(_, p)| p.timestamp == point.timestamp && p.labels == point.labels)
+            .map(|(i, _)| i);
+
+        if let Some(i) = existing {
+            let samples = self.samples[i] as f64;
+            let merged = &mut self.data[i];
+            merged.value = (merged.value * samples + point.value) / (samples + 1.0);
+            self.samples[i] += 1;
+            return;
+        }
+        self.push(point);
     }
 
     pub fn get_range(&self, start_time: u64, end_time: u64) -> Vec<MetricDataPoint> {
@@ -60,10 +89,12 @@ impl TimeSeriesBuffer {
             .collect()
     }
 
+    #[allow(dead_code)]
     pub fn get_all(&self) -> Vec<MetricDataPoint> {
         self.data.iter().cloned().collect()
     }
 
+    #[allow(dead_code)]
     pub fn get_latest(&self) -> Option<&MetricDataPoint> {
         self.data.back()
     }
@@ -72,11 +103,128 @@ impl TimeSeriesBuffer {
         self.data.len()
     }
 
+    #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
 }
 
+// Default histogram layout: exponential buckets growing 10% per bucket from 0.01,
+// covering roughly 0.01..37000 in the metric's unit
+pub const DEFAULT_HISTOGRAM_START: f64 = 0.01;
+pub const DEFAULT_HISTOGRAM_FACTOR: f64 = 1.1;
+pub const DEFAULT_HISTOGRAM_BUCKETS: usize = 160;
+
+// Fixed-bucket histogram updated on every recording, so percentile queries
+// walk the buckets instead of sorting raw points
+#[derive(Debug, Clone)]
+pub struct IncrementalHistogram {
+    // Ascending upper bounds; counts has one extra overflow bucket
+    bounds: Vec<f64>,
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl IncrementalHistogram {
+    pub fn new(mut bounds: Vec<f64>) -> Self {
+        bounds.retain(|b| b.is_finite());
+        bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        bounds.dedup();
+        let counts = vec![0; bounds.len() + 1];
+        Self {
+            bounds,
+            counts,
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn exponential(start: f64, factor: f64, buckets: usize) -> Self {
+        let bounds = (0..buckets)
+            .map(|i| start * factor.powi(i as i32))
+            .collect();
+        Self::new(bounds)
+    }
+
+    // Empty histogram with the same bucket layout
+    pub fn cleared(&self) -> Self {
+        Self::new(self.bounds.clone())
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    // Estimate a percentile (0-100), interpolating linearly inside the bucket
+    // that holds the target rank. Bucket edges are clamped to the observed
+    // min/max so the open-ended first and overflow buckets stay bounded.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = (p.clamp(0.0, 100.0) / 100.0) * (self.count - 1) as f64;
+        let mut seen = 0u64;
+        for (i, &bucket_count) in self.counts.iter().enumerate() {
+            if bucket_count == 0 {
+                continue;
+            }
+            if rank < (seen + bucket_count) as f64 {
+                let lower = if i == 0 { self.min } else { self.bounds[i - 1].max(self.min) };
+                let upper = self.bounds.get(i).copied().unwrap_or(self.max).min(self.max);
+                let fraction = if bucket_count > 1 {
+                    (rank - seen as f64) / (bucket_count - 1) as f64
+                } else {
+                    0.5
+                };
+                return Some(lower + (upper - lower) * fraction.clamp(0.0, 1.0));
+            }
+            seen += bucket_count;
+        }
+
+        Some(self.max)
+    }
+}
+
+impl Default for IncrementalHistogram {
+    fn default() -> Self {
+        Self::exponential(
+            DEFAULT_HISTOGRAM_START,
+            DEFAULT_HISTOGRAM_FACTOR,
+            DEFAULT_HISTOGRAM_BUCKETS,
+        )
+    }
+}
+
 // Main metric collector
 pub struct MetricCollector {
     // Time-series storage
@@ -85,9 +233,13 @@ pub struct MetricCollector {
     // Metric metadata
     metadata: Arc<RwLock<HashMap<String, MetricMetadata>>>,
 
+    // Incremental histograms for Histogram-typed metrics
+    histograms: Arc<RwLock<HashMap<String, IncrementalHistogram>>>,
+
     // Configuration
     buffer_size: usize,
     collection_interval_secs: u64,
+    align_to_interval: bool,
 }
 
 impl MetricCollector {
@@ -100,8 +252,10 @@ impl MetricCollector {
         let collector = Self {
             time_series: Arc::new(RwLock::new(HashMap::new())),
             metadata: Arc::new(RwLock::new(HashMap::new())),
+            histograms: Arc::new(RwLock::new(HashMap::new())),
             buffer_size,
             collection_interval_secs,
+            align_to_interval: false,
         };
 
         // Register default metrics
@@ -110,6 +264,18 @@ impl MetricCollector {
         collector
     }
 
+    // Snap recorded points to collection interval boundaries, averaging the
+    // recordings that land in the same interval. Keeps bursty recorders from
+    // producing irregular series that skew rate calculations.
+    pub fn with_interval_alignment(mut self, enabled: bool) -> Self {
+        self.align_to_interval = enabled;
+        self
+    }
+
+    pub fn collection_interval_secs(&self) -> u64 {
+        self.collection_interval_secs
+    }
+
     // Register default system metrics
     fn register_default_metrics(&self) {
         let default_metrics = vec![
@@ -131,6 +297,10 @@ impl MetricCollector {
             ("betanet_latency", "Network latency", MetricType::Histogram, Some("ms")),
             ("betanet_connections", "Active connections", MetricType::Gauge, Some("count")),
 
+            // Relay reputation metrics (labeled by relay address)
+            ("relay_reputation", "Relay reputation score", MetricType::Gauge, Some("score")),
+            ("relay_cost_of_forgery", "Relay cost of forgery", MetricType::Gauge, None),
+
             // System metrics
             ("system_uptime", "System uptime", MetricType::Counter, Some("seconds")),
             ("system_total_nodes", "Total nodes in cluster", MetricType::Gauge, Some("count")),
@@ -157,6 +327,15 @@ impl MetricCollector {
         metric_type: MetricType,
         unit: Option<String>,
     ) {
+        if metric_type == MetricType::Histogram {
+            self.histograms
+                .write()
+                .unwrap()
+                .insert(name.clone(), IncrementalHistogram::default());
+        } else {
+            self.histograms.write().unwrap().remove(&name);
+        }
+
         let metadata = MetricMetadata {
             name: name.clone(),
             description,
@@ -170,7 +349,7 @@ impl MetricCollector {
         self.time_series
             .write()
             .unwrap()
-            .insert(name.clone(), TimeSeriesBuffer::new(name, self.buffer_size));
+            .insert(name.clone(), TimeSeriesBuffer::new(name.clone(), self.buffer_size));
 
         debug!("Registered metric: {}", name);
     }
@@ -182,6 +361,24 @@ impl MetricCollector {
             .unwrap()
             .as_secs();
 
+        self.record_metric_at(name, value, labels, timestamp);
+    }
+
+    // Record a metric value observed at a given unix timestamp (seconds)
+    pub fn record_metric_at(
+        &self,
+        name: &str,
+        value: f64,
+        labels: HashMap<String, String>,
+        timestamp: u64,
+    ) {
+        let aligned = self.align_to_interval && self.collection_interval_secs > 0;
+        let timestamp = if aligned {
+            timestamp - timestamp % self.collection_interval_secs
+        } else {
+            timestamp
+        };
+
         let point = MetricDataPoint {
             timestamp,
             value,
@@ -189,7 +386,14 @@ impl MetricCollector {
         };
 
         if let Some(buffer) = self.time_series.write().unwrap().get_mut(name) {
-            buffer.push(point);
+            if aligned {
+                buffer.push_bucketed(point);
+            } else {
+                buffer.push(point);
+            }
+            if let Some(histogram) = self.histograms.write().unwrap().get_mut(name) {
+                histogram.observe(value);
+            }
             debug!("Recorded {}: {}", name, value);
         } else {
             warn!("Attempted to record unknown metric: {}", name);
@@ -197,6 +401,7 @@ impl MetricCollector {
     }
 
     // Get latest value for a metric
+    #[allow(dead_code)]
     pub fn get_latest(&self, name: &str) -> Option<MetricDataPoint> {
         self.time_series
             .read()
@@ -216,6 +421,7 @@ impl MetricCollector {
     }
 
     // Get all data for a metric
+    #[allow(dead_code)]
     pub fn get_all_data(&self, name: &str) -> Vec<MetricDataPoint> {
         self.time_series
             .read()
@@ -225,6 +431,11 @@ impl MetricCollector {
             .unwrap_or_default()
     }
 
+    // Snapshot of the incremental histogram of a Histogram metric
+    pub fn get_histogram(&self, name: &str) -> Option<IncrementalHistogram> {
+        self.histograms.read().unwrap().get(name).cloned()
+    }
+
     // Get metric metadata
     pub fn get_metadata(&self, name: &str) -> Option<MetricMetadata> {
         self.metadata.read().unwrap().get(name).cloned()
@@ -246,7 +457,7 @@ impl MetricCollector {
     }
 
     // Collect metrics from multiple sources
-    pub async fn collect_from_sources(&self, sources: &[MetricSource]) {
+    pub async fn collect_from_sources(&self, sources: &[Box<dyn MetricSource>]) {
         for source in sources {
             match source.fetch_metrics().await {
                 Ok(metrics) => {
@@ -331,6 +542,53 @@ impl MetricSource for DeploymentMetricSource {
     }
 }
 
+// Reputation of one relay, as exported by the mixnode's ReputationManager
+// (see ReputationManager::relay_metrics in the betanet crate)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayReputationSample {
+    pub relay: String,
+    pub reputation: f64,
+    pub cost_of_forgery: f64,
+}
+
+// Relay reputation source
+//
+// The provider is called on every collection so values track the shared
+// ReputationManager; the exporter only needs a snapshot, not the manager type.
+pub struct ReputationMetricSource {
+    provider: Arc<dyn Fn() -> Vec<RelayReputationSample> + Send + Sync>,
+}
+
+impl ReputationMetricSource {
+    pub fn new<F>(provider: F) -> Self
+    where
+        F: Fn() -> Vec<RelayReputationSample> + Send + Sync + 'static,
+    {
+        Self {
+            provider: Arc::new(provider),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MetricSource for ReputationMetricSource {
+    fn name(&self) -> &str {
+        "relay_reputation"
+    }
+
+    async fn fetch_metrics(&self) -> Result<Vec<(String, f64, HashMap<String, String>)>, String> {
+        let mut metrics = Vec::new();
+        for sample in (self.provider)() {
+            let mut labels = HashMap::new();
+            labels.insert("relay".to_string(), sample.relay);
+
+            metrics.push(("relay_reputation".to_string(), sample.reputation, labels.clone()));
+            metrics.push(("relay_cost_of_forgery".to_string(), sample.cost_of_forgery, labels));
+        }
+        Ok(metrics)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -388,6 +646,104 @@ mod tests {
         assert_eq!(latest.unwrap().value, 42.0);
     }
 
+    #[test]
+    fn test_histogram_maintained_only_for_histogram_metrics() {
+        let collector = MetricCollector::new(100, 15);
+
+        collector.record_metric("betanet_latency", 12.0, HashMap::new());
+        collector.record_metric("node_cpu_usage", 40.0, HashMap::new());
+
+        assert_eq!(collector.get_histogram("betanet_latency").unwrap().count(), 1);
+        assert!(collector.get_histogram("node_cpu_usage").is_none());
+        // Raw points are still kept alongside the histogram
+        assert_eq!(collector.get_all_data("betanet_latency").len(), 1);
+        assert_eq!(collector.get_all_data("node_cpu_usage").len(), 1);
+    }
+
+    #[test]
+    fn test_interval_alignment_buckets_irregular_recordings() {
+        let collector = MetricCollector::new(100, 15).with_interval_alignment(true);
+
+        for (timestamp, value) in [(100, 10.0), (103, 20.0), (104, 30.0), (121, 5.0), (149, 7.0)] {
+            collector.record_metric_at("node_cpu_usage", value, HashMap::new(), timestamp);
+        }
+
+        let series: Vec<(u64, f64)> = collector
+            .get_all_data("node_cpu_usage")
+            .iter()
+            .map(|p| (p.timestamp, p.value))
+            .collect();
+        assert_eq!(series, vec![(90, 20.0), (120, 5.0), (135, 7.0)]);
+
+        // Without alignment every recording is kept as-is
+        let raw = MetricCollector::new(100, 15);
+        raw.record_metric_at("node_cpu_usage", 10.0, HashMap::new(), 100);
+        raw.record_metric_at("node_cpu_usage", 20.0, HashMap::new(), 103);
+        assert_eq!(raw.get_all_data("node_cpu_usage").len(), 2);
+    }
+
+    #[test]
+    fn test_interval_alignment_merges_interleaved_label_sets() {
+        let collector = MetricCollector::new(100, 15).with_interval_alignment(true);
+        let node = |id: &str| HashMap::from([("node_id".to_string(), id.to_string())]);
+
+        for (timestamp, id, value) in [
+            (100, "node-1", 10.0),
+            (101, "node-2", 50.0),
+            (102, "node-1", 20.0),
+            (103, "node-2", 70.0),
+            (104, "node-1", 30.0),
+            (104, "node-2", 60.0),
+        ] {
+            collector.record_metric_at("node_cpu_usage", value, node(id), timestamp);
+        }
+
+        let series: Vec<(u64, String, f64)> = collector
+            .get_all_data("node_cpu_usage")
+            .iter()
+            .map(|p| (p.timestamp, p.labels["node_id"].clone(), p.value))
+            .collect();
+        assert_eq!(
+            series,
+            vec![(90, "node-1".to_string(), 20.0), (90, "node-2".to_string(), 60.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reputation_source_labels_reach_aggregator() {
+        use crate::aggregator::MetricAggregator;
+
+        let shared = Arc::new(RwLock::new(vec![
+            RelayReputationSample {
+                relay: "10.0.0.1:9000".to_string(),
+                reputation: 0.5,
+                cost_of_forgery: 1.5,
+            },
+            RelayReputationSample {
+                relay: "10.0.0.2:9000".to_string(),
+                reputation: 0.9,
+                cost_of_forgery: 42.0,
+            },
+        ]));
+        let snapshot = shared.clone();
+        let sources: Vec<Box<dyn MetricSource>> = vec![Box::new(ReputationMetricSource::new(
+            move || snapshot.read().unwrap().clone(),
+        ))];
+
+        let collector = Arc::new(MetricCollector::new(100, 15));
+        collector.collect_from_sources(&sources).await;
+
+        let aggregator = MetricAggregator::new(collector.clone());
+        let by_relay = aggregator.aggregate_by_label("relay_reputation", 0, u64::MAX, "relay");
+        assert_eq!(by_relay.len(), 2);
+        assert_eq!(by_relay["10.0.0.2:9000"].avg, 0.9);
+
+        let cost = aggregator.aggregate_by_label("relay_cost_of_forgery", 0, u64::MAX, "relay");
+        assert_eq!(cost["10.0.0.1:9000"].avg, 1.5);
+        let exported = aggregator.export_prometheus_format(&cost["10.0.0.2:9000"]);
+        assert!(exported.contains("relay_cost_of_forgery{relay=\"10.0.0.2:9000\",stat=\"avg\"} 42\n"));
+    }
+
     #[tokio::test]
     async fn test_node_metric_source() {
         let source = NodeMetricSource::new("node-1".to_string());
@@ -0,0 +1,417 @@
+// OTLP push exporter
+//
+// Periodically pushes the mixnode's own MixnodeStats, plus PipelineStats
+// snapshots where a batch pipeline runs, to an OpenTelemetry collector using
+// OTLP over HTTP with JSON encoding (POST {endpoint}/v1/metrics), for stacks
+// that ingest by push instead of Prometheus scraping. Pushes retry with
+// exponential backoff and sit behind the same circuit breaker the Betanet
+// client uses, so an unreachable collector costs one skipped push per
+// interval instead of a retry storm.
+
+use betanet::utils::packet::Packet;
+use betanet::{MixnodeStats, PipelineStatsSnapshot};
+use bytes::Bytes;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{self, sleep, timeout};
+use log::{debug, error, info, warn};
+
+use crate::betanet_client::CircuitBreaker;
+
+// OTLP aggregation temporality: values are totals since process start
+const AGGREGATION_TEMPORALITY_CUMULATIVE: u8 = 2;
+
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    // Collector base URL, e.g. http://localhost:4318
+    pub endpoint: String,
+    // Reported as the service.name resource attribute
+    pub service_name: String,
+    pub push_interval: Duration,
+    pub request_timeout: Duration,
+    // Backoff before each retry; the push fails once these run out
+    pub retry_delays: Vec<Duration>,
+}
+
+impl OtlpConfig {
+    pub fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            service_name: "betanet".to_string(),
+            push_interval: Duration::from_secs(15),
+            request_timeout: Duration::from_secs(5),
+            retry_delays: vec![
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+            ],
+        }
+    }
+}
+
+// One node's stats as pushed on each interval
+#[derive(Debug, Clone, Default)]
+pub struct NodeStats {
+    pub mixnode: MixnodeStats,
+    // Only nodes running the batch pipeline have these
+    pub pipeline: Option<PipelineStatsSnapshot>,
+}
+
+pub struct OtlpExporter {
+    client: Client,
+    config: OtlpConfig,
+    circuit_breaker: Mutex<CircuitBreaker>,
+}
+
+impl OtlpExporter {
+    pub fn new(config: OtlpConfig) -> Self {
+        let client = Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            config,
+            circuit_breaker: Mutex::new(CircuitBreaker::new()),
+        }
+    }
+
+    // Take a snapshot with `fetch` and push it every push_interval, forever
+    pub async fn run<F, Fut>(self: Arc<Self>, fetch: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<NodeStats, String>>,
+    {
+        let mut interval = time::interval(self.config.push_interval);
+
+        loop {
+            interval.tick().await;
+            match fetch().await {
+                Ok(stats) => {
+                    if let Err(e) = self.push(&stats).await {
+                        error!("OTLP push failed: {}", e);
+                        if self.is_circuit_open() {
+                            warn!("OTLP circuit breaker is OPEN - pushes paused");
+                        }
+                    }
+                }
+                Err(e) => warn!("Skipping OTLP push, no mixnode stats: {}", e),
+            }
+        }
+    }
+
+    // Push one snapshot, retrying with backoff
+    pub async fn push(&self, stats: &NodeStats) -> Result<(), String> {
+        if !self.circuit_breaker.lock().unwrap().can_attempt() {
+            return Err("Circuit breaker open, skipping OTLP push".to_string());
+        }
+
+        let body = encode_metrics(stats, &self.config.service_name, unix_nanos());
+        let mut attempt = 0;
+        loop {
+            match self.send(&body).await {
+                Ok(()) => {
+                    if attempt > 0 {
+                        info!("OTLP push succeeded on attempt {}", attempt + 1);
+                    }
+                    self.circuit_breaker.lock().unwrap().record_success();
+                    return Ok(());
+                }
+                Err(e) => match self.config.retry_delays.get(attempt) {
+                    Some(delay) => {
+                        warn!("OTLP push attempt {} failed: {}, retrying in {:?}",
+                              attempt + 1, e, delay);
+                        sleep(*delay).await;
+                        attempt += 1;
+                    }
+                    None => {
+                        self.circuit_breaker.lock().unwrap().record_failure();
+                        return Err(format!("All OTLP push attempts failed: {}", e));
+                    }
+                },
+            }
+        }
+    }
+
+    pub fn is_circuit_open(&self) -> bool {
+        self.circuit_breaker.lock().unwrap().is_open()
+    }
+
+    async fn send(&self, body: &Value) -> Result<(), String> {
+        let url = format!("{}/v1/metrics", self.config.endpoint.trim_end_matches('/'));
+        debug!("Pushing metrics to {}", url);
+
+        let response = self.client
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| format!("HTTP request failed: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Collector returned {}", response.status()))
+        }
+    }
+}
+
+// Ask a mixnode for its MixnodeStats with a `stats` control frame
+//
+// The mixnode answers on the same connection with the stats as JSON. Its
+// pipeline stats are not served over the wire, so `pipeline` is left empty.
+pub async fn query_mixnode_stats(addr: SocketAddr, wait: Duration) -> Result<NodeStats, String> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(addr)
+            .await
+            .map_err(|e| format!("Cannot reach mixnode at {}: {}", addr, e))?;
+        let request = Packet::control(Bytes::from_static(b"stats"))
+            .encode()
+            .map_err(|e| e.to_string())?;
+        stream.write_all(&request).await.map_err(|e| e.to_string())?;
+
+        let mut buf = vec![0u8; betanet::MAX_PACKET_SIZE + 64];
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        let response = Packet::parse(&buf[..n]).map_err(|e| e.to_string())?;
+        serde_json::from_slice::<MixnodeStats>(&response.payload)
+            .map_err(|e| format!("Malformed stats from mixnode: {}", e))
+    };
+
+    let mixnode = timeout(wait, exchange)
+        .await
+        .map_err(|_| format!("Mixnode at {} did not answer within {:?}", addr, wait))??;
+    Ok(NodeStats { mixnode, pipeline: None })
+}
+
+// Encode a snapshot as an OTLP ExportMetricsServiceRequest (JSON mapping)
+pub fn encode_metrics(stats: &NodeStats, service_name: &str, time_unix_nano: u128) -> Value {
+    let time = time_unix_nano.to_string();
+    let gauge = |name: &str, unit: &str, value: f64| {
+        json!({
+            "name": name,
+            "unit": unit,
+            "gauge": { "dataPoints": [{ "timeUnixNano": time, "asDouble": value }] }
+        })
+    };
+    let sum = |name: &str, unit: &str, points: Vec<Value>| {
+        json!({
+            "name": name,
+            "unit": unit,
+            "sum": {
+                "dataPoints": points,
+                "aggregationTemporality": AGGREGATION_TEMPORALITY_CUMULATIVE,
+                "isMonotonic": true
+            }
+        })
+    };
+    let point = |value: u64| json!({ "timeUnixNano": time, "asInt": value.to_string() });
+    let counter = |name: &str, unit: &str, value: u64| sum(name, unit, vec![point(value)]);
+
+    let mixnode = &stats.mixnode;
+    let drops_by_reason = mixnode
+        .drop_reasons
+        .iter()
+        .map(|(reason, count)| {
+            let mut point = point(*count);
+            point["attributes"] = json!([{ "key": "reason", "value": { "stringValue": reason } }]);
+            point
+        })
+        .collect();
+    let mut metrics = vec![
+        counter("betanet.mixnode.packets.processed", "{packet}", mixnode.packets_processed),
+        counter("betanet.mixnode.packets.forwarded", "{packet}", mixnode.packets_forwarded),
+        counter("betanet.mixnode.packets.dropped", "{packet}", mixnode.packets_dropped),
+        sum("betanet.mixnode.packets.dropped_by_reason", "{packet}", drops_by_reason),
+        counter("betanet.mixnode.cover_traffic.sent", "{packet}", mixnode.cover_traffic_sent),
+        gauge("betanet.mixnode.processing_time.avg", "us", mixnode.avg_processing_time_us),
+        gauge("betanet.mixnode.uptime", "s", mixnode.uptime_secs as f64),
+    ];
+
+    if let Some(pipeline) = &stats.pipeline {
+        metrics.extend([
+            counter("betanet.pipeline.packets.processed", "{packet}", pipeline.packets_processed),
+            counter("betanet.pipeline.packets.dropped", "{packet}", pipeline.packets_dropped),
+            counter("betanet.pipeline.packets.expired", "{packet}", pipeline.packets_expired),
+            counter("betanet.pipeline.packets.timed_out", "{packet}", pipeline.packets_timed_out),
+            counter("betanet.pipeline.packets.shed", "{packet}", pipeline.packets_shed),
+            counter("betanet.pipeline.batches.processed", "{batch}", pipeline.batches_processed),
+            gauge("betanet.pipeline.queue_depth.avg", "{packet}", pipeline.avg_queue_depth as f64),
+            gauge("betanet.pipeline.pool_hit_rate", "%", pipeline.pool_hit_rate_pct),
+            gauge(
+                "betanet.pipeline.processing_time.avg",
+                "ns",
+                pipeline.avg_processing_time_ns as f64,
+            ),
+            gauge("betanet.pipeline.batch_size.p50", "{packet}", pipeline.batch_size_p50 as f64),
+            gauge("betanet.pipeline.batch_size.p99", "{packet}", pipeline.batch_size_p99 as f64),
+        ]);
+    }
+
+    json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [{
+                    "key": "service.name",
+                    "value": { "stringValue": service_name }
+                }]
+            },
+            "scopeMetrics": [{
+                "scope": { "name": "betanet_exporter", "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics
+            }]
+        }]
+    })
+}
+
+fn unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use warp::Filter;
+
+    #[tokio::test]
+    async fn test_metrics_pushed_to_mock_receiver() {
+        // Mock collector: fails the first request, then accepts
+        let received: Arc<Mutex<Vec<Value>>> = Arc::new(Mutex::new(Vec::new()));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let route = {
+            let received = received.clone();
+            let requests = requests.clone();
+            warp::path!("v1" / "metrics")
+                .and(warp::post())
+                .and(warp::body::json())
+                .map(move |body: Value| {
+                    if requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return warp::http::StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    received.lock().unwrap().push(body);
+                    warp::http::StatusCode::OK
+                })
+        };
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let mut config = OtlpConfig::new(format!("http://{}", addr));
+        config.retry_delays = vec![Duration::from_millis(10)];
+        let exporter = OtlpExporter::new(config);
+        let mut mixnode = MixnodeStats::new();
+        for _ in 0..900 {
+            mixnode.record_processed(Duration::from_micros(40));
+        }
+        for _ in 0..5 {
+            mixnode.record_dropped_with_reason("no_route");
+        }
+        mixnode.record_dropped_with_reason("ttl_expired");
+        mixnode.record_dropped_with_reason("ttl_expired");
+        let pipeline = PipelineStatsSnapshot {
+            packets_processed: 1200,
+            batches_processed: 12,
+            pool_hit_rate_pct: 97.5,
+            ..Default::default()
+        };
+        let snapshot = NodeStats { mixnode, pipeline: Some(pipeline) };
+
+        exporter.push(&snapshot).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(!exporter.is_circuit_open());
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let resource = &received[0]["resourceMetrics"][0];
+        assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "betanet");
+        let metrics = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        let find = |name: &str| metrics.iter().find(|m| m["name"] == name).unwrap().clone();
+        let processed = find("betanet.mixnode.packets.processed");
+        assert_eq!(processed["sum"]["dataPoints"][0]["asInt"], "900");
+        assert_eq!(processed["sum"]["isMonotonic"], true);
+        let avg = find("betanet.mixnode.processing_time.avg");
+        assert_eq!(avg["gauge"]["dataPoints"][0]["asDouble"], 40.0);
+        assert_eq!(find("betanet.mixnode.packets.dropped")["sum"]["dataPoints"][0]["asInt"], "7");
+        let by_reason = find("betanet.mixnode.packets.dropped_by_reason");
+        let by_reason = by_reason["sum"]["dataPoints"].as_array().unwrap();
+        assert_eq!(by_reason.len(), 2);
+        assert_eq!(by_reason[0]["attributes"][0]["value"]["stringValue"], "no_route");
+        assert_eq!(by_reason[0]["asInt"], "5");
+        let batches = find("betanet.pipeline.batches.processed");
+        assert_eq!(batches["sum"]["dataPoints"][0]["asInt"], "12");
+        let hit_rate = find("betanet.pipeline.pool_hit_rate");
+        assert_eq!(hit_rate["gauge"]["dataPoints"][0]["asDouble"], 97.5);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_collector_opens_circuit() {
+        // Nothing listens on the discard port
+        let mut config = OtlpConfig::new("http://127.0.0.1:9".to_string());
+        config.retry_delays = Vec::new();
+        let exporter = OtlpExporter::new(config);
+        let snapshot = NodeStats::default();
+
+        for _ in 0..5 {
+            assert!(exporter.push(&snapshot).await.is_err());
+        }
+        assert!(exporter.is_circuit_open());
+        let err = exporter.push(&snapshot).await.unwrap_err();
+        assert!(err.contains("Circuit breaker open"), "{}", err);
+    }
+
+    #[test]
+    fn test_pipeline_metrics_only_with_pipeline_stats() {
+        let names = |stats: &NodeStats| -> Vec<String> {
+            encode_metrics(stats, "betanet", 0)["resourceMetrics"][0]["scopeMetrics"][0]
+                ["metrics"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| m["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let mixnode_only = NodeStats::default();
+        assert!(names(&mixnode_only).iter().all(|n| n.starts_with("betanet.mixnode.")));
+        let with_pipeline = NodeStats {
+            pipeline: Some(PipelineStatsSnapshot::default()),
+            ..Default::default()
+        };
+        assert!(names(&with_pipeline).iter().any(|n| n.starts_with("betanet.pipeline.")));
+    }
+
+    #[tokio::test]
+    async fn test_stats_queried_from_running_mixnode() {
+        use betanet::{MixnodeConfig, MixnodeTrait, StandardMixnode};
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let config = MixnodeConfig {
+            listen_addr: addr,
+            ..Default::default()
+        };
+        let mut mixnode = StandardMixnode::new(config).unwrap();
+        mixnode.start().await.unwrap();
+
+        let stats = query_mixnode_stats(addr, Duration::from_secs(2)).await.unwrap();
+        assert_eq!(stats.mixnode.packets_processed, 0);
+        assert!(stats.pipeline.is_none());
+        mixnode.stop().await.unwrap();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        assert!(query_mixnode_stats(closed, Duration::from_secs(1)).await.is_err());
+    }
+
+}
@@ -0,0 +1,49 @@
+//! Pipeline submit benchmark
+//!
+//! Measures `submit_packet` throughput with several producer threads
+//! submitting at once, comparing the mutex-guarded input queue against the
+//! lock-free ring used by `QueueStrategy::MaxThroughput`.
+
+use betanet::{PacketPipeline, PipelinePacket, QueueStrategy};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+const PACKETS_PER_PRODUCER: usize = 1_000;
+
+fn submit_concurrently(pipeline: &PacketPipeline, producers: usize) {
+    std::thread::scope(|scope| {
+        for _ in 0..producers {
+            scope.spawn(|| {
+                futures::executor::block_on(async {
+                    for _ in 0..PACKETS_PER_PRODUCER {
+                        let packet = PipelinePacket::new(Bytes::from_static(&[0u8; 256]));
+                        pipeline.submit_packet(packet).await.unwrap();
+                    }
+                })
+            });
+        }
+    });
+}
+
+fn bench_submit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pipeline_submit");
+    for producers in [1usize, 4, 8] {
+        group.throughput(Throughput::Elements((producers * PACKETS_PER_PRODUCER) as u64));
+        for (name, strategy) in [
+            ("mutex", QueueStrategy::Balanced),
+            ("lock_free_ring", QueueStrategy::MaxThroughput),
+        ] {
+            group.bench_with_input(BenchmarkId::new(name, producers), &producers, |b, &n| {
+                b.iter_batched(
+                    || PacketPipeline::new(1).with_queue_strategy(strategy),
+                    |pipeline| submit_concurrently(&pipeline, n),
+                    BatchSize::PerIteration,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_submit);
+criterion_main!(benches);
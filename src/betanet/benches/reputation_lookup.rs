@@ -0,0 +1,92 @@
+//! Reputation lookup benchmark
+//!
+//! Compares `ReputationManager` lookups, keyed by `SocketAddr`, against the
+//! previous `addr.to_string()` keyed map, and reports heap allocations per
+//! lookup for each using a counting allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use betanet::core::reputation::{NodeReputation, ReputationManager};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn populate(size: usize) -> (ReputationManager, HashMap<String, NodeReputation>, Vec<SocketAddr>) {
+    let mut manager = ReputationManager::new();
+    let mut string_keyed = HashMap::with_capacity(size);
+    let mut addrs = Vec::with_capacity(size);
+    for i in 0..size {
+        let addr: SocketAddr = format!("10.{}.{}.{}:9000", i >> 16, (i >> 8) & 0xff, i & 0xff)
+            .parse()
+            .unwrap();
+        manager.add_node(addr, 1000 + i as u64);
+        string_keyed.insert(addr.to_string(), manager.get_reputation(&addr).unwrap());
+        addrs.push(addr);
+    }
+    (manager, string_keyed, addrs)
+}
+
+fn allocations_per_lookup(addrs: &[SocketAddr], mut lookup: impl FnMut(&SocketAddr) -> i32) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for addr in addrs {
+        black_box(lookup(addr));
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / addrs.len() as f64
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reputation_lookup");
+    for size in [1_000usize, 10_000] {
+        let (manager, string_keyed, addrs) = populate(size);
+
+        let addr_allocs = allocations_per_lookup(&addrs, |a| manager.get_reputation_points(a));
+        let string_allocs = allocations_per_lookup(&addrs, |a| {
+            string_keyed
+                .get(&a.to_string())
+                .map(|r| r.reputation_points)
+                .unwrap_or(100)
+        });
+        println!(
+            "reputation_lookup/{}: {:.2} allocations per lookup (SocketAddr key) vs {:.2} (String key)",
+            size, addr_allocs, string_allocs
+        );
+
+        group.bench_with_input(BenchmarkId::new("socket_addr_key", size), &addrs, |b, addrs| {
+            b.iter(|| {
+                for addr in addrs {
+                    black_box(manager.get_reputation_points(addr));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("string_key", size), &addrs, |b, addrs| {
+            b.iter(|| {
+                for addr in addrs {
+                    black_box(string_keyed.get(&addr.to_string()).map(|r| r.reputation_points));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lookup);
+criterion_main!(benches);
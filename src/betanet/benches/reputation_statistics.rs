@@ -0,0 +1,56 @@
+//! Reputation statistics aggregation benchmark
+//!
+//! Compares `ReputationManager::statistics` (single pass, one clock read)
+//! against the previous multi-pass aggregation over the same nodes.
+
+use std::net::SocketAddr;
+
+use betanet::core::reputation::{NodeReputation, ReputationAction, ReputationManager};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn populate(size: usize) -> (ReputationManager, Vec<NodeReputation>) {
+    let mut manager = ReputationManager::new();
+    let mut nodes = Vec::with_capacity(size);
+    for i in 0..size {
+        let addr: SocketAddr = format!("10.{}.{}.{}:9000", i >> 16, (i >> 8) & 0xff, i & 0xff)
+            .parse()
+            .unwrap();
+        manager.add_node(addr, 1000 + i as u64);
+        if i % 3 == 0 {
+            manager
+                .update_reputation(&addr, ReputationAction::TaskFailure)
+                .unwrap();
+        }
+        nodes.push(manager.get_reputation(&addr).unwrap());
+    }
+    (manager, nodes)
+}
+
+fn multi_pass(nodes: &[NodeReputation], threshold: i32) -> (f64, i32, f64, usize) {
+    let n = nodes.len();
+    let avg_reputation = nodes.iter().map(|r| r.reputation).sum::<f64>() / n as f64;
+    let avg_points = nodes.iter().map(|r| r.reputation_points).sum::<i32>() / n as i32;
+    let avg_cost = nodes.iter().map(|r| r.cost_of_forgery()).sum::<f64>() / n as f64;
+    let above = nodes
+        .iter()
+        .filter(|r| r.reputation_points >= threshold)
+        .count();
+    (avg_reputation, avg_points, avg_cost, above)
+}
+
+fn bench_statistics(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reputation_statistics");
+    for size in [1_000usize, 10_000, 100_000] {
+        let (manager, nodes) = populate(size);
+        group.bench_with_input(BenchmarkId::new("single_pass", size), &manager, |b, m| {
+            b.iter(|| black_box(m.statistics()))
+        });
+        group.bench_with_input(BenchmarkId::new("multi_pass", size), &nodes, |b, n| {
+            b.iter(|| black_box(multi_pass(n, 50)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_statistics);
+criterion_main!(benches);
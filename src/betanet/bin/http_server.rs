@@ -1,7 +1,13 @@
 // Betanet HTTP Server Binary
 // Run with: cargo run --bin http_server
+//
+// Set BETANET_REPUTATION_FILE to serve relay reputation saved by a mixnode.
 
-use betanet::server::http;
+use std::sync::{Arc, RwLock};
+
+use betanet::core::reputation::ReputationManager;
+use betanet::server::http::HttpServer;
+use betanet::utils::rate::{RateLimitedTrafficShaper, RateLimitingConfig};
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
@@ -10,5 +16,17 @@ async fn main() -> std::io::Result<()> {
     println!("║   Port: 9000                              ║");
     println!("╚═══════════════════════════════════════════╝");
 
-    http::run_server().await
+    let mut reputation = ReputationManager::new();
+    if let Ok(path) = std::env::var("BETANET_REPUTATION_FILE") {
+        if let Err(e) = reputation.load_from_file(path.as_ref()) {
+            eprintln!("Not loading relay reputation from {}: {}", path, e);
+        }
+    }
+    let shaper = Arc::new(RateLimitedTrafficShaper::new(RateLimitingConfig::default()));
+
+    HttpServer::new()
+        .with_rate_limiter(shaper)
+        .with_reputation(Arc::new(RwLock::new(reputation)))
+        .run()
+        .await
 }
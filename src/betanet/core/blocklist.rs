@@ -0,0 +1,450 @@
+//! Relay/peer blocklist with CIDR ranges
+//!
+//! Entries come from operator-maintained files or threat feeds served over
+//! plain HTTP: one IP or CIDR per line, `#` starts a comment. The shared
+//! handle can be reloaded at runtime, watched for changes on disk or
+//! refreshed from its feed URL on an interval.
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+use crate::{MixnodeError, Result};
+
+/// Longest a feed fetch may take, from connect to the last body byte
+pub const FEED_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest feed response accepted, headers included
+pub const MAX_FEED_BYTES: usize = 8 * 1024 * 1024;
+
+/// IP network in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CidrBlock {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl CidrBlock {
+    /// Parse `a.b.c.d/len`, `v6::/len`, or a bare address
+    pub fn parse(entry: &str) -> Result<Self> {
+        let entry = entry.trim();
+        let (addr, prefix) = match entry.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (entry, None),
+        };
+
+        let network: IpAddr = addr
+            .parse()
+            .map_err(|_| MixnodeError::Config(format!("Invalid blocklist address: {}", entry)))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| {
+                    MixnodeError::Config(format!("Invalid blocklist prefix: {}", entry))
+                })?,
+            None => max_len,
+        };
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Check if an address falls inside this block
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = prefix_mask_u32(self.prefix_len);
+                u32::from(net) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = prefix_mask_u128(self.prefix_len);
+                u128::from(net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_mask_u32(len: u8) -> u32 {
+    if len == 0 {
+        0
+    } else {
+        u32::MAX << (32 - len as u32)
+    }
+}
+
+fn prefix_mask_u128(len: u8) -> u128 {
+    if len == 0 {
+        0
+    } else {
+        u128::MAX << (128 - len as u32)
+    }
+}
+
+/// Set of blocked CIDR ranges
+#[derive(Debug, Clone, Default)]
+pub struct Blocklist {
+    entries: Vec<CidrBlock>,
+}
+
+impl Blocklist {
+    /// Create empty blocklist
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse blocklist text (one entry per line, `#` comments)
+    pub fn parse(text: &str) -> Result<Self> {
+        let entries = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .map(CidrBlock::parse)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Load blocklist from file
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(MixnodeError::Io)?;
+        Self::parse(&text)
+    }
+
+    /// Fetch and parse a blocklist feed from an `http://` URL
+    pub async fn fetch(url: &str) -> Result<Self> {
+        Self::parse(&fetch_feed(url).await?)
+    }
+
+    /// Add a single entry
+    pub fn add(&mut self, block: CidrBlock) {
+        self.entries.push(block);
+    }
+
+    /// Check if an address is blocked
+    pub fn is_blocked(&self, addr: &IpAddr) -> bool {
+        self.entries.iter().any(|block| block.contains(addr))
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if blocklist is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Cloneable, hot-reloadable blocklist handle
+///
+/// Consumers can compare [`SharedBlocklist::generation`] to detect reloads.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBlocklist {
+    inner: Arc<RwLock<Blocklist>>,
+    generation: Arc<AtomicU64>,
+}
+
+impl SharedBlocklist {
+    /// Wrap a blocklist
+    pub fn new(blocklist: Blocklist) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(blocklist)),
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Load from file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Ok(Self::new(Blocklist::load_from_file(path)?))
+    }
+
+    /// Check if an address is blocked
+    pub fn is_blocked(&self, addr: &IpAddr) -> bool {
+        self.inner.read().unwrap().is_blocked(addr)
+    }
+
+    /// Replace the current entries
+    pub fn replace(&self, blocklist: Blocklist) {
+        *self.inner.write().unwrap() = blocklist;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reload entries from file, keeping the old list on error
+    pub fn reload_from_file(&self, path: &Path) -> Result<usize> {
+        let blocklist = Blocklist::load_from_file(path)?;
+        let count = blocklist.len();
+        self.replace(blocklist);
+        Ok(count)
+    }
+
+    /// Reload entries from a feed URL, keeping the old list on error
+    pub async fn reload_from_url(&self, url: &str) -> Result<usize> {
+        let blocklist = Blocklist::fetch(url).await?;
+        let count = blocklist.len();
+        self.replace(blocklist);
+        Ok(count)
+    }
+
+    /// Reload counter, bumped on every replace
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().len()
+    }
+
+    /// Check if blocklist is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().unwrap().is_empty()
+    }
+
+    /// Poll `path` every `interval` and reload when its mtime changes
+    pub fn watch_file(&self, path: PathBuf, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let handle = self.clone();
+        tokio::spawn(async move {
+            let mut last_modified = modified_time(&path);
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let modified = modified_time(&path);
+                if modified.is_none() || modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match handle.reload_from_file(&path) {
+                    Ok(count) => info!("Reloaded blocklist from {:?}: {} entries", path, count),
+                    Err(e) => warn!("Keeping previous blocklist, reload failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Fetch `url` every `interval` and reload when the feed changes
+    ///
+    /// The first fetch happens straight away. A failed fetch or a feed that
+    /// doesn't parse keeps the previous entries until the next tick.
+    pub fn watch_url(&self, url: String, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let handle = self.clone();
+        tokio::spawn(async move {
+            let mut last_feed = None;
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let feed = match fetch_feed(&url).await {
+                    Ok(feed) => feed,
+                    Err(e) => {
+                        warn!("Keeping previous blocklist, fetch from {} failed: {}", url, e);
+                        continue;
+                    }
+                };
+                if last_feed.as_ref() == Some(&feed) {
+                    debug!("Blocklist feed {} unchanged", url);
+                    continue;
+                }
+
+                match Blocklist::parse(&feed) {
+                    Ok(blocklist) => {
+                        info!("Reloaded blocklist from {}: {} entries", url, blocklist.len());
+                        handle.replace(blocklist);
+                        last_feed = Some(feed);
+                    }
+                    Err(e) => warn!("Keeping previous blocklist, feed {} invalid: {}", url, e),
+                }
+            }
+        })
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// GET a feed body over plain HTTP
+///
+/// Speaks HTTP/1.0 so the body needs no chunked decoding; TLS feeds have to
+/// be mirrored to a local file or plain-HTTP endpoint.
+async fn fetch_feed(url: &str) -> Result<String> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        MixnodeError::Config(format!("Blocklist feed must be an http:// URL: {}", url))
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => (host, port.parse().unwrap()),
+        _ => (authority, 80),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let fetch = async {
+        let mut stream = TcpStream::connect((host, port)).await.map_err(MixnodeError::Io)?;
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: betanet-mixnode\r\n\r\n",
+            path, authority
+        );
+        stream.write_all(request.as_bytes()).await.map_err(MixnodeError::Io)?;
+
+        let mut response = Vec::new();
+        (&mut stream)
+            .take(MAX_FEED_BYTES as u64 + 1)
+            .read_to_end(&mut response)
+            .await
+            .map_err(MixnodeError::Io)?;
+        Ok::<_, MixnodeError>(response)
+    };
+    let response = tokio::time::timeout(FEED_FETCH_TIMEOUT, fetch)
+        .await
+        .map_err(|_| MixnodeError::Network(format!("Timed out fetching {}", url)))??;
+    if response.len() > MAX_FEED_BYTES {
+        return Err(MixnodeError::Network(format!(
+            "Feed {} exceeds {} bytes",
+            url, MAX_FEED_BYTES
+        )));
+    }
+
+    let text = String::from_utf8(response)
+        .map_err(|_| MixnodeError::Network(format!("Feed {} is not UTF-8", url)))?;
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| MixnodeError::Network(format!("Malformed response from {}", url)))?;
+    let status = head.lines().next().unwrap_or("");
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(MixnodeError::Network(format!(
+            "Feed {} answered {:?}",
+            url, status
+        )));
+    }
+    Ok(body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cidr_contains() {
+        let block = CidrBlock::parse("10.1.0.0/16").unwrap();
+        assert!(block.contains(&"10.1.200.3".parse().unwrap()));
+        assert!(!block.contains(&"10.2.0.1".parse().unwrap()));
+        assert!(!block.contains(&"::1".parse().unwrap()));
+
+        let single = CidrBlock::parse("192.168.1.7").unwrap();
+        assert!(single.contains(&"192.168.1.7".parse().unwrap()));
+        assert!(!single.contains(&"192.168.1.8".parse().unwrap()));
+
+        let v6 = CidrBlock::parse("2001:db8::/32").unwrap();
+        assert!(v6.contains(&"2001:db8:1::1".parse().unwrap()));
+
+        assert!(CidrBlock::parse("10.0.0.0/33").is_err());
+        assert!(CidrBlock::parse("not-an-ip").is_err());
+    }
+
+    #[test]
+    fn test_parse_and_reload() {
+        let list = Blocklist::parse("# feed\n10.0.0.0/8\n\n203.0.113.5 # single\n").unwrap();
+        assert_eq!(list.len(), 2);
+
+        let path = std::env::temp_dir().join(format!("betanet-blocklist-{}.txt", std::process::id()));
+        std::fs::write(&path, "198.51.100.0/24\n").unwrap();
+
+        let shared = SharedBlocklist::new(list);
+        assert!(shared.is_blocked(&"10.9.9.9".parse().unwrap()));
+
+        assert_eq!(shared.reload_from_file(&path).unwrap(), 1);
+        assert_eq!(shared.generation(), 1);
+        assert!(!shared.is_blocked(&"10.9.9.9".parse().unwrap()));
+        assert!(shared.is_blocked(&"198.51.100.20".parse().unwrap()));
+
+        // A bad reload keeps the previous entries
+        std::fs::write(&path, "garbage/99\n").unwrap();
+        assert!(shared.reload_from_file(&path).is_err());
+        assert!(shared.is_blocked(&"198.51.100.20".parse().unwrap()));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// Serve `feed` over HTTP, answering 503 while it is `None`
+    async fn serve_feed(feed: Arc<RwLock<Option<String>>>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/feeds/blocklist.txt", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 256];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                assert!(request.starts_with(b"GET /feeds/blocklist.txt HTTP/1.0\r\n"));
+
+                let response = match feed.read().unwrap().clone() {
+                    Some(body) => format!("HTTP/1.0 200 OK\r\n\r\n{}", body),
+                    None => "HTTP/1.0 503 Service Unavailable\r\n\r\n".to_string(),
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_feed_url() {
+        let feed = Arc::new(RwLock::new(Some("# feed\n10.0.0.0/8\n".to_string())));
+        let url = serve_feed(feed.clone()).await;
+
+        let list = Blocklist::fetch(&url).await.unwrap();
+        assert!(list.is_blocked(&"10.9.9.9".parse().unwrap()));
+
+        let shared = SharedBlocklist::default();
+        assert_eq!(shared.reload_from_url(&url).await.unwrap(), 1);
+        assert!(shared.is_blocked(&"10.9.9.9".parse().unwrap()));
+
+        // An unavailable or invalid feed keeps the previous entries
+        *feed.write().unwrap() = None;
+        assert!(shared.reload_from_url(&url).await.is_err());
+        *feed.write().unwrap() = Some("garbage/99\n".to_string());
+        assert!(shared.reload_from_url(&url).await.is_err());
+        assert!(shared.is_blocked(&"10.9.9.9".parse().unwrap()));
+        assert_eq!(shared.generation(), 1);
+
+        assert!(Blocklist::fetch("https://example.com/feed").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_watch_url_refreshes_on_change() {
+        let feed = Arc::new(RwLock::new(Some("10.0.0.0/8\n".to_string())));
+        let url = serve_feed(feed.clone()).await;
+
+        let shared = SharedBlocklist::default();
+        let watcher = shared.watch_url(url, Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(shared.is_blocked(&"10.9.9.9".parse().unwrap()));
+        // Unchanged feeds don't count as reloads
+        assert_eq!(shared.generation(), 1);
+
+        *feed.write().unwrap() = Some("198.51.100.0/24\n".to_string());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!shared.is_blocked(&"10.9.9.9".parse().unwrap()));
+        assert!(shared.is_blocked(&"198.51.100.20".parse().unwrap()));
+        assert_eq!(shared.generation(), 2);
+
+        watcher.abort();
+    }
+}
@@ -0,0 +1,293 @@
+//! Circuit lifetime tracking
+//!
+//! Long-lived circuits are easier to correlate, so each circuit is given a
+//! maximum age. Once it is exceeded the tracker hands back a rotation signal
+//! telling the owner to tear the circuit down and build a fresh one.
+//! [`CircuitManager`] is that owner for circuits drawn from a [`RelayLottery`].
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::core::config::MixnodeConfig;
+use crate::core::relay_lottery::{OnShortage, RelayLottery};
+use crate::Result;
+
+/// Default maximum circuit age (10 minutes)
+pub const DEFAULT_MAX_CIRCUIT_LIFETIME: Duration = Duration::from_secs(600);
+
+/// Circuit identifier
+pub type CircuitId = u64;
+
+/// Signal that a circuit outlived its lifetime and must be rebuilt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitRotation {
+    /// Circuit to tear down
+    pub circuit_id: CircuitId,
+    /// Age of the circuit when it was retired
+    pub age: Duration,
+}
+
+/// Tracks circuit creation times against a maximum age
+#[derive(Debug, Clone)]
+pub struct CircuitLifetimeTracker {
+    circuits: HashMap<CircuitId, Instant>,
+    max_age: Duration,
+}
+
+impl CircuitLifetimeTracker {
+    /// Create tracker with the given maximum circuit age
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            circuits: HashMap::new(),
+            max_age,
+        }
+    }
+
+    /// Create tracker using `max_circuit_lifetime` from the config
+    pub fn from_config(config: &MixnodeConfig) -> Self {
+        Self::new(config.max_circuit_lifetime)
+    }
+
+    /// Maximum circuit age
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Start tracking a circuit built now
+    pub fn open(&mut self, circuit_id: CircuitId) {
+        self.open_at(circuit_id, Instant::now());
+    }
+
+    /// Start tracking a circuit built at `at`
+    ///
+    /// Reopening a tracked circuit id restarts its lifetime.
+    pub fn open_at(&mut self, circuit_id: CircuitId, at: Instant) {
+        self.circuits.insert(circuit_id, at);
+    }
+
+    /// Stop tracking a circuit closed by its owner
+    pub fn close(&mut self, circuit_id: CircuitId) -> bool {
+        self.circuits.remove(&circuit_id).is_some()
+    }
+
+    /// Age of a circuit as of `now`
+    pub fn age_at(&self, circuit_id: CircuitId, now: Instant) -> Option<Duration> {
+        self.circuits
+            .get(&circuit_id)
+            .map(|opened| now.saturating_duration_since(*opened))
+    }
+
+    /// Number of tracked circuits
+    pub fn len(&self) -> usize {
+        self.circuits.len()
+    }
+
+    /// Check if no circuits are tracked
+    pub fn is_empty(&self) -> bool {
+        self.circuits.is_empty()
+    }
+
+    /// Retire every circuit past its lifetime
+    pub fn expire(&mut self) -> Vec<CircuitRotation> {
+        self.expire_at(Instant::now())
+    }
+
+    /// Retire every circuit past its lifetime as of `now`
+    ///
+    /// Retired circuits stop being tracked; the owner rebuilds and reopens.
+    pub fn expire_at(&mut self, now: Instant) -> Vec<CircuitRotation> {
+        let max_age = self.max_age;
+        let mut rotations = Vec::new();
+        self.circuits.retain(|&circuit_id, opened| {
+            let age = now.saturating_duration_since(*opened);
+            if age < max_age {
+                return true;
+            }
+            debug!("Rotating circuit {} after {:?}", circuit_id, age);
+            rotations.push(CircuitRotation { circuit_id, age });
+            false
+        });
+        rotations.sort_by_key(|rotation| rotation.circuit_id);
+        rotations
+    }
+}
+
+impl Default for CircuitLifetimeTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CIRCUIT_LIFETIME)
+    }
+}
+
+/// Builds circuits from the relay lottery and rebuilds them as they age out
+#[derive(Debug, Clone)]
+pub struct CircuitManager {
+    hops: usize,
+    on_shortage: OnShortage,
+    paths: HashMap<CircuitId, Vec<SocketAddr>>,
+    lifetimes: CircuitLifetimeTracker,
+    next_id: CircuitId,
+}
+
+impl CircuitManager {
+    /// Create manager building `hops`-long circuits retired by `lifetimes`
+    pub fn new(hops: usize, lifetimes: CircuitLifetimeTracker) -> Self {
+        Self {
+            hops,
+            on_shortage: OnShortage::default(),
+            paths: HashMap::new(),
+            lifetimes,
+            next_id: 1,
+        }
+    }
+
+    /// Create manager with one hop per mix layer and the configured lifetime
+    pub fn from_config(config: &MixnodeConfig) -> Self {
+        Self::new(config.layers as usize, CircuitLifetimeTracker::from_config(config))
+    }
+
+    /// Set how path selection handles a relay shortage
+    pub fn with_on_shortage(mut self, on_shortage: OnShortage) -> Self {
+        self.on_shortage = on_shortage;
+        self
+    }
+
+    /// Build a circuit now
+    pub fn build(&mut self, lottery: &mut RelayLottery) -> Result<CircuitId> {
+        self.build_at(lottery, Instant::now())
+    }
+
+    /// Build a circuit at `now` and start its lifetime
+    pub fn build_at(&mut self, lottery: &mut RelayLottery, now: Instant) -> Result<CircuitId> {
+        let path = lottery.select_path(self.hops, self.on_shortage)?;
+        let circuit_id = self.next_id;
+        self.next_id += 1;
+        self.paths.insert(circuit_id, path);
+        self.lifetimes.open_at(circuit_id, now);
+        Ok(circuit_id)
+    }
+
+    /// Hops of a live circuit
+    pub fn path(&self, circuit_id: CircuitId) -> Option<&[SocketAddr]> {
+        self.paths.get(&circuit_id).map(Vec::as_slice)
+    }
+
+    /// Number of live circuits
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Check if no circuits are live
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Tear down a circuit closed by its user
+    pub fn teardown(&mut self, circuit_id: CircuitId) -> bool {
+        self.lifetimes.close(circuit_id);
+        self.paths.remove(&circuit_id).is_some()
+    }
+
+    /// Replace every circuit past its lifetime
+    pub fn rotate_expired(
+        &mut self,
+        lottery: &mut RelayLottery,
+    ) -> Result<Vec<(CircuitRotation, CircuitId)>> {
+        self.rotate_expired_at(lottery, Instant::now())
+    }
+
+    /// Replace every circuit past its lifetime as of `now`
+    ///
+    /// Each retired circuit is torn down and paired with the id of its
+    /// replacement. If a rebuild fails the remaining retired circuits stay
+    /// torn down and the error is returned.
+    pub fn rotate_expired_at(
+        &mut self,
+        lottery: &mut RelayLottery,
+        now: Instant,
+    ) -> Result<Vec<(CircuitRotation, CircuitId)>> {
+        let rotations = self.lifetimes.expire_at(now);
+        for rotation in &rotations {
+            self.paths.remove(&rotation.circuit_id);
+        }
+        rotations
+            .into_iter()
+            .map(|rotation| Ok((rotation, self.build_at(lottery, now)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_after_lifetime() {
+        let start = Instant::now();
+        let mut tracker = CircuitLifetimeTracker::new(Duration::from_secs(60));
+        tracker.open_at(1, start);
+        tracker.open_at(2, start + Duration::from_secs(30));
+
+        assert!(tracker.expire_at(start + Duration::from_secs(59)).is_empty());
+
+        let rotations = tracker.expire_at(start + Duration::from_secs(61));
+        assert_eq!(
+            rotations,
+            vec![CircuitRotation {
+                circuit_id: 1,
+                age: Duration::from_secs(61)
+            }]
+        );
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(
+            tracker.age_at(2, start + Duration::from_secs(61)),
+            Some(Duration::from_secs(31))
+        );
+
+        // A closed circuit is never rotated
+        assert!(tracker.close(2));
+        assert!(tracker.expire_at(start + Duration::from_secs(600)).is_empty());
+    }
+
+    #[test]
+    fn test_manager_rebuilds_expired_circuits() {
+        use crate::core::relay_lottery::WeightedRelay;
+
+        let mut lottery = RelayLottery::new();
+        for port in 9000..9005 {
+            let addr = format!("127.0.0.1:{}", port).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 0.9, 0.8, 1000));
+        }
+        let start = Instant::now();
+        let lifetimes = CircuitLifetimeTracker::new(Duration::from_secs(60));
+        let mut manager = CircuitManager::new(3, lifetimes);
+
+        let kept = manager.build_at(&mut lottery, start).unwrap();
+        let closed = manager.build_at(&mut lottery, start).unwrap();
+        assert_eq!(manager.path(kept).unwrap().len(), 3);
+        assert!(manager.teardown(closed));
+        assert!(manager.path(closed).is_none());
+
+        let later = start + Duration::from_secs(61);
+        let rotated = manager.rotate_expired_at(&mut lottery, later).unwrap();
+        assert_eq!(rotated.len(), 1);
+        let (rotation, replacement) = &rotated[0];
+        assert_eq!(rotation.circuit_id, kept);
+        assert!(manager.path(kept).is_none());
+        assert_eq!(manager.path(*replacement).unwrap().len(), 3);
+        assert_eq!(manager.len(), 1);
+
+        // The replacement starts a fresh lifetime
+        assert!(manager.rotate_expired_at(&mut lottery, later).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_manager_uses_configured_layers_and_lifetime() {
+        let config = MixnodeConfig::default();
+        let manager = CircuitManager::from_config(&config);
+        assert_eq!(manager.hops, config.layers as usize);
+        assert_eq!(manager.lifetimes.max_age(), config.max_circuit_lifetime);
+    }
+}
@@ -170,13 +170,7 @@ impl TranslationContext {
 
     /// Check if feature is available in negotiated context
     pub fn has_feature(&self, feature: Feature) -> bool {
-        match feature {
-            Feature::RelayLottery => self.negotiated_features.relay_lottery,
-            Feature::VrfDelays => self.negotiated_features.vrf_delays,
-            Feature::CoverTraffic => self.negotiated_features.cover_traffic,
-            Feature::BatchProcessing => self.negotiated_features.batch_processing,
-            Feature::EnhancedSphinx => self.negotiated_features.enhanced_sphinx,
-        }
+        self.negotiated_features.has(feature)
     }
 
     /// Get packet adapter for this context
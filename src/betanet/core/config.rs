@@ -6,6 +6,9 @@ use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::server::tcp::{BufferStrategy, MAX_FRAME_LEN};
+use crate::utils::delay::{DelayOverflowPolicy, MixStrategy};
+
 /// Mixnode configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MixnodeConfig {
@@ -42,8 +45,68 @@ pub struct MixnodeConfig {
     /// Connection timeout
     pub connection_timeout: Duration,
 
-    /// Network buffer size
+    /// Close connections that deliver no packet for this long (if set)
+    ///
+    /// Unlike `connection_timeout`, which bounds a single read, this runs
+    /// from the last complete non-empty packet, so keep-alive frames and
+    /// partial reads don't hold a connection open.
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: Option<Duration>,
+
+    /// Network buffer size; must hold a length-prefixed packet of
+    /// `MAX_PACKET_SIZE` bytes
     pub buffer_size: usize,
+
+    /// How connection read buffers grow and shrink
+    #[serde(default)]
+    pub buffer_strategy: BufferStrategy,
+
+    /// Where to write the final report on shutdown (if set)
+    #[serde(default)]
+    pub shutdown_report_path: Option<PathBuf>,
+
+    /// Maximum circuit age before forced rotation
+    #[serde(default = "default_max_circuit_lifetime")]
+    pub max_circuit_lifetime: Duration,
+
+    /// Maximum number of packets held in the delay queue at once
+    #[serde(default = "default_max_delayed_packets")]
+    pub max_delayed_packets: usize,
+
+    /// What to do with packets arriving while the delay queue is full
+    #[serde(default)]
+    pub delay_overflow_policy: DelayOverflowPolicy,
+
+    /// How the delay queue mixes packets before forwarding
+    #[serde(default)]
+    pub mix_strategy: MixStrategy,
+
+    /// Longest a packet may spend inside the node, from arrival to
+    /// forwarding (if set)
+    ///
+    /// Covers queueing, crypto and the mixing delay. Packets past it when
+    /// they come up for forwarding are dropped instead of sent late.
+    #[serde(default)]
+    pub max_node_latency: Option<Duration>,
+
+    /// Write forwarded packets to their next hop over TCP
+    ///
+    /// Probes each hop's MTU before first use and fragments packets that
+    /// don't fit. Off, forwarding stops once the next hop is chosen.
+    #[serde(default)]
+    pub transmit_to_next_hop: bool,
+}
+
+fn default_max_circuit_lifetime() -> Duration {
+    crate::core::circuits::DEFAULT_MAX_CIRCUIT_LIFETIME
+}
+
+fn default_idle_timeout() -> Option<Duration> {
+    Some(Duration::from_secs(300))
+}
+
+fn default_max_delayed_packets() -> usize {
+    65_536
 }
 
 impl Default for MixnodeConfig {
@@ -60,7 +123,16 @@ impl Default for MixnodeConfig {
             cover_traffic_interval: Duration::from_secs(10),
             max_queue_size: 1000,
             connection_timeout: Duration::from_secs(30),
+            idle_timeout: default_idle_timeout(),
             buffer_size: 8192,
+            buffer_strategy: BufferStrategy::default(),
+            shutdown_report_path: None,
+            max_circuit_lifetime: default_max_circuit_lifetime(),
+            max_delayed_packets: default_max_delayed_packets(),
+            delay_overflow_policy: DelayOverflowPolicy::default(),
+            mix_strategy: MixStrategy::default(),
+            max_node_latency: None,
+            transmit_to_next_hop: false,
         }
     }
 }
@@ -83,27 +155,215 @@ impl MixnodeConfig {
         Ok(())
     }
 
+    /// Start building a configuration from the defaults
+    pub fn builder() -> MixnodeConfigBuilder {
+        MixnodeConfigBuilder::default()
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> crate::Result<()> {
+        let errors = self.validation_errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::MixnodeError::Config(errors.join("; ")))
+        }
+    }
+
+    /// Every validation problem with this configuration
+    pub fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
         if self.layers == 0 {
-            return Err(crate::MixnodeError::Config(
-                "Layers must be > 0".to_string(),
-            ));
+            errors.push("Layers must be > 0".to_string());
         }
 
         if self.min_delay >= self.max_delay {
-            return Err(crate::MixnodeError::Config(
-                "min_delay must be < max_delay".to_string(),
-            ));
+            errors.push("min_delay must be < max_delay".to_string());
         }
 
         if self.max_queue_size == 0 {
-            return Err(crate::MixnodeError::Config(
-                "max_queue_size must be > 0".to_string(),
+            errors.push("max_queue_size must be > 0".to_string());
+        }
+
+        if self.idle_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            errors.push("idle_timeout must be > 0".to_string());
+        }
+
+        if self.max_circuit_lifetime.is_zero() {
+            errors.push("max_circuit_lifetime must be > 0".to_string());
+        }
+
+        if self.buffer_size < MAX_FRAME_LEN {
+            errors.push(format!(
+                "buffer_size must be >= {} (MAX_PACKET_SIZE + 4-byte length prefix)",
+                MAX_FRAME_LEN
             ));
         }
 
-        Ok(())
+        if let BufferStrategy::Adaptive { baseline: 0 } = self.buffer_strategy {
+            errors.push("Adaptive buffer baseline must be > 0".to_string());
+        }
+
+        if self.max_delayed_packets == 0 {
+            errors.push("max_delayed_packets must be > 0".to_string());
+        }
+
+        if self.mix_strategy == (MixStrategy::Timed { window: Duration::ZERO }) {
+            errors.push("Timed mix window must be > 0".to_string());
+        }
+
+        if self.max_node_latency.is_some_and(|budget| budget <= self.max_delay) {
+            errors.push("max_node_latency must be > max_delay".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Fluent builder for [`MixnodeConfig`] that validates on `build`
+#[derive(Debug, Clone, Default)]
+pub struct MixnodeConfigBuilder {
+    config: MixnodeConfig,
+}
+
+impl MixnodeConfigBuilder {
+    /// Listen address
+    pub fn listen_addr(mut self, addr: SocketAddr) -> Self {
+        self.config.listen_addr = addr;
+        self
+    }
+
+    /// Private key file path
+    pub fn private_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.private_key_file = Some(path.into());
+        self
+    }
+
+    /// Number of layers in the mix network
+    pub fn layers(mut self, layers: u8) -> Self {
+        self.config.layers = layers;
+        self
+    }
+
+    /// Enable Sphinx packet processing
+    pub fn enable_sphinx(mut self, enabled: bool) -> Self {
+        self.config.enable_sphinx = enabled;
+        self
+    }
+
+    /// Enable VRF-based delays
+    pub fn enable_vrf(mut self, enabled: bool) -> Self {
+        self.config.enable_vrf = enabled;
+        self
+    }
+
+    /// Enable cover traffic generation
+    pub fn enable_cover_traffic(mut self, enabled: bool) -> Self {
+        self.config.enable_cover_traffic = enabled;
+        self
+    }
+
+    /// Packet processing delay range
+    pub fn delay_range(mut self, min_delay: Duration, max_delay: Duration) -> Self {
+        self.config.min_delay = min_delay;
+        self.config.max_delay = max_delay;
+        self
+    }
+
+    /// Minimum delay for packet processing
+    pub fn min_delay(mut self, delay: Duration) -> Self {
+        self.config.min_delay = delay;
+        self
+    }
+
+    /// Maximum delay for packet processing
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.config.max_delay = delay;
+        self
+    }
+
+    /// Cover traffic interval
+    pub fn cover_traffic_interval(mut self, interval: Duration) -> Self {
+        self.config.cover_traffic_interval = interval;
+        self
+    }
+
+    /// Maximum packet queue size
+    pub fn max_queue_size(mut self, size: usize) -> Self {
+        self.config.max_queue_size = size;
+        self
+    }
+
+    /// Connection timeout
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connection_timeout = timeout;
+        self
+    }
+
+    /// Idle timeout
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Network buffer size
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.config.buffer_size = size;
+        self
+    }
+
+    /// Connection read buffer strategy
+    pub fn buffer_strategy(mut self, strategy: BufferStrategy) -> Self {
+        self.config.buffer_strategy = strategy;
+        self
+    }
+
+    /// Where to write the final report on shutdown
+    pub fn shutdown_report_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.shutdown_report_path = Some(path.into());
+        self
+    }
+
+    /// Maximum circuit age before forced rotation
+    pub fn max_circuit_lifetime(mut self, lifetime: Duration) -> Self {
+        self.config.max_circuit_lifetime = lifetime;
+        self
+    }
+
+    /// Delay queue cap and the policy applied once it is reached
+    pub fn delay_queue_limit(mut self, max_packets: usize, policy: DelayOverflowPolicy) -> Self {
+        self.config.max_delayed_packets = max_packets;
+        self.config.delay_overflow_policy = policy;
+        self
+    }
+
+    /// Strategy the delay queue mixes packets with
+    pub fn mix_strategy(mut self, strategy: MixStrategy) -> Self {
+        self.config.mix_strategy = strategy;
+        self
+    }
+
+    /// Longest a packet may spend inside the node before it is dropped
+    pub fn max_node_latency(mut self, budget: Duration) -> Self {
+        self.config.max_node_latency = Some(budget);
+        self
+    }
+
+    /// Write forwarded packets to their next hop over TCP
+    pub fn transmit_to_next_hop(mut self, enabled: bool) -> Self {
+        self.config.transmit_to_next_hop = enabled;
+        self
+    }
+
+    /// Validate and return the configuration, or every validation error
+    pub fn build(self) -> Result<MixnodeConfig, Vec<String>> {
+        let errors = self.config.validation_errors();
+        if errors.is_empty() {
+            Ok(self.config)
+        } else {
+            Err(errors)
+        }
     }
 }
 
@@ -120,6 +380,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_config_validation() {
         let mut config = MixnodeConfig::default();
         config.layers = 0;
@@ -129,5 +390,67 @@ mod tests {
         config.min_delay = Duration::from_secs(2);
         config.max_delay = Duration::from_secs(1);
         assert!(config.validate().is_err());
+
+        config.max_delay = Duration::from_secs(3);
+        config.idle_timeout = Some(Duration::ZERO);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_buffer_must_hold_max_packet() {
+        let config = MixnodeConfig {
+            buffer_size: crate::MAX_PACKET_SIZE,
+            ..Default::default()
+        };
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("buffer_size must be >= 2052"), "{}", errors[0]);
+
+        let config = MixnodeConfig {
+            buffer_size: MAX_FRAME_LEN,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_builder_builds_valid_config() {
+        let addr: SocketAddr = "0.0.0.0:9100".parse().unwrap();
+        let config = MixnodeConfig::builder()
+            .listen_addr(addr)
+            .layers(5)
+            .delay_range(Duration::from_millis(10), Duration::from_millis(50))
+            .enable_cover_traffic(true)
+            .max_queue_size(4096)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.listen_addr, addr);
+        assert_eq!(config.layers, 5);
+        assert_eq!(config.min_delay, Duration::from_millis(10));
+        assert!(config.enable_cover_traffic);
+        assert_eq!(config.max_queue_size, 4096);
+        // Untouched fields keep their defaults
+        assert_eq!(config.buffer_size, MixnodeConfig::default().buffer_size);
+    }
+
+    #[test]
+    fn test_builder_reports_every_error() {
+        let errors = MixnodeConfig::builder()
+            .layers(0)
+            .min_delay(Duration::from_secs(2))
+            .max_delay(Duration::from_secs(1))
+            .max_queue_size(0)
+            .build()
+            .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                "Layers must be > 0".to_string(),
+                "min_delay must be < max_delay".to_string(),
+                "max_queue_size must be > 0".to_string(),
+            ]
+        );
     }
 }
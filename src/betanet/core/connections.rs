@@ -0,0 +1,296 @@
+//! Live peer connection registry
+//!
+//! Tracks when each peer connection was established and whether it has
+//! stayed healthy, so long-lived stable connections can earn
+//! `ReputationAction::UptimeMilestone` rewards. Each connection also keeps
+//! the feature set negotiated in its handshake so feature use can be gated
+//! per peer, and the full handshake outcome for diagnostics.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::core::compatibility::Feature;
+use crate::core::protocol_version::{FeatureFlags, NegotiatedProtocol};
+use crate::core::reputation::{ReputationAction, ReputationManager};
+
+/// Default connection-age milestones: 1 hour, 6 hours, 1 day, 1 week
+pub const DEFAULT_UPTIME_MILESTONES: [Duration; 4] = [
+    Duration::from_secs(3600),
+    Duration::from_secs(6 * 3600),
+    Duration::from_secs(24 * 3600),
+    Duration::from_secs(7 * 24 * 3600),
+];
+
+#[derive(Debug, Clone)]
+struct ConnectionEntry {
+    /// Start of the current healthy stretch
+    healthy_since: Instant,
+    /// Milestones already rewarded for this stretch
+    milestones_awarded: usize,
+    /// Features negotiated with the peer, if known
+    features: Option<FeatureFlags>,
+    /// Full handshake outcome, if the connection was registered with it
+    negotiated: Option<NegotiatedProtocol>,
+}
+
+/// Milestone reward granted to a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MilestoneReward {
+    /// Rewarded peer
+    pub peer: SocketAddr,
+    /// Connection age that was reached
+    pub milestone: Duration,
+}
+
+/// Registry of healthy peer connections
+#[derive(Debug, Clone)]
+pub struct ConnectionRegistry {
+    connections: HashMap<SocketAddr, ConnectionEntry>,
+    milestones: Vec<Duration>,
+}
+
+impl ConnectionRegistry {
+    /// Create registry with the default milestones
+    pub fn new() -> Self {
+        Self::with_milestones(DEFAULT_UPTIME_MILESTONES.to_vec())
+    }
+
+    /// Create registry with custom milestones (sorted, duplicates removed)
+    pub fn with_milestones(mut milestones: Vec<Duration>) -> Self {
+        milestones.sort();
+        milestones.dedup();
+        Self {
+            connections: HashMap::new(),
+            milestones,
+        }
+    }
+
+    /// Record a newly established connection
+    pub fn register(&mut self, peer: SocketAddr) {
+        self.register_at(peer, Instant::now());
+    }
+
+    /// Record a connection established at `at`
+    ///
+    /// Re-registering a connected peer keeps its existing healthy stretch.
+    pub fn register_at(&mut self, peer: SocketAddr, at: Instant) {
+        self.connections.entry(peer).or_insert(ConnectionEntry {
+            healthy_since: at,
+            milestones_awarded: 0,
+            features: None,
+            negotiated: None,
+        });
+    }
+
+    /// Record a newly established connection with its negotiated features
+    pub fn register_with_features(&mut self, peer: SocketAddr, features: FeatureFlags) {
+        self.register_with_features_at(peer, features, Instant::now());
+    }
+
+    /// Record a connection established at `at` with its negotiated features
+    ///
+    /// The features always replace any previously stored set, since they
+    /// come from the latest handshake.
+    pub fn register_with_features_at(
+        &mut self,
+        peer: SocketAddr,
+        features: FeatureFlags,
+        at: Instant,
+    ) {
+        self.register_at(peer, at);
+        if let Some(entry) = self.connections.get_mut(&peer) {
+            entry.features = Some(features);
+        }
+    }
+
+    /// Record a newly established connection with its handshake outcome
+    pub fn register_negotiated(&mut self, peer: SocketAddr, negotiated: NegotiatedProtocol) {
+        self.register_with_features(peer, negotiated.features.clone());
+        if let Some(entry) = self.connections.get_mut(&peer) {
+            entry.negotiated = Some(negotiated);
+        }
+    }
+
+    /// Handshake outcome of a peer's connection
+    pub fn negotiated(&self, peer: &SocketAddr) -> Option<&NegotiatedProtocol> {
+        self.connections
+            .get(peer)
+            .and_then(|entry| entry.negotiated.as_ref())
+    }
+
+    /// Connections registered with their handshake outcome
+    pub fn negotiated_peers(&self) -> impl Iterator<Item = (SocketAddr, &NegotiatedProtocol)> {
+        self.connections
+            .iter()
+            .filter_map(|(peer, entry)| entry.negotiated.as_ref().map(|n| (*peer, n)))
+    }
+
+    /// Features negotiated with a peer
+    pub fn features(&self, peer: &SocketAddr) -> Option<&FeatureFlags> {
+        self.connections
+            .get(peer)
+            .and_then(|entry| entry.features.as_ref())
+    }
+
+    /// Check whether a feature may be used with a peer
+    ///
+    /// Unknown peers and peers without negotiated features support nothing.
+    pub fn peer_supports(&self, peer: &SocketAddr, feature: Feature) -> bool {
+        self.features(peer).is_some_and(|features| features.has(feature))
+    }
+
+    /// Restart a peer's healthy stretch after an error
+    pub fn mark_unhealthy(&mut self, peer: &SocketAddr) {
+        self.mark_unhealthy_at(peer, Instant::now());
+    }
+
+    /// Restart a peer's healthy stretch at `at`
+    pub fn mark_unhealthy_at(&mut self, peer: &SocketAddr, at: Instant) {
+        if let Some(entry) = self.connections.get_mut(peer) {
+            entry.healthy_since = at;
+            entry.milestones_awarded = 0;
+        }
+    }
+
+    /// Forget a closed connection
+    pub fn remove(&mut self, peer: &SocketAddr) {
+        self.connections.remove(peer);
+    }
+
+    /// Current healthy connection age of a peer
+    pub fn connection_age(&self, peer: &SocketAddr) -> Option<Duration> {
+        self.connections
+            .get(peer)
+            .map(|entry| entry.healthy_since.elapsed())
+    }
+
+    /// Number of tracked connections
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Check if no connections are tracked
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Reward every milestone crossed since the last call
+    pub fn reward_milestones(&mut self, reputation: &mut ReputationManager) -> Vec<MilestoneReward> {
+        self.reward_milestones_at(Instant::now(), reputation)
+    }
+
+    /// Reward every milestone crossed as of `now`
+    ///
+    /// Each milestone is rewarded at most once per healthy stretch.
+    pub fn reward_milestones_at(
+        &mut self,
+        now: Instant,
+        reputation: &mut ReputationManager,
+    ) -> Vec<MilestoneReward> {
+        let mut rewards = Vec::new();
+        for (peer, entry) in self.connections.iter_mut() {
+            let age = now.saturating_duration_since(entry.healthy_since);
+            while let Some(&milestone) = self.milestones.get(entry.milestones_awarded) {
+                if age < milestone {
+                    break;
+                }
+                entry.milestones_awarded += 1;
+
+                if reputation
+                    .update_reputation(peer, ReputationAction::UptimeMilestone)
+                    .is_ok()
+                {
+                    debug!("Peer {} reached {:?} connection milestone", peer, milestone);
+                    rewards.push(MilestoneReward {
+                        peer: *peer,
+                        milestone,
+                    });
+                }
+            }
+        }
+        rewards
+    }
+}
+
+impl Default for ConnectionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_gating_per_peer() {
+        let peer: SocketAddr = "127.0.0.1:9601".parse().unwrap();
+        let stranger: SocketAddr = "127.0.0.1:9602".parse().unwrap();
+        let mut registry = ConnectionRegistry::new();
+
+        registry.register(peer);
+        assert!(!registry.peer_supports(&peer, Feature::BatchProcessing));
+
+        let features = FeatureFlags {
+            vrf_delays: false,
+            ..FeatureFlags::v1_2_0()
+        };
+        registry.register_with_features(peer, features.clone());
+        assert_eq!(registry.features(&peer), Some(&features));
+        assert!(registry.peer_supports(&peer, Feature::BatchProcessing));
+        assert!(!registry.peer_supports(&peer, Feature::VrfDelays));
+        assert!(!registry.peer_supports(&stranger, Feature::BatchProcessing));
+    }
+
+    #[test]
+    fn test_milestone_rewarded_once() {
+        let peer: SocketAddr = "127.0.0.1:9600".parse().unwrap();
+        let mut reputation = ReputationManager::new();
+        reputation.add_node(peer, 1000);
+        let base_points = reputation.get_reputation_points(&peer);
+
+        let start = Instant::now();
+        let mut registry = ConnectionRegistry::with_milestones(vec![
+            Duration::from_secs(60),
+            Duration::from_secs(600),
+        ]);
+        registry.register_at(peer, start);
+
+        assert!(registry
+            .reward_milestones_at(start + Duration::from_secs(30), &mut reputation)
+            .is_empty());
+
+        let rewards = registry.reward_milestones_at(start + Duration::from_secs(61), &mut reputation);
+        assert_eq!(
+            rewards,
+            vec![MilestoneReward {
+                peer,
+                milestone: Duration::from_secs(60)
+            }]
+        );
+
+        // Same milestone isn't rewarded again
+        assert!(registry
+            .reward_milestones_at(start + Duration::from_secs(120), &mut reputation)
+            .is_empty());
+        let milestone_points = ReputationAction::UptimeMilestone.points_delta();
+        assert_eq!(
+            reputation.get_reputation_points(&peer),
+            base_points + milestone_points
+        );
+
+        // An unhealthy period restarts the stretch
+        registry.mark_unhealthy_at(&peer, start + Duration::from_secs(130));
+        assert!(registry
+            .reward_milestones_at(start + Duration::from_secs(660), &mut reputation)
+            .iter()
+            .all(|r| r.milestone == Duration::from_secs(60)));
+        assert_eq!(
+            reputation.get_reputation(&peer).unwrap().history.uptime_milestones,
+            2
+        );
+    }
+}
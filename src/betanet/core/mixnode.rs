@@ -1,29 +1,134 @@
 //! Standard mixnode implementation
 
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{broadcast, RwLock};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use crate::{
     core::config::MixnodeConfig,
     core::routing::RoutingTable,
-    utils::delay::DelayQueue,
-    utils::packet::{Packet, PacketType},
-    MixnodeError, MixnodeStats, MixnodeTrait, Result,
+    utils::delay::{DelayOverflow, DelayQueue},
+    utils::mtu::{ForwardPlan, FragmentReassembler, MtuCache},
+    utils::packet::{packet_trace_id, Packet, PacketType},
+    MetricsSink, MixnodeError, MixnodeStats, MixnodeTrait, Result,
 };
 
+/// Why a mixnode was stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownReason {
+    /// Operator or API requested stop
+    Requested,
+    /// Process received a termination signal
+    Signal,
+    /// Planned maintenance
+    Maintenance,
+    /// Unrecoverable error
+    Error(String),
+}
+
+impl std::fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShutdownReason::Requested => write!(f, "requested"),
+            ShutdownReason::Signal => write!(f, "signal"),
+            ShutdownReason::Maintenance => write!(f, "maintenance"),
+            ShutdownReason::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+/// Final summary produced when a mixnode stops
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    pub reason: ShutdownReason,
+    pub uptime_secs: u64,
+    pub packets_processed: u64,
+    pub packets_forwarded: u64,
+    pub packets_dropped: u64,
+    pub drop_reasons: std::collections::BTreeMap<String, u64>,
+    pub cover_traffic_sent: u64,
+    pub avg_processing_time_us: f64,
+}
+
+impl ShutdownReport {
+    /// Assemble a report from node statistics
+    pub fn from_stats(reason: ShutdownReason, stats: &MixnodeStats, uptime: Duration) -> Self {
+        Self {
+            reason,
+            uptime_secs: uptime.as_secs(),
+            packets_processed: stats.packets_processed,
+            packets_forwarded: stats.packets_forwarded,
+            packets_dropped: stats.packets_dropped,
+            drop_reasons: stats.drop_reasons.clone(),
+            cover_traffic_sent: stats.cover_traffic_sent,
+            avg_processing_time_us: stats.avg_processing_time_us,
+        }
+    }
+
+    /// Write the report as JSON
+    pub fn write_to_file(&self, path: &std::path::Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| MixnodeError::Config(format!("Failed to serialize report: {}", e)))?;
+        std::fs::write(path, contents).map_err(MixnodeError::Io)?;
+        Ok(())
+    }
+}
+
+/// Liveness summary answered to `health` control requests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MixnodeHealth {
+    /// Node is refusing new connections for maintenance
+    pub paused: bool,
+    /// Packets waiting in the delay queue
+    pub queued_packets: usize,
+    /// Seconds since the node was created
+    pub uptime_secs: u64,
+}
+
+/// Settings a node is actually running with
+///
+/// Holds the configuration with every default filled in, plus what the node
+/// derived from it and from the build, so a snapshot taken during an
+/// incident can be replayed exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    /// Mixnode protocol version
+    pub protocol_version: u8,
+    /// Optional features compiled into this build
+    pub features: Vec<String>,
+    /// Configuration as resolved at construction
+    pub config: MixnodeConfig,
+    /// Sphinx layers are peeled; off when disabled or not compiled in
+    pub sphinx_active: bool,
+    /// Delays are drawn from VRF output rather than uniformly
+    pub vrf_delays_active: bool,
+    /// Interval cover traffic is generated at, if it is generated
+    pub cover_traffic_interval: Option<Duration>,
+    /// Largest frame advertised to MTU probes
+    pub advertised_mtu: u32,
+    /// Node is refusing new connections
+    pub paused: bool,
+}
+
 /// Standard mixnode implementation
 pub struct StandardMixnode {
     config: MixnodeConfig,
     stats: Arc<RwLock<MixnodeStats>>,
+    /// Where packet events are recorded; `stats` unless replaced
+    metrics: Arc<RwLock<dyn MetricsSink>>,
     delay_queue: Arc<RwLock<DelayQueue>>,
     routing_table: Arc<RwLock<RoutingTable>>,
+    mtu_cache: Arc<RwLock<MtuCache>>,
     shutdown_tx: Option<broadcast::Sender<()>>,
+    paused: Arc<AtomicBool>,
     start_time: Instant,
 }
 
@@ -32,21 +137,134 @@ impl StandardMixnode {
     pub fn new(config: MixnodeConfig) -> Result<Self> {
         config.validate()?;
 
+        let delay_queue = DelayQueue::new()
+            .with_capacity_limit(config.max_delayed_packets, config.delay_overflow_policy)
+            .with_mix_strategy(config.mix_strategy);
+        let stats = Arc::new(RwLock::new(MixnodeStats::new()));
         Ok(Self {
             config,
-            stats: Arc::new(RwLock::new(MixnodeStats::new())),
-            delay_queue: Arc::new(RwLock::new(DelayQueue::new())),
+            metrics: stats.clone(),
+            stats,
+            delay_queue: Arc::new(RwLock::new(delay_queue)),
             routing_table: Arc::new(RwLock::new(RoutingTable::new())),
+            mtu_cache: Arc::new(RwLock::new(MtuCache::default())),
             shutdown_tx: None,
+            paused: Arc::new(AtomicBool::new(false)),
             start_time: Instant::now(),
         })
     }
 
+    /// Record packet events to `sink` instead of the built-in stats
+    ///
+    /// The stats behind [`MixnodeTrait::stats`] (and the shutdown report)
+    /// stop counting packets once replaced; wrap them in the sink to keep both.
+    pub fn with_metrics_sink(mut self, sink: Arc<RwLock<dyn MetricsSink>>) -> Self {
+        self.metrics = sink;
+        self
+    }
+
+    /// Stop taking new traffic without stopping the node
+    ///
+    /// New connections are closed as soon as they are accepted and cover
+    /// traffic is suppressed, while open connections keep being served and
+    /// the delay queue keeps forwarding what it already holds.
+    pub fn pause(&self) {
+        if !self.paused.swap(true, Ordering::SeqCst) {
+            info!("Mixnode paused");
+        }
+    }
+
+    /// Accept new connections and send cover traffic again
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            info!("Mixnode resumed");
+        }
+    }
+
+    /// Check if the node is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Snapshot of the settings in effect
+    pub fn effective_config(&self) -> EffectiveConfig {
+        let features = [
+            ("sphinx", cfg!(feature = "sphinx")),
+            ("vrf", cfg!(feature = "vrf")),
+            ("cover-traffic", cfg!(feature = "cover-traffic")),
+            ("debug-echo", cfg!(feature = "debug-echo")),
+        ];
+        EffectiveConfig {
+            protocol_version: crate::MIXNODE_VERSION,
+            features: features
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            config: self.config.clone(),
+            sphinx_active: cfg!(feature = "sphinx") && self.config.enable_sphinx,
+            vrf_delays_active: cfg!(feature = "vrf") && self.config.enable_vrf,
+            cover_traffic_interval: self
+                .config
+                .enable_cover_traffic
+                .then_some(self.config.cover_traffic_interval),
+            advertised_mtu: self.config.buffer_size as u32,
+            paused: self.is_paused(),
+        }
+    }
+
+    /// Current liveness summary
+    pub async fn health(&self) -> MixnodeHealth {
+        MixnodeHealth {
+            paused: self.is_paused(),
+            queued_packets: self.delay_queue.read().await.size(),
+            uptime_secs: self.start_time.elapsed().as_secs(),
+        }
+    }
+
+    /// Stop the node and produce a final report
+    ///
+    /// The report is logged and, if `shutdown_report_path` is configured,
+    /// written to that file.
+    pub async fn stop_with_reason(&mut self, reason: ShutdownReason) -> Result<ShutdownReport> {
+        self.stop().await?;
+
+        let report = {
+            let stats = self.stats.read().await;
+            ShutdownReport::from_stats(reason, &stats, self.start_time.elapsed())
+        };
+
+        info!(
+            reason = %report.reason,
+            uptime_secs = report.uptime_secs,
+            packets_processed = report.packets_processed,
+            packets_forwarded = report.packets_forwarded,
+            packets_dropped = report.packets_dropped,
+            drop_reasons = ?report.drop_reasons,
+            avg_processing_time_us = report.avg_processing_time_us,
+            "Mixnode shutdown report"
+        );
+
+        if let Some(path) = &self.config.shutdown_report_path {
+            report.write_to_file(path)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Per-hop MTU cache used by the forward path
+    pub fn mtu_cache(&self) -> Arc<RwLock<MtuCache>> {
+        Arc::clone(&self.mtu_cache)
+    }
+
     /// Handle incoming connection
     async fn handle_connection(&self, stream: TcpStream, peer_addr: SocketAddr) -> Result<()> {
         debug!("Handling connection from {}", peer_addr);
 
         let mut buffer = vec![0u8; self.config.buffer_size];
+        // Frames may arrive split across reads or several to a read
+        let mut pending = Vec::new();
+        let mut reassembler = FragmentReassembler::default();
 
         loop {
             match tokio::time::timeout(self.config.connection_timeout, stream.readable()).await {
@@ -56,29 +274,11 @@ impl StandardMixnode {
                         break;
                     }
                     Ok(n) => {
-                        let packet = Packet::parse(&buffer[..n])?;
-                        if packet.header.packet_type == PacketType::Control
-                            && packet.payload.as_ref() == b"stats"
-                        {
-                            let mut stats = self.stats.read().await.clone();
-                            stats.uptime_secs = self.start_time.elapsed().as_secs();
-                            let json = serde_json::to_vec(&stats)
-                                .map_err(|e| MixnodeError::Network(e.to_string()))?;
-                            let response = Packet::control(Bytes::from(json)).encode()?;
-                            stream.writable().await.map_err(MixnodeError::Io)?;
-                            stream.try_write(&response).map_err(MixnodeError::Io)?;
-                        } else {
-                            let start_time = Instant::now();
-
-                            if let Some(processed) = self.process_packet(&buffer[..n]).await? {
-                                let delay = self.calculate_delay().await;
-                                let mut delay_queue = self.delay_queue.write().await;
-                                delay_queue.add_packet(processed, delay).await;
-                            }
-
-                            let processing_time = start_time.elapsed();
-                            let mut stats = self.stats.write().await;
-                            stats.record_processed(processing_time);
+                        pending.extend_from_slice(&buffer[..n]);
+                        while let Some(len) = Packet::frame_len(&pending)? {
+                            let frame: Vec<u8> = pending.drain(..len).collect();
+                            self.handle_frame(&stream, &frame, peer_addr, &mut reassembler)
+                                .await?;
                         }
                     }
                     Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
@@ -101,6 +301,53 @@ impl StandardMixnode {
         Ok(())
     }
 
+    /// Answer a control frame, or take in a data frame or fragment
+    async fn handle_frame(
+        &self,
+        stream: &TcpStream,
+        frame: &[u8],
+        peer_addr: SocketAddr,
+        reassembler: &mut FragmentReassembler,
+    ) -> Result<()> {
+        let packet = Packet::parse(frame)?;
+        if packet.header.packet_type == PacketType::Control
+            && packet.payload.as_ref() == b"stats"
+        {
+            let mut stats = self.stats.read().await.clone();
+            stats.uptime_secs = self.start_time.elapsed().as_secs();
+            let json = serde_json::to_vec(&stats)
+                .map_err(|e| MixnodeError::Network(e.to_string()))?;
+            let response = Packet::control(Bytes::from(json)).encode()?;
+            stream.writable().await.map_err(MixnodeError::Io)?;
+            stream.try_write(&response).map_err(MixnodeError::Io)?;
+        } else if packet.header.packet_type == PacketType::Control
+            && packet.payload.as_ref() == b"health"
+        {
+            let json = serde_json::to_vec(&self.health().await)
+                .map_err(|e| MixnodeError::Network(e.to_string()))?;
+            let response = Packet::control(Bytes::from(json)).encode()?;
+            stream.writable().await.map_err(MixnodeError::Io)?;
+            stream.try_write(&response).map_err(MixnodeError::Io)?;
+        } else if packet.header.packet_type == PacketType::Control
+            && packet.payload.as_ref() == b"mtu"
+        {
+            // MTU probe: advertise the largest frame we accept
+            let mtu = self.config.buffer_size as u32;
+            let response =
+                Packet::control(Bytes::copy_from_slice(&mtu.to_be_bytes())).encode()?;
+            stream.writable().await.map_err(MixnodeError::Io)?;
+            stream.try_write(&response).map_err(MixnodeError::Io)?;
+        } else if packet.is_fragment() {
+            if let Some(whole) = reassembler.add_fragment(peer_addr, &packet.payload)? {
+                debug!("Reassembled {} byte packet from {}", whole.len(), peer_addr);
+                self.ingest(&whole).await?;
+            }
+        } else {
+            self.ingest(frame).await?;
+        }
+        Ok(())
+    }
+
     /// Calculate packet delay
     async fn calculate_delay(&self) -> Duration {
         #[cfg(feature = "vrf")]
@@ -130,60 +377,233 @@ impl StandardMixnode {
             return;
         }
 
-        let stats = Arc::clone(&self.stats);
+        let metrics = Arc::clone(&self.metrics);
+        let paused = Arc::clone(&self.paused);
         let interval = self.config.cover_traffic_interval;
 
         tokio::spawn(async move {
             let mut ticker = tokio::time::interval(interval);
             loop {
                 ticker.tick().await;
+                if paused.load(Ordering::SeqCst) {
+                    continue;
+                }
 
                 // Generate and send cover traffic
                 debug!("Generating cover traffic");
 
                 // Update statistics
-                let mut stats = stats.write().await;
-                stats.record_cover_traffic();
+                metrics.write().await.record_cover_traffic();
             }
         });
     }
 
+    /// Process an incoming packet and queue it for delayed forwarding
+    ///
+    /// Opens the `packet` span (anonymized id, no payload) that the
+    /// `process`, `delay` and `forward` spans of this packet nest under.
+    async fn ingest(&self, data: &[u8]) -> Result<()> {
+        let id = format!("{:016x}", packet_trace_id(data));
+        let packet_span = info_span!("packet", id = %id, bytes = data.len());
+        let start_time = Instant::now();
+        let deadline = self.config.max_node_latency.map(|budget| start_time + budget);
+
+        let processed = self
+            .process_packet(data)
+            .instrument(info_span!(parent: &packet_span, "process"))
+            .await?;
+        if let Some(processed) = processed {
+            let delay = self.calculate_delay().await;
+            let delay_span = info_span!(
+                parent: &packet_span,
+                "delay",
+                delay_ms = delay.as_millis() as u64
+            );
+            let overflow = self
+                .delay_queue
+                .write()
+                .await
+                .add_packet_arrived(processed, delay, start_time)
+                .instrument(delay_span)
+                .await;
+            match overflow {
+                Some(DelayOverflow::ReleasedEarly(packet, span)) => {
+                    let (routing, mtu) = (&self.routing_table, &self.mtu_cache);
+                    let transmit = self.transmit_timeout();
+                    Self::forward(packet, deadline, routing, mtu, &self.metrics, transmit)
+                        .instrument(info_span!(parent: &span, "forward"))
+                        .await;
+                }
+                Some(DelayOverflow::Dropped(reason)) => {
+                    self.metrics.write().await.record_dropped(reason);
+                }
+                None => {}
+            }
+        }
+
+        let processing_time = start_time.elapsed();
+        self.metrics.write().await.record_processed(processing_time);
+        Ok(())
+    }
+
+    /// How long each step of a transmission may take, if the node writes
+    /// forwarded packets to their next hop at all
+    fn transmit_timeout(&self) -> Option<Duration> {
+        self.config
+            .transmit_to_next_hop
+            .then_some(self.config.connection_timeout)
+    }
+
+    /// Route a delayed packet to its next hop
+    ///
+    /// A packet that has overstayed its latency budget (`deadline`) is
+    /// dropped here rather than sent late. With `transmit` set, the packet is
+    /// written to the hop, fragmented to the hop's probed MTU.
+    async fn forward(
+        packet: Vec<u8>,
+        deadline: Option<Instant>,
+        routing_table: &RwLock<RoutingTable>,
+        mtu_cache: &RwLock<MtuCache>,
+        metrics: &RwLock<dyn MetricsSink>,
+        transmit: Option<Duration>,
+    ) {
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            if now > deadline {
+                warn!("Packet {:?} past its latency budget, dropping", now - deadline);
+                metrics.write().await.record_dropped("latency_budget_exceeded");
+                return;
+            }
+        }
+        debug!("Forwarding delayed packet");
+
+        // Parse packet to get routing info
+        if let Ok(parsed_packet) = Packet::parse(&packet) {
+            let next_hop = routing_table.read().await.get_next_hop(&parsed_packet).await;
+            if let Some(next_hop) = next_hop {
+                // The connection probes the hop's MTU, so open it before planning
+                let mut stream = None;
+                if let Some(timeout) = transmit {
+                    match Self::with_timeout(timeout, Self::connect_hop(next_hop, mtu_cache)).await
+                    {
+                        Ok(connected) => stream = Some(connected),
+                        Err(e) => {
+                            warn!("Cannot reach {}: {}", next_hop, e);
+                            metrics.write().await.record_dropped("send_failed");
+                            return;
+                        }
+                    }
+                }
+
+                // Forward to next hop, fragmenting for small-MTU hops
+                let plan = mtu_cache.write().await.plan(&next_hop, &packet);
+                match plan {
+                    Ok(plan) => {
+                        debug!("Forwarding to {} in {} frame(s)", next_hop, plan.frame_count());
+                        if let (Some(stream), Some(timeout)) = (stream.as_mut(), transmit) {
+                            let written =
+                                Self::with_timeout(timeout, Self::write_plan(stream, &plan)).await;
+                            if let Err(e) = written {
+                                warn!("Failed to send to {}: {}", next_hop, e);
+                                metrics.write().await.record_dropped("send_failed");
+                                return;
+                            }
+                        }
+                        metrics.write().await.record_forwarded();
+                    }
+                    Err(e) => {
+                        warn!("Cannot fit packet to {}: {}", next_hop, e);
+                        metrics.write().await.record_dropped("mtu_exceeded");
+                    }
+                }
+            } else {
+                warn!("No route found for packet");
+
+                metrics.write().await.record_dropped("no_route");
+            }
+        }
+    }
+
+    /// Run `step`, failing if it takes longer than `timeout`
+    async fn with_timeout<T>(
+        timeout: Duration,
+        step: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        tokio::time::timeout(timeout, step)
+            .await
+            .map_err(|_| MixnodeError::Network(format!("Timed out after {:?}", timeout)))?
+    }
+
+    /// Connect to a next hop, probing its MTU unless the cache has it fresh
+    async fn connect_hop(hop: SocketAddr, mtu_cache: &RwLock<MtuCache>) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect(hop).await.map_err(MixnodeError::Io)?;
+        if mtu_cache.read().await.needs_probe(&hop) {
+            let probe = Packet::control(Bytes::from_static(b"mtu")).encode()?;
+            stream.write_all(&probe).await.map_err(MixnodeError::Io)?;
+            let response = read_frame(&mut stream).await?;
+            let mtu: [u8; 4] = response.payload.as_ref().try_into().map_err(|_| {
+                MixnodeError::Protocol(format!("Invalid MTU probe response from {}", hop))
+            })?;
+            let mtu = u32::from_be_bytes(mtu) as usize;
+            debug!("{} advertised MTU {}", hop, mtu);
+            mtu_cache.write().await.record_probe(hop, mtu);
+        }
+        Ok(stream)
+    }
+
+    /// Write every frame of a forward plan to the hop
+    async fn write_plan(stream: &mut TcpStream, plan: &ForwardPlan) -> Result<()> {
+        for frame in plan.frames() {
+            stream.write_all(frame).await.map_err(MixnodeError::Io)?;
+        }
+        stream.flush().await.map_err(MixnodeError::Io)
+    }
+
     /// Process packets from delay queue
     async fn process_delay_queue(&self, mut shutdown_rx: broadcast::Receiver<()>) {
         let delay_queue = Arc::clone(&self.delay_queue);
         let routing_table = Arc::clone(&self.routing_table);
-        let stats = Arc::clone(&self.stats);
+        let mtu_cache = Arc::clone(&self.mtu_cache);
+        let metrics = Arc::clone(&self.metrics);
+        let max_node_latency = self.config.max_node_latency;
+        let transmit = self.transmit_timeout();
 
         tokio::spawn(async move {
             let mut ticker = tokio::time::interval(Duration::from_millis(10));
             loop {
                 tokio::select! {
                     _ = ticker.tick() => {
-                        let packet = {
+                        // Drain everything due, so a closed timed-mix window
+                        // goes out together rather than one packet per tick
+                        let ready = {
                             let mut queue = delay_queue.write().await;
-                            queue.pop_ready().await
+                            let mut ready = Vec::new();
+                            while let Some(entry) = queue.pop_ready_timed().await {
+                                ready.push(entry);
+                            }
+                            ready
                         };
 
-                        if let Some(packet) = packet {
-                            // Forward the packet
-                            debug!("Forwarding delayed packet");
-
-                            // Parse packet to get routing info
-                            if let Ok(parsed_packet) = Packet::parse(&packet) {
-                                let routing = routing_table.read().await;
-                                if let Some(next_hop) = routing.get_next_hop(&parsed_packet).await {
-                                    // Forward to next hop
-                                    debug!("Forwarding to {}", next_hop);
-
-                                    let mut stats = stats.write().await;
-                                    stats.record_forwarded();
-                                } else {
-                                    warn!("No route found for packet");
-
-                                    let mut stats = stats.write().await;
-                                    stats.record_dropped();
+                        // Forward concurrently, so one slow hop doesn't hold up the rest
+                        for (packet, span, arrived_at) in ready {
+                            let deadline = max_node_latency.map(|budget| arrived_at + budget);
+                            let routing_table = Arc::clone(&routing_table);
+                            let mtu_cache = Arc::clone(&mtu_cache);
+                            let metrics = Arc::clone(&metrics);
+                            tokio::spawn(
+                                async move {
+                                    Self::forward(
+                                        packet,
+                                        deadline,
+                                        &routing_table,
+                                        &mtu_cache,
+                                        &metrics,
+                                        transmit,
+                                    )
+                                    .await
                                 }
-                            }
+                                .instrument(info_span!(parent: &span, "forward")),
+                            );
                         }
                     }
                     _ = shutdown_rx.recv() => {
@@ -218,8 +638,11 @@ impl MixnodeTrait for StandardMixnode {
         tokio::spawn({
             let config = self.config.clone();
             let stats = Arc::clone(&self.stats);
+            let metrics = Arc::clone(&self.metrics);
             let delay_queue = Arc::clone(&self.delay_queue);
             let routing_table = Arc::clone(&self.routing_table);
+            let mtu_cache = Arc::clone(&self.mtu_cache);
+            let paused = Arc::clone(&self.paused);
             let start_time = self.start_time;
             let mut shutdown_rx = shutdown_tx.subscribe();
 
@@ -229,14 +652,22 @@ impl MixnodeTrait for StandardMixnode {
                         result = listener.accept() => {
                             match result {
                                 Ok((stream, addr)) => {
+                                    if paused.load(Ordering::SeqCst) {
+                                        debug!("Paused, refusing connection from {}", addr);
+                                        drop(stream);
+                                        continue;
+                                    }
                                     debug!("Accepted connection from {}", addr);
 
                                     let mixnode = StandardMixnode {
                                         config: config.clone(),
                                         stats: Arc::clone(&stats),
+                                        metrics: Arc::clone(&metrics),
                                         delay_queue: Arc::clone(&delay_queue),
                                         routing_table: Arc::clone(&routing_table),
+                                        mtu_cache: Arc::clone(&mtu_cache),
                                         shutdown_tx: None,
+                                        paused: Arc::clone(&paused),
                                         start_time,
                                     };
 
@@ -276,15 +707,33 @@ impl MixnodeTrait for StandardMixnode {
     async fn process_packet(&self, packet: &[u8]) -> Result<Option<Vec<u8>>> {
         debug!("Processing packet of {} bytes", packet.len());
 
-        let _parsed_packet = Packet::parse(packet)?;
+        let mut parsed_packet = Packet::parse(packet)?;
+        if !parsed_packet.header.take_hop() {
+            debug!("Dropping packet with exhausted hop TTL");
+            self.metrics.write().await.record_dropped("ttl_expired");
+            return Ok(None);
+        }
 
         #[cfg(feature = "sphinx")]
         if self.config.enable_sphinx {
-            return crate::crypto::sphinx::process_sphinx_packet(&_parsed_packet).await;
+            let processed = crate::crypto::sphinx::process_sphinx_packet(&parsed_packet).await?;
+            // Re-frame the peeled layer, carrying the decremented TTL
+            return processed
+                .map(|payload| {
+                    let forwarded = Packet {
+                        header: crate::utils::packet::PacketHeader {
+                            length: payload.len() as u16,
+                            ..parsed_packet.header
+                        },
+                        payload: Bytes::from(payload),
+                    };
+                    forwarded.encode().map(|bytes| bytes.to_vec())
+                })
+                .transpose();
         }
 
-        // Fallback to simple forwarding
-        Ok(Some(packet.to_vec()))
+        // Fallback to simple forwarding with the decremented TTL
+        Ok(Some(parsed_packet.encode()?.to_vec()))
     }
 
     fn stats(&self) -> Arc<RwLock<MixnodeStats>> {
@@ -296,6 +745,22 @@ impl MixnodeTrait for StandardMixnode {
     }
 }
 
+/// Read one whole frame from a stream
+async fn read_frame(stream: &mut TcpStream) -> Result<Packet> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 512];
+    loop {
+        if let Some(len) = Packet::frame_len(&data)? {
+            return Packet::parse(&data[..len]);
+        }
+        let n = stream.read(&mut buf).await.map_err(MixnodeError::Io)?;
+        if n == 0 {
+            return Err(MixnodeError::Network("Connection closed mid-frame".to_string()));
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,6 +772,177 @@ mod tests {
         assert!(mixnode.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_packet_dropped_when_ttl_exhausted() {
+        let config = MixnodeConfig {
+            enable_sphinx: false,
+            ..Default::default()
+        };
+        let hops: Vec<StandardMixnode> = (0..5)
+            .map(|_| StandardMixnode::new(config.clone()).unwrap())
+            .collect();
+
+        let mut packet = Packet::data(Bytes::from("loop"), 1)
+            .with_ttl(3)
+            .encode()
+            .unwrap()
+            .to_vec();
+        let mut dropped_at = None;
+        for (hop, mixnode) in hops.iter().enumerate() {
+            match mixnode.process_packet(&packet).await.unwrap() {
+                Some(next) => packet = next,
+                None => {
+                    dropped_at = Some(hop);
+                    break;
+                }
+            }
+        }
+
+        // Three hops forward it, the fourth drops it
+        assert_eq!(dropped_at, Some(3));
+        let stats = hops[3].stats.read().await;
+        assert_eq!(stats.drop_reasons["ttl_expired"], 1);
+        assert_eq!(hops[2].stats.read().await.packets_dropped, 0);
+    }
+
+    #[derive(Clone)]
+    struct RecordedSpan {
+        name: String,
+        parent: Option<String>,
+        fields: Vec<String>,
+    }
+
+    /// Records each new span's name, parent name and field names
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<std::sync::Mutex<Vec<RecordedSpan>>>);
+
+    impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let span = ctx.span(id).unwrap();
+            self.0.lock().unwrap().push(RecordedSpan {
+                name: span.name().to_string(),
+                parent: span.parent().map(|p| p.name().to_string()),
+                fields: attrs.fields().iter().map(|f| f.name().to_string()).collect(),
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_packet_lifecycle_spans() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let config = MixnodeConfig {
+            enable_sphinx: false,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            ..Default::default()
+        };
+        let mixnode = StandardMixnode::new(config).unwrap();
+        let packet = Packet::data(Bytes::from_static(b"secret payload"), 1)
+            .encode()
+            .unwrap();
+        mixnode.ingest(&packet).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let (queued, span) = mixnode
+            .delay_queue
+            .write()
+            .await
+            .pop_ready_traced()
+            .await
+            .unwrap();
+        let (routing, mtu) = (&mixnode.routing_table, &mixnode.mtu_cache);
+        StandardMixnode::forward(queued, None, routing, mtu, &mixnode.metrics, None)
+            .instrument(info_span!(parent: &span, "forward"))
+            .await;
+
+        let spans = recorder.0.lock().unwrap().clone();
+        let parent_of = |name: &str| {
+            spans
+                .iter()
+                .find(|span| span.name == name)
+                .map(|span| span.parent.clone())
+                .unwrap_or_else(|| panic!("missing {} span", name))
+        };
+        assert_eq!(parent_of("packet"), None);
+        assert_eq!(parent_of("process"), Some("packet".to_string()));
+        assert_eq!(parent_of("delay"), Some("packet".to_string()));
+        assert_eq!(parent_of("forward"), Some("delay".to_string()));
+
+        // Only metadata is recorded, never the payload
+        let fields: Vec<&String> = spans.iter().flat_map(|span| &span.fields).collect();
+        assert!(fields
+            .iter()
+            .all(|f| ["id", "bytes", "delay_ms"].contains(&f.as_str())));
+    }
+
+    #[test]
+    fn test_effective_config_reflects_overrides() {
+        use crate::utils::delay::{DelayOverflowPolicy, MixStrategy};
+
+        // Partial file: everything left out takes its default
+        let config: MixnodeConfig = serde_json::from_value(serde_json::json!({
+            "listen_addr": "127.0.0.1:9411",
+            "private_key_file": null,
+            "layers": 5,
+            "enable_sphinx": false,
+            "enable_vrf": true,
+            "enable_cover_traffic": true,
+            "min_delay": { "secs": 0, "nanos": 20_000_000 },
+            "max_delay": { "secs": 0, "nanos": 80_000_000 },
+            "cover_traffic_interval": { "secs": 2, "nanos": 0 },
+            "max_queue_size": 500,
+            "connection_timeout": { "secs": 5, "nanos": 0 },
+            "buffer_size": 4096,
+            "delay_overflow_policy": "release_soonest",
+        }))
+        .unwrap();
+        let mixnode = StandardMixnode::new(config).unwrap();
+        mixnode.pause();
+
+        let snapshot = mixnode.effective_config();
+        assert_eq!(snapshot.config.layers, 5);
+        assert_eq!(snapshot.config.min_delay, Duration::from_millis(20));
+        assert_eq!(snapshot.config.delay_overflow_policy, DelayOverflowPolicy::ReleaseSoonest);
+        // Defaults the file never mentioned are spelled out
+        assert_eq!(snapshot.config.idle_timeout, Some(Duration::from_secs(300)));
+        assert_eq!(snapshot.config.max_delayed_packets, 65_536);
+        assert_eq!(snapshot.config.mix_strategy, MixStrategy::Continuous);
+        // Derived settings
+        assert!(!snapshot.sphinx_active);
+        assert_eq!(snapshot.vrf_delays_active, cfg!(feature = "vrf"));
+        assert_eq!(snapshot.cover_traffic_interval, Some(Duration::from_secs(2)));
+        assert_eq!(snapshot.advertised_mtu, 4096);
+        assert!(snapshot.paused);
+        assert_eq!(snapshot.features.contains(&"vrf".to_string()), cfg!(feature = "vrf"));
+
+        // The snapshot round-trips, and its config rebuilds the same node
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: EffectiveConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.config.max_queue_size, 500);
+        let replayed = StandardMixnode::new(restored.config).unwrap();
+        assert_eq!(
+            serde_json::to_value(&replayed.effective_config().config).unwrap(),
+            serde_json::to_value(&snapshot.config).unwrap()
+        );
+        assert!(!replayed.effective_config().paused);
+
+        let defaults = StandardMixnode::new(MixnodeConfig::default()).unwrap();
+        assert_eq!(defaults.effective_config().cover_traffic_interval, None);
+    }
+
     #[tokio::test]
     async fn test_delay_calculation() {
         let config = MixnodeConfig::default();
@@ -326,4 +962,311 @@ mod tests {
         let stats = stats_handle.read().await;
         assert_eq!(stats.packets_processed, 0);
     }
+
+    #[tokio::test]
+    async fn test_forward_plan_respects_hop_mtu() {
+        let mixnode = StandardMixnode::new(MixnodeConfig::default()).unwrap();
+        let hop: SocketAddr = "127.0.0.1:9201".parse().unwrap();
+
+        let cache = mixnode.mtu_cache();
+        cache.write().await.record_probe(hop, 256);
+
+        let packet = Packet::data(Bytes::from(vec![1u8; 1000]), 0).encode().unwrap();
+        let plan = cache.write().await.plan(&hop, &packet).unwrap();
+        assert!(plan.frame_count() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_packet_fragmented_and_delivered_intact() {
+        let relay_config = |listen_addr| MixnodeConfig {
+            listen_addr,
+            enable_sphinx: false,
+            enable_vrf: false,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            transmit_to_next_hop: true,
+            ..Default::default()
+        };
+
+        // Exit hop: answers the MTU probe, then hands back what it receives
+        let exit = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let exit_addr = exit.local_addr().unwrap();
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = exit.accept().await.unwrap();
+            let probe = read_frame(&mut stream).await.unwrap();
+            assert_eq!(probe.payload.as_ref(), b"mtu");
+            let mtu = Packet::control(Bytes::copy_from_slice(&4096u32.to_be_bytes()));
+            stream.write_all(&mtu.encode().unwrap()).await.unwrap();
+            read_frame(&mut stream).await.unwrap()
+        });
+
+        // Middle hop: a running node that reassembles and forwards
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let middle_addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let mut middle = StandardMixnode::new(relay_config(middle_addr)).unwrap();
+        middle.routing_table.write().await.add_route(1, vec![exit_addr]);
+        middle.start().await.unwrap();
+
+        // Entry hop: knows the middle hop only takes 256-byte frames
+        let entry_addr = "127.0.0.1:0".parse().unwrap();
+        let mut entry = StandardMixnode::new(relay_config(entry_addr)).unwrap();
+        entry.routing_table.write().await.add_route(1, vec![middle_addr]);
+        entry.mtu_cache().write().await.record_probe(middle_addr, 256);
+
+        let payload: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let packet = Packet::data(Bytes::from(payload.clone()), 1).encode().unwrap();
+        let frames = entry.mtu_cache().write().await.plan(&middle_addr, &packet).unwrap();
+        assert!(frames.frame_count() > 1);
+
+        entry.ingest(&packet).await.unwrap();
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        entry.shutdown_tx = Some(shutdown_tx);
+        entry.process_delay_queue(shutdown_rx).await;
+
+        let delivered = tokio::time::timeout(Duration::from_secs(5), received)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!delivered.is_fragment());
+        assert_eq!(delivered.payload.as_ref(), payload.as_slice());
+        assert_eq!(delivered.header.ttl, crate::utils::packet::DEFAULT_HOP_TTL - 2);
+        // Forwards are counted once the write completes
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(entry.stats.read().await.packets_forwarded, 1);
+        let middle_stats = middle.stats.read().await.clone();
+        assert_eq!(middle_stats.packets_processed, 1);
+        assert_eq!(middle_stats.packets_forwarded, 1);
+        // The middle hop learned the exit hop's MTU from its probe
+        let cache = middle.mtu_cache();
+        assert!(!cache.read().await.needs_probe(&exit_addr));
+        assert_eq!(cache.read().await.mtu_for(&exit_addr), 4096);
+
+        entry.stop().await.unwrap();
+        middle.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_with_reason_report() {
+        let report_path = std::env::temp_dir().join(format!(
+            "betanet-shutdown-{}.json",
+            std::process::id()
+        ));
+        let config = MixnodeConfig {
+            shutdown_report_path: Some(report_path.clone()),
+            ..Default::default()
+        };
+        let mut mixnode = StandardMixnode::new(config).unwrap();
+
+        {
+            let stats_handle = mixnode.stats();
+            let mut stats = stats_handle.write().await;
+            for _ in 0..5 {
+                stats.record_processed(Duration::from_micros(50));
+            }
+            stats.record_forwarded();
+            stats.record_forwarded();
+            stats.record_dropped_with_reason("no_route");
+            stats.record_dropped_with_reason("no_route");
+            stats.record_dropped_with_reason("mtu_exceeded");
+        }
+
+        let report = mixnode
+            .stop_with_reason(ShutdownReason::Maintenance)
+            .await
+            .unwrap();
+
+        assert_eq!(report.reason, ShutdownReason::Maintenance);
+        assert_eq!(report.packets_processed, 5);
+        assert_eq!(report.packets_forwarded, 2);
+        assert_eq!(report.packets_dropped, 3);
+        assert_eq!(report.drop_reasons["no_route"], 2);
+        assert_eq!(report.drop_reasons["mtu_exceeded"], 1);
+        assert_eq!(report.avg_processing_time_us, 50.0);
+
+        let written: ShutdownReport =
+            serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert_eq!(written.packets_dropped, 3);
+        let _ = std::fs::remove_file(&report_path);
+    }
+
+    async fn query_health(stream: &mut TcpStream) -> MixnodeHealth {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let request = Packet::control(Bytes::from_static(b"health")).encode().unwrap();
+        stream.write_all(&request).await.unwrap();
+        let mut buf = vec![0u8; 2048];
+        let n = stream.read(&mut buf).await.unwrap();
+        serde_json::from_slice(&Packet::parse(&buf[..n]).unwrap().payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pause_refuses_connections_but_forwards_queue() {
+        use tokio::io::AsyncReadExt;
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let config = MixnodeConfig {
+            listen_addr: addr,
+            enable_sphinx: false,
+            enable_vrf: false,
+            enable_cover_traffic: true,
+            cover_traffic_interval: Duration::from_millis(10),
+            min_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(110),
+            ..Default::default()
+        };
+        let mut mixnode = StandardMixnode::new(config).unwrap();
+        let next_hop: SocketAddr = "127.0.0.1:9301".parse().unwrap();
+        mixnode.routing_table.write().await.add_route(1, vec![next_hop]);
+        mixnode.start().await.unwrap();
+
+        let mut held = TcpStream::connect(addr).await.unwrap();
+        assert!(!query_health(&mut held).await.paused);
+        let packet = Packet::data(Bytes::from_static(b"in flight"), 1).encode().unwrap();
+        mixnode.ingest(&packet).await.unwrap();
+
+        mixnode.pause();
+        assert!(mixnode.is_paused());
+        // Let a cover tick that was already under way finish
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let cover_at_pause = mixnode.stats.read().await.cover_traffic_sent;
+
+        // New connections are closed straight away
+        let mut refused = TcpStream::connect(addr).await.unwrap();
+        let mut buf = [0u8; 16];
+        let read = tokio::time::timeout(Duration::from_secs(1), refused.read(&mut buf))
+            .await
+            .unwrap();
+        assert!(matches!(read, Ok(0) | Err(_)));
+
+        // The open connection is still served and reports the pause
+        let health = query_health(&mut held).await;
+        assert!(health.paused);
+
+        // The queued packet still goes out, but no cover traffic does
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        {
+            let stats = mixnode.stats.read().await;
+            assert_eq!(stats.packets_forwarded, 1);
+            assert_eq!(stats.cover_traffic_sent, cover_at_pause);
+        }
+        assert_eq!(mixnode.health().await.queued_packets, 0);
+
+        mixnode.resume();
+        let mut fresh = TcpStream::connect(addr).await.unwrap();
+        assert!(!query_health(&mut fresh).await.paused);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(mixnode.stats.read().await.cover_traffic_sent > cover_at_pause);
+
+        mixnode.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_packet_over_latency_budget_dropped() {
+        let config = MixnodeConfig {
+            enable_sphinx: false,
+            enable_vrf: false,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            max_node_latency: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let mut mixnode = StandardMixnode::new(config).unwrap();
+        let next_hop: SocketAddr = "127.0.0.1:9401".parse().unwrap();
+        mixnode.routing_table.write().await.add_route(1, vec![next_hop]);
+
+        let packet = Packet::data(Bytes::from_static(b"on time"), 1).encode().unwrap();
+        mixnode.ingest(&packet).await.unwrap();
+        // Stand-in for a packet stuck 200ms in crypto before it was queued
+        let stalled_since = Instant::now()
+            .checked_sub(Duration::from_millis(200))
+            .unwrap();
+        mixnode
+            .delay_queue
+            .write()
+            .await
+            .add_packet_arrived(packet.to_vec(), Duration::from_millis(1), stalled_since)
+            .await;
+
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        mixnode.shutdown_tx = Some(shutdown_tx);
+        mixnode.process_delay_queue(shutdown_rx).await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let stats = mixnode.stats.read().await;
+        assert_eq!(stats.packets_forwarded, 1);
+        assert_eq!(stats.packets_dropped, 1);
+        assert_eq!(stats.drop_reasons.get("latency_budget_exceeded"), Some(&1));
+        drop(stats);
+        mixnode.stop().await.unwrap();
+    }
+
+    /// Sink that only remembers which calls it received
+    #[derive(Default)]
+    struct RecordingSink(Vec<String>);
+
+    impl MetricsSink for RecordingSink {
+        fn record_processed(&mut self, _processing_time: Duration) {
+            self.0.push("processed".to_string());
+        }
+
+        fn record_forwarded(&mut self) {
+            self.0.push("forwarded".to_string());
+        }
+
+        fn record_dropped(&mut self, reason: &str) {
+            self.0.push(format!("dropped:{}", reason));
+        }
+
+        fn record_cover_traffic(&mut self) {
+            self.0.push("cover".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_metrics_sink_receives_packet_events() {
+        let config = MixnodeConfig {
+            enable_sphinx: false,
+            enable_vrf: false,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..Default::default()
+        };
+        let sink = Arc::new(RwLock::new(RecordingSink::default()));
+        let mut mixnode = StandardMixnode::new(config)
+            .unwrap()
+            .with_metrics_sink(sink.clone());
+        let next_hop: SocketAddr = "127.0.0.1:9501".parse().unwrap();
+        mixnode.routing_table.write().await.add_route(1, vec![next_hop]);
+
+        let routed = Packet::data(Bytes::from_static(b"routed"), 1).encode().unwrap();
+        let expired = Packet::data(Bytes::from_static(b"expired"), 1)
+            .with_ttl(0)
+            .encode()
+            .unwrap();
+        mixnode.ingest(&routed).await.unwrap();
+        mixnode.ingest(&expired).await.unwrap();
+
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        mixnode.shutdown_tx = Some(shutdown_tx);
+        mixnode.process_delay_queue(shutdown_rx).await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        assert_eq!(
+            sink.read().await.0,
+            vec!["processed", "dropped:ttl_expired", "processed", "forwarded"]
+        );
+        // The built-in stats are bypassed
+        assert_eq!(mixnode.stats.read().await.packets_processed, 0);
+        mixnode.stop().await.unwrap();
+    }
 }
@@ -2,8 +2,12 @@
 //!
 //! Contains the main mixnode implementation, configuration, and routing logic.
 
+pub mod blocklist;
+pub mod circuits;
 pub mod mixnode;
+pub mod probe;
 pub mod config;
+pub mod connections;
 pub mod routing;
 pub mod protocol_version;
 pub mod relay_lottery;
@@ -11,15 +15,30 @@ pub mod reputation;
 pub mod compatibility;
 pub mod versions;
 
-pub use mixnode::StandardMixnode;
-pub use config::MixnodeConfig;
+pub use mixnode::{EffectiveConfig, MixnodeHealth, StandardMixnode};
+pub use circuits::{CircuitId, CircuitLifetimeTracker, CircuitManager, CircuitRotation};
+pub use config::{MixnodeConfig, MixnodeConfigBuilder};
+pub use connections::{ConnectionRegistry, MilestoneReward};
+pub use probe::{ProbeConfig, ProbeOutcome, ProbeScheduler, ProbeTransport, TcpProbeTransport};
 pub use routing::RoutingTable;
-pub use protocol_version::{ProtocolVersion, NegotiationResult, FeatureFlags, ProtocolAdvertisement};
-pub use relay_lottery::{RelayLottery, WeightedRelay, LotteryProof, LotteryStatistics};
+pub use protocol_version::{
+    ProtocolVersion, NegotiationResult, NegotiatedProtocol, FeatureFlags, ProtocolAdvertisement,
+    ConnectionRole,
+};
+pub use relay_lottery::{
+    RelayLottery, WeightedRelay, LotteryProof, LotteryStatistics, StakeNormalization,
+    LotteryState, OnShortage, WeightBounds, ProofVerification, DiversityObjective,
+    ManipulationGuard, WeightFreeze,
+    DiverseCircuit,
+};
 pub use reputation::{
     NodeReputation, ReputationManager, PenaltyType, RewardType, PerformanceMetrics,
     ReputationAction, ReputationHistory, ReputationStatistics,
-    ReputationPoints, CostOfForgery
+    ReputationPoints, CostOfForgery, ReputationChange, ReputationPolicy, DecayModel,
+    DecayMode, RelayReputationMetric, ReputationGossipLimits, DecayAnchor, ReputationEvent,
+    MergeStrategy
 };
 pub use compatibility::{PacketAdapter, TranslationContext, Feature};
-pub use versions::{VersionRegistry, VersionMetadata, DeprecationTimeline};
\ No newline at end of file
+pub use versions::{
+    DeprecationPolicy, DeprecationStatus, DeprecationTimeline, VersionMetadata, VersionRegistry,
+};
\ No newline at end of file
@@ -0,0 +1,289 @@
+//! Relay performance probing
+//!
+//! Claimed relay performance is only checked against real forwards, so a
+//! relay that is rarely selected keeps a stale score. The probe scheduler
+//! periodically pings a random sample of relays and feeds the measured
+//! latency and outcome into the lottery's measurement and reputation path.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use tokio::sync::broadcast;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+use crate::core::relay_lottery::SharedRelayLottery;
+use crate::server::tcp::TcpClient;
+use crate::Result;
+
+/// Way of reaching a relay for a probe
+#[async_trait::async_trait]
+pub trait ProbeTransport: Send + Sync {
+    /// Probe `relay`, returning the round-trip time on success
+    async fn probe(&self, relay: SocketAddr, timeout: Duration) -> Result<Duration>;
+}
+
+/// Probe over TCP using [`TcpClient`]
+///
+/// Measures the time to connect, send the probe payload and read the
+/// relay's first frame back.
+#[derive(Debug, Clone)]
+pub struct TcpProbeTransport {
+    payload: Vec<u8>,
+}
+
+impl TcpProbeTransport {
+    /// Create transport sending `payload` as the probe
+    pub fn new(payload: Vec<u8>) -> Self {
+        Self { payload }
+    }
+}
+
+impl Default for TcpProbeTransport {
+    fn default() -> Self {
+        Self::new(vec![0u8; 16])
+    }
+}
+
+#[async_trait::async_trait]
+impl ProbeTransport for TcpProbeTransport {
+    async fn probe(&self, relay: SocketAddr, timeout: Duration) -> Result<Duration> {
+        let client = TcpClient::new(relay);
+        let started = Instant::now();
+        client.send_packet_with_timeout(&self.payload, timeout).await?;
+        Ok(started.elapsed())
+    }
+}
+
+/// Probe scheduling settings
+#[derive(Debug, Clone)]
+pub struct ProbeConfig {
+    /// Time between probe rounds
+    pub interval: Duration,
+    /// Relays probed per round
+    pub sample_size: usize,
+    /// Per-probe timeout; a timed-out probe counts as a failure
+    pub timeout: Duration,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            sample_size: 8,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Result of probing one relay
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeOutcome {
+    /// Probed relay
+    pub relay: SocketAddr,
+    /// Round-trip time (the timeout for failed probes)
+    pub latency: Duration,
+    /// Whether the relay answered
+    pub success: bool,
+}
+
+/// Periodically probes relays and records their measured performance
+pub struct ProbeScheduler {
+    lottery: SharedRelayLottery,
+    transport: Arc<dyn ProbeTransport>,
+    config: ProbeConfig,
+}
+
+impl ProbeScheduler {
+    /// Create scheduler probing relays of `lottery` over `transport`
+    pub fn new(
+        lottery: SharedRelayLottery,
+        transport: Arc<dyn ProbeTransport>,
+        config: ProbeConfig,
+    ) -> Self {
+        Self {
+            lottery,
+            transport,
+            config,
+        }
+    }
+
+    /// Create scheduler probing over TCP
+    pub fn with_tcp(lottery: SharedRelayLottery, config: ProbeConfig) -> Self {
+        Self::new(lottery, Arc::new(TcpProbeTransport::default()), config)
+    }
+
+    /// Scheduling settings
+    pub fn config(&self) -> &ProbeConfig {
+        &self.config
+    }
+
+    /// Probe one random sample of relays and record the results
+    ///
+    /// Probes run concurrently; the lottery is only locked to pick the
+    /// sample and to record the outcomes.
+    pub async fn probe_round(&self) -> Vec<ProbeOutcome> {
+        let sample = {
+            let mut addresses = self.lottery.lock().await.relay_addresses();
+            addresses.shuffle(&mut rand::thread_rng());
+            addresses.truncate(self.config.sample_size);
+            addresses
+        };
+
+        let mut probes = JoinSet::new();
+        for relay in sample {
+            let transport = Arc::clone(&self.transport);
+            let timeout = self.config.timeout;
+            probes.spawn(async move {
+                match tokio::time::timeout(timeout, transport.probe(relay, timeout)).await {
+                    Ok(Ok(latency)) => ProbeOutcome {
+                        relay,
+                        latency,
+                        success: true,
+                    },
+                    Ok(Err(e)) => {
+                        debug!("Probe to {} failed: {}", relay, e);
+                        ProbeOutcome {
+                            relay,
+                            latency: timeout,
+                            success: false,
+                        }
+                    }
+                    Err(_) => ProbeOutcome {
+                        relay,
+                        latency: timeout,
+                        success: false,
+                    },
+                }
+            });
+        }
+
+        let mut outcomes = Vec::new();
+        while let Some(joined) = probes.join_next().await {
+            match joined {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => warn!("Probe task failed: {}", e),
+            }
+        }
+        outcomes.sort_by_key(|outcome| outcome.relay);
+
+        let mut lottery = self.lottery.lock().await;
+        for outcome in &outcomes {
+            lottery.record_forward(&outcome.relay, outcome.latency, outcome.success);
+            lottery.update_relay_reputation(&outcome.relay, outcome.success);
+        }
+        lottery.verify_claimed_performance();
+
+        outcomes
+    }
+
+    /// Run probe rounds every `interval` until `shutdown` fires
+    pub async fn run(&self, mut shutdown: broadcast::Receiver<()>) {
+        let mut ticker = tokio::time::interval(self.config.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let outcomes = self.probe_round().await;
+                    debug!("Probed {} relays", outcomes.len());
+                }
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::relay_lottery::{RelayLottery, WeightedRelay};
+    use crate::MixnodeError;
+    use std::collections::HashMap;
+
+    /// Answers probes with fixed latencies; unknown relays fail
+    struct FixedLatencies(HashMap<SocketAddr, Duration>);
+
+    #[async_trait::async_trait]
+    impl ProbeTransport for FixedLatencies {
+        async fn probe(&self, relay: SocketAddr, _timeout: Duration) -> Result<Duration> {
+            self.0
+                .get(&relay)
+                .copied()
+                .ok_or_else(|| MixnodeError::Network("unreachable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_round_updates_measured_performance() {
+        let fast: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let slow: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+        let dead: SocketAddr = "10.0.0.3:9000".parse().unwrap();
+
+        let mut lottery = RelayLottery::new();
+        for addr in [fast, slow, dead] {
+            lottery.add_relay(WeightedRelay::new(addr, 0.5, 0.9, 1000));
+        }
+        let lottery = SharedRelayLottery::new(lottery);
+
+        let transport = FixedLatencies(HashMap::from([
+            (fast, Duration::from_millis(20)),
+            (slow, Duration::from_millis(150)),
+        ]));
+        let scheduler = ProbeScheduler::new(
+            lottery.clone(),
+            Arc::new(transport),
+            ProbeConfig {
+                sample_size: 3,
+                timeout: Duration::from_millis(200),
+                ..Default::default()
+            },
+        );
+
+        let outcomes = scheduler.probe_round().await;
+        assert_eq!(outcomes.len(), 3);
+        assert!(!outcomes.iter().find(|o| o.relay == dead).unwrap().success);
+
+        let guard = lottery.lock().await;
+        let measured = |addr| guard.get_relay(&addr).unwrap().measured.clone().unwrap();
+        assert_eq!(measured(fast).avg_latency_ms, 20.0);
+        assert_eq!(measured(slow).avg_latency_ms, 150.0);
+        assert!((measured(fast).score() - 0.9).abs() < 1e-9);
+        assert!((measured(slow).score() - 0.25).abs() < 1e-9);
+        assert_eq!(measured(dead).score(), 0.0);
+
+        // The reputation path moves with the outcome too
+        assert!(guard.get_relay(&fast).unwrap().reputation > 0.5);
+        assert!(guard.get_relay(&dead).unwrap().reputation < 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_probe_round_samples_subset() {
+        let mut lottery = RelayLottery::new();
+        let mut latencies = HashMap::new();
+        for i in 0..10 {
+            let addr: SocketAddr = format!("10.0.1.{}:9000", i).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 0.5, 0.5, 1000));
+            latencies.insert(addr, Duration::from_millis(10));
+        }
+        let lottery = SharedRelayLottery::new(lottery);
+        let scheduler = ProbeScheduler::new(
+            lottery.clone(),
+            Arc::new(FixedLatencies(latencies)),
+            ProbeConfig {
+                sample_size: 4,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(scheduler.probe_round().await.len(), 4);
+        let guard = lottery.lock().await;
+        let probed = guard
+            .relay_addresses()
+            .iter()
+            .filter(|addr| guard.get_relay(addr).unwrap().measured.is_some())
+            .count();
+        assert_eq!(probed, 4);
+    }
+}
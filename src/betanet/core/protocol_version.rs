@@ -6,6 +6,14 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::core::compatibility::Feature;
+
+/// Handshake advertisement size cap for v1.0 peers (bytes)
+pub const BASE_ADVERTISEMENT_CAP: usize = 1024;
+
+/// Absolute advertisement size cap for any version (bytes)
+pub const MAX_ADVERTISEMENT_CAP: usize = 16 * 1024;
+
 /// Betanet protocol version following semantic versioning (MAJOR.MINOR.PATCH).
 ///
 /// Protocol versions determine compatibility between mixnodes in the Betanet network.
@@ -175,6 +183,26 @@ impl ProtocolVersion {
     pub fn to_protocol_id(&self) -> String {
         format!("/betanet/mix/{}.{}.{}", self.major, self.minor, self.patch)
     }
+
+    /// Maximum handshake advertisement size accepted from a peer at this version.
+    ///
+    /// Doubles with each minor version as feature sets grow (v1.0 = 1 KiB,
+    /// v1.2 = 4 KiB, v1.3 = 8 KiB), never exceeding [`MAX_ADVERTISEMENT_CAP`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use betanet::core::protocol_version::ProtocolVersion;
+    ///
+    /// assert_eq!(ProtocolVersion::new(1, 0, 0).max_advertisement_size(), 1024);
+    /// assert_eq!(ProtocolVersion::V1_2_0.max_advertisement_size(), 4096);
+    /// ```
+    pub fn max_advertisement_size(&self) -> usize {
+        if self.major != 1 {
+            return BASE_ADVERTISEMENT_CAP;
+        }
+        (BASE_ADVERTISEMENT_CAP << self.minor.min(4)).min(MAX_ADVERTISEMENT_CAP)
+    }
 }
 
 impl fmt::Display for ProtocolVersion {
@@ -284,6 +312,17 @@ impl FeatureFlags {
             && (!other.enhanced_sphinx || self.enhanced_sphinx)
     }
 
+    /// Check whether a single feature is enabled
+    pub fn has(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::RelayLottery => self.relay_lottery,
+            Feature::VrfDelays => self.vrf_delays,
+            Feature::CoverTraffic => self.cover_traffic,
+            Feature::BatchProcessing => self.batch_processing,
+            Feature::EnhancedSphinx => self.enhanced_sphinx,
+        }
+    }
+
     /// Get intersection of two feature sets (common features)
     pub fn intersect(&self, other: &Self) -> Self {
         Self {
@@ -302,6 +341,43 @@ impl Default for FeatureFlags {
     }
 }
 
+/// Purpose of a connection, declared by both sides in the handshake
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionRole {
+    /// Sphinx packet forwarding
+    ///
+    /// Payloads are already encrypted and look random, so compressing them
+    /// only costs CPU.
+    #[default]
+    Forwarding,
+    /// Control and gossip messages, compressed when both sides support it
+    Control,
+}
+
+impl ConnectionRole {
+    fn is_forwarding(&self) -> bool {
+        *self == Self::Forwarding
+    }
+}
+
+/// Outcome of a completed version handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedProtocol {
+    /// Version both sides agreed on
+    pub version: ProtocolVersion,
+    /// Features both sides actually advertised
+    ///
+    /// A peer may claim a version without every feature that version
+    /// implies, so this is the intersection of the two advertisements
+    /// rather than `FeatureFlags::for_version(version)`.
+    pub features: FeatureFlags,
+    /// Connection role; `Control` only when both sides declared it
+    pub role: ConnectionRole,
+    /// Whether frames on this connection are compressed
+    pub compression: bool,
+}
+
 /// Protocol capabilities advertisement
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtocolAdvertisement {
@@ -313,24 +389,56 @@ pub struct ProtocolAdvertisement {
     pub capabilities: Vec<ProtocolCapability>,
     /// Node identifier
     pub node_id: String,
+    /// Role the sender wants for this connection
+    ///
+    /// Left off the wire for forwarding connections, so their advertisement
+    /// matches what older peers send.
+    #[serde(default, skip_serializing_if = "ConnectionRole::is_forwarding")]
+    pub role: ConnectionRole,
+    /// Whether the sender offers to compress frames
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compression: bool,
 }
 
 impl ProtocolAdvertisement {
-    /// Create new protocol advertisement
+    /// Create new protocol advertisement for a forwarding connection
     pub fn new(version: ProtocolVersion, node_id: String) -> Self {
         Self {
             features: FeatureFlags::for_version(&version),
             capabilities: vec![ProtocolCapability::l4_privacy_hop()],
             version,
             node_id,
+            role: ConnectionRole::Forwarding,
+            compression: false,
         }
     }
 
+    /// Declare the role of the connection this advertisement opens
+    ///
+    /// Control connections offer compression.
+    pub fn with_role(mut self, role: ConnectionRole) -> Self {
+        self.role = role;
+        self.compression = role == ConnectionRole::Control;
+        self
+    }
+
     /// Check compatibility with another advertisement
     pub fn is_compatible_with(&self, other: &Self) -> bool {
         self.version.is_compatible_with(&other.version)
     }
 
+    /// Role and compression setting agreed with `other`
+    ///
+    /// A connection is only treated as control traffic when both sides say
+    /// so, and only control connections are compressed.
+    pub fn negotiate_role(&self, other: &Self) -> (ConnectionRole, bool) {
+        if self.role == ConnectionRole::Control && other.role == ConnectionRole::Control {
+            (ConnectionRole::Control, self.compression && other.compression)
+        } else {
+            (ConnectionRole::Forwarding, false)
+        }
+    }
+
     /// Encode to bytes for handshake
     pub fn encode(&self) -> Result<Vec<u8>, String> {
         serde_json::to_vec(self).map_err(|e| format!("Failed to encode advertisement: {}", e))
@@ -402,6 +510,21 @@ mod tests {
         assert!(!v1_1.is_compatible_with(&v1_2));
     }
 
+    #[test]
+    fn test_advertisement_cap_grows_with_version() {
+        assert_eq!(ProtocolVersion::new(1, 0, 0).max_advertisement_size(), 1024);
+        assert_eq!(ProtocolVersion::V1_1_0.max_advertisement_size(), 2048);
+        assert_eq!(ProtocolVersion::new(1, 3, 0).max_advertisement_size(), 8192);
+        assert_eq!(
+            ProtocolVersion::new(1, 15, 0).max_advertisement_size(),
+            MAX_ADVERTISEMENT_CAP
+        );
+        assert_eq!(
+            ProtocolVersion::new(2, 0, 0).max_advertisement_size(),
+            BASE_ADVERTISEMENT_CAP
+        );
+    }
+
     #[test]
     fn test_protocol_id() {
         let v1_2 = ProtocolVersion::V1_2_0;
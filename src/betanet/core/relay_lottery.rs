@@ -10,10 +10,16 @@
 
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
+use rand::rngs::ThreadRng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::net::SocketAddr;
-
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::core::blocklist::SharedBlocklist;
+use crate::utils::epoch::EpochClock;
 use crate::{MixnodeError, Result};
 
 #[cfg(feature = "vrf")]
@@ -24,12 +30,13 @@ use sha2::{Digest, Sha256};
 // Import reputation module (stub implementation)
 #[allow(dead_code)]
 use crate::core::reputation::ReputationManager;
+use crate::core::reputation::ReputationPoints;
 
 /// Node reputation score (0.0 to 1.0)
 pub type ReputationScore = f64;
 
 /// Weighted relay for lottery selection
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WeightedRelay {
     /// Node address
     pub address: SocketAddr,
@@ -41,6 +48,304 @@ pub struct WeightedRelay {
     pub stake: u64,
     /// Combined weight for lottery
     pub weight: f64,
+    /// Performance measured from real forwards
+    #[serde(default)]
+    pub measured: Option<MeasuredPerformance>,
+    /// Stake score (0.0 to 1.0) assigned by the lottery's stake
+    /// normalization; `None` uses the default log scale
+    #[serde(default)]
+    pub stake_score: Option<f64>,
+    /// Hot spare: skipped by normal selection, drawn only by
+    /// `select_including_standby` once active relays run out
+    #[serde(default)]
+    pub standby: bool,
+    /// Latency class (e.g. region or datacenter) used to spread circuit
+    /// hops; untagged relays share one unknown class
+    #[serde(default)]
+    pub latency_class: Option<String>,
+    /// Autonomous system number, when known
+    #[serde(default)]
+    pub asn: Option<u32>,
+}
+
+/// How raw stake is mapped onto the [0, 1] stake term of the weight
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StakeNormalization {
+    /// `ln(stake) / divisor`, clamped to [0, 1]
+    Log {
+        /// Natural-log value that maps to a full stake score
+        divisor: f64,
+    },
+    /// `ln(1 + stake) / ln(1 + max_stake)` against the largest stake in
+    /// the lottery, so the top staker always scores 1.0
+    NetworkMax,
+}
+
+impl StakeNormalization {
+    /// Stake score for `stake` given the largest stake in the network
+    pub fn score(&self, stake: u64, max_stake: u64) -> f64 {
+        let score = match *self {
+            Self::Log { divisor } => (stake as f64).ln() / divisor,
+            Self::NetworkMax if max_stake == 0 => 0.0,
+            Self::NetworkMax => (stake as f64).ln_1p() / (max_stake as f64).ln_1p(),
+        };
+        if score.is_nan() {
+            0.0
+        } else {
+            score.clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl Default for StakeNormalization {
+    fn default() -> Self {
+        Self::Log { divisor: 20.0 }
+    }
+}
+
+/// Range selection weights are rescaled into before sampling
+///
+/// Weights are scaled so the largest maps to `max`. When that would push
+/// the smallest positive weight below `min`, weights are instead mapped
+/// log-linearly onto `[min, max]`, so the heaviest relay is at most
+/// `max / min` times as likely as the lightest one. Order is preserved
+/// either way and zero (ineligible) weights stay zero.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeightBounds {
+    /// Floor for any positive weight
+    pub min: f64,
+    /// Weight of the heaviest relay
+    pub max: f64,
+}
+
+impl Default for WeightBounds {
+    fn default() -> Self {
+        Self { min: 0.01, max: 1.0 }
+    }
+}
+
+impl WeightBounds {
+    /// Rescale `weights` in place; non-finite or non-positive weights
+    /// become zero
+    pub fn normalize(&self, weights: &mut [f64]) {
+        let usable = |w: f64| w.is_finite() && w > 0.0;
+        let (lo, hi) = weights
+            .iter()
+            .copied()
+            .filter(|&w| usable(w))
+            .fold((f64::INFINITY, 0.0f64), |(lo, hi), w| (lo.min(w), hi.max(w)));
+        let (min, max) = (self.min.min(self.max), self.max);
+
+        for w in weights.iter_mut() {
+            *w = if !usable(*w) {
+                0.0
+            } else if lo * (max / hi) >= min {
+                *w * (max / hi)
+            } else {
+                let t = (w.ln() - lo.ln()) / (hi.ln() - lo.ln());
+                (min.ln() + t * (max.ln() - min.ln())).exp()
+            };
+        }
+    }
+}
+
+/// What path selection does when fewer relays are eligible than hops
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OnShortage {
+    /// Fail the selection
+    #[default]
+    Error,
+    /// Reuse relays once every eligible relay holds a hop, never placing
+    /// the same relay on two adjacent hops
+    AllowReuseNonAdjacent,
+}
+
+/// How circuit diversity is scored
+///
+/// Each dimension scores 0.0 when every hop shares one value and 1.0 when
+/// all hops differ; the circuit score is their weighted mean. The AS
+/// dimension only counts when every hop has a known ASN.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DiversityObjective {
+    /// Weight of distinct IP subnets
+    pub subnet: f64,
+    /// Weight of distinct latency classes
+    pub latency_class: f64,
+    /// Weight of distinct autonomous systems
+    pub asn: f64,
+    /// Prefix length grouping IPv4 relays into one subnet
+    pub ipv4_prefix: u8,
+    /// Prefix length grouping IPv6 relays into one subnet
+    pub ipv6_prefix: u8,
+}
+
+impl Default for DiversityObjective {
+    fn default() -> Self {
+        Self {
+            subnet: 1.0,
+            latency_class: 1.0,
+            asn: 1.0,
+            ipv4_prefix: 24,
+            ipv6_prefix: 48,
+        }
+    }
+}
+
+impl DiversityObjective {
+    /// Diversity score (0.0 to 1.0) of a circuit through `hops`
+    pub fn score(&self, hops: &[&WeightedRelay]) -> f64 {
+        if hops.len() < 2 {
+            return 1.0;
+        }
+        let spread = |distinct: usize| (distinct - 1) as f64 / (hops.len() - 1) as f64;
+
+        let subnets: HashSet<IpAddr> = hops
+            .iter()
+            .map(|r| ip_prefix(r.address.ip(), self.ipv4_prefix, self.ipv6_prefix))
+            .collect();
+        let classes: HashSet<Option<&str>> =
+            hops.iter().map(|r| r.latency_class.as_deref()).collect();
+        let mut total = self.subnet * spread(subnets.len())
+            + self.latency_class * spread(classes.len());
+        let mut weight = self.subnet + self.latency_class;
+
+        if let Some(asns) = hops.iter().map(|r| r.asn).collect::<Option<HashSet<u32>>>() {
+            total += self.asn * spread(asns.len());
+            weight += self.asn;
+        }
+
+        if weight > 0.0 {
+            total / weight
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Circuit chosen for diversity
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiverseCircuit {
+    /// Hop addresses in order
+    pub hops: Vec<SocketAddr>,
+    /// Score the circuit achieved under the objective it was built with
+    pub diversity_score: f64,
+}
+
+/// `ip` with everything past the first `v4_bits` (IPv4) or `v6_bits` (IPv6)
+/// bits zeroed
+pub(crate) fn ip_prefix(ip: IpAddr, v4_bits: u8, v6_bits: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let bits = u32::from(v4);
+            let mask = u32::MAX.checked_shl(32 - u32::from(v4_bits.min(32))).unwrap_or(0);
+            IpAddr::from((bits & mask).to_be_bytes())
+        }
+        IpAddr::V6(v6) => {
+            let bits = u128::from(v6);
+            let mask = u128::MAX.checked_shl(128 - u32::from(v6_bits.min(128))).unwrap_or(0);
+            IpAddr::from((bits & mask).to_be_bytes())
+        }
+    }
+}
+
+/// Thresholds for spotting coordinated reputation gaming
+///
+/// Many large rewards landing on relays in one subnet at once look like
+/// colluding nodes vouching for each other. Once a subnet collects
+/// `max_correlated_rewards` of them within `window`, the lottery stops
+/// applying reputation to relay weights until the freeze is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ManipulationGuard {
+    /// Rewards worth at least this many points count toward a burst
+    pub min_reward_points: ReputationPoints,
+    /// Window rewards are counted over
+    pub window: Duration,
+    /// Rewards to one subnet within `window` that trigger a freeze
+    pub max_correlated_rewards: usize,
+    /// IPv4 prefix length grouping relays into a subnet
+    pub ipv4_prefix: u8,
+    /// IPv6 prefix length grouping relays into a subnet
+    pub ipv6_prefix: u8,
+}
+
+impl Default for ManipulationGuard {
+    fn default() -> Self {
+        Self {
+            min_reward_points: 20,
+            window: Duration::from_secs(10),
+            max_correlated_rewards: 5,
+            ipv4_prefix: 24,
+            ipv6_prefix: 48,
+        }
+    }
+}
+
+/// Why relay weight updates are frozen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightFreeze {
+    /// Subnet prefix the reward burst went to
+    pub subnet: IpAddr,
+    /// Large rewards the subnet collected within the window
+    pub rewards: usize,
+    /// When the freeze engaged
+    pub since: Instant,
+}
+
+/// Forwarding performance observed for a relay
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeasuredPerformance {
+    /// Number of observed forwards
+    pub samples: u64,
+    /// Success rate (exponential moving average)
+    pub success_rate: f64,
+    /// Latency in milliseconds (exponential moving average)
+    pub avg_latency_ms: f64,
+    /// Claimed minus measured performance at the last verification
+    pub divergence: f64,
+    /// Whether the relay is currently weighted by measured performance
+    pub penalized: bool,
+}
+
+impl MeasuredPerformance {
+    /// Record one forward
+    pub fn record(&mut self, latency_ms: f64, success: bool) {
+        const ALPHA: f64 = 0.1;
+        let outcome = if success { 1.0 } else { 0.0 };
+
+        if self.samples == 0 {
+            self.success_rate = outcome;
+            self.avg_latency_ms = latency_ms;
+        } else {
+            self.success_rate = ALPHA * outcome + (1.0 - ALPHA) * self.success_rate;
+            self.avg_latency_ms = ALPHA * latency_ms + (1.0 - ALPHA) * self.avg_latency_ms;
+        }
+        self.samples += 1;
+    }
+
+    /// Measured performance score (0.0 to 1.0)
+    pub fn score(&self) -> f64 {
+        // Same latency scale as reputation metrics: 0ms = 1.0, 200ms+ = 0.0
+        let latency_score = (1.0 - self.avg_latency_ms / 200.0).max(0.0);
+        self.success_rate * latency_score
+    }
+}
+
+/// Thresholds for comparing claimed and measured performance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceVerificationConfig {
+    /// Forwards required before a relay is judged
+    pub min_samples: u64,
+    /// Allowed claimed-minus-measured gap before down-weighting
+    pub tolerance: f64,
+}
+
+impl Default for PerformanceVerificationConfig {
+    fn default() -> Self {
+        Self {
+            min_samples: 20,
+            tolerance: 0.2,
+        }
+    }
 }
 
 impl WeightedRelay {
@@ -51,22 +356,81 @@ impl WeightedRelay {
         performance: f64,
         stake: u64,
     ) -> Self {
-        // Calculate combined weight:
-        // - 50% reputation (trust/reliability)
-        // - 30% performance (latency/bandwidth)
-        // - 20% stake (economic commitment)
-        let stake_score = (stake as f64).ln() / 20.0; // Log scale, normalized
-        let weight = reputation * 0.5 + performance * 0.3 + stake_score.min(1.0) * 0.2;
-
         Self {
             address,
             reputation,
             performance,
             stake,
-            weight: weight.max(0.01), // Minimum weight to prevent zero
+            weight: Self::compute_weight(reputation, performance, stake),
+            measured: None,
+            stake_score: None,
+            standby: false,
+            latency_class: None,
+            asn: None,
+        }
+    }
+
+    /// Tag relay with a latency class
+    pub fn with_latency_class(mut self, class: impl Into<String>) -> Self {
+        self.latency_class = Some(class.into());
+        self
+    }
+
+    /// Tag relay with its autonomous system number
+    pub fn with_asn(mut self, asn: u32) -> Self {
+        self.asn = Some(asn);
+        self
+    }
+
+    /// Mark relay as a standby hot spare
+    pub fn with_standby(mut self, standby: bool) -> Self {
+        self.standby = standby;
+        self
+    }
+
+    /// Combined lottery weight using the default stake normalization
+    pub fn compute_weight(reputation: ReputationScore, performance: f64, stake: u64) -> f64 {
+        let stake_score = StakeNormalization::default().score(stake, 0);
+        Self::weight_from_scores(reputation, performance, stake_score)
+    }
+
+    /// Combined lottery weight:
+    /// - 50% reputation (trust/reliability)
+    /// - 30% performance (latency/bandwidth)
+    /// - 20% stake (economic commitment)
+    pub fn weight_from_scores(reputation: ReputationScore, performance: f64, stake_score: f64) -> f64 {
+        let weight = reputation * 0.5 + performance * 0.3 + stake_score * 0.2;
+        weight.max(0.01) // Minimum weight to prevent zero
+    }
+
+    /// Stake term of the weight (0.0 to 1.0)
+    pub fn stake_score(&self) -> f64 {
+        self.stake_score
+            .unwrap_or_else(|| StakeNormalization::default().score(self.stake, 0))
+    }
+
+    /// Performance used for weighting: measured if the claim was found
+    /// to diverge, otherwise the claimed value
+    pub fn effective_performance(&self) -> f64 {
+        match &self.measured {
+            Some(measured) if measured.penalized => measured.score(),
+            _ => self.performance,
         }
     }
 
+    /// Recalculate weight from current reputation, performance and stake
+    pub fn recalculate_weight(&mut self) {
+        self.weight =
+            Self::weight_from_scores(self.reputation, self.effective_performance(), self.stake_score());
+    }
+
+    /// Record the outcome of a real forward through this relay
+    pub fn record_measurement(&mut self, latency_ms: f64, success: bool) {
+        self.measured
+            .get_or_insert_with(MeasuredPerformance::default)
+            .record(latency_ms, success);
+    }
+
     /// Update reputation based on recent performance
     pub fn update_reputation(&mut self, success: bool) {
         const ALPHA: f64 = 0.1; // Learning rate
@@ -79,13 +443,22 @@ impl WeightedRelay {
             self.reputation = (self.reputation - ALPHA * self.reputation).max(0.0);
         }
 
-        // Recalculate weight
-        let stake_score = (self.stake as f64).ln() / 20.0;
-        self.weight = self.reputation * 0.5 + self.performance * 0.3 + stake_score.min(1.0) * 0.2;
-        self.weight = self.weight.max(0.01);
+        self.recalculate_weight();
     }
 }
 
+/// Outcome of checking a lottery proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofVerification {
+    /// The VRF proof checked out
+    Verified,
+    /// The proof is malformed or its VRF proof failed
+    Invalid,
+    /// The proof is well-formed but could not be checked cryptographically,
+    /// e.g. in a build without the `vrf` feature
+    Unverifiable,
+}
+
 /// Lottery proof for verifiable randomness
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LotteryProof {
@@ -98,26 +471,135 @@ pub struct LotteryProof {
     pub selected: Vec<SocketAddr>,
     /// Selection weights used
     pub weights: Vec<f64>,
-    /// Timestamp of lottery draw
+    /// Timestamp of lottery draw; the start of the draw's epoch when the
+    /// lottery runs on an [`EpochClock`]
     pub timestamp: u64,
 }
 
 impl LotteryProof {
-    /// Verify the lottery proof is valid
+    /// Check the draw happened within `tolerance` epochs of the current
+    /// epoch on `clock`
+    pub fn is_within_epochs(&self, clock: &EpochClock, tolerance: u64) -> bool {
+        clock.epoch_at(self.timestamp).abs_diff(clock.current_epoch()) <= tolerance
+    }
+
+    /// Verify the draw's VRF proof over `seed` against `vrf_public_key` and
+    /// that `selected` is the draw that VRF output picks from `eligible`
+    ///
+    /// `eligible` is the drawing lottery's eligible relays, in lottery order.
+    /// Proofs without VRF bytes (draws made without a keypair), malformed
+    /// bytes, proofs that don't match the key and seed, and proofs whose
+    /// selection was edited all verify as `false`.
     #[cfg(feature = "vrf")]
-    pub fn verify(&self, _vrf_public_key: &[u8; 32]) -> Result<bool> {
-        // Implementation would verify VRF proof
-        // For now, just check basic validity
-        Ok(self.vrf_proof.is_some() && !self.seed.is_empty() && !self.selected.is_empty())
+    pub fn verify(&self, vrf_public_key: &[u8; 32], eligible: &[SocketAddr]) -> Result<bool> {
+        let Some(vrf_proof) = &self.vrf_proof else {
+            return Ok(false);
+        };
+        if self.seed.is_empty() || self.selected.is_empty() || eligible.is_empty() {
+            return Ok(false);
+        }
+        use crate::vrf::vrf_delay::verify_bytes_with_public_key;
+        let Some(io) = verify_bytes_with_public_key(vrf_public_key, &self.seed, vrf_proof) else {
+            return Ok(false);
+        };
+        Ok(vrf_selection(&io, eligible, self.selected.len()) == self.selected)
     }
 
+    /// Check the proof as far as a build without VRF support can
+    ///
+    /// Never reports [`ProofVerification::Verified`]: a well-formed proof is
+    /// [`ProofVerification::Unverifiable`], so callers can't mistake it for
+    /// one whose randomness was checked.
     #[cfg(not(feature = "vrf"))]
-    pub fn verify(&self) -> Result<bool> {
-        Ok(!self.seed.is_empty() && !self.selected.is_empty())
+    pub fn verify(&self) -> Result<ProofVerification> {
+        if self.seed.is_empty() || self.selected.is_empty() {
+            Ok(ProofVerification::Invalid)
+        } else {
+            Ok(ProofVerification::Unverifiable)
+        }
+    }
+}
+
+/// Relays a VRF output draws from `eligible`, one per derived index
+#[cfg(feature = "vrf")]
+fn vrf_selection(
+    io: &schnorrkel::vrf::VRFInOut,
+    eligible: &[SocketAddr],
+    count: usize,
+) -> Vec<SocketAddr> {
+    let base_random: [u8; 32] = io.make_bytes(b"lottery");
+    (0..count)
+        .map(|i| {
+            let mut hasher = Sha256::new();
+            hasher.update(base_random);
+            hasher.update(i.to_be_bytes());
+            let derived_random = hasher.finalize();
+
+            let mut random_bytes = [0u8; 8];
+            random_bytes.copy_from_slice(&derived_random[..8]);
+            let random_value = u64::from_be_bytes(random_bytes);
+            eligible[(random_value as usize) % eligible.len()]
+        })
+        .collect()
+}
+
+/// Portable lottery state for handing the lottery to a standby node
+///
+/// Holds the relays exactly as weighted plus the selection configuration.
+/// The VRF keypair is never exported and must be provisioned on the
+/// importing node; the blocklist and reputation manager also stay local.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LotteryState {
+    /// Relays with their current weights and measurements
+    pub relays: Vec<WeightedRelay>,
+    /// Sybil resistance enabled
+    pub sybil_resistance: bool,
+    /// Minimum stake required for participation
+    pub min_stake: u64,
+    /// Mapping from raw stake to the stake term of relay weights
+    pub stake_normalization: StakeNormalization,
+    /// Claimed-vs-measured performance thresholds
+    pub performance_verification: PerformanceVerificationConfig,
+    /// Range selection weights are rescaled into before sampling
+    #[serde(default)]
+    pub weight_bounds: Option<WeightBounds>,
+}
+
+impl LotteryState {
+    /// Check the state is one a lottery could have exported
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for relay in &self.relays {
+            if !seen.insert(relay.address) {
+                return Err(MixnodeError::Config(format!(
+                    "Duplicate relay {} in lottery state",
+                    relay.address
+                )));
+            }
+            if !relay.weight.is_finite() || relay.weight < 0.0 {
+                return Err(MixnodeError::Config(format!(
+                    "Invalid weight {} for relay {}",
+                    relay.weight, relay.address
+                )));
+            }
+        }
+        if let StakeNormalization::Log { divisor } = self.stake_normalization {
+            if !(divisor.is_finite() && divisor > 0.0) {
+                return Err(MixnodeError::Config(format!(
+                    "Invalid stake normalization divisor {}",
+                    divisor
+                )));
+            }
+        }
+        Ok(())
     }
 }
 
 /// Relay lottery for weighted node selection
+///
+/// `RelayLottery` is `Send + Sync`, but selection lazily rebuilds the
+/// weighted index and therefore takes `&mut self`. Use [`SharedRelayLottery`]
+/// when several tasks need to draw from the same lottery.
 pub struct RelayLottery {
     /// Available relays with weights
     relays: Vec<WeightedRelay>,
@@ -134,6 +616,63 @@ pub struct RelayLottery {
     sybil_resistance: bool,
     /// Minimum stake required for participation
     min_stake: u64,
+    /// Blocked address ranges excluded from selection
+    blocklist: Option<SharedBlocklist>,
+    /// Blocklist generation the weighted index was built against
+    blocklist_generation: u64,
+    /// Claimed-vs-measured performance thresholds
+    performance_verification: PerformanceVerificationConfig,
+    /// Mapping from raw stake to the stake term of relay weights
+    stake_normalization: StakeNormalization,
+    /// Network epoch clock stamping lottery proofs
+    epoch_clock: Option<EpochClock>,
+    /// Range selection weights are rescaled into before sampling
+    weight_bounds: Option<WeightBounds>,
+    /// Reward burst detection
+    manipulation_guard: Option<ManipulationGuard>,
+    /// Recent large rewards by relay subnet, oldest first
+    recent_rewards: VecDeque<(Instant, IpAddr)>,
+    /// Engaged weight freeze, if any
+    weight_freeze: Option<WeightFreeze>,
+    /// Seeded generator for non-VRF draws; `thread_rng()` when unset
+    seeded_rng: Option<std::sync::Mutex<StdRng>>,
+}
+
+/// Randomness for one draw: the lottery's seeded generator, held locked
+/// for the draw, or the thread-local one
+enum DrawRng<'a> {
+    Seeded(std::sync::MutexGuard<'a, StdRng>),
+    Thread(ThreadRng),
+}
+
+impl RngCore for DrawRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            Self::Seeded(rng) => rng.next_u32(),
+            Self::Thread(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            Self::Seeded(rng) => rng.next_u64(),
+            Self::Thread(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            Self::Seeded(rng) => rng.fill_bytes(dest),
+            Self::Thread(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+        match self {
+            Self::Seeded(rng) => rng.try_fill_bytes(dest),
+            Self::Thread(rng) => rng.try_fill_bytes(dest),
+        }
+    }
 }
 
 impl RelayLottery {
@@ -148,6 +687,34 @@ impl RelayLottery {
             reputation_manager: None,
             sybil_resistance: false,
             min_stake: 0,
+            blocklist: None,
+            blocklist_generation: 0,
+            performance_verification: PerformanceVerificationConfig::default(),
+            stake_normalization: StakeNormalization::default(),
+            epoch_clock: None,
+            weight_bounds: None,
+            manipulation_guard: None,
+            recent_rewards: VecDeque::new(),
+            weight_freeze: None,
+            seeded_rng: None,
+        }
+    }
+
+    /// Draw from a generator seeded with `seed` instead of `thread_rng()`
+    ///
+    /// Two lotteries seeded and populated alike make the same selections in
+    /// the same order, which makes distribution experiments reproducible.
+    /// VRF-backed draws are unaffected.
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.seeded_rng = Some(std::sync::Mutex::new(StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    /// Randomness for a non-VRF draw
+    fn draw_rng(&self) -> DrawRng<'_> {
+        match &self.seeded_rng {
+            Some(rng) => DrawRng::Seeded(rng.lock().unwrap_or_else(|e| e.into_inner())),
+            None => DrawRng::Thread(thread_rng()),
         }
     }
 
@@ -155,22 +722,17 @@ impl RelayLottery {
     #[cfg(feature = "vrf")]
     pub fn with_vrf() -> Self {
         Self {
-            relays: Vec::new(),
-            relay_map: HashMap::new(),
-            weighted_index: None,
             vrf_keypair: Some(VrfKeyPair::generate()),
             reputation_manager: Some(ReputationManager::default()),
             sybil_resistance: true,
             min_stake: 1000, // Minimum stake of 1000 tokens
+            ..Self::new()
         }
     }
 
     /// Create lottery with custom configuration
     pub fn with_config(sybil_resistance: bool, min_stake: u64) -> Self {
         Self {
-            relays: Vec::new(),
-            relay_map: HashMap::new(),
-            weighted_index: None,
             #[cfg(feature = "vrf")]
             vrf_keypair: if sybil_resistance {
                 Some(VrfKeyPair::generate())
@@ -184,6 +746,213 @@ impl RelayLottery {
             },
             sybil_resistance,
             min_stake,
+            ..Self::new()
+        }
+    }
+
+    /// Stamp lottery proofs with epoch starts from `clock` instead of raw
+    /// system time
+    pub fn with_epoch_clock(mut self, clock: EpochClock) -> Self {
+        self.epoch_clock = Some(clock);
+        self
+    }
+
+    /// Epoch clock stamping lottery proofs, if any
+    pub fn epoch_clock(&self) -> Option<&EpochClock> {
+        self.epoch_clock.as_ref()
+    }
+
+    /// Use a pre-populated reputation manager, e.g. one restored from disk
+    ///
+    /// Replaces any manager created by the constructor. The lottery owns the
+    /// manager; call [`sync_with_reputation_manager`](Self::sync_with_reputation_manager)
+    /// to apply its scores to relay weights.
+    pub fn with_reputation_manager(mut self, manager: ReputationManager) -> Self {
+        self.reputation_manager = Some(manager);
+        self.weighted_index = None;
+        self
+    }
+
+    /// Attached reputation manager
+    pub fn reputation_manager(&self) -> Option<&ReputationManager> {
+        self.reputation_manager.as_ref()
+    }
+
+    /// Exclude relays in blocked ranges from selection
+    pub fn with_blocklist(mut self, blocklist: SharedBlocklist) -> Self {
+        self.set_blocklist(blocklist);
+        self
+    }
+
+    /// Install or replace the blocklist
+    pub fn set_blocklist(&mut self, blocklist: SharedBlocklist) {
+        self.blocklist = Some(blocklist);
+        self.weighted_index = None;
+    }
+
+    /// Rescale selection weights into `bounds` before sampling, keeping
+    /// the weighted index stable when weights span many magnitudes
+    pub fn with_weight_bounds(mut self, bounds: WeightBounds) -> Self {
+        self.weight_bounds = Some(bounds);
+        self.weighted_index = None;
+        self
+    }
+
+    /// Freeze weight updates when rewards burst into one subnet
+    pub fn with_manipulation_guard(mut self, guard: ManipulationGuard) -> Self {
+        self.manipulation_guard = Some(guard);
+        self
+    }
+
+    /// Engaged weight freeze, if any
+    pub fn weight_freeze(&self) -> Option<&WeightFreeze> {
+        self.weight_freeze.as_ref()
+    }
+
+    /// Resume applying reputation to relay weights
+    ///
+    /// Rewards seen so far are forgotten, so the same burst doesn't
+    /// re-engage the freeze.
+    pub fn clear_weight_freeze(&mut self) {
+        self.weight_freeze = None;
+        self.recent_rewards.clear();
+    }
+
+    /// Feed a reward of `points` to `relay`, as of `now`, to the guard
+    ///
+    /// Rewards reaching the manager through
+    /// [`update_relay_reputation_via_manager`](Self::update_relay_reputation_via_manager)
+    /// are fed automatically. Returns whether this reward engaged a freeze.
+    pub fn record_reward_at(
+        &mut self,
+        relay: &SocketAddr,
+        points: ReputationPoints,
+        now: Instant,
+    ) -> bool {
+        let Some(guard) = self.manipulation_guard else {
+            return false;
+        };
+        if points < guard.min_reward_points {
+            return false;
+        }
+
+        while let Some(&(at, _)) = self.recent_rewards.front() {
+            if now.saturating_duration_since(at) < guard.window {
+                break;
+            }
+            self.recent_rewards.pop_front();
+        }
+        let subnet = ip_prefix(relay.ip(), guard.ipv4_prefix, guard.ipv6_prefix);
+        self.recent_rewards.push_back((now, subnet));
+        if self.weight_freeze.is_some() {
+            return false;
+        }
+
+        let rewards = self.recent_rewards.iter().filter(|(_, s)| *s == subnet).count();
+        if rewards < guard.max_correlated_rewards {
+            return false;
+        }
+        tracing::warn!(
+            subnet = %subnet,
+            rewards,
+            "Suspected reputation manipulation, freezing relay weight updates"
+        );
+        self.weight_freeze = Some(WeightFreeze {
+            subnet,
+            rewards,
+            since: now,
+        });
+        true
+    }
+
+    /// Normalize relay stakes with the given mapping
+    pub fn with_stake_normalization(mut self, normalization: StakeNormalization) -> Self {
+        self.set_stake_normalization(normalization);
+        self
+    }
+
+    /// Change stake normalization and rescore every relay
+    pub fn set_stake_normalization(&mut self, normalization: StakeNormalization) {
+        self.stake_normalization = normalization;
+        self.renormalize_stakes();
+    }
+
+    /// Current stake normalization
+    pub fn stake_normalization(&self) -> StakeNormalization {
+        self.stake_normalization
+    }
+
+    /// Recompute every relay's stake score and weight
+    fn renormalize_stakes(&mut self) {
+        let max_stake = self.relays.iter().map(|r| r.stake).max().unwrap_or(0);
+        for relay in &mut self.relays {
+            relay.stake_score = Some(self.stake_normalization.score(relay.stake, max_stake));
+            relay.recalculate_weight();
+        }
+        self.weighted_index = None;
+    }
+
+    /// Check whether a relay is outside every blocked range
+    fn is_unblocked(&self, relay: &WeightedRelay) -> bool {
+        match &self.blocklist {
+            Some(blocklist) => !blocklist.is_blocked(&relay.address.ip()),
+            None => true,
+        }
+    }
+
+    /// Check whether the reputation manager, if any, clears a relay for circuits
+    fn meets_selection_threshold(&self, relay: &WeightedRelay) -> bool {
+        match &self.reputation_manager {
+            Some(manager) => manager.is_selectable(&relay.address),
+            None => true,
+        }
+    }
+
+    /// Check whether a relay may currently be selected
+    fn is_eligible(&self, relay: &WeightedRelay) -> bool {
+        !relay.standby && self.is_unblocked(relay) && self.meets_selection_threshold(relay)
+    }
+
+    /// Weight used for sampling (zero for ineligible relays)
+    fn selection_weight(&self, relay: &WeightedRelay) -> f64 {
+        if self.is_eligible(relay) {
+            relay.weight
+        } else {
+            0.0
+        }
+    }
+
+    /// Indices of relays that may currently be selected
+    fn eligible_indices(&self) -> Vec<usize> {
+        (0..self.relays.len())
+            .filter(|&i| self.is_eligible(&self.relays[i]))
+            .collect()
+    }
+
+    /// Addresses of eligible relays, in lottery order
+    #[cfg(feature = "vrf")]
+    fn eligible_addresses(&self) -> Vec<SocketAddr> {
+        self.eligible_indices()
+            .into_iter()
+            .map(|i| self.relays[i].address)
+            .collect()
+    }
+
+    /// Indices of standby relays that could be promoted
+    fn standby_indices(&self) -> Vec<usize> {
+        (0..self.relays.len())
+            .filter(|&i| {
+                let relay = &self.relays[i];
+                relay.standby && self.is_unblocked(relay) && self.meets_selection_threshold(relay)
+            })
+            .collect()
+    }
+
+    /// Set or clear a relay's standby flag
+    pub fn set_standby(&mut self, address: &SocketAddr, standby: bool) {
+        if let Some(&index) = self.relay_map.get(address) {
+            self.relays[index].standby = standby;
+            self.weighted_index = None;
         }
     }
 
@@ -194,27 +963,45 @@ impl RelayLottery {
     }
 
     /// Add relay to lottery
-    pub fn add_relay(&mut self, relay: WeightedRelay) {
+    pub fn add_relay(&mut self, mut relay: WeightedRelay) {
         let address = relay.address;
         let index = self.relays.len();
 
+        // A new network maximum changes every relay's score
+        let max_stake = self.relays.iter().map(|r| r.stake).max().unwrap_or(0);
+        let rescore_all = matches!(self.stake_normalization, StakeNormalization::NetworkMax)
+            && relay.stake > max_stake;
+        relay.stake_score = Some(
+            self.stake_normalization
+                .score(relay.stake, max_stake.max(relay.stake)),
+        );
+        relay.recalculate_weight();
+
         self.relays.push(relay);
         self.relay_map.insert(address, index);
 
+        if rescore_all {
+            self.renormalize_stakes();
+        }
+
         // Invalidate cached weighted index
         self.weighted_index = None;
     }
 
     /// Remove relay from lottery
+    ///
+    /// The last relay is moved into the freed slot, so relay order is not
+    /// preserved and only the moved relay's index changes.
     pub fn remove_relay(&mut self, address: &SocketAddr) {
-        if let Some(&index) = self.relay_map.get(address) {
-            self.relays.remove(index);
-            self.relay_map.remove(address);
+        if let Some(index) = self.relay_map.remove(address) {
+            self.relays.swap_remove(index);
+            if let Some(moved) = self.relays.get(index) {
+                self.relay_map.insert(moved.address, index);
+            }
+            self.debug_assert_map_consistent();
 
-            // Rebuild relay map
-            self.relay_map.clear();
-            for (i, relay) in self.relays.iter().enumerate() {
-                self.relay_map.insert(relay.address, i);
+            if matches!(self.stake_normalization, StakeNormalization::NetworkMax) {
+                self.renormalize_stakes();
             }
 
             // Invalidate cached weighted index
@@ -234,8 +1021,59 @@ impl RelayLottery {
         }
     }
 
+    /// Set thresholds for claimed-vs-measured performance checks
+    pub fn set_performance_verification(&mut self, config: PerformanceVerificationConfig) {
+        self.performance_verification = config;
+    }
+
+    /// Record a measured forward through a relay
+    pub fn record_forward(&mut self, address: &SocketAddr, latency: std::time::Duration, success: bool) {
+        if let Some(&index) = self.relay_map.get(address) {
+            self.relays[index].record_measurement(latency.as_secs_f64() * 1000.0, success);
+        }
+    }
+
+    /// Compare claimed performance against measurements
+    ///
+    /// Meant to run periodically. Relays whose claim exceeds the measured
+    /// score by more than the tolerance are weighted by the measured score
+    /// until they come back within tolerance. Returns the divergent relays.
+    pub fn verify_claimed_performance(&mut self) -> Vec<(SocketAddr, f64)> {
+        let config = self.performance_verification.clone();
+        let mut divergent = Vec::new();
+
+        for relay in &mut self.relays {
+            let claimed = relay.performance;
+            let Some(measured) = relay.measured.as_mut() else {
+                continue;
+            };
+            if measured.samples < config.min_samples {
+                continue;
+            }
+
+            measured.divergence = claimed - measured.score();
+            measured.penalized = measured.divergence > config.tolerance;
+            if measured.penalized {
+                divergent.push((relay.address, measured.divergence));
+            }
+            relay.recalculate_weight();
+        }
+
+        self.weighted_index = None;
+        divergent
+    }
+
     /// Build weighted index for sampling
     fn ensure_weighted_index(&mut self) -> Result<()> {
+        // Rebuild if the blocklist was hot-reloaded since the last build
+        if let Some(blocklist) = &self.blocklist {
+            let generation = blocklist.generation();
+            if generation != self.blocklist_generation {
+                self.blocklist_generation = generation;
+                self.weighted_index = None;
+            }
+        }
+
         if self.weighted_index.is_none() {
             if self.relays.is_empty() {
                 return Err(MixnodeError::Config(
@@ -243,22 +1081,38 @@ impl RelayLottery {
                 ));
             }
 
-            let weights: Vec<f64> = self.relays.iter().map(|r| r.weight).collect();
+            let weights: Vec<f64> = self.relays.iter().map(|r| self.selection_weight(r)).collect();
+            if weights.iter().all(|&w| w == 0.0) {
+                return Err(MixnodeError::Config(
+                    "No eligible relays available for lottery".to_string(),
+                ));
+            }
 
-            self.weighted_index = Some(
-                WeightedIndex::new(&weights)
-                    .map_err(|e| MixnodeError::Config(format!("Invalid weights: {}", e)))?,
-            );
+            self.weighted_index = Some(self.build_index(weights)?);
         }
 
         Ok(())
     }
 
-    /// Select a random relay using weighted lottery
-    pub fn select_relay(&mut self) -> Result<&WeightedRelay> {
-        self.ensure_weighted_index()?;
+    /// Build a weighted index, applying the configured weight bounds
+    fn build_index(&self, weights: Vec<f64>) -> Result<WeightedIndex<f64>> {
+        WeightedIndex::new(self.bounded_weights(weights))
+            .map_err(|e| MixnodeError::Config(format!("Invalid weights: {}", e)))
+    }
+
+    /// `weights` as sampled, after the configured [`WeightBounds`] rescale them
+    fn bounded_weights(&self, mut weights: Vec<f64>) -> Vec<f64> {
+        if let Some(bounds) = &self.weight_bounds {
+            bounds.normalize(&mut weights);
+        }
+        weights
+    }
+
+    /// Select a random relay using weighted lottery
+    pub fn select_relay(&mut self) -> Result<&WeightedRelay> {
+        self.ensure_weighted_index()?;
 
-        let mut rng = thread_rng();
+        let mut rng = self.draw_rng();
         let index = self.weighted_index.as_ref().unwrap().sample(&mut rng);
 
         Ok(&self.relays[index])
@@ -268,7 +1122,7 @@ impl RelayLottery {
     pub fn select_relays(&mut self, count: usize) -> Result<Vec<SocketAddr>> {
         self.ensure_weighted_index()?;
 
-        let mut rng = thread_rng();
+        let mut rng = self.draw_rng();
         let weighted_index = self.weighted_index.as_ref().unwrap();
 
         let mut selected = Vec::with_capacity(count);
@@ -280,21 +1134,340 @@ impl RelayLottery {
         Ok(selected)
     }
 
+    /// Select multiple relays with replacement, never drawing excluded addresses
+    ///
+    /// Draws from a one-off weighted index over the eligible relays left
+    /// after exclusion, e.g. to keep the relays of the last few circuits out
+    /// of the next one. Fails when fewer than `count` relays remain.
+    pub fn select_relays_excluding(
+        &mut self,
+        count: usize,
+        exclude: &HashSet<SocketAddr>,
+    ) -> Result<Vec<SocketAddr>> {
+        let candidates: Vec<usize> = self
+            .eligible_indices()
+            .into_iter()
+            .filter(|&i| !exclude.contains(&self.relays[i].address))
+            .collect();
+        if count > candidates.len() {
+            return Err(MixnodeError::Config(format!(
+                "Cannot select {} relays, only {} available after excluding {}",
+                count,
+                candidates.len(),
+                exclude.len()
+            )));
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let weights = candidates.iter().map(|&i| self.relays[i].weight).collect();
+        let index = self.build_index(weights)?;
+        let mut rng = self.draw_rng();
+        Ok((0..count)
+            .map(|_| self.relays[candidates[index.sample(&mut rng)]].address)
+            .collect())
+    }
+
     /// Select multiple unique relays without replacement (each relay selected at most once)
     pub fn select_unique_relays(&mut self, count: usize) -> Result<Vec<SocketAddr>> {
-        if count > self.relays.len() {
+        let available_indices = self.eligible_indices();
+        if count > available_indices.len() {
             return Err(MixnodeError::Config(format!(
                 "Cannot select {} unique relays from {} available",
                 count,
-                self.relays.len()
+                available_indices.len()
             )));
         }
 
         self.ensure_weighted_index()?;
+        self.sample_without_replacement(available_indices, count)
+    }
+
+    /// Select relays for a `hops`-long circuit path
+    ///
+    /// Hops are unique while enough relays are eligible. On a shortage,
+    /// `on_shortage` decides between failing and reusing relays that are
+    /// not adjacent; reuse still needs two eligible relays.
+    pub fn select_path(
+        &mut self,
+        hops: usize,
+        on_shortage: OnShortage,
+    ) -> Result<Vec<SocketAddr>> {
+        let available = self.eligible_indices();
+        if hops <= available.len() || on_shortage == OnShortage::Error {
+            return self.select_unique_relays(hops);
+        }
+        if available.len() < 2 {
+            return Err(MixnodeError::Config(format!(
+                "Cannot build a {}-hop path from {} relay(s) without adjacent reuse",
+                hops,
+                available.len()
+            )));
+        }
+
+        // Every relay once, then top up avoiding the previous hop
+        let unique = available.len();
+        let mut path = self.sample_without_replacement(available.clone(), unique)?;
+        while path.len() < hops {
+            let previous = path[path.len() - 1];
+            let candidates: Vec<usize> = available
+                .iter()
+                .copied()
+                .filter(|&i| self.relays[i].address != previous)
+                .collect();
+            path.extend(self.sample_without_replacement(candidates, 1)?);
+        }
+        Ok(path)
+    }
+
+    /// Select unique relays, skipping the given addresses
+    ///
+    /// Used when rebuilding a circuit after a hop failure: pass the hops
+    /// already in use (and the failed one) to draw weighted replacements
+    /// from the remaining relays.
+    pub fn select_excluding(
+        &mut self,
+        count: usize,
+        exclude: &HashSet<SocketAddr>,
+    ) -> Result<Vec<SocketAddr>> {
+        let available_indices: Vec<usize> = self
+            .eligible_indices()
+            .into_iter()
+            .filter(|&i| !exclude.contains(&self.relays[i].address))
+            .collect();
+        if count > available_indices.len() {
+            return Err(MixnodeError::Config(format!(
+                "Cannot select {} relays from {} remaining after exclusions",
+                count,
+                available_indices.len()
+            )));
+        }
+
+        self.sample_without_replacement(available_indices, count)
+    }
+
+    /// Select unique relays, falling back to standby relays
+    ///
+    /// Active relays are always used first; standby relays only fill the
+    /// slots left once every eligible active relay has been drawn.
+    pub fn select_including_standby(&mut self, count: usize) -> Result<Vec<SocketAddr>> {
+        let active = self.eligible_indices();
+        if count <= active.len() {
+            return self.sample_without_replacement(active, count);
+        }
+
+        let standby = self.standby_indices();
+        let shortfall = count - active.len();
+        if shortfall > standby.len() {
+            return Err(MixnodeError::Config(format!(
+                "Cannot select {} relays from {} active and {} standby",
+                count,
+                active.len(),
+                standby.len()
+            )));
+        }
+
+        let active_count = active.len();
+        let mut selected = self.sample_without_replacement(active, active_count)?;
+        selected.extend(self.sample_without_replacement(standby, shortfall)?);
+        Ok(selected)
+    }
+
+    /// Select unique relays for a circuit, spreading hops across latency classes
+    ///
+    /// Each hop is drawn by weight from the relays whose class has
+    /// been used least so far, so no class repeats until every available
+    /// class holds a hop.
+    pub fn select_latency_diverse(&mut self, count: usize) -> Result<Vec<SocketAddr>> {
+        let mut available_indices = self.eligible_indices();
+        if count > available_indices.len() {
+            return Err(MixnodeError::Config(format!(
+                "Cannot select {} unique relays from {} available",
+                count,
+                available_indices.len()
+            )));
+        }
+
+        let mut rng = self.draw_rng();
+        let mut class_usage: HashMap<Option<&str>, usize> = HashMap::new();
+        let mut selected = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let usage_of = |i: usize| {
+                class_usage
+                    .get(&self.relays[i].latency_class.as_deref())
+                    .copied()
+                    .unwrap_or(0)
+            };
+            let least_used = available_indices.iter().map(|&i| usage_of(i)).min().unwrap_or(0);
+            let candidates: Vec<usize> = (0..available_indices.len())
+                .filter(|&pos| usage_of(available_indices[pos]) == least_used)
+                .collect();
+
+            let weights: Vec<f64> = candidates
+                .iter()
+                .map(|&pos| self.relays[available_indices[pos]].weight)
+                .collect();
+            let weighted_index = self.build_index(weights)?;
+
+            let global_index = available_indices.remove(candidates[weighted_index.sample(&mut rng)]);
+            let relay = &self.relays[global_index];
+            *class_usage.entry(relay.latency_class.as_deref()).or_insert(0) += 1;
+            selected.push(relay.address);
+        }
+
+        Ok(selected)
+    }
+
+    /// Select unique relays for a circuit, greedily maximizing diversity
+    ///
+    /// After a weighted first hop, each hop is drawn by weight from the
+    /// relays that raise the circuit's [`DiversityObjective`] score the most,
+    /// so weight only decides between equally diverse candidates.
+    pub fn select_most_diverse(
+        &mut self,
+        hops: usize,
+        objective: &DiversityObjective,
+    ) -> Result<DiverseCircuit> {
+        let mut available_indices = self.eligible_indices();
+        if hops > available_indices.len() {
+            return Err(MixnodeError::Config(format!(
+                "Cannot select {} unique relays from {} available",
+                hops,
+                available_indices.len()
+            )));
+        }
+
+        let mut rng = self.draw_rng();
+        let mut path: Vec<usize> = Vec::with_capacity(hops);
+        let score_with = |path: &[usize], candidate: Option<usize>| {
+            let relays: Vec<&WeightedRelay> = path
+                .iter()
+                .chain(candidate.as_ref())
+                .map(|&i| &self.relays[i])
+                .collect();
+            objective.score(&relays)
+        };
+
+        for _ in 0..hops {
+            let scores: Vec<f64> = available_indices
+                .iter()
+                .map(|&i| score_with(&path, Some(i)))
+                .collect();
+            let best = scores.iter().copied().fold(f64::MIN, f64::max);
+            let candidates: Vec<usize> = (0..available_indices.len())
+                .filter(|&pos| scores[pos] >= best - 1e-9)
+                .collect();
+
+            let weights: Vec<f64> = candidates
+                .iter()
+                .map(|&pos| self.relays[available_indices[pos]].weight)
+                .collect();
+            let weighted_index = self.build_index(weights)?;
+            path.push(available_indices.remove(candidates[weighted_index.sample(&mut rng)]));
+        }
+
+        Ok(DiverseCircuit {
+            hops: path.iter().map(|&i| self.relays[i].address).collect(),
+            diversity_score: score_with(&path, None),
+        })
+    }
+
+    /// Select unique relays for a circuit with no two hops in one subnet
+    ///
+    /// Hops are drawn by weight, each from the relays whose IP prefix of
+    /// `prefix_bits` bits (for both address families; IPv4 caps at 32) no
+    /// earlier hop shares.
+    pub fn select_diverse_path(
+        &mut self,
+        hops: usize,
+        prefix_bits: u8,
+    ) -> Result<Vec<SocketAddr>> {
+        let mut available_indices = self.eligible_indices();
+        let prefix_of = |i: usize| ip_prefix(self.relays[i].address.ip(), prefix_bits, prefix_bits);
+        let subnets: HashSet<IpAddr> = available_indices.iter().map(|&i| prefix_of(i)).collect();
+        if hops > subnets.len() {
+            return Err(MixnodeError::Config(format!(
+                "Cannot select {} relays in distinct /{} prefixes from {} spanning {} prefix(es)",
+                hops,
+                prefix_bits,
+                available_indices.len(),
+                subnets.len()
+            )));
+        }
+
+        let mut rng = self.draw_rng();
+        let mut path = Vec::with_capacity(hops);
+        for _ in 0..hops {
+            let weights: Vec<f64> = available_indices
+                .iter()
+                .map(|&i| self.relays[i].weight)
+                .collect();
+            let weighted_index = self.build_index(weights)?;
+            let chosen = available_indices.swap_remove(weighted_index.sample(&mut rng));
+            let subnet = prefix_of(chosen);
+            available_indices.retain(|&i| prefix_of(i) != subnet);
+            path.push(self.relays[chosen].address);
+        }
+
+        Ok(path)
+    }
+
+    /// Diversity score of a circuit through known relays
+    ///
+    /// Addresses the lottery doesn't know are skipped.
+    pub fn diversity_score(&self, hops: &[SocketAddr], objective: &DiversityObjective) -> f64 {
+        let relays: Vec<&WeightedRelay> = hops.iter().filter_map(|a| self.get_relay(a)).collect();
+        objective.score(&relays)
+    }
+
+    /// Select unique peers to gossip reputation updates to
+    ///
+    /// Targets are drawn by reputation alone, from the reputation manager
+    /// when one is attached and the relay's own score otherwise, so
+    /// low-reputation peers rarely get to relay (and amplify) updates.
+    /// Returns fewer than `count` targets when fewer eligible peers have a
+    /// non-zero reputation.
+    pub fn select_gossip_targets(&self, count: usize) -> Result<Vec<SocketAddr>> {
+        let available_indices: Vec<usize> = self
+            .eligible_indices()
+            .into_iter()
+            .filter(|&i| self.gossip_weight(&self.relays[i]) > 0.0)
+            .collect();
+        let count = count.min(available_indices.len());
+
+        self.sample_weighted_without_replacement(available_indices, count, |relay| {
+            self.gossip_weight(relay)
+        })
+    }
 
-        let mut rng = thread_rng();
+    /// Reputation used to weight a relay as a gossip target
+    fn gossip_weight(&self, relay: &WeightedRelay) -> f64 {
+        match &self.reputation_manager {
+            Some(manager) => manager.get_reputation_score(&relay.address),
+            None => relay.reputation,
+        }
+    }
+
+    /// Weighted sampling without replacement over the given relay indices
+    fn sample_without_replacement(
+        &self,
+        available_indices: Vec<usize>,
+        count: usize,
+    ) -> Result<Vec<SocketAddr>> {
+        self.sample_weighted_without_replacement(available_indices, count, |relay| relay.weight)
+    }
+
+    /// Sampling without replacement with caller-chosen relay weights
+    fn sample_weighted_without_replacement(
+        &self,
+        mut available_indices: Vec<usize>,
+        count: usize,
+        weight: impl Fn(&WeightedRelay) -> f64,
+    ) -> Result<Vec<SocketAddr>> {
+        let mut rng = self.draw_rng();
         let mut selected = Vec::with_capacity(count);
-        let mut available_indices: Vec<usize> = (0..self.relays.len()).collect();
 
         // Weighted sampling without replacement
         for _ in 0..count {
@@ -305,11 +1478,10 @@ impl RelayLottery {
             // Build weights for remaining relays
             let weights: Vec<f64> = available_indices
                 .iter()
-                .map(|&i| self.relays[i].weight)
+                .map(|&i| weight(&self.relays[i]))
                 .collect();
 
-            let weighted_index = WeightedIndex::new(&weights)
-                .map_err(|e| MixnodeError::Config(format!("Invalid weights: {}", e)))?;
+            let weighted_index = self.build_index(weights)?;
 
             let local_index = weighted_index.sample(&mut rng);
             let global_index = available_indices[local_index];
@@ -328,6 +1500,135 @@ impl RelayLottery {
         self.relays.len()
     }
 
+    /// Addresses of every relay, standby and blocked ones included
+    pub fn relay_addresses(&self) -> Vec<SocketAddr> {
+        self.relays.iter().map(|r| r.address).collect()
+    }
+
+    /// Probability that a single draw picks each relay, from current weights
+    ///
+    /// Standby and blocklisted relays are listed with probability 0.0. The map
+    /// is empty when no relay can be selected. Meant for tests and audits that
+    /// compare observed selection frequencies against the weights.
+    pub fn expected_probabilities(&self) -> HashMap<SocketAddr, f64> {
+        let weights =
+            self.bounded_weights(self.relays.iter().map(|r| self.selection_weight(r)).collect());
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return HashMap::new();
+        }
+
+        self.relays
+            .iter()
+            .zip(weights)
+            .map(|(relay, weight)| (relay.address, weight / total))
+            .collect()
+    }
+
+    /// Probability that a `hops`-relay circuit drawn by
+    /// [`select_unique_relays`](Self::select_unique_relays) lies entirely
+    /// within `colluding`
+    ///
+    /// Exact for sampling without replacement from the current weights,
+    /// rescaled by the configured [`WeightBounds`] before every draw as the
+    /// sampler does. Enumerates ordered draws from the colluding set, so cost
+    /// grows as `colluding.len()^hops`; fine for audit-sized sets and circuit
+    /// lengths. Returns 0.0 when fewer than `hops` relays are eligible.
+    pub fn compromise_probability(&self, colluding: &HashSet<SocketAddr>, hops: usize) -> f64 {
+        let eligible = self.eligible_indices();
+        if hops > eligible.len() {
+            return 0.0;
+        }
+
+        let weights: Vec<f64> = eligible.iter().map(|&i| self.relays[i].weight).collect();
+        let colluder: Vec<bool> = eligible
+            .iter()
+            .map(|&i| colluding.contains(&self.relays[i].address))
+            .collect();
+
+        fn all_colluding(
+            lottery: &RelayLottery,
+            weights: &[f64],
+            colluder: &[bool],
+            used: &mut [bool],
+            hops: usize,
+        ) -> f64 {
+            if hops == 0 {
+                return 1.0;
+            }
+            let remaining: Vec<usize> = (0..weights.len()).filter(|&i| !used[i]).collect();
+            let bounded =
+                lottery.bounded_weights(remaining.iter().map(|&i| weights[i]).collect());
+            let total: f64 = bounded.iter().sum();
+            if total <= 0.0 {
+                return 0.0;
+            }
+
+            let mut probability = 0.0;
+            for (&i, &weight) in remaining.iter().zip(&bounded) {
+                if !colluder[i] {
+                    continue;
+                }
+                used[i] = true;
+                probability +=
+                    weight / total * all_colluding(lottery, weights, colluder, used, hops - 1);
+                used[i] = false;
+            }
+            probability
+        }
+
+        let mut used = vec![false; weights.len()];
+        all_colluding(self, &weights, &colluder, &mut used, hops)
+    }
+
+    /// Snapshot relays, weights and selection config for replication
+    pub fn export_state(&self) -> LotteryState {
+        LotteryState {
+            relays: self.relays.clone(),
+            sybil_resistance: self.sybil_resistance,
+            min_stake: self.min_stake,
+            stake_normalization: self.stake_normalization,
+            performance_verification: self.performance_verification.clone(),
+            weight_bounds: self.weight_bounds,
+        }
+    }
+
+    /// Replace relays and selection config with an exported state
+    ///
+    /// Weights are taken as exported, not recomputed, so the importing
+    /// lottery selects with the same probabilities. The local VRF keypair,
+    /// blocklist and reputation manager are kept. On error nothing changes.
+    pub fn import_state(&mut self, state: LotteryState) -> Result<()> {
+        state.validate()?;
+
+        self.relay_map = state
+            .relays
+            .iter()
+            .enumerate()
+            .map(|(i, relay)| (relay.address, i))
+            .collect();
+        self.relays = state.relays;
+        self.sybil_resistance = state.sybil_resistance;
+        self.min_stake = state.min_stake;
+        self.stake_normalization = state.stake_normalization;
+        self.performance_verification = state.performance_verification;
+        self.weight_bounds = state.weight_bounds;
+        self.weighted_index = None;
+        Ok(())
+    }
+
+    /// Check that `relay_map` indexes exactly the entries of `relays`
+    fn debug_assert_map_consistent(&self) {
+        debug_assert_eq!(self.relay_map.len(), self.relays.len(), "relay_map size desync");
+        debug_assert!(
+            self.relays
+                .iter()
+                .enumerate()
+                .all(|(i, relay)| self.relay_map.get(&relay.address) == Some(&i)),
+            "relay_map index desync"
+        );
+    }
+
     /// Get relay by address
     pub fn get_relay(&self, address: &SocketAddr) -> Option<&WeightedRelay> {
         self.relay_map.get(address).map(|&i| &self.relays[i])
@@ -342,27 +1643,19 @@ impl RelayLottery {
             // Generate VRF proof for the seed
             let vrf_proof = vrf_keypair.prove(seed)?;
 
-            // Extract randomness from VRF output
-            let random_bytes: [u8; 8] = vrf_proof.io.make_bytes(b"lottery");
-            let random_value = u64::from_be_bytes(random_bytes);
-
-            // Use VRF output to deterministically select relay
-            let index = (random_value as usize) % self.relays.len();
-            let selected_relay = &self.relays[index];
+            // Use VRF output to deterministically select an eligible relay
+            let selected = vrf_selection(&vrf_proof.io, &self.eligible_addresses(), 1);
 
             // Create lottery proof
             let proof = LotteryProof {
-                vrf_proof: Some(vec![]), // Serialize VRF proof here
+                vrf_proof: Some(vrf_proof.to_bytes()),
                 seed: seed.to_vec(),
-                selected: vec![selected_relay.address],
+                selected: selected.clone(),
                 weights: self.relays.iter().map(|r| r.weight).collect(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                timestamp: self.proof_timestamp(),
             };
 
-            Ok((selected_relay.address, proof))
+            Ok((selected[0], proof))
         } else {
             // Fallback to non-VRF selection
             let relay = self.select_relay()?;
@@ -373,10 +1666,7 @@ impl RelayLottery {
                 seed: seed.to_vec(),
                 selected: vec![relay_address],
                 weights,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                timestamp: self.proof_timestamp(),
             };
             Ok((relay_address, proof))
         }
@@ -391,39 +1681,19 @@ impl RelayLottery {
     ) -> Result<(Vec<SocketAddr>, LotteryProof)> {
         self.ensure_weighted_index()?;
 
-        let mut selected = Vec::with_capacity(count);
-
         if let Some(vrf_keypair) = &self.vrf_keypair {
             // Generate VRF proof for the seed
             let vrf_proof = vrf_keypair.prove(seed)?;
 
-            // Use VRF output as base randomness
-            let base_random: [u8; 32] = vrf_proof.io.make_bytes(b"lottery");
-
-            // Generate selections using derived randomness
-            for i in 0..count {
-                let mut hasher = Sha256::new();
-                hasher.update(&base_random);
-                hasher.update(&i.to_be_bytes());
-                let derived_random = hasher.finalize();
-
-                let mut random_bytes = [0u8; 8];
-                random_bytes.copy_from_slice(&derived_random[..8]);
-                let random_value = u64::from_be_bytes(random_bytes);
-
-                let index = (random_value as usize) % self.relays.len();
-                selected.push(self.relays[index].address);
-            }
+            // Generate selections using randomness derived from the VRF output
+            let selected = vrf_selection(&vrf_proof.io, &self.eligible_addresses(), count);
 
             let proof = LotteryProof {
-                vrf_proof: Some(vec![]), // Serialize VRF proof here
+                vrf_proof: Some(vrf_proof.to_bytes()),
                 seed: seed.to_vec(),
                 selected: selected.clone(),
                 weights: self.relays.iter().map(|r| r.weight).collect(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                timestamp: self.proof_timestamp(),
             };
 
             Ok((selected, proof))
@@ -435,15 +1705,24 @@ impl RelayLottery {
                 seed: seed.to_vec(),
                 selected: selected.clone(),
                 weights: self.relays.iter().map(|r| r.weight).collect(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                timestamp: self.proof_timestamp(),
             };
             Ok((selected, proof))
         }
     }
 
+    /// Timestamp for a new lottery proof
+    #[cfg(feature = "vrf")]
+    fn proof_timestamp(&self) -> u64 {
+        match &self.epoch_clock {
+            Some(clock) => clock.epoch_start(clock.current_epoch()),
+            None => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+
     /// Calculate cost of forgery for Sybil resistance
     pub fn cost_of_forgery(&self, attacker_stake: u64) -> f64 {
         if !self.sybil_resistance {
@@ -467,19 +1746,31 @@ impl RelayLottery {
         }
     }
 
-    /// Verify a lottery proof
+    /// Verify a lottery proof against this lottery's key and eligible relays
     #[cfg(feature = "vrf")]
     pub fn verify_lottery_proof(&self, proof: &LotteryProof) -> Result<bool> {
+        let eligible = self.eligible_addresses();
         if let Some(vrf_key) = self.vrf_public_key() {
-            proof.verify(&vrf_key)
+            proof.verify(&vrf_key, &eligible)
         } else {
-            proof.verify(&[0u8; 32])
+            proof.verify(&[0u8; 32], &eligible)
         }
     }
 
+    /// Verify a lottery proof without VRF support
+    #[cfg(not(feature = "vrf"))]
+    pub fn verify_lottery_proof(&self, proof: &LotteryProof) -> Result<ProofVerification> {
+        proof.verify()
+    }
+
     /// Integrate with reputation manager - FUNC-10 Full Implementation
     /// Syncs relay weights with reputation scores and applies decay
+    ///
+    /// Does nothing while a [`WeightFreeze`] is engaged.
     pub fn sync_with_reputation_manager(&mut self) {
+        if self.weight_freeze.is_some() {
+            return;
+        }
         if let Some(reputation_manager) = &mut self.reputation_manager {
             // Apply time-based decay to all nodes
             reputation_manager.apply_decay_all();
@@ -491,11 +1782,7 @@ impl RelayLottery {
                     relay.reputation = node_rep.reputation;
 
                     // Recalculate weight with new reputation
-                    let stake_score = (relay.stake as f64).ln() / 20.0;
-                    relay.weight = relay.reputation * 0.5
-                        + relay.performance * 0.3
-                        + stake_score.min(1.0) * 0.2;
-                    relay.weight = relay.weight.max(0.01);
+                    relay.recalculate_weight();
                 }
             }
 
@@ -511,8 +1798,10 @@ impl RelayLottery {
         action: crate::core::reputation::ReputationAction
     ) -> crate::Result<()> {
         if let Some(reputation_manager) = &mut self.reputation_manager {
+            let points = reputation_manager.policy().delta(action);
             reputation_manager.update_reputation(address, action)
-                .map_err(|e| crate::MixnodeError::Config(e))?;
+                .map_err(crate::MixnodeError::Config)?;
+            self.record_reward_at(address, points, Instant::now());
 
             // Sync relay weights after update
             self.sync_with_reputation_manager();
@@ -575,10 +1864,90 @@ impl Default for RelayLottery {
     }
 }
 
+/// Cloneable handle to a lottery shared across async tasks
+#[derive(Clone, Default)]
+pub struct SharedRelayLottery {
+    inner: Arc<Mutex<RelayLottery>>,
+}
+
+impl SharedRelayLottery {
+    /// Wrap an existing lottery
+    pub fn new(lottery: RelayLottery) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(lottery)),
+        }
+    }
+
+    /// Add a relay to the shared lottery
+    pub async fn add_relay(&self, relay: WeightedRelay) {
+        self.inner.lock().await.add_relay(relay);
+    }
+
+    /// Remove a relay from the shared lottery
+    pub async fn remove_relay(&self, address: &SocketAddr) {
+        self.inner.lock().await.remove_relay(address);
+    }
+
+    /// Select a single relay address
+    pub async fn select_relay(&self) -> Result<SocketAddr> {
+        self.inner.lock().await.select_relay().map(|r| r.address)
+    }
+
+    /// Select multiple relays (with replacement)
+    pub async fn select_relays(&self, count: usize) -> Result<Vec<SocketAddr>> {
+        self.inner.lock().await.select_relays(count)
+    }
+
+    /// Select multiple unique relays
+    pub async fn select_unique_relays(&self, count: usize) -> Result<Vec<SocketAddr>> {
+        self.inner.lock().await.select_unique_relays(count)
+    }
+
+    /// Select unique relays, falling back to standby relays
+    pub async fn select_including_standby(&self, count: usize) -> Result<Vec<SocketAddr>> {
+        self.inner.lock().await.select_including_standby(count)
+    }
+
+    /// Select relays for a circuit path, applying `on_shortage`
+    pub async fn select_path(
+        &self,
+        hops: usize,
+        on_shortage: OnShortage,
+    ) -> Result<Vec<SocketAddr>> {
+        self.inner.lock().await.select_path(hops, on_shortage)
+    }
+
+    /// Select unique relays spread across latency classes
+    pub async fn select_latency_diverse(&self, count: usize) -> Result<Vec<SocketAddr>> {
+        self.inner.lock().await.select_latency_diverse(count)
+    }
+
+    /// Select unique gossip targets weighted by reputation
+    pub async fn select_gossip_targets(&self, count: usize) -> Result<Vec<SocketAddr>> {
+        self.inner.lock().await.select_gossip_targets(count)
+    }
+
+    /// Number of relays in the lottery
+    pub async fn relay_count(&self) -> usize {
+        self.inner.lock().await.relay_count()
+    }
+
+    /// Reputation statistics taken under a single lock
+    pub async fn reputation_statistics(
+        &self,
+    ) -> Option<crate::core::reputation::ReputationStatistics> {
+        self.inner.lock().await.get_reputation_statistics()
+    }
+
+    /// Lock the underlying lottery for operations not covered above
+    pub async fn lock(&self) -> MutexGuard<'_, RelayLottery> {
+        self.inner.lock().await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashSet;
 
     #[test]
     fn test_weighted_relay_creation() {
@@ -638,6 +2007,475 @@ mod tests {
         assert!(selection_count[&addr_high] > selection_count[&addr_low]);
     }
 
+    #[test]
+    fn test_selection_threshold_gates_candidacy() {
+        use crate::core::reputation::ReputationAction;
+
+        let low: SocketAddr = "127.0.0.1:8310".parse().unwrap();
+        let high: SocketAddr = "127.0.0.1:8311".parse().unwrap();
+        let lottery_for = |admission, selection| {
+            let mut manager = ReputationManager::new()
+                .with_admission_threshold(admission)
+                .with_selection_threshold(selection);
+            manager.add_node(low, 1000);
+            manager.add_node(high, 1000);
+            manager
+                .update_reputation(&low, ReputationAction::TaskFailure)
+                .unwrap();
+            manager
+                .update_reputation(&high, ReputationAction::HighQualityService)
+                .unwrap();
+
+            let mut lottery = RelayLottery::new().with_reputation_manager(manager);
+            lottery.add_relay(WeightedRelay::new(low, 0.5, 0.8, 1000));
+            lottery.add_relay(WeightedRelay::new(high, 0.5, 0.8, 1000));
+            lottery
+        };
+
+        // Admitted to connect, but kept out of circuits
+        let mut lottery = lottery_for(50, 100);
+        let manager = lottery.reputation_manager().unwrap();
+        assert!(manager.is_admissible(&low));
+        assert!(!manager.is_selectable(&low));
+        for addr in lottery.select_relays(50).unwrap() {
+            assert_eq!(addr, high);
+        }
+
+        // Selectable, though this threshold would refuse it at accept
+        let mut lottery = lottery_for(100, 50);
+        let manager = lottery.reputation_manager().unwrap();
+        assert!(!manager.is_admissible(&low));
+        assert!(manager.is_selectable(&low));
+        assert!(lottery.select_relays(200).unwrap().contains(&low));
+    }
+
+    #[test]
+    fn test_injected_reputation_manager_drives_weights() {
+        use crate::core::reputation::ReputationAction;
+
+        let trusted: SocketAddr = "127.0.0.1:8300".parse().unwrap();
+        let flagged: SocketAddr = "127.0.0.1:8301".parse().unwrap();
+
+        let mut manager = ReputationManager::new();
+        manager.add_node(trusted, 1000);
+        manager.add_node(flagged, 1000);
+        for _ in 0..3 {
+            manager
+                .update_reputation(&trusted, ReputationAction::HighQualityService)
+                .unwrap();
+        }
+        manager
+            .update_reputation(&flagged, ReputationAction::MaliciousBehavior)
+            .unwrap();
+
+        let mut lottery = RelayLottery::new().with_reputation_manager(manager);
+        lottery.add_relay(WeightedRelay::new(trusted, 0.5, 0.8, 1000));
+        lottery.add_relay(WeightedRelay::new(flagged, 0.5, 0.8, 1000));
+        let before = lottery.get_relay(&trusted).unwrap().weight;
+        assert_eq!(before, lottery.get_relay(&flagged).unwrap().weight);
+
+        lottery.sync_with_reputation_manager();
+
+        let manager = lottery.reputation_manager().unwrap();
+        for addr in [trusted, flagged] {
+            assert_eq!(
+                lottery.get_relay(&addr).unwrap().reputation,
+                manager.get_reputation_score(&addr)
+            );
+        }
+        assert!(lottery.get_relay(&trusted).unwrap().weight > before);
+        assert!(lottery.get_relay(&flagged).unwrap().weight < before);
+    }
+
+    #[test]
+    fn test_correlated_reward_burst_freezes_weights() {
+        use crate::core::reputation::ReputationAction;
+
+        let reward = ReputationAction::HighQualityService;
+        let colluding: Vec<SocketAddr> = (1..=6)
+            .map(|i| format!("10.7.7.{}:9000", i).parse().unwrap())
+            .collect();
+        let honest: Vec<SocketAddr> = (1..=6)
+            .map(|i| format!("10.{}.0.1:9000", i).parse().unwrap())
+            .collect();
+
+        let mut manager = ReputationManager::new();
+        for addr in colluding.iter().chain(&honest) {
+            manager.add_node(*addr, 1000);
+        }
+        let mut lottery = RelayLottery::new()
+            .with_reputation_manager(manager)
+            .with_manipulation_guard(ManipulationGuard::default());
+        for addr in colluding.iter().chain(&honest) {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.5, 0.8, 1000));
+        }
+
+        // High rewards spread over many subnets are ordinary
+        for addr in &honest {
+            lottery
+                .update_relay_reputation_via_manager(addr, reward)
+                .unwrap();
+        }
+        assert!(lottery.weight_freeze().is_none());
+
+        // The same number landing on one /24 at once is not
+        for addr in &colluding[..4] {
+            lottery
+                .update_relay_reputation_via_manager(addr, reward)
+                .unwrap();
+        }
+        assert!(lottery.weight_freeze().is_none());
+        let weight_before = lottery.get_relay(&colluding[4]).unwrap().weight;
+        lottery
+            .update_relay_reputation_via_manager(&colluding[4], reward)
+            .unwrap();
+        let freeze = lottery.weight_freeze().unwrap().clone();
+        assert_eq!(freeze.subnet, "10.7.7.0".parse::<IpAddr>().unwrap());
+        assert_eq!(freeze.rewards, 5);
+
+        // Reputation still moves, weights don't
+        for _ in 0..3 {
+            lottery
+                .update_relay_reputation_via_manager(&colluding[4], reward)
+                .unwrap();
+        }
+        lottery.sync_with_reputation_manager();
+        assert_eq!(lottery.get_relay(&colluding[4]).unwrap().weight, weight_before);
+        let manager = lottery.reputation_manager().unwrap();
+        assert_eq!(manager.get_reputation_points(&colluding[4]), 180);
+
+        lottery.clear_weight_freeze();
+        lottery.sync_with_reputation_manager();
+        assert!(lottery.get_relay(&colluding[4]).unwrap().weight > weight_before);
+
+        // Small rewards and rewards outside the window don't add up
+        let start = Instant::now();
+        assert!(!lottery.record_reward_at(&colluding[0], 10, start));
+        for i in 0..4u64 {
+            let at = start + Duration::from_secs(11 * i);
+            assert!(!lottery.record_reward_at(&colluding[0], 20, at));
+        }
+        assert!(lottery.weight_freeze().is_none());
+    }
+
+    #[test]
+    fn test_seeded_lotteries_repeat_selections() {
+        let populate = |mut lottery: RelayLottery| {
+            for i in 1..=12u8 {
+                let addr = format!("10.0.{}.1:9000", i).parse().unwrap();
+                let reputation = f64::from(i) / 12.0;
+                lottery.add_relay(WeightedRelay::new(addr, reputation, 0.9, 1000 * u64::from(i)));
+            }
+            lottery
+        };
+        let draw = |lottery: &mut RelayLottery| -> Vec<SocketAddr> {
+            let mut draws: Vec<SocketAddr> =
+                (0..100).map(|_| lottery.select_relay().unwrap().address).collect();
+            draws.extend(lottery.select_unique_relays(5).unwrap());
+            draws.extend(lottery.select_diverse_path(3, 24).unwrap());
+            draws
+        };
+
+        let mut first = populate(RelayLottery::new().with_rng_seed(42));
+        let mut second = populate(RelayLottery::new().with_rng_seed(42));
+        let sequence = draw(&mut first);
+        assert_eq!(sequence, draw(&mut second));
+        assert!(sequence[..100].iter().collect::<HashSet<_>>().len() > 1);
+
+        let mut reseeded = populate(RelayLottery::new().with_rng_seed(43));
+        assert_ne!(sequence, draw(&mut reseeded));
+    }
+
+    #[cfg(feature = "vrf")]
+    #[test]
+    fn test_tampered_vrf_proof_fails_verification() {
+        let mut lottery = RelayLottery::with_vrf();
+        for i in 1..=10 {
+            let addr = format!("10.0.{}.1:9000", i).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 0.8, 0.9, 5000));
+        }
+        let key = lottery.vrf_public_key().unwrap();
+        let eligible = lottery.eligible_addresses();
+
+        let (_, single) = lottery.select_relay_with_proof(b"epoch-42").unwrap();
+        let (_, multi) = lottery.select_relays_with_proof(b"epoch-42", 3).unwrap();
+        for proof in [single, multi] {
+            let bytes = proof.vrf_proof.clone().unwrap();
+            assert_eq!(bytes.len(), crate::vrf::vrf_delay::VRF_PROOF_BYTES);
+            assert!(proof.verify(&key, &eligible).unwrap());
+
+            // One flipped byte in the pre-output or in the proof
+            for position in [0, 31, 32, bytes.len() - 1] {
+                let mut tampered = proof.clone();
+                tampered.vrf_proof.as_mut().unwrap()[position] ^= 0x01;
+                assert!(!tampered.verify(&key, &eligible).unwrap(), "byte {} tampered", position);
+            }
+
+            let mut reseeded = proof.clone();
+            reseeded.seed = b"epoch-43".to_vec();
+            assert!(!reseeded.verify(&key, &eligible).unwrap());
+
+            let other_key = VrfKeyPair::generate().public_key();
+            assert!(!proof.verify(&other_key, &eligible).unwrap());
+
+            let mut truncated = proof.clone();
+            truncated.vrf_proof.as_mut().unwrap().pop();
+            assert!(!truncated.verify(&key, &eligible).unwrap());
+
+            // A valid VRF proof doesn't vouch for an edited selection
+            let mut reselected = proof.clone();
+            let swapped = eligible.iter().find(|a| **a != proof.selected[0]).unwrap();
+            reselected.selected[0] = *swapped;
+            assert!(!reselected.verify(&key, &eligible).unwrap());
+
+            let mut extended = proof.clone();
+            extended.selected.push("10.9.9.9:9000".parse().unwrap());
+            assert!(!extended.verify(&key, &eligible).unwrap());
+
+            assert!(lottery.verify_lottery_proof(&proof).unwrap());
+            assert!(!lottery.verify_lottery_proof(&reselected).unwrap());
+        }
+    }
+
+    #[cfg(feature = "vrf")]
+    #[test]
+    fn test_proof_timestamp_follows_epoch_clock() {
+        use crate::utils::epoch::ManualTimeSource;
+        use std::time::Duration;
+
+        let genesis = 1_700_000_000;
+        let source = Arc::new(ManualTimeSource::new(genesis + 3 * 600 + 125));
+        let clock = EpochClock::with_source(genesis, Duration::from_secs(600), source.clone());
+        let mut lottery = RelayLottery::with_vrf().with_epoch_clock(clock.clone());
+        for i in 0..3 {
+            let addr = format!("127.0.0.1:{}", 8500 + i).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 0.8, 0.8, 5000));
+        }
+
+        let (_, proof) = lottery.select_relay_with_proof(b"epoch-seed").unwrap();
+        assert!(clock.epoch_window(3).contains(&proof.timestamp));
+        assert_eq!(proof.timestamp, clock.epoch_start(3));
+        assert!(proof.is_within_epochs(&clock, 0));
+
+        let (_, proof) = lottery.select_relays_with_proof(b"epoch-seed", 2).unwrap();
+        assert_eq!(proof.timestamp, clock.epoch_start(3));
+
+        // The proof ages out once the network moves on
+        source.advance(Duration::from_secs(600));
+        assert!(proof.is_within_epochs(&clock, 1));
+        source.advance(Duration::from_secs(600));
+        assert!(!proof.is_within_epochs(&clock, 1));
+    }
+
+    #[test]
+    fn test_path_reuses_relays_only_non_adjacent() {
+        let mut lottery = RelayLottery::new();
+        let addrs: Vec<SocketAddr> = (0..3)
+            .map(|i| format!("127.0.0.1:{}", 8400 + i).parse().unwrap())
+            .collect();
+        for addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.8, 0.8, 1000));
+        }
+
+        assert!(lottery.select_path(5, OnShortage::Error).is_err());
+
+        for _ in 0..100 {
+            let path = lottery
+                .select_path(5, OnShortage::AllowReuseNonAdjacent)
+                .unwrap();
+            assert_eq!(path.len(), 5);
+            assert!(path.iter().all(|hop| addrs.contains(hop)));
+            assert!(path.windows(2).all(|pair| pair[0] != pair[1]));
+            // Every relay is used before any repeats
+            let first: HashSet<_> = path[..3].iter().collect();
+            assert_eq!(first.len(), 3);
+        }
+
+        // Enough relays: no reuse either way
+        let path = lottery
+            .select_path(3, OnShortage::AllowReuseNonAdjacent)
+            .unwrap();
+        assert_eq!(path.iter().collect::<HashSet<_>>().len(), 3);
+
+        // A single relay can't avoid adjacent reuse
+        let mut single = RelayLottery::new();
+        single.add_relay(WeightedRelay::new(addrs[0], 0.8, 0.8, 1000));
+        assert!(single
+            .select_path(2, OnShortage::AllowReuseNonAdjacent)
+            .is_err());
+    }
+
+    #[test]
+    fn test_gossip_targets_favor_high_reputation() {
+        let mut lottery = RelayLottery::new();
+        let high: SocketAddr = "127.0.0.1:8200".parse().unwrap();
+        let mid: SocketAddr = "127.0.0.1:8201".parse().unwrap();
+        let low: SocketAddr = "127.0.0.1:8202".parse().unwrap();
+        let silent: SocketAddr = "127.0.0.1:8203".parse().unwrap();
+        // Same performance and stake, so only reputation differs
+        lottery.add_relay(WeightedRelay::new(high, 0.9, 0.8, 1000));
+        lottery.add_relay(WeightedRelay::new(mid, 0.5, 0.8, 1000));
+        lottery.add_relay(WeightedRelay::new(low, 0.1, 0.8, 1000));
+        lottery.add_relay(WeightedRelay::new(silent, 0.0, 0.8, 1000));
+
+        let mut counts: HashMap<SocketAddr, usize> = HashMap::new();
+        for _ in 0..3000 {
+            for target in lottery.select_gossip_targets(1).unwrap() {
+                *counts.entry(target).or_insert(0) += 1;
+            }
+        }
+        assert!(counts[&high] > counts[&mid]);
+        assert!(counts[&mid] > counts[&low] * 2);
+        assert!(!counts.contains_key(&silent));
+
+        // A fanout wider than the usable peers returns each of them once
+        let mut all = lottery.select_gossip_targets(10).unwrap();
+        all.sort();
+        assert_eq!(all, vec![high, mid, low]);
+    }
+
+    #[test]
+    fn test_remove_middle_relays_keeps_map_consistent() {
+        let mut lottery = RelayLottery::new();
+        let addrs: Vec<SocketAddr> = (0..6)
+            .map(|i| format!("127.0.0.1:{}", 8100 + i).parse().unwrap())
+            .collect();
+        for addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.8, 0.8, 1000));
+        }
+
+        lottery.remove_relay(&addrs[1]);
+        lottery.remove_relay(&addrs[3]);
+        lottery.remove_relay(&addrs[1]); // already gone
+        assert_eq!(lottery.relay_count(), 4);
+
+        let remaining = [addrs[0], addrs[2], addrs[4], addrs[5]];
+        for addr in &remaining {
+            assert_eq!(lottery.get_relay(addr).unwrap().address, *addr);
+        }
+        assert!(lottery.get_relay(&addrs[1]).is_none());
+        assert!(lottery.get_relay(&addrs[3]).is_none());
+
+        for _ in 0..200 {
+            let selected = lottery.select_relay().unwrap().address;
+            assert!(remaining.contains(&selected));
+        }
+
+        // Drain down to a single relay
+        lottery.remove_relay(&addrs[4]);
+        lottery.remove_relay(&addrs[2]);
+        lottery.remove_relay(&addrs[0]);
+        assert_eq!(lottery.get_relay(&addrs[5]).unwrap().address, addrs[5]);
+        assert_eq!(lottery.select_relay().unwrap().address, addrs[5]);
+    }
+
+    #[test]
+    fn test_expected_probabilities_match_weights() {
+        let mut lottery = RelayLottery::new();
+        assert!(lottery.expected_probabilities().is_empty());
+
+        let addrs: Vec<SocketAddr> = (0..3)
+            .map(|i| format!("127.0.0.1:{}", 9100 + i).parse().unwrap())
+            .collect();
+        lottery.add_relay(WeightedRelay::new(addrs[0], 0.9, 0.8, 1000));
+        lottery.add_relay(WeightedRelay::new(addrs[1], 0.3, 0.5, 100));
+        lottery.add_relay(WeightedRelay::new(addrs[2], 0.6, 0.6, 500).with_standby(true));
+
+        let probabilities = lottery.expected_probabilities();
+        let total: f64 = probabilities.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        // Manual computation over the eligible relays only
+        let w0 = lottery.get_relay(&addrs[0]).unwrap().weight;
+        let w1 = lottery.get_relay(&addrs[1]).unwrap().weight;
+        assert!((probabilities[&addrs[0]] - w0 / (w0 + w1)).abs() < 1e-12);
+        assert!((probabilities[&addrs[1]] - w1 / (w0 + w1)).abs() < 1e-12);
+        assert_eq!(probabilities[&addrs[2]], 0.0);
+    }
+
+    #[test]
+    fn test_compromise_probability_without_replacement() {
+        let mut lottery = RelayLottery::new();
+        let addrs: Vec<SocketAddr> = (0..4)
+            .map(|i| format!("127.0.0.1:{}", 9300 + i).parse().unwrap())
+            .collect();
+        for &addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(addr, 0.5, 0.5, 100));
+        }
+        // Weights 1, 2, 3, 4
+        for (i, addr) in addrs.iter().enumerate() {
+            lottery.relays[lottery.relay_map[addr]].weight = (i + 1) as f64;
+        }
+        let colluding: HashSet<SocketAddr> = [addrs[2], addrs[3]].into_iter().collect();
+
+        // P = 3/10 * 4/7 + 4/10 * 3/6
+        let expected = 0.3 * (4.0 / 7.0) + 0.4 * 0.5;
+        assert!((lottery.compromise_probability(&colluding, 2) - expected).abs() < 1e-12);
+        // One hop is just the colluders' share of the weight
+        assert!((lottery.compromise_probability(&colluding, 1) - 0.7).abs() < 1e-12);
+        // Two colluders can't fill three distinct hops
+        assert_eq!(lottery.compromise_probability(&colluding, 3), 0.0);
+        assert_eq!(lottery.compromise_probability(&colluding, 5), 0.0);
+    }
+
+    #[test]
+    fn test_audit_probabilities_follow_weight_bounds() {
+        let mut lottery = RelayLottery::new().with_weight_bounds(WeightBounds::default());
+        let addrs: Vec<SocketAddr> = (0..3)
+            .map(|i| format!("127.0.0.1:{}", 9320 + i).parse().unwrap())
+            .collect();
+        for &addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(addr, 0.5, 0.5, 100));
+        }
+        // Log-spaced raw weights rescale to 0.01, 0.1 and 1.0
+        for (addr, weight) in addrs.iter().zip([1e-6, 1.0, 1e6]) {
+            lottery.relays[lottery.relay_map[addr]].weight = weight;
+        }
+        lottery.weighted_index = None;
+
+        let probabilities = lottery.expected_probabilities();
+        assert!((probabilities[&addrs[0]] - 0.01 / 1.11).abs() < 1e-12);
+        assert!((probabilities[&addrs[1]] - 0.1 / 1.11).abs() < 1e-12);
+        assert!((probabilities[&addrs[2]] - 1.0 / 1.11).abs() < 1e-12);
+
+        // The last pair left after either colluder is drawn rescales to
+        // 0.01 and 1.0, so the other colluder follows with 1/1.01
+        let colluding: HashSet<SocketAddr> = [addrs[1], addrs[2]].into_iter().collect();
+        let expected = (0.1 / 1.11 + 1.0 / 1.11) / 1.01;
+        assert!((lottery.compromise_probability(&colluding, 2) - expected).abs() < 1e-12);
+        assert!((lottery.compromise_probability(&colluding, 1) - 1.1 / 1.11).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_state_round_trip_preserves_probabilities() {
+        let mut lottery = RelayLottery::with_config(false, 500)
+            .with_stake_normalization(StakeNormalization::NetworkMax);
+        for i in 0..4u16 {
+            let addr: SocketAddr = format!("127.0.0.1:{}", 9200 + i).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 0.2 * i as f64, 0.7, 1000 * (i as u64 + 1)));
+        }
+        let measured: SocketAddr = "127.0.0.1:9201".parse().unwrap();
+        for _ in 0..5 {
+            lottery.record_forward(&measured, std::time::Duration::from_millis(40), true);
+        }
+
+        let json = serde_json::to_string(&lottery.export_state()).unwrap();
+        let state: LotteryState = serde_json::from_str(&json).unwrap();
+
+        let mut standby = RelayLottery::new();
+        standby.import_state(state).unwrap();
+        assert_eq!(standby.export_state(), lottery.export_state());
+        assert_eq!(standby.expected_probabilities(), lottery.expected_probabilities());
+        assert_eq!(standby.get_relay(&measured).unwrap().measured.as_ref().unwrap().samples, 5);
+        assert_eq!(standby.select_unique_relays(4).unwrap().len(), 4);
+
+        // A corrupt state is rejected and leaves the lottery untouched
+        let mut corrupt = lottery.export_state();
+        corrupt.relays.push(corrupt.relays[0].clone());
+        assert!(standby.import_state(corrupt).is_err());
+        assert_eq!(standby.relay_count(), 4);
+    }
+
     #[test]
     fn test_unique_relay_selection() {
         let mut lottery = RelayLottery::new();
@@ -658,4 +2496,454 @@ mod tests {
         let unique: HashSet<_> = selected.iter().collect();
         assert_eq!(unique.len(), 5);
     }
+
+    #[test]
+    fn test_weight_bounds_tame_extreme_spread() {
+        let spread = [1e-300, 1e-100, 1e-10, 1.0, 1e200, 1e308];
+        let build = |bounds: Option<WeightBounds>| {
+            let mut lottery = RelayLottery::new();
+            if let Some(bounds) = bounds {
+                lottery = lottery.with_weight_bounds(bounds);
+            }
+            for (i, &weight) in spread.iter().enumerate() {
+                let addr = format!("127.0.0.1:{}", 9600 + i).parse().unwrap();
+                lottery.add_relay(WeightedRelay::new(addr, 0.8, 0.8, 1000));
+                lottery.relays[i].weight = weight;
+            }
+            lottery.weighted_index = None;
+            lottery
+        };
+
+        // Raw weights: everything below the top relay is lost to rounding
+        let mut raw = build(None);
+        for _ in 0..1000 {
+            assert_eq!(raw.select_relay().unwrap().weight, 1e308);
+        }
+
+        let mut lottery = build(Some(WeightBounds::default()));
+        let mut weights = spread.to_vec();
+        WeightBounds::default().normalize(&mut weights);
+        assert!(weights.iter().all(|&w| (0.01..=1.0).contains(&w)));
+        assert!(weights.windows(2).all(|w| w[0] <= w[1]));
+        assert!((weights[0] - 0.01).abs() < 1e-12);
+
+        let mut counts = vec![0usize; spread.len()];
+        for _ in 0..20_000 {
+            let address = lottery.select_relay().unwrap().address;
+            counts[lottery.relay_map[&address]] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 0), "{:?}", counts);
+        assert!(counts[0] < counts[3] && counts[3] < counts[5], "{:?}", counts);
+
+        // Modest spreads keep their exact proportions
+        let mut modest = vec![0.2, 0.4, 0.0, 0.8];
+        WeightBounds::default().normalize(&mut modest);
+        assert_eq!(modest, vec![0.25, 0.5, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_stake_normalization_spans_range() {
+        fn stake_scores(normalization: StakeNormalization, stakes: &[u64]) -> Vec<f64> {
+            let mut lottery = RelayLottery::new().with_stake_normalization(normalization);
+            for (i, &stake) in stakes.iter().enumerate() {
+                let addr = format!("127.0.0.1:{}", 9700 + i).parse().unwrap();
+                lottery.add_relay(WeightedRelay::new(addr, 0.8, 0.8, stake));
+            }
+            lottery.relays.iter().map(|r| r.stake_score()).collect()
+        }
+
+        let tiny: Vec<u64> = (1..=10).collect();
+        let huge: Vec<u64> = (12..=18).map(|exp| 10u64.pow(exp)).collect();
+
+        // The fixed divisor squashes tiny stakes and saturates huge ones
+        let default_tiny = stake_scores(StakeNormalization::default(), &tiny);
+        assert!(default_tiny.iter().all(|&s| s < 0.15));
+        let default_huge = stake_scores(StakeNormalization::default(), &huge);
+        assert!(default_huge.iter().all(|&s| s == 1.0));
+
+        for stakes in [&tiny, &huge] {
+            let scores = stake_scores(StakeNormalization::NetworkMax, stakes);
+            assert_eq!(*scores.last().unwrap(), 1.0);
+            assert!(scores.windows(2).all(|w| w[0] < w[1]));
+            assert!(scores[0] < 0.7);
+        }
+
+        // A configurable divisor fits a known distribution
+        let scores = stake_scores(StakeNormalization::Log { divisor: 42.0 }, &huge);
+        assert!(scores.windows(2).all(|w| w[0] < w[1]));
+        assert!(scores.iter().all(|&s| s > 0.6 && s <= 1.0));
+    }
+
+    #[test]
+    fn test_standby_relays_used_only_on_shortage() {
+        let mut lottery = RelayLottery::new();
+        let active: Vec<SocketAddr> = (0..3)
+            .map(|i| format!("127.0.0.1:{}", 9750 + i).parse().unwrap())
+            .collect();
+        let spare: SocketAddr = "127.0.0.1:9760".parse().unwrap();
+        for addr in &active {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.8, 0.8, 1000));
+        }
+        lottery.add_relay(WeightedRelay::new(spare, 1.0, 1.0, 1_000_000).with_standby(true));
+
+        for _ in 0..50 {
+            assert_ne!(lottery.select_relay().unwrap().address, spare);
+            assert!(!lottery.select_unique_relays(3).unwrap().contains(&spare));
+            assert!(!lottery.select_including_standby(3).unwrap().contains(&spare));
+        }
+        assert!(lottery.select_unique_relays(4).is_err());
+
+        // Active capacity exhausted: the spare fills the last slot
+        let selected = lottery.select_including_standby(4).unwrap();
+        assert_eq!(selected.len(), 4);
+        assert!(selected.contains(&spare));
+        assert!(active.iter().all(|addr| selected.contains(addr)));
+        assert!(lottery.select_including_standby(5).is_err());
+
+        // With every active relay gone, standby is the only option
+        for addr in &active {
+            lottery.set_standby(addr, true);
+        }
+        assert!(lottery.select_relay().is_err());
+        assert_eq!(lottery.select_including_standby(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_standby_relays_respect_selection_threshold() {
+        use crate::core::reputation::ReputationAction;
+
+        let active: SocketAddr = "127.0.0.1:9770".parse().unwrap();
+        let trusted: SocketAddr = "127.0.0.1:9771".parse().unwrap();
+        let untrusted: SocketAddr = "127.0.0.1:9772".parse().unwrap();
+        let mut manager = ReputationManager::new().with_selection_threshold(90);
+        for addr in [active, trusted, untrusted] {
+            manager.add_node(addr, 1000);
+        }
+        manager
+            .update_reputation(&untrusted, ReputationAction::TaskFailure)
+            .unwrap();
+
+        let mut lottery = RelayLottery::new().with_reputation_manager(manager);
+        lottery.add_relay(WeightedRelay::new(active, 0.8, 0.8, 1000));
+        lottery.add_relay(WeightedRelay::new(trusted, 0.8, 0.8, 1000).with_standby(true));
+        lottery.add_relay(
+            WeightedRelay::new(untrusted, 1.0, 1.0, 1_000_000).with_standby(true),
+        );
+
+        // The spare below the selection threshold is never promoted
+        for _ in 0..50 {
+            let selected = lottery.select_including_standby(2).unwrap();
+            assert!(selected.contains(&trusted));
+            assert!(!selected.contains(&untrusted));
+        }
+        assert!(lottery.select_including_standby(3).is_err());
+    }
+
+    #[test]
+    fn test_diversity_optimized_circuits_beat_plain_selection() {
+        let mut lottery = RelayLottery::new();
+        // A heavy cluster sharing subnet, datacenter and AS, plus three
+        // light relays that each differ on all three
+        for i in 0..9 {
+            lottery.add_relay(
+                WeightedRelay::new(format!("10.0.0.{}:9000", i).parse().unwrap(), 1.0, 1.0, 10_000)
+                    .with_latency_class("dc-a")
+                    .with_asn(64500),
+            );
+        }
+        for i in 1..=3u8 {
+            lottery.add_relay(
+                WeightedRelay::new(format!("10.{}.0.1:9000", i).parse().unwrap(), 0.2, 0.2, 10)
+                    .with_latency_class(format!("dc-{}", i))
+                    .with_asn(64500 + u32::from(i)),
+            );
+        }
+
+        let objective = DiversityObjective::default();
+        let runs = 100;
+        let mut plain_total = 0.0;
+        for _ in 0..runs {
+            let plain = lottery.select_unique_relays(3).unwrap();
+            plain_total += lottery.diversity_score(&plain, &objective);
+
+            let circuit = lottery.select_most_diverse(3, &objective).unwrap();
+            assert_eq!(circuit.hops.len(), 3);
+            assert_eq!(circuit.diversity_score, 1.0, "circuit {:?}", circuit.hops);
+            assert_eq!(lottery.diversity_score(&circuit.hops, &objective), 1.0);
+        }
+        let plain_avg = plain_total / runs as f64;
+        assert!(plain_avg < 0.6, "plain selection averaged {:.2}", plain_avg);
+
+        // Subnet alone: a /8 prefix puts every relay in one subnet
+        let coarse = DiversityObjective {
+            latency_class: 0.0,
+            asn: 0.0,
+            ipv4_prefix: 8,
+            ..Default::default()
+        };
+        let circuit = lottery.select_most_diverse(3, &coarse).unwrap();
+        assert_eq!(circuit.diversity_score, 0.0);
+        assert!(lottery.select_most_diverse(13, &objective).is_err());
+    }
+
+    #[test]
+    fn test_diverse_path_spreads_hops_across_subnets() {
+        let mut lottery = RelayLottery::new();
+        // A heavy /24 that plain weighted selection keeps drawing from,
+        // plus one light relay in each of three other /24s
+        for i in 1..=8 {
+            let addr = format!("10.1.1.{}:9000", i).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 1.0, 1.0, 10_000));
+        }
+        for subnet in 2..=4 {
+            let addr = format!("10.1.{}.7:9000", subnet).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 0.1, 0.1, 10));
+        }
+
+        let subnet_of = |addr: &SocketAddr| ip_prefix(addr.ip(), 24, 24);
+        for _ in 0..100 {
+            let path = lottery.select_diverse_path(4, 24).unwrap();
+            assert_eq!(path.len(), 4);
+            let subnets: HashSet<IpAddr> = path.iter().map(subnet_of).collect();
+            assert_eq!(subnets.len(), 4, "hops share a subnet: {:?}", path);
+        }
+
+        // Five hops need five /24s; at /16 everything is one subnet
+        let err = lottery.select_diverse_path(5, 24).unwrap_err();
+        assert!(err.to_string().contains("4 prefix(es)"), "{}", err);
+        assert_eq!(lottery.select_diverse_path(1, 16).unwrap().len(), 1);
+        assert!(lottery.select_diverse_path(2, 16).is_err());
+    }
+
+    #[test]
+    fn test_diverse_path_rejects_single_subnet_pool() {
+        let mut lottery = RelayLottery::new();
+        for i in 1..=10 {
+            let addr = format!("127.0.0.{}:9000", i).parse().unwrap();
+            lottery.add_relay(WeightedRelay::new(addr, 1.0, 1.0, 1000));
+        }
+
+        // Plenty of relays, but all in 127.0.0.0/24
+        assert!(lottery.select_unique_relays(3).is_ok());
+        match lottery.select_diverse_path(3, 24) {
+            Err(MixnodeError::Config(msg)) => {
+                assert!(msg.contains("distinct /24 prefixes"), "{}", msg);
+                assert!(msg.contains("from 10 spanning 1 prefix(es)"), "{}", msg);
+            }
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_latency_diverse_circuit_spans_classes() {
+        let mut lottery = RelayLottery::new();
+        // One heavily weighted datacenter with many relays, two small ones
+        for i in 0..6 {
+            lottery.add_relay(
+                WeightedRelay::new(format!("10.0.0.{}:9000", i).parse().unwrap(), 1.0, 1.0, 10_000)
+                    .with_latency_class("dc-a"),
+            );
+        }
+        lottery.add_relay(
+            WeightedRelay::new("10.1.0.1:9000".parse().unwrap(), 0.2, 0.2, 10)
+                .with_latency_class("dc-b"),
+        );
+        lottery.add_relay(
+            WeightedRelay::new("10.2.0.1:9000".parse().unwrap(), 0.2, 0.2, 10)
+                .with_latency_class("dc-c"),
+        );
+
+        let class_of = |lottery: &RelayLottery, addr: &SocketAddr| {
+            lottery.get_relay(addr).unwrap().latency_class.clone().unwrap()
+        };
+
+        for _ in 0..50 {
+            let circuit = lottery.select_latency_diverse(3).unwrap();
+            let classes: HashSet<String> = circuit.iter().map(|a| class_of(&lottery, a)).collect();
+            assert_eq!(classes.len(), 3, "circuit {:?} repeated a class", circuit);
+
+            // Longer circuits only reuse a class once all are covered
+            let circuit = lottery.select_latency_diverse(5).unwrap();
+            let unique: HashSet<SocketAddr> = circuit.iter().copied().collect();
+            assert_eq!(unique.len(), 5);
+            let classes: HashSet<String> = circuit[..3].iter().map(|a| class_of(&lottery, a)).collect();
+            assert_eq!(classes.len(), 3);
+        }
+
+        assert!(lottery.select_latency_diverse(9).is_err());
+    }
+
+    #[test]
+    fn test_select_excluding() {
+        let mut lottery = RelayLottery::new();
+        let addrs: Vec<SocketAddr> = (0..8)
+            .map(|i| format!("127.0.0.1:809{}", i).parse().unwrap())
+            .collect();
+        for addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.8, 0.8, 1000));
+        }
+
+        let exclude: HashSet<SocketAddr> = addrs[..5].iter().copied().collect();
+        for _ in 0..20 {
+            let selected = lottery.select_excluding(3, &exclude).unwrap();
+            assert_eq!(selected.len(), 3);
+            assert!(selected.iter().all(|addr| !exclude.contains(addr)));
+        }
+
+        assert!(lottery.select_excluding(4, &exclude).is_err());
+    }
+
+    #[test]
+    fn test_select_relays_excluding_skips_recent_relays() {
+        let mut lottery = RelayLottery::new().with_rng_seed(11);
+        let addrs: Vec<SocketAddr> = (0..6)
+            .map(|i| format!("127.0.0.1:818{}", i).parse().unwrap())
+            .collect();
+        for addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.8, 0.8, 1000));
+        }
+
+        // Relays of the previous circuit stay out of the next one
+        let recent: HashSet<SocketAddr> = addrs[..3].iter().copied().collect();
+        let mut seen = HashSet::new();
+        for _ in 0..50 {
+            let selected = lottery.select_relays_excluding(3, &recent).unwrap();
+            assert_eq!(selected.len(), 3);
+            assert!(selected.iter().all(|addr| !recent.contains(addr)));
+            seen.extend(selected);
+        }
+        assert_eq!(seen, addrs[3..].iter().copied().collect());
+    }
+
+    #[test]
+    fn test_select_relays_excluding_reports_shortfall() {
+        let mut lottery = RelayLottery::new();
+        let addrs: Vec<SocketAddr> = (0..4)
+            .map(|i| format!("127.0.0.1:819{}", i).parse().unwrap())
+            .collect();
+        for addr in &addrs {
+            lottery.add_relay(WeightedRelay::new(*addr, 0.8, 0.8, 1000));
+        }
+
+        let exclude: HashSet<SocketAddr> = addrs[..3].iter().copied().collect();
+        let err = lottery.select_relays_excluding(2, &exclude).unwrap_err();
+        assert!(err.to_string().contains("only 1 available"), "{}", err);
+        assert_eq!(lottery.select_relays_excluding(1, &exclude).unwrap(), vec![addrs[3]]);
+    }
+
+    #[tokio::test]
+    async fn test_shared_lottery_concurrent_selection() {
+        let shared = SharedRelayLottery::default();
+        for i in 0..5 {
+            shared
+                .add_relay(WeightedRelay::new(
+                    format!("127.0.0.1:809{}", i).parse().unwrap(),
+                    0.8,
+                    0.8,
+                    1000,
+                ))
+                .await;
+        }
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let shared = shared.clone();
+                tokio::spawn(async move { shared.select_unique_relays(3).await })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap().len(), 3);
+        }
+        assert_eq!(shared.relay_count().await, 5);
+    }
+
+    #[test]
+    fn test_overclaiming_relay_down_weighted() {
+        let mut lottery = RelayLottery::new();
+        let liar: SocketAddr = "127.0.0.1:8180".parse().unwrap();
+        let honest: SocketAddr = "127.0.0.1:8181".parse().unwrap();
+        lottery.add_relay(WeightedRelay::new(liar, 0.8, 0.9, 1000));
+        lottery.add_relay(WeightedRelay::new(honest, 0.8, 0.9, 1000));
+
+        let initial_weight = lottery.get_relay(&liar).unwrap().weight;
+
+        for i in 0..30 {
+            // Liar: slow and drops a third of packets
+            lottery.record_forward(&liar, std::time::Duration::from_millis(150), i % 3 != 0);
+            lottery.record_forward(&honest, std::time::Duration::from_millis(10), true);
+        }
+
+        let divergent = lottery.verify_claimed_performance();
+        assert_eq!(divergent.len(), 1);
+        assert_eq!(divergent[0].0, liar);
+        assert!(divergent[0].1 > 0.2);
+
+        let liar_relay = lottery.get_relay(&liar).unwrap();
+        assert!(liar_relay.measured.as_ref().unwrap().penalized);
+        assert!(liar_relay.weight < initial_weight);
+        assert!(lottery.get_relay(&honest).unwrap().weight >= initial_weight - 1e-9);
+
+        // Reputation updates keep using the measured performance
+        lottery.update_relay_reputation(&liar, true);
+        assert!(lottery.get_relay(&liar).unwrap().weight < initial_weight);
+    }
+
+    #[test]
+    fn test_blocklisted_relays_excluded() {
+        use crate::core::blocklist::{Blocklist, SharedBlocklist};
+
+        let blocklist = SharedBlocklist::new(Blocklist::parse("10.0.0.0/8").unwrap());
+        let mut lottery = RelayLottery::new().with_blocklist(blocklist.clone());
+
+        for i in 1..=3 {
+            lottery.add_relay(WeightedRelay::new(
+                format!("10.0.0.{}:9000", i).parse().unwrap(),
+                0.9,
+                0.9,
+                1000,
+            ));
+            lottery.add_relay(WeightedRelay::new(
+                format!("192.0.2.{}:9000", i).parse().unwrap(),
+                0.5,
+                0.5,
+                1000,
+            ));
+        }
+
+        for addr in lottery.select_relays(200).unwrap() {
+            assert!(!blocklist.is_blocked(&addr.ip()));
+        }
+        assert!(lottery.select_unique_relays(4).is_err());
+        assert_eq!(lottery.select_unique_relays(3).unwrap().len(), 3);
+
+        // Hot reload: block the other range instead
+        blocklist.replace(Blocklist::parse("192.0.2.0/24").unwrap());
+        for addr in lottery.select_relays(200).unwrap() {
+            assert_eq!(addr.ip().to_string().split('.').next(), Some("10"));
+        }
+    }
+
+    #[cfg(not(feature = "vrf"))]
+    #[test]
+    fn test_proof_unverifiable_without_vrf() {
+        let relay: SocketAddr = "127.0.0.1:8400".parse().unwrap();
+        let lottery = RelayLottery::new();
+        let proof = LotteryProof {
+            seed: b"epoch-7".to_vec(),
+            selected: vec![relay],
+            weights: vec![1.0],
+            timestamp: 0,
+        };
+
+        let result = lottery.verify_lottery_proof(&proof).unwrap();
+        assert_eq!(result, ProofVerification::Unverifiable);
+        assert_ne!(result, ProofVerification::Verified);
+
+        let empty = LotteryProof {
+            selected: Vec::new(),
+            ..proof
+        };
+        assert_eq!(lottery.verify_lottery_proof(&empty).unwrap(), ProofVerification::Invalid);
+    }
 }
@@ -10,16 +10,31 @@
 //! Reputation affects relay selection probability in the network.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::borrow::Cow;
+use std::collections::{vec_deque, HashMap, VecDeque};
+use std::ffi::OsString;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::utils::epoch::EpochClock;
+
 /// Reputation score in points (0-200 range, base 100 for new nodes)
 pub type ReputationPoints = i32;
 
 /// Cost of forgery metric (higher = harder to fake reputation)
 pub type CostOfForgery = f64;
 
+/// Audited reputation event: Unix time, action, and the points it left
+pub type ReputationEvent = (u64, ReputationAction, ReputationPoints);
+
+/// Default number of events each node keeps in its audit log
+pub const DEFAULT_AUDIT_LOG_LEN: usize = 256;
+
+/// How far ahead of our clock a peer's merged `last_active` may be, in seconds
+pub const MAX_MERGE_CLOCK_SKEW_SECS: u64 = 60;
+
 /// Node reputation record with full tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeReputation {
@@ -32,6 +47,25 @@ pub struct NodeReputation {
     pub history: ReputationHistory,
     pub last_active: u64,        // Unix timestamp of last activity
     pub created_at: u64,         // Unix timestamp of node creation
+    /// Start of the inactivity stretch `apply_time_decay` measures from
+    #[serde(default)]
+    pub decay_anchor: Option<DecayAnchor>,
+    /// Most recent actions, oldest first, capped by the policy's
+    /// `audit_log_len`
+    #[serde(default)]
+    pub events: VecDeque<ReputationEvent>,
+}
+
+/// Points a node held when its current inactivity stretch began
+///
+/// Time decay always recomputes from here, so many short ticks truncate
+/// points once instead of once per tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecayAnchor {
+    /// Unix time decay is measured from
+    pub since: u64,
+    /// Points held at `since`
+    pub points: ReputationPoints,
 }
 
 impl NodeReputation {
@@ -52,6 +86,8 @@ impl NodeReputation {
             history: ReputationHistory::default(),
             last_active: now,
             created_at: now,
+            decay_anchor: None,
+            events: VecDeque::new(),
         }
     }
 
@@ -62,39 +98,107 @@ impl NodeReputation {
         node
     }
 
-    /// Update reputation points based on action
+    /// Update reputation points based on action, using default deltas
     pub fn apply_action(&mut self, action: ReputationAction) {
-        let delta = action.points_delta();
-        self.reputation_points = (self.reputation_points + delta).clamp(0, 200);
+        self.apply_action_with_policy(action, &ReputationPolicy::default());
+    }
 
-        // Update normalized scores
-        self.reputation = self.reputation_points as f64 / 200.0;
-        self.score = self.reputation;
+    /// Update reputation points using the deltas from `policy`
+    pub fn apply_action_with_policy(&mut self, action: ReputationAction, policy: &ReputationPolicy) {
+        let delta = policy.delta(action);
+        self.set_points(self.reputation_points.saturating_add(delta), policy);
+        self.decay_anchor = None;
 
         // Track in history
         self.history.record_action(action);
         self.update_last_active();
+        self.record_event(action, policy.audit_log_len);
+    }
+
+    /// Up to the `n` most recent audited events, oldest first
+    pub fn recent_events(&self, n: usize) -> vec_deque::Iter<'_, ReputationEvent> {
+        self.events.range(self.events.len().saturating_sub(n)..)
+    }
+
+    /// Append to the audit log, evicting the oldest events past `cap`
+    fn record_event(&mut self, action: ReputationAction, cap: usize) {
+        self.events.push_back((self.last_active, action, self.reputation_points));
+        while self.events.len() > cap {
+            self.events.pop_front();
+        }
     }
 
     /// Apply time-based decay (-1% per day of inactivity)
     pub fn apply_decay(&mut self, days_inactive: u32) {
+        self.apply_decay_with_model(days_inactive, &DecayModel::default());
+    }
+
+    /// Apply time-based decay using a specific decay model
+    pub fn apply_decay_with_model(&mut self, days_inactive: u32, model: &DecayModel) {
+        self.apply_decay_with_policy(days_inactive, model, &ReputationPolicy::default());
+    }
+
+    /// Apply time-based decay, keeping points within the bounds of `policy`
+    pub fn apply_decay_with_policy(
+        &mut self,
+        days_inactive: u32,
+        model: &DecayModel,
+        policy: &ReputationPolicy,
+    ) {
         if days_inactive == 0 {
             return;
         }
 
-        // Apply 1% decay per day: reputation *= 0.99^days
-        let decay_factor = 0.99_f64.powi(days_inactive as i32);
-        let new_points = (self.reputation_points as f64 * decay_factor) as i32;
+        let new_points = model.decay(self.reputation_points, days_inactive);
+        self.set_points(new_points, policy);
+        self.decay_anchor = None;
 
-        self.reputation_points = new_points.clamp(0, 200);
-        self.reputation = self.reputation_points as f64 / 200.0;
-        self.score = self.reputation;
+        self.history.decay_events += 1;
+    }
 
+    /// Decay for the time since this node went inactive, as of `now`
+    ///
+    /// Partial days decay proportionally. Returns whether points changed.
+    pub fn apply_time_decay_with_policy(
+        &mut self,
+        now: u64,
+        model: &DecayModel,
+        policy: &ReputationPolicy,
+    ) -> bool {
+        let anchor = match self.decay_anchor {
+            Some(anchor) if anchor.since >= self.last_active => anchor,
+            _ => DecayAnchor {
+                since: self.last_active,
+                points: self.reputation_points,
+            },
+        };
+        self.decay_anchor = Some(anchor);
+
+        let days = now.saturating_sub(anchor.since) as f64 / 86400.0;
+        let old_points = self.reputation_points;
+        self.set_points(model.decay_fractional(anchor.points, days), policy);
+        if self.reputation_points == old_points {
+            return false;
+        }
         self.history.decay_events += 1;
+        true
+    }
+
+    /// Store `points` clamped to the policy bounds and refresh the
+    /// normalized scores
+    fn set_points(&mut self, points: ReputationPoints, policy: &ReputationPolicy) {
+        self.reputation_points = policy.clamp(points);
+        self.reputation = policy.normalize(self.reputation_points);
+        self.score = self.reputation;
     }
 
     /// Calculate cost of forgery for this node
     pub fn cost_of_forgery(&self) -> CostOfForgery {
+        self.cost_of_forgery_at(unix_now())
+    }
+
+    /// Calculate cost of forgery as of `now` (Unix seconds)
+    pub fn cost_of_forgery_at(&self, now: u64) -> CostOfForgery {
         // Cost factors:
         // 1. Stake (economic commitment)
         // 2. Reputation points (history/trust)
@@ -103,7 +207,7 @@ impl NodeReputation {
 
         let stake_factor = (self.stake as f64).ln().max(1.0);
         let reputation_factor = (self.reputation_points as f64 / 100.0).max(0.1);
-        let age_factor = self.account_age_days().min(365.0) / 365.0; // Cap at 1 year
+        let age_factor = self.account_age_days_at(now).min(365.0) / 365.0; // Cap at 1 year
         let success_factor = self.metrics.success_rate();
 
         // Combined cost: multiply factors
@@ -113,22 +217,22 @@ impl NodeReputation {
 
     /// Get account age in days
     pub fn account_age_days(&self) -> f64 {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.account_age_days_at(unix_now())
+    }
 
-        ((now - self.created_at) as f64) / 86400.0 // seconds to days
+    /// Get account age in days as of `now` (Unix seconds)
+    pub fn account_age_days_at(&self, now: u64) -> f64 {
+        (now.saturating_sub(self.created_at) as f64) / 86400.0 // seconds to days
     }
 
     /// Get days since last activity
     pub fn days_since_active(&self) -> u32 {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.days_since_active_at(unix_now())
+    }
 
-        ((now - self.last_active) / 86400) as u32
+    /// Get days since last activity as of `now` (Unix seconds)
+    pub fn days_since_active_at(&self, now: u64) -> u32 {
+        (now.saturating_sub(self.last_active) / 86400) as u32
     }
 
     /// Update last active timestamp
@@ -146,7 +250,7 @@ impl NodeReputation {
 }
 
 /// Reputation action types with point deltas
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReputationAction {
     /// Successful task completion (+10 points)
     SuccessfulTask,
@@ -165,22 +269,161 @@ pub enum ReputationAction {
 }
 
 impl ReputationAction {
-    /// Get point delta for this action
+    /// Get point delta for this action under the default policy
     pub fn points_delta(&self) -> i32 {
-        match self {
-            ReputationAction::SuccessfulTask => 10,
-            ReputationAction::UptimeMilestone => 5,
-            ReputationAction::HighQualityService => 20,
-            ReputationAction::TaskFailure => -15,
-            ReputationAction::DroppedConnection => -25,
-            ReputationAction::MaliciousBehavior => -50,
-            ReputationAction::Custom(delta) => *delta,
+        ReputationPolicy::default().delta(*self)
+    }
+}
+
+/// Point delta applied for each reputation action, and the range points
+/// are kept in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReputationPolicy {
+    pub successful_task: ReputationPoints,
+    pub uptime_milestone: ReputationPoints,
+    pub high_quality_service: ReputationPoints,
+    pub task_failure: ReputationPoints,
+    pub dropped_connection: ReputationPoints,
+    pub malicious_behavior: ReputationPoints,
+    /// Lowest points a node can fall to
+    pub min_points: ReputationPoints,
+    /// Highest points a node can reach; normalized scores are relative to it
+    pub max_points: ReputationPoints,
+    /// Events kept in each node's audit log; 0 disables the log
+    pub audit_log_len: usize,
+}
+
+impl ReputationPolicy {
+    /// Point delta for `action`; `Custom` carries its own delta
+    pub fn delta(&self, action: ReputationAction) -> ReputationPoints {
+        match action {
+            ReputationAction::SuccessfulTask => self.successful_task,
+            ReputationAction::UptimeMilestone => self.uptime_milestone,
+            ReputationAction::HighQualityService => self.high_quality_service,
+            ReputationAction::TaskFailure => self.task_failure,
+            ReputationAction::DroppedConnection => self.dropped_connection,
+            ReputationAction::MaliciousBehavior => self.malicious_behavior,
+            ReputationAction::Custom(delta) => delta,
+        }
+    }
+
+    /// Clamp `points` into `min_points..=max_points`
+    ///
+    /// An inverted range clamps to `max_points` rather than panicking.
+    pub fn clamp(&self, points: ReputationPoints) -> ReputationPoints {
+        points.max(self.min_points).min(self.max_points)
+    }
+
+    /// Normalized score (0.0-1.0) for `points`
+    pub fn normalize(&self, points: ReputationPoints) -> f64 {
+        if self.max_points <= 0 {
+            return 0.0;
+        }
+        (points.max(0) as f64 / self.max_points as f64).min(1.0)
+    }
+}
+
+impl Default for ReputationPolicy {
+    fn default() -> Self {
+        Self {
+            successful_task: 10,
+            uptime_milestone: 5,
+            high_quality_service: 20,
+            task_failure: -15,
+            dropped_connection: -25,
+            malicious_behavior: -50,
+            min_points: 0,
+            max_points: 200,
+            audit_log_len: DEFAULT_AUDIT_LOG_LEN,
+        }
+    }
+}
+
+/// How reputation points decay over days of inactivity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DecayModel {
+    /// Multiply points by `rate` per day (`points * rate^days`)
+    Exponential { rate: f64 },
+    /// Subtract `per_day` points per day
+    Linear { per_day: ReputationPoints },
+    /// Exponential decay that stops at `floor` points; nodes already below
+    /// the floor are left alone
+    ExponentialWithFloor { rate: f64, floor: ReputationPoints },
+}
+
+impl DecayModel {
+    /// Points left after `days` days of inactivity
+    pub fn decay(&self, points: ReputationPoints, days: u32) -> ReputationPoints {
+        if days == 0 {
+            return points;
+        }
+        match *self {
+            DecayModel::Exponential { rate } => Self::exponential(points, rate, days),
+            DecayModel::Linear { per_day } => {
+                let loss = (per_day.max(0) as i64).saturating_mul(days as i64);
+                (points as i64 - loss).max(0) as ReputationPoints
+            }
+            DecayModel::ExponentialWithFloor { rate, floor } => {
+                if points <= floor {
+                    points
+                } else {
+                    Self::exponential(points, rate, days).max(floor)
+                }
+            }
         }
     }
+
+    /// Points left after `days` (possibly fractional) days of inactivity
+    pub fn decay_fractional(&self, points: ReputationPoints, days: f64) -> ReputationPoints {
+        if days <= 0.0 {
+            return points;
+        }
+        let exponential = |rate: f64| {
+            (points as f64 * rate.clamp(0.0, 1.0).powf(days)) as ReputationPoints
+        };
+        match *self {
+            DecayModel::Exponential { rate } => exponential(rate),
+            DecayModel::Linear { per_day } => {
+                (points as f64 - per_day.max(0) as f64 * days).max(0.0) as ReputationPoints
+            }
+            DecayModel::ExponentialWithFloor { rate, floor } => {
+                if points <= floor {
+                    points
+                } else {
+                    exponential(rate).max(floor)
+                }
+            }
+        }
+    }
+
+    fn exponential(points: ReputationPoints, rate: f64, days: u32) -> ReputationPoints {
+        let decay_factor = rate.clamp(0.0, 1.0).powi(days.min(i32::MAX as u32) as i32);
+        (points as f64 * decay_factor) as ReputationPoints
+    }
+}
+
+impl Default for DecayModel {
+    /// 1% decay per day
+    fn default() -> Self {
+        DecayModel::Exponential { rate: 0.99 }
+    }
+}
+
+/// When inactivity decay is applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DecayMode {
+    /// Stored points decay only when `apply_decay_all` sweeps them
+    #[default]
+    Scheduled,
+    /// Reads report decayed points computed from `days_since_active`;
+    /// stored points catch up on the node's next update and
+    /// `apply_decay_all` is a no-op
+    Lazy,
 }
 
 /// Historical reputation tracking
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ReputationHistory {
     pub successful_tasks: u32,
     pub failed_tasks: u32,
@@ -192,21 +435,6 @@ pub struct ReputationHistory {
     pub total_actions: u32,
 }
 
-impl Default for ReputationHistory {
-    fn default() -> Self {
-        Self {
-            successful_tasks: 0,
-            failed_tasks: 0,
-            uptime_milestones: 0,
-            quality_bonuses: 0,
-            dropped_connections: 0,
-            malicious_events: 0,
-            decay_events: 0,
-            total_actions: 0,
-        }
-    }
-}
-
 impl ReputationHistory {
     /// Record an action in history
     fn record_action(&mut self, action: ReputationAction) {
@@ -232,12 +460,60 @@ impl ReputationHistory {
     }
 }
 
+/// Notification emitted when a node's points move past the change threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReputationChange {
+    pub node: SocketAddr,
+    pub old_points: ReputationPoints,
+    pub new_points: ReputationPoints,
+}
+
+impl ReputationChange {
+    /// Signed change in points
+    pub fn delta(&self) -> ReputationPoints {
+        self.new_points - self.old_points
+    }
+}
+
+/// Callback invoked on significant reputation changes
+///
+/// Runs inline with the update, so it should hand work off (e.g. to a
+/// channel) rather than block.
+pub type ReputationChangeCallback = Box<dyn Fn(ReputationChange) + Send + Sync>;
+
 /// Reputation manager for all network nodes
-#[derive(Debug, Default)]
+///
+/// Plain owned state (`Send + Sync`, not internally synchronized); share it
+/// between tasks behind an `Arc<RwLock<_>>`.
+#[derive(Default)]
 pub struct ReputationManager {
-    reputations: HashMap<String, NodeReputation>,
-    decay_rate: f64,
-    min_reputation_threshold: ReputationPoints,
+    reputations: HashMap<SocketAddr, NodeReputation>,
+    decay_model: DecayModel,
+    admission_threshold: ReputationPoints,
+    selection_threshold: ReputationPoints,
+    change_callback: Option<ReputationChangeCallback>,
+    change_threshold: ReputationPoints,
+    policy: ReputationPolicy,
+    decay_mode: DecayMode,
+    epoch_clock: Option<EpochClock>,
+    last_decayed_epoch: Option<u64>,
+}
+
+impl std::fmt::Debug for ReputationManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReputationManager")
+            .field("reputations", &self.reputations)
+            .field("decay_model", &self.decay_model)
+            .field("admission_threshold", &self.admission_threshold)
+            .field("selection_threshold", &self.selection_threshold)
+            .field("change_callback", &self.change_callback.is_some())
+            .field("change_threshold", &self.change_threshold)
+            .field("policy", &self.policy)
+            .field("decay_mode", &self.decay_mode)
+            .field("epoch_clock", &self.epoch_clock)
+            .field("last_decayed_epoch", &self.last_decayed_epoch)
+            .finish()
+    }
 }
 
 impl ReputationManager {
@@ -245,61 +521,228 @@ impl ReputationManager {
     pub fn new() -> Self {
         Self {
             reputations: HashMap::new(),
-            decay_rate: 0.99,  // 1% decay per day
-            min_reputation_threshold: 50, // Minimum 50 points to participate
+            decay_model: DecayModel::default(), // 1% decay per day
+            admission_threshold: 50, // Minimum 50 points to participate
+            selection_threshold: 50,
+            change_callback: None,
+            change_threshold: 0,
+            policy: ReputationPolicy::default(),
+            decay_mode: DecayMode::default(),
+            epoch_clock: None,
+            last_decayed_epoch: None,
         }
     }
 
-    /// Create with custom threshold
+    /// Create with one threshold for both admission and selection
     pub fn with_threshold(min_threshold: ReputationPoints) -> Self {
         Self {
             reputations: HashMap::new(),
-            decay_rate: 0.99,
-            min_reputation_threshold: min_threshold,
+            decay_model: DecayModel::default(),
+            admission_threshold: min_threshold,
+            selection_threshold: min_threshold,
+            change_callback: None,
+            change_threshold: 0,
+            policy: ReputationPolicy::default(),
+            decay_mode: DecayMode::default(),
+            epoch_clock: None,
+            last_decayed_epoch: None,
+        }
+    }
+
+    /// Points a node needs to be allowed to connect
+    pub fn with_admission_threshold(mut self, points: ReputationPoints) -> Self {
+        self.admission_threshold = points;
+        self
+    }
+
+    /// Points a node needs to be eligible for circuit selection
+    ///
+    /// Usually set above the admission threshold, so a node can keep
+    /// connecting while it earns its way back into circuits.
+    pub fn with_selection_threshold(mut self, points: ReputationPoints) -> Self {
+        self.selection_threshold = points;
+        self
+    }
+
+    /// Points needed to connect
+    pub fn admission_threshold(&self) -> ReputationPoints {
+        self.admission_threshold
+    }
+
+    /// Points needed to be selected for circuits
+    pub fn selection_threshold(&self) -> ReputationPoints {
+        self.selection_threshold
+    }
+
+    /// Apply actions with custom point deltas
+    pub fn with_policy(mut self, policy: ReputationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Replace the point deltas used for future actions
+    pub fn set_policy(&mut self, policy: ReputationPolicy) {
+        self.policy = policy;
+    }
+
+    /// Current point deltas
+    pub fn policy(&self) -> &ReputationPolicy {
+        &self.policy
+    }
+
+    /// Decay inactive nodes using `model`
+    pub fn with_decay_model(mut self, model: DecayModel) -> Self {
+        self.decay_model = model;
+        self
+    }
+
+    /// Replace the decay model used by `apply_decay_all`
+    pub fn set_decay_model(&mut self, model: DecayModel) {
+        self.decay_model = model;
+    }
+
+    /// Current decay model
+    pub fn decay_model(&self) -> DecayModel {
+        self.decay_model
+    }
+
+    /// Decay on read or on sweep
+    pub fn with_decay_mode(mut self, mode: DecayMode) -> Self {
+        self.decay_mode = mode;
+        self
+    }
+
+    /// Current decay mode
+    pub fn decay_mode(&self) -> DecayMode {
+        self.decay_mode
+    }
+
+    /// Measure inactivity in epochs of `clock` for
+    /// [`apply_decay_for_epoch`](Self::apply_decay_for_epoch)
+    pub fn with_epoch_clock(mut self, clock: EpochClock) -> Self {
+        self.epoch_clock = Some(clock);
+        self
+    }
+
+    /// Epoch clock used for epoch-aligned decay
+    pub fn epoch_clock(&self) -> Option<&EpochClock> {
+        self.epoch_clock.as_ref()
+    }
+
+    /// Last epoch decay was applied for
+    pub fn last_decayed_epoch(&self) -> Option<u64> {
+        self.last_decayed_epoch
+    }
+
+    /// Points `rep` holds as seen by readers as of `now`
+    fn effective_points(&self, rep: &NodeReputation, now: u64) -> ReputationPoints {
+        match self.decay_mode {
+            DecayMode::Scheduled => rep.reputation_points,
+            DecayMode::Lazy => self.policy.clamp(
+                self.decay_model
+                    .decay(rep.reputation_points, rep.days_since_active_at(now)),
+            ),
+        }
+    }
+
+    /// `rep` as seen by readers as of `now`, with points and scores decayed
+    /// under [`DecayMode::Lazy`]
+    fn view_at<'a>(&self, rep: &'a NodeReputation, now: u64) -> Cow<'a, NodeReputation> {
+        match self.decay_mode {
+            DecayMode::Scheduled => Cow::Borrowed(rep),
+            DecayMode::Lazy => {
+                let mut view = rep.clone();
+                view.reputation_points = self.effective_points(rep, now);
+                view.reputation = self.policy.normalize(view.reputation_points);
+                view.score = view.reputation;
+                Cow::Owned(view)
+            }
+        }
+    }
+
+    /// Register a callback fired when a node's points change by more than
+    /// the change threshold (replaces any previous callback)
+    pub fn on_reputation_change(&mut self, callback: ReputationChangeCallback) {
+        self.change_callback = Some(callback);
+    }
+
+    /// Set the minimum absolute point change that triggers the callback
+    pub fn set_change_threshold(&mut self, threshold: ReputationPoints) {
+        self.change_threshold = threshold.max(0);
+    }
+
+    /// Invoke the change callback if the change is significant
+    fn notify_change(&self, node: SocketAddr, old_points: ReputationPoints, new_points: ReputationPoints) {
+        if let Some(callback) = &self.change_callback {
+            if (new_points - old_points).abs() > self.change_threshold {
+                callback(ReputationChange {
+                    node,
+                    old_points,
+                    new_points,
+                });
+            }
         }
     }
 
     /// Add new node with stake
     pub fn add_node(&mut self, addr: SocketAddr, stake: u64) {
-        let node_id = addr.to_string();
-        let rep = NodeReputation::with_stake(node_id, stake);
-        self.reputations.insert(addr.to_string(), rep);
+        let rep = NodeReputation::with_stake(addr.to_string(), stake);
+        self.reputations.insert(addr, rep);
     }
 
-    /// Get node reputation
+    /// Get node reputation, decayed to now under [`DecayMode::Lazy`]
     pub fn get_reputation(&self, addr: &SocketAddr) -> Option<NodeReputation> {
-        self.reputations.get(&addr.to_string()).cloned()
+        let now = unix_now();
+        self.reputation(addr)
+            .map(|r| self.view_at(r, now).into_owned())
+    }
+
+    /// Borrow the stored node reputation without cloning the record
+    ///
+    /// Under [`DecayMode::Lazy`] this is the record as last written, without
+    /// pending decay.
+    pub fn reputation(&self, addr: &SocketAddr) -> Option<&NodeReputation> {
+        self.reputations.get(addr)
     }
 
     /// Get reputation score (0.0-1.0)
     pub fn get_reputation_score(&self, addr: &SocketAddr) -> f64 {
-        self.get_reputation(addr)
-            .map(|r| r.reputation)
+        self.reputation(addr)
+            .map(|r| self.policy.normalize(self.effective_points(r, unix_now())))
             .unwrap_or(0.5) // Default to middle reputation for unknown nodes
     }
 
     /// Get reputation points
     pub fn get_reputation_points(&self, addr: &SocketAddr) -> ReputationPoints {
-        self.get_reputation(addr)
-            .map(|r| r.reputation_points)
+        self.reputation(addr)
+            .map(|r| self.effective_points(r, unix_now()))
             .unwrap_or(100) // Default to base points for unknown nodes
     }
 
     /// Calculate cost of forgery for a node
     pub fn calculate_cost_of_forgery(&self, addr: &SocketAddr) -> CostOfForgery {
-        self.get_reputation(addr)
-            .map(|r| r.cost_of_forgery())
+        self.reputation(addr)
+            .map(|r| self.view_at(r, unix_now()).cost_of_forgery())
             .unwrap_or(1.0) // Low cost for unknown nodes
     }
 
     /// Update reputation based on action
     pub fn update_reputation(&mut self, addr: &SocketAddr, action: ReputationAction) -> Result<(), String> {
-        let node_id = addr.to_string();
         let reputation = self.reputations
-            .entry(node_id.clone())
-            .or_insert_with(|| NodeReputation::new(node_id));
+            .entry(*addr)
+            .or_insert_with(|| NodeReputation::new(addr.to_string()));
+
+        // Settle pending lazy decay before the action refreshes activity
+        if self.decay_mode == DecayMode::Lazy {
+            let days_inactive = reputation.days_since_active();
+            reputation.apply_decay_with_policy(days_inactive, &self.decay_model, &self.policy);
+        }
+
+        let old_points = reputation.reputation_points;
+        reputation.apply_action_with_policy(action, &self.policy);
+        let new_points = reputation.reputation_points;
 
-        reputation.apply_action(action);
+        self.notify_change(*addr, old_points, new_points);
         Ok(())
     }
 
@@ -324,40 +767,150 @@ impl ReputationManager {
     }
 
     /// Apply decay to all nodes based on inactivity
+    ///
+    /// Does nothing under [`DecayMode::Lazy`], where reads already decay.
     pub fn apply_decay_all(&mut self) {
-        for reputation in self.reputations.values_mut() {
+        if self.decay_mode == DecayMode::Lazy {
+            return;
+        }
+        let mut changes = Vec::new();
+        for (addr, reputation) in self.reputations.iter_mut() {
             let days_inactive = reputation.days_since_active();
             if days_inactive > 0 {
-                // Use decay_rate from manager configuration
-                let decay_factor = self.decay_rate.powi(days_inactive as i32);
-                let new_points = (reputation.reputation_points as f64 * decay_factor) as i32;
-
-                reputation.reputation_points = new_points.clamp(0, 200);
-                reputation.reputation = reputation.reputation_points as f64 / 200.0;
-                reputation.score = reputation.reputation;
-                reputation.history.decay_events += 1;
+                let old_points = reputation.reputation_points;
+                reputation.apply_decay_with_policy(days_inactive, &self.decay_model, &self.policy);
+
+                changes.push((*addr, old_points, reputation.reputation_points));
             }
         }
+
+        for (addr, old_points, new_points) in changes {
+            self.notify_change(addr, old_points, new_points);
+        }
+    }
+
+    /// Decay every node for the wall-clock time it has been inactive as of
+    /// `now` (Unix seconds)
+    ///
+    /// Fractional days decay proportionally and each call recomputes from
+    /// the start of the inactivity stretch, so a background task can tick
+    /// this as often as it likes without over- or under-penalizing. Does
+    /// nothing under [`DecayMode::Lazy`].
+    pub fn apply_time_decay(&mut self, now: u64) {
+        if self.decay_mode == DecayMode::Lazy {
+            return;
+        }
+        let mut changes = Vec::new();
+        for (addr, reputation) in self.reputations.iter_mut() {
+            let old_points = reputation.reputation_points;
+            if reputation.apply_time_decay_with_policy(now, &self.decay_model, &self.policy) {
+                changes.push((*addr, old_points, reputation.reputation_points));
+            }
+        }
+
+        for (addr, old_points, new_points) in changes {
+            self.notify_change(addr, old_points, new_points);
+        }
+    }
+
+    /// Decay all nodes up to `epoch` on the shared epoch clock
+    ///
+    /// The decay model's unit becomes one epoch instead of one day. Each node
+    /// decays by the epochs between its last active epoch (or the previous
+    /// sweep, if later) and `epoch`, so managers sharing a clock decay in
+    /// lockstep. Returns `Ok(false)` without changes when `epoch` has already
+    /// been applied, or under [`DecayMode::Lazy`]; fails without an
+    /// [`EpochClock`].
+    pub fn apply_decay_for_epoch(&mut self, epoch: u64) -> Result<bool, String> {
+        let clock = self
+            .epoch_clock
+            .as_ref()
+            .ok_or_else(|| "Epoch decay needs an epoch clock".to_string())?;
+        if self.decay_mode == DecayMode::Lazy
+            || self.last_decayed_epoch.is_some_and(|last| last >= epoch)
+        {
+            return Ok(false);
+        }
+
+        let mut changes = Vec::new();
+        for (addr, reputation) in self.reputations.iter_mut() {
+            let active_epoch = clock.epoch_at(reputation.last_active);
+            let since = self
+                .last_decayed_epoch
+                .map_or(active_epoch, |last| last.max(active_epoch));
+            let epochs = epoch.saturating_sub(since).min(u32::MAX as u64) as u32;
+            if epochs > 0 {
+                let old_points = reputation.reputation_points;
+                reputation.apply_decay_with_policy(epochs, &self.decay_model, &self.policy);
+                changes.push((*addr, old_points, reputation.reputation_points));
+            }
+        }
+        self.last_decayed_epoch = Some(epoch);
+
+        for (addr, old_points, new_points) in changes {
+            self.notify_change(addr, old_points, new_points);
+        }
+        Ok(true)
     }
 
     /// Get weighted relay candidates above threshold
     pub fn get_weighted_relay_candidates(&self, min_reputation: ReputationPoints) -> Vec<(SocketAddr, f64)> {
+        let now = unix_now();
         self.reputations
             .iter()
+            .map(|(addr, rep)| (*addr, self.view_at(rep, now)))
             .filter(|(_, rep)| rep.reputation_points >= min_reputation)
-            .filter_map(|(addr_str, rep)| {
-                addr_str.parse::<SocketAddr>().ok().map(|addr| (addr, rep.reputation))
-            })
+            .map(|(addr, rep)| (addr, rep.reputation))
             .collect()
     }
 
-    /// Check if node meets minimum threshold
+    /// Check if node meets the admission threshold
     pub fn meets_threshold(&self, addr: &SocketAddr) -> bool {
-        self.get_reputation(addr)
-            .map(|r| r.meets_threshold(self.min_reputation_threshold))
+        self.is_admissible(addr)
+    }
+
+    /// Check if node may connect
+    pub fn is_admissible(&self, addr: &SocketAddr) -> bool {
+        self.is_admissible_at(addr, unix_now())
+    }
+
+    /// Check if node may connect as of `now` (Unix seconds)
+    pub fn is_admissible_at(&self, addr: &SocketAddr, now: u64) -> bool {
+        self.reputation(addr)
+            .map(|r| self.effective_points(r, now) >= self.admission_threshold)
             .unwrap_or(true) // Allow new nodes by default
     }
 
+    /// Check if a connection from `ip` may be accepted
+    ///
+    /// Inbound connections arrive from ephemeral ports, so this matches every
+    /// tracked node on that host and refuses if any is below the admission
+    /// threshold.
+    pub fn admits_ip(&self, ip: &IpAddr) -> bool {
+        self.admits_ip_at(ip, unix_now())
+    }
+
+    /// Check if a connection from `ip` may be accepted as of `now` (Unix
+    /// seconds)
+    pub fn admits_ip_at(&self, ip: &IpAddr, now: u64) -> bool {
+        self.reputations
+            .iter()
+            .filter(|(addr, _)| addr.ip() == *ip)
+            .all(|(_, r)| self.effective_points(r, now) >= self.admission_threshold)
+    }
+
+    /// Check if node may be picked for circuits
+    pub fn is_selectable(&self, addr: &SocketAddr) -> bool {
+        self.is_selectable_at(addr, unix_now())
+    }
+
+    /// Check if node may be picked for circuits as of `now` (Unix seconds)
+    pub fn is_selectable_at(&self, addr: &SocketAddr, now: u64) -> bool {
+        self.reputation(addr)
+            .map(|r| self.effective_points(r, now) >= self.selection_threshold)
+            .unwrap_or(true)
+    }
+
     /// Get total number of tracked nodes
     pub fn node_count(&self) -> usize {
         self.reputations.len()
@@ -369,47 +922,322 @@ impl ReputationManager {
             return ReputationStatistics::default();
         }
 
-        let total_nodes = self.reputations.len();
-        let avg_reputation = self.reputations.values()
-            .map(|r| r.reputation)
-            .sum::<f64>() / total_nodes as f64;
-
-        let avg_points = self.reputations.values()
-            .map(|r| r.reputation_points)
-            .sum::<i32>() / total_nodes as i32;
-
-        let avg_cost_of_forgery = self.reputations.values()
-            .map(|r| r.cost_of_forgery())
-            .sum::<f64>() / total_nodes as f64;
-
-        let above_threshold = self.reputations.values()
-            .filter(|r| r.reputation_points >= self.min_reputation_threshold)
-            .count();
+        // Single pass over one borrow of the map, with one clock read, so
+        // every aggregate describes the same state
+        let now = unix_now();
+        let mut reputation_sum = 0.0;
+        let mut points_sum: i64 = 0;
+        let mut cost_sum = 0.0;
+        let mut above_threshold = 0;
+        for node in self.reputations.values() {
+            let node = self.view_at(node, now);
+            reputation_sum += node.reputation;
+            points_sum += node.reputation_points as i64;
+            cost_sum += node.cost_of_forgery_at(now);
+            if node.reputation_points >= self.admission_threshold {
+                above_threshold += 1;
+            }
+        }
 
+        let total_nodes = self.reputations.len();
         ReputationStatistics {
             total_nodes,
-            avg_reputation,
-            avg_points,
-            avg_cost_of_forgery,
+            avg_reputation: reputation_sum / total_nodes as f64,
+            avg_points: (points_sum / total_nodes as i64) as ReputationPoints,
+            avg_cost_of_forgery: cost_sum / total_nodes as f64,
             nodes_above_threshold: above_threshold,
-            min_threshold: self.min_reputation_threshold,
+            min_threshold: self.admission_threshold,
         }
     }
 
+    /// Per-relay reputation samples for metrics export, ordered by address
+    ///
+    /// Field names match the monitoring exporter's `RelayReputationSample`, so
+    /// the serialized list can be handed to its `ReputationMetricSource`.
+    pub fn relay_metrics(&self) -> Vec<RelayReputationMetric> {
+        let now = unix_now();
+        let mut addrs: Vec<&SocketAddr> = self.reputations.keys().collect();
+        addrs.sort();
+        addrs
+            .into_iter()
+            .map(|addr| {
+                let node = self.view_at(&self.reputations[addr], now);
+                RelayReputationMetric {
+                    relay: addr.to_string(),
+                    reputation: node.reputation,
+                    reputation_points: node.reputation_points,
+                    cost_of_forgery: node.cost_of_forgery_at(now),
+                }
+            })
+            .collect()
+    }
+
     /// Persist reputation data to JSON (for cross-session storage)
     pub fn save_to_json(&self) -> Result<String, String> {
-        serde_json::to_string_pretty(&self.reputations)
+        serde_json::to_string_pretty(&PersistedReputations(&self.reputations))
             .map_err(|e| format!("Failed to serialize reputation data: {}", e))
     }
 
     /// Load reputation data from JSON
     pub fn load_from_json(&mut self, json_data: &str) -> Result<(), String> {
-        let reputations: HashMap<String, NodeReputation> = serde_json::from_str(json_data)
+        let reputations = addr_keyed::deserialize(&mut serde_json::Deserializer::from_str(json_data))
             .map_err(|e| format!("Failed to deserialize reputation data: {}", e))?;
 
         self.reputations = reputations;
         Ok(())
     }
+
+    /// Save reputation data to `path`, creating parent directories
+    ///
+    /// The JSON is written to a `.tmp` sibling and renamed over `path`, so a
+    /// process killed mid-save leaves the previous file intact.
+    pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        let json = self.save_to_json()?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let tmp = tmp_sibling(path);
+        let write = || -> std::io::Result<()> {
+            let mut file = std::fs::File::create(&tmp)?;
+            file.write_all(json.as_bytes())?;
+            file.sync_all()
+        };
+        write().map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, path)
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+
+    /// Load reputation data saved by [`save_to_file`](Self::save_to_file)
+    ///
+    /// A missing file loads nothing and leaves the current state untouched,
+    /// so a first start needs no special case and a wrong path can't wipe
+    /// live scores; a file that exists but doesn't parse is an error and
+    /// also leaves the current state untouched.
+    pub fn load_from_file(&mut self, path: &Path) -> Result<(), String> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        self.load_from_json(&json)
+            .map_err(|e| format!("{} ({})", e, path.display()))
+    }
+
+    /// Encode this node's reputation view for gossip
+    pub fn to_gossip(&self) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(&PersistedReputations(&self.reputations))
+            .map_err(|e| format!("Failed to serialize reputation gossip: {}", e))
+    }
+
+    /// Decode a peer's gossiped reputation view
+    ///
+    /// Oversized payloads are refused before parsing, and decoding stops at
+    /// the first entry past `limits.max_entries`, so a hostile peer cannot
+    /// make the receiver materialize an arbitrarily large map. The returned
+    /// manager uses default settings and only holds the peer's view.
+    pub fn from_gossip(payload: &[u8], limits: &ReputationGossipLimits) -> Result<Self, String> {
+        if payload.len() > limits.max_bytes {
+            return Err(format!(
+                "Reputation gossip of {} bytes exceeds the {} byte limit",
+                payload.len(),
+                limits.max_bytes
+            ));
+        }
+
+        let reputations = addr_keyed::deserialize_bounded(
+            &mut serde_json::Deserializer::from_slice(payload),
+            limits.max_entries,
+        )
+        .map_err(|e| format!("Rejected reputation gossip: {}", e))?;
+
+        Ok(Self {
+            reputations,
+            ..Self::new()
+        })
+    }
+
+    /// Reconcile another manager's view into this one
+    ///
+    /// Nodes only `other` knows are copied in. For nodes both know,
+    /// `strategy` decides the result; see [`MergeStrategy`]. Points are
+    /// kept within this manager's policy bounds and significant changes
+    /// reach the change callback.
+    pub fn merge_from(&mut self, other: &ReputationManager, strategy: MergeStrategy) {
+        self.merge_from_at(other, strategy, unix_now());
+    }
+
+    /// [`merge_from`](Self::merge_from) with our clock reading `now`
+    ///
+    /// The peer's `last_active` times are capped at `now` plus
+    /// [`MAX_MERGE_CLOCK_SKEW_SECS`] first, so a peer claiming activity in
+    /// the future can't pin its record of a node.
+    pub fn merge_from_at(&mut self, other: &ReputationManager, strategy: MergeStrategy, now: u64) {
+        let latest_credible = now.saturating_add(MAX_MERGE_CLOCK_SKEW_SECS);
+        for (addr, theirs) in &other.reputations {
+            let mut theirs = Cow::Borrowed(theirs);
+            if theirs.last_active > latest_credible {
+                theirs.to_mut().last_active = latest_credible;
+            }
+
+            let Some(ours) = self.reputations.get_mut(addr) else {
+                let mut node = theirs.into_owned();
+                node.set_points(node.reputation_points, &self.policy);
+                self.reputations.insert(*addr, node);
+                continue;
+            };
+
+            let old_points = ours.reputation_points;
+            match strategy {
+                MergeStrategy::KeepHigher => {
+                    ours.stake = ours.stake.max(theirs.stake);
+                    ours.last_active = ours.last_active.max(theirs.last_active);
+                    ours.created_at = ours.created_at.min(theirs.created_at);
+                    ours.set_points(old_points.max(theirs.reputation_points), &self.policy);
+                }
+                MergeStrategy::KeepNewer => {
+                    if theirs.last_active > ours.last_active {
+                        *ours = theirs.into_owned();
+                    }
+                    ours.set_points(ours.reputation_points, &self.policy);
+                }
+                MergeStrategy::Average => {
+                    let mean = (old_points as i64 + theirs.reputation_points as i64) / 2;
+                    ours.stake = ((ours.stake as u128 + theirs.stake as u128) / 2) as u64;
+                    ours.last_active = ours.last_active.max(theirs.last_active);
+                    ours.created_at = ours.created_at.min(theirs.created_at);
+                    ours.set_points(mean as ReputationPoints, &self.policy);
+                }
+            }
+
+            let new_points = ours.reputation_points;
+            if new_points != old_points {
+                ours.decay_anchor = None;
+            }
+            self.notify_change(*addr, old_points, new_points);
+        }
+    }
+}
+
+/// How [`ReputationManager::merge_from`] resolves a node both views know
+///
+/// Except under `KeepNewer`, metrics, history and the audit log stay as
+/// this node observed them; only points, stake and timestamps merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeStrategy {
+    /// Higher points and stake, latest activity, earliest creation
+    KeepHigher,
+    /// The whole record from whichever side was active more recently;
+    /// ties keep ours
+    KeepNewer,
+    /// Mean points and stake (rounded down), latest activity, earliest
+    /// creation
+    Average,
+}
+
+/// Bounds on reputation gossip accepted from a peer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReputationGossipLimits {
+    /// Maximum number of node entries in one message
+    pub max_entries: usize,
+    /// Maximum encoded message size in bytes
+    pub max_bytes: usize,
+}
+
+impl Default for ReputationGossipLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            max_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Borrowed view of the reputation map for persistence
+#[derive(Serialize)]
+#[serde(transparent)]
+struct PersistedReputations<'a>(
+    #[serde(with = "addr_keyed")] &'a HashMap<SocketAddr, NodeReputation>,
+);
+
+/// (De)serialize an address-keyed map as a JSON object keyed by `ip:port`
+/// strings, the format written before the map was keyed by `SocketAddr`
+mod addr_keyed {
+    use super::NodeReputation;
+    use serde::de::{Error as _, MapAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+
+    pub fn serialize<S: Serializer>(
+        map: &&HashMap<SocketAddr, NodeReputation>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(addr, rep)| (addr.to_string(), rep)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<SocketAddr, NodeReputation>, D::Error> {
+        deserialize_bounded(deserializer, usize::MAX)
+    }
+
+    /// Deserialize, failing as soon as the map holds more than `max_entries`
+    pub fn deserialize_bounded<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        max_entries: usize,
+    ) -> Result<HashMap<SocketAddr, NodeReputation>, D::Error> {
+        deserializer.deserialize_map(BoundedMapVisitor { max_entries })
+    }
+
+    struct BoundedMapVisitor {
+        max_entries: usize,
+    }
+
+    impl<'de> Visitor<'de> for BoundedMapVisitor {
+        type Value = HashMap<SocketAddr, NodeReputation>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "a map of at most {} node reputations", self.max_entries)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let capacity = access.size_hint().unwrap_or(0).min(self.max_entries);
+            let mut map = HashMap::with_capacity(capacity);
+            let mut entries = 0usize;
+            while let Some(key) = access.next_key::<String>()? {
+                entries += 1;
+                if entries > self.max_entries {
+                    return Err(A::Error::custom(format!(
+                        "more than {} entries",
+                        self.max_entries
+                    )));
+                }
+                let rep = access.next_value::<NodeReputation>()?;
+                let addr = key.parse::<SocketAddr>().map_err(|e| {
+                    A::Error::custom(format!("invalid node address {:?}: {}", key, e))
+                })?;
+                map.insert(addr, rep);
+            }
+            Ok(map)
+        }
+    }
+}
+
+/// `path` with `.tmp` appended to its file name
+fn tmp_sibling(path: &Path) -> PathBuf {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
 }
 
 /// Reputation statistics for the network
@@ -436,6 +1264,16 @@ impl Default for ReputationStatistics {
     }
 }
 
+/// Reputation of a single relay as exported to monitoring
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayReputationMetric {
+    /// Relay address, used as the metric label
+    pub relay: String,
+    pub reputation: f64,
+    pub reputation_points: ReputationPoints,
+    pub cost_of_forgery: CostOfForgery,
+}
+
 /// Penalty types (legacy compatibility)
 #[derive(Debug, Clone, Copy)]
 pub enum PenaltyType {
@@ -453,6 +1291,15 @@ pub enum RewardType {
     LowLatency,
 }
 
+/// Upper bounds of the latency histogram buckets (ms); one overflow bucket follows
+pub const LATENCY_BUCKET_BOUNDS_MS: [f64; 6] = [25.0, 50.0, 100.0, 200.0, 500.0, 1000.0];
+
+/// Samples at or under this latency earn full credit in `latency_score`
+pub const LATENCY_TARGET_MS: f64 = 100.0;
+
+/// Histogram counts are halved once they exceed this many samples
+pub const LATENCY_HISTOGRAM_CAP: u64 = 1000;
+
 /// Performance metrics for node evaluation
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
@@ -461,14 +1308,54 @@ pub struct PerformanceMetrics {
     pub packets_dropped: u64,
     pub uptime_percent: f64,
     pub avg_latency_ms: f64,
+    /// Latency sample counts per `LATENCY_BUCKET_BOUNDS_MS` bucket
+    #[serde(default)]
+    pub latency_buckets: [u64; LATENCY_BUCKET_BOUNDS_MS.len() + 1],
 }
 
 impl PerformanceMetrics {
     /// Calculate latency score (0.0-1.0)
+    ///
+    /// Average per-sample credit, so an occasional spike costs only its own
+    /// share instead of dragging the whole average. Samples at or under
+    /// `LATENCY_TARGET_MS` earn 1.0, slower ones `target / latency` averaged
+    /// over their bucket, and those past the last bound nothing. Falls back
+    /// to the EMA before any samples exist.
     pub fn latency_score(&self) -> f64 {
-        // Better (lower) latency = higher score
-        // 0ms = 1.0, 100ms = 0.5, 200ms+ = 0.0
-        (1.0 - (self.avg_latency_ms / 200.0)).max(0.0)
+        let total = self.latency_sample_count();
+        if total == 0 {
+            // 0ms = 1.0, 100ms = 0.5, 200ms+ = 0.0
+            return (1.0 - (self.avg_latency_ms / 200.0)).max(0.0);
+        }
+
+        let credit: f64 = LATENCY_BUCKET_BOUNDS_MS
+            .iter()
+            .enumerate()
+            .zip(self.latency_buckets.iter())
+            .map(|((i, &upper), &count)| {
+                let lower = if i == 0 { 0.0 } else { LATENCY_BUCKET_BOUNDS_MS[i - 1] };
+                count as f64 * Self::bucket_latency_credit(lower, upper)
+            })
+            .sum();
+        credit / total as f64
+    }
+
+    /// Mean of `min(1, target / latency)` over latencies spread evenly in
+    /// `(lower, upper]`
+    fn bucket_latency_credit(lower: f64, upper: f64) -> f64 {
+        let target = LATENCY_TARGET_MS;
+        if upper <= target {
+            return 1.0;
+        }
+        let lower = lower.max(0.0);
+        let full = (target - lower).max(0.0);
+        let partial = target * (upper / lower.max(target)).ln();
+        (full + partial) / (upper - lower)
+    }
+
+    /// Number of samples currently in the latency histogram
+    pub fn latency_sample_count(&self) -> u64 {
+        self.latency_buckets.iter().sum()
     }
 
     /// Calculate success rate (0.0-1.0)
@@ -497,6 +1384,19 @@ impl PerformanceMetrics {
         } else {
             self.avg_latency_ms = ALPHA * latency_ms + (1.0 - ALPHA) * self.avg_latency_ms;
         }
+
+        let bucket = LATENCY_BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKET_BOUNDS_MS.len());
+        self.latency_buckets[bucket] += 1;
+
+        // Keep the histogram bounded and biased towards recent samples
+        if self.latency_sample_count() > LATENCY_HISTOGRAM_CAP {
+            for count in self.latency_buckets.iter_mut() {
+                *count /= 2;
+            }
+        }
     }
 }
 
@@ -504,6 +1404,86 @@ impl PerformanceMetrics {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_single_pass_statistics_match_multi_pass() {
+        let mut manager = ReputationManager::with_threshold(90);
+        for i in 0..50u16 {
+            let addr: SocketAddr = format!("10.0.0.{}:{}", i % 250, 9000 + i).parse().unwrap();
+            manager.add_node(addr, 100 * (i as u64 + 1));
+            for _ in 0..(i % 7) {
+                manager
+                    .update_reputation(&addr, ReputationAction::TaskFailure)
+                    .unwrap();
+            }
+            if i % 3 == 0 {
+                manager
+                    .update_reputation(&addr, ReputationAction::HighQualityService)
+                    .unwrap();
+            }
+        }
+
+        let stats = manager.statistics();
+        let nodes: Vec<&NodeReputation> = manager.reputations.values().collect();
+        let n = nodes.len();
+        let avg_reputation = nodes.iter().map(|r| r.reputation).sum::<f64>() / n as f64;
+        let avg_points = nodes.iter().map(|r| r.reputation_points).sum::<i32>() / n as i32;
+        let avg_cost = nodes.iter().map(|r| r.cost_of_forgery()).sum::<f64>() / n as f64;
+        let above = nodes.iter().filter(|r| r.reputation_points >= 90).count();
+
+        assert_eq!(stats.total_nodes, n);
+        assert!((stats.avg_reputation - avg_reputation).abs() < 1e-9);
+        assert_eq!(stats.avg_points, avg_points);
+        assert!((stats.avg_cost_of_forgery - avg_cost).abs() < 1e-6);
+        assert_eq!(stats.nodes_above_threshold, above);
+    }
+
+    #[test]
+    fn test_latency_score_tolerates_spikes() {
+        // Both relays average 128ms
+        let mut spiky = PerformanceMetrics::default();
+        for _ in 0..9 {
+            spiky.update_latency(20.0);
+        }
+        spiky.update_latency(1100.0);
+
+        let mut mediocre = PerformanceMetrics::default();
+        for _ in 0..10 {
+            mediocre.update_latency(128.0);
+        }
+
+        assert!((spiky.latency_score() - 0.9).abs() < 1e-9);
+        assert!(mediocre.latency_score() > 0.0);
+        assert!(spiky.latency_score() > mediocre.latency_score());
+    }
+
+    #[test]
+    fn test_latency_score_degrades_gradually_above_target() {
+        let score = |latency_ms: f64| {
+            let mut metrics = PerformanceMetrics::default();
+            for _ in 0..10 {
+                metrics.update_latency(latency_ms);
+            }
+            metrics.latency_score()
+        };
+
+        assert_eq!(score(LATENCY_TARGET_MS), 1.0);
+        let scores: Vec<f64> = [150.0, 300.0, 800.0, 5000.0].into_iter().map(score).collect();
+        assert!(scores.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", scores);
+        // A relay just over target keeps most of its credit
+        assert!((scores[0] - std::f64::consts::LN_2).abs() < 1e-9);
+        assert_eq!(scores[3], 0.0);
+    }
+
+    #[test]
+    fn test_latency_histogram_is_bounded() {
+        let mut metrics = PerformanceMetrics::default();
+        for _ in 0..(LATENCY_HISTOGRAM_CAP * 3) {
+            metrics.update_latency(10.0);
+        }
+        assert!(metrics.latency_sample_count() <= LATENCY_HISTOGRAM_CAP);
+        assert_eq!(metrics.latency_score(), 1.0);
+    }
+
     #[test]
     fn test_new_node_base_reputation() {
         let node = NodeReputation::new("test-node".to_string());
@@ -535,6 +1515,100 @@ mod tests {
         assert_eq!(node.reputation_points, 95);
     }
 
+    #[test]
+    fn test_custom_policy_deltas() {
+        let policy = ReputationPolicy {
+            successful_task: 3,
+            dropped_connection: -60,
+            ..Default::default()
+        };
+        let addr: SocketAddr = "127.0.0.1:9800".parse().unwrap();
+        let mut manager = ReputationManager::new().with_policy(policy.clone());
+        manager.add_node(addr, 1000);
+
+        manager.update_reputation(&addr, ReputationAction::SuccessfulTask).unwrap();
+        assert_eq!(manager.get_reputation_points(&addr), 103);
+        manager.update_reputation(&addr, ReputationAction::DroppedConnection).unwrap();
+        assert_eq!(manager.get_reputation_points(&addr), 43);
+        // Unchanged deltas keep their defaults
+        manager.update_reputation(&addr, ReputationAction::TaskFailure).unwrap();
+        assert_eq!(manager.get_reputation_points(&addr), 28);
+        manager.update_reputation(&addr, ReputationAction::Custom(7)).unwrap();
+        assert_eq!(manager.get_reputation_points(&addr), 35);
+
+        let mut node = NodeReputation::new("policy".to_string());
+        node.apply_action_with_policy(ReputationAction::SuccessfulTask, &policy);
+        assert_eq!(node.reputation_points, 103);
+        assert_eq!(node.history.successful_tasks, 1);
+    }
+
+    #[test]
+    fn test_policy_bounds_and_single_event_zeroing() {
+        let addr: SocketAddr = "127.0.0.1:9801".parse().unwrap();
+        let mut manager = ReputationManager::new().with_policy(ReputationPolicy {
+            malicious_behavior: -200,
+            ..Default::default()
+        });
+        manager.add_node(addr, 1000);
+        manager.update_reputation(&addr, ReputationAction::HighQualityService).unwrap();
+        manager.update_reputation(&addr, ReputationAction::MaliciousBehavior).unwrap();
+        assert_eq!(manager.get_reputation_points(&addr), 0);
+        assert_eq!(manager.get_reputation_score(&addr), 0.0);
+
+        // Narrower bounds clamp both ways and rescale the normalized score
+        let strict = ReputationPolicy {
+            min_points: 20,
+            max_points: 120,
+            ..Default::default()
+        };
+        let mut node = NodeReputation::new("strict".to_string());
+        node.apply_action_with_policy(ReputationAction::HighQualityService, &strict);
+        node.apply_action_with_policy(ReputationAction::HighQualityService, &strict);
+        assert_eq!(node.reputation_points, 120);
+        assert_eq!(node.reputation, 1.0);
+        node.apply_action_with_policy(ReputationAction::Custom(-500), &strict);
+        assert_eq!(node.reputation_points, 20);
+
+        assert_eq!(ReputationAction::DroppedConnection.points_delta(), -25);
+    }
+
+    #[test]
+    fn test_audit_log_keeps_latest_events() {
+        let mut node = NodeReputation::new("audited".to_string());
+        for i in 0..300 {
+            node.apply_action(ReputationAction::Custom(if i % 2 == 0 { 3 } else { -1 }));
+        }
+
+        assert_eq!(node.events.len(), DEFAULT_AUDIT_LOG_LEN);
+        assert_eq!(node.recent_events(1000).len(), DEFAULT_AUDIT_LOG_LEN);
+        // The first 44 were evicted; what is left stays in order
+        let mut points = 100;
+        for i in 0..300 {
+            points = (points + if i % 2 == 0 { 3 } else { -1 }).min(200);
+            if i >= 44 {
+                let (at, action, after) = node.events[i - 44];
+                assert_eq!(action, ReputationAction::Custom(if i % 2 == 0 { 3 } else { -1 }));
+                assert_eq!(after, points);
+                assert!(at <= node.last_active);
+            }
+        }
+        assert!(node.recent_events(2).eq(node.events.range(254..)));
+        assert_eq!(node.recent_events(1).next().unwrap().2, node.reputation_points);
+
+        // The log survives persistence
+        let json = serde_json::to_string(&node).unwrap();
+        let restored: NodeReputation = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.events, node.events);
+
+        let mut quiet = NodeReputation::new("quiet".to_string());
+        let off = ReputationPolicy {
+            audit_log_len: 0,
+            ..Default::default()
+        };
+        quiet.apply_action_with_policy(ReputationAction::SuccessfulTask, &off);
+        assert_eq!(quiet.recent_events(10).len(), 0);
+    }
+
     #[test]
     fn test_reputation_bounds() {
         let mut node = NodeReputation::new("test".to_string());
@@ -568,6 +1642,204 @@ mod tests {
         assert_eq!(node.reputation_points, 90); // 100 * 0.99^10 ≈ 90
     }
 
+    #[test]
+    fn test_decay_models_over_days() {
+        let exponential = DecayModel::Exponential { rate: 0.9 };
+        let trajectory: Vec<_> = (0..=3).map(|days| exponential.decay(100, days)).collect();
+        assert_eq!(trajectory, vec![100, 90, 81, 72]);
+
+        let linear = DecayModel::Linear { per_day: 15 };
+        let trajectory: Vec<_> = (0..=8).map(|days| linear.decay(100, days)).collect();
+        assert_eq!(trajectory, vec![100, 85, 70, 55, 40, 25, 10, 0, 0]);
+
+        let floored = DecayModel::ExponentialWithFloor { rate: 0.5, floor: 30 };
+        let trajectory: Vec<_> = (0..=4).map(|days| floored.decay(100, days)).collect();
+        assert_eq!(trajectory, vec![100, 50, 30, 30, 30]);
+        // Decay never lifts a node that is already under the floor
+        assert_eq!(floored.decay(20, 5), 20);
+    }
+
+    #[test]
+    fn test_manager_decay_uses_model() {
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let mut manager = ReputationManager::new()
+            .with_decay_model(DecayModel::ExponentialWithFloor { rate: 0.5, floor: 60 });
+        manager.add_node(addr, 1000);
+
+        // Three days of inactivity on each pass
+        for expected in [60, 60] {
+            manager.reputations.get_mut(&addr).unwrap().last_active = unix_now() - 3 * 86400;
+            manager.apply_decay_all();
+            assert_eq!(manager.get_reputation_points(&addr), expected);
+        }
+
+        manager.set_decay_model(DecayModel::Linear { per_day: 10 });
+        manager.reputations.get_mut(&addr).unwrap().last_active = unix_now() - 2 * 86400;
+        manager.apply_decay_all();
+        assert_eq!(manager.get_reputation_points(&addr), 40);
+        assert_eq!(manager.reputation(&addr).unwrap().history.decay_events, 3);
+    }
+
+    #[test]
+    fn test_time_decay_ticks_add_up() {
+        let addr: SocketAddr = "127.0.0.1:8083".parse().unwrap();
+        let start = 1_700_000_000;
+        let manager = || {
+            let mut manager =
+                ReputationManager::new().with_decay_model(DecayModel::Exponential { rate: 0.5 });
+            manager.add_node(addr, 1000);
+            manager.reputations.get_mut(&addr).unwrap().last_active = start;
+            manager
+        };
+
+        let mut ticked = manager();
+        ticked.apply_time_decay(start + 12 * 3600);
+        assert_eq!(ticked.get_reputation_points(&addr), 70);
+        ticked.apply_time_decay(start + 24 * 3600);
+
+        let mut once = manager();
+        once.apply_time_decay(start + 24 * 3600);
+
+        assert_eq!(once.get_reputation_points(&addr), 50);
+        assert!(
+            (ticked.get_reputation_score(&addr) - once.get_reputation_score(&addr)).abs() < 1e-9
+        );
+
+        // Hourly ticks over a day land in the same place
+        let mut hourly = manager();
+        for hour in 1..=24 {
+            hourly.apply_time_decay(start + hour * 3600);
+        }
+        assert_eq!(hourly.get_reputation_points(&addr), 50);
+
+        // Activity starts a fresh stretch from the new points
+        hourly
+            .update_reputation(&addr, ReputationAction::SuccessfulTask)
+            .unwrap();
+        let now = hourly.reputation(&addr).unwrap().last_active;
+        hourly.apply_time_decay(now + 86400);
+        assert_eq!(hourly.get_reputation_points(&addr), 30);
+    }
+
+    #[test]
+    fn test_epoch_decay_once_per_epoch_across_managers() {
+        use crate::utils::epoch::ManualTimeSource;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let genesis = 1_000_000;
+        let source = Arc::new(ManualTimeSource::new(genesis));
+        let clock = EpochClock::with_source(genesis, Duration::from_secs(600), source.clone());
+        let addr: SocketAddr = "127.0.0.1:8082".parse().unwrap();
+        let manager = || {
+            let mut manager = ReputationManager::new()
+                .with_decay_model(DecayModel::Linear { per_day: 10 })
+                .with_epoch_clock(clock.clone());
+            manager.add_node(addr, 1000);
+            manager.reputations.get_mut(&addr).unwrap().last_active = genesis + 30;
+            manager
+        };
+        let (mut a, mut b) = (manager(), manager());
+
+        source.advance(Duration::from_secs(600));
+        for m in [&mut a, &mut b] {
+            assert!(m.apply_decay_for_epoch(clock.current_epoch()).unwrap());
+            assert!(!m.apply_decay_for_epoch(clock.current_epoch()).unwrap());
+            assert_eq!(m.get_reputation_points(&addr), 90);
+        }
+
+        // `b` sleeps through epoch 2 and catches up in epoch 3
+        source.advance(Duration::from_secs(600));
+        a.apply_decay_for_epoch(clock.current_epoch()).unwrap();
+        source.advance(Duration::from_secs(600));
+        for m in [&mut a, &mut b] {
+            m.apply_decay_for_epoch(clock.current_epoch()).unwrap();
+            assert_eq!(m.last_decayed_epoch(), Some(3));
+        }
+        assert_eq!(a.get_reputation_points(&addr), 70);
+        assert_eq!(b.get_reputation_points(&addr), 70);
+        assert_eq!(a.reputation(&addr).unwrap().history.decay_events, 3);
+
+        assert!(ReputationManager::new().apply_decay_for_epoch(1).is_err());
+    }
+
+    #[test]
+    fn test_lazy_decay_applies_on_read() {
+        let addr: SocketAddr = "127.0.0.1:8081".parse().unwrap();
+        let mut manager = ReputationManager::new()
+            .with_decay_model(DecayModel::Linear { per_day: 10 })
+            .with_decay_mode(DecayMode::Lazy);
+        manager.add_node(addr, 1000);
+        manager.reputations.get_mut(&addr).unwrap().last_active = unix_now() - 3 * 86400;
+
+        // Reads see the decay; the stored record is untouched
+        assert_eq!(manager.get_reputation_points(&addr), 70);
+        assert_eq!(manager.get_reputation(&addr).unwrap().reputation_points, 70);
+        assert!((manager.get_reputation_score(&addr) - 0.35).abs() < 1e-9);
+        assert_eq!(manager.reputation(&addr).unwrap().reputation_points, 100);
+
+        // A sweep would double count, so it is skipped
+        manager.apply_decay_all();
+        assert_eq!(manager.reputation(&addr).unwrap().reputation_points, 100);
+
+        // The next write settles the decay, then applies the action
+        manager
+            .update_reputation(&addr, ReputationAction::SuccessfulTask)
+            .unwrap();
+        assert_eq!(manager.reputation(&addr).unwrap().reputation_points, 80);
+        assert_eq!(manager.get_reputation_points(&addr), 80);
+    }
+
+    #[test]
+    fn test_lazy_decay_drops_node_below_thresholds() {
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let mut manager = ReputationManager::new()
+            .with_decay_model(DecayModel::Linear { per_day: 10 })
+            .with_decay_mode(DecayMode::Lazy)
+            .with_admission_threshold(40)
+            .with_selection_threshold(70);
+        manager.add_node(addr, 1000);
+        let active = manager.reputation(&addr).unwrap().last_active;
+
+        // 80 points after two idle days
+        assert!(manager.is_admissible_at(&addr, active + 2 * 86400));
+        assert!(manager.is_selectable_at(&addr, active + 2 * 86400));
+
+        // 60 points: still connects, no longer picked for circuits
+        assert!(manager.is_admissible_at(&addr, active + 4 * 86400));
+        assert!(!manager.is_selectable_at(&addr, active + 4 * 86400));
+
+        // 30 points: refused on both paths
+        assert!(!manager.is_admissible_at(&addr, active + 7 * 86400));
+        assert!(!manager.admits_ip_at(&addr.ip(), active + 7 * 86400));
+        assert_eq!(manager.reputation(&addr).unwrap().reputation_points, 100);
+    }
+
+    #[test]
+    fn test_lazy_decay_applies_to_aggregate_reads() {
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let mut manager = ReputationManager::new()
+            .with_decay_model(DecayModel::Linear { per_day: 10 })
+            .with_decay_mode(DecayMode::Lazy);
+        manager.add_node(addr, 1000);
+        manager.reputations.get_mut(&addr).unwrap().last_active = unix_now() - 7 * 86400;
+
+        assert!(!manager.is_admissible(&addr));
+        assert!(!manager.meets_threshold(&addr));
+        assert!(!manager.is_selectable(&addr));
+        assert!(!manager.admits_ip(&addr.ip()));
+        assert!(manager.get_weighted_relay_candidates(50).is_empty());
+
+        let stats = manager.statistics();
+        assert_eq!(stats.avg_points, 30);
+        assert_eq!(stats.nodes_above_threshold, 0);
+        assert!((stats.avg_reputation - 0.15).abs() < 1e-9);
+
+        let metrics = manager.relay_metrics();
+        assert_eq!(metrics[0].reputation_points, 30);
+        assert!((metrics[0].reputation - 0.15).abs() < 1e-9);
+    }
+
     #[test]
     fn test_cost_of_forgery() {
         let mut node = NodeReputation::with_stake("test".to_string(), 10000);
@@ -638,6 +1910,23 @@ mod tests {
         assert_eq!(high_candidates.len(), 1); // Only addr1 has > 100 points
     }
 
+    #[test]
+    fn test_relay_metrics_export() {
+        let mut manager = ReputationManager::new();
+        let low: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let high: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+        manager.add_node(high, 10_000);
+        manager.add_node(low, 0);
+        manager.update_reputation(&high, ReputationAction::SuccessfulTask).unwrap();
+
+        let metrics = manager.relay_metrics();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].relay, "10.0.0.1:9000");
+        assert_eq!(metrics[1].reputation_points, 110);
+        assert!(metrics[1].cost_of_forgery > metrics[0].cost_of_forgery);
+        assert_eq!(metrics[1].reputation, manager.get_reputation_score(&high));
+    }
+
     #[test]
     fn test_persistence() {
         let mut manager = ReputationManager::new();
@@ -655,4 +1944,257 @@ mod tests {
 
         assert_eq!(manager2.get_reputation_points(&addr), 110);
     }
+
+    #[test]
+    fn test_file_persistence_is_atomic() {
+        let dir = std::env::temp_dir().join(format!("betanet-reputation-{}", std::process::id()));
+        let path = dir.join("state").join("reputation.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Missing file loads as empty
+        let mut restored = ReputationManager::new();
+        restored.load_from_file(&path).unwrap();
+        assert_eq!(restored.node_count(), 0);
+
+        let mut manager = ReputationManager::new();
+        let addrs: Vec<SocketAddr> = (0..1000)
+            .map(|i| format!("10.0.{}.{}:9000", i / 256, i % 256).parse().unwrap())
+            .collect();
+        for (i, addr) in addrs.iter().enumerate() {
+            manager.add_node(*addr, i as u64);
+        }
+        manager.update_reputation(&addrs[7], ReputationAction::TaskFailure).unwrap();
+        manager.save_to_file(&path).unwrap();
+
+        restored.load_from_file(&path).unwrap();
+        assert_eq!(restored.node_count(), 1000);
+        assert_eq!(restored.get_reputation_points(&addrs[7]), 85);
+        assert_eq!(restored.reputation(&addrs[999]).unwrap().stake, 999);
+
+        // A save killed mid-write leaves garbage in the tmp file only
+        std::fs::write(tmp_sibling(&path), b"{\"10.0.0.1:9000\": {trunc").unwrap();
+        let mut after_crash = ReputationManager::new();
+        after_crash.load_from_file(&path).unwrap();
+        assert_eq!(after_crash.node_count(), 1000);
+
+        // The next save replaces the leftover
+        manager.save_to_file(&path).unwrap();
+        assert!(!tmp_sibling(&path).exists());
+
+        std::fs::write(&path, b"not json").unwrap();
+        let err = after_crash.load_from_file(&path).unwrap_err();
+        assert!(err.contains("Failed to deserialize"), "{}", err);
+        assert_eq!(after_crash.node_count(), 1000);
+
+        // A missing file keeps live scores rather than clearing them
+        after_crash.load_from_file(&dir.join("missing.json")).unwrap();
+        assert_eq!(after_crash.node_count(), 1000);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_persistence_stays_string_keyed() {
+        let mut manager = ReputationManager::new();
+        let v4: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:9001".parse().unwrap();
+        manager.add_node(v4, 100);
+        manager.add_node(v6, 200);
+        manager.update_reputation(&v6, ReputationAction::TaskFailure).unwrap();
+
+        let json = manager.save_to_json().unwrap();
+        let raw: HashMap<String, serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert!(raw.contains_key("10.0.0.1:9000"));
+        assert!(raw.contains_key("[2001:db8::1]:9001"));
+
+        let mut restored = ReputationManager::new();
+        restored.load_from_json(&json).unwrap();
+        assert_eq!(restored.node_count(), 2);
+        assert_eq!(restored.get_reputation_points(&v4), 100);
+        assert_eq!(restored.get_reputation_points(&v6), 85);
+        assert_eq!(restored.reputation(&v6).unwrap().stake, 200);
+
+        let bad_key = json.replace("10.0.0.1:9000", "not-an-addr");
+        assert!(restored.load_from_json(&bad_key).is_err());
+    }
+
+    #[test]
+    fn test_merge_strategies_reconcile_diverged_nodes() {
+        use std::sync::{Arc, Mutex};
+
+        let shared: SocketAddr = "127.0.0.1:9820".parse().unwrap();
+        let only_theirs: SocketAddr = "127.0.0.1:9821".parse().unwrap();
+
+        // Same node, diverged: we saw it fail, the peer saw it succeed
+        let diverged = || {
+            let mut ours = ReputationManager::new();
+            ours.add_node(shared, 1000);
+            ours.update_reputation(&shared, ReputationAction::DroppedConnection).unwrap();
+            ours.update_reputation(&shared, ReputationAction::DroppedConnection).unwrap();
+            let mut theirs = ReputationManager::new();
+            theirs.add_node(shared, 3000);
+            theirs.update_reputation(&shared, ReputationAction::HighQualityService).unwrap();
+            theirs.add_node(only_theirs, 10);
+            theirs.update_reputation(&only_theirs, ReputationAction::SuccessfulTask).unwrap();
+            (ours, theirs)
+        };
+        let set_last_active = |manager: &mut ReputationManager, at: u64| {
+            manager.reputations.get_mut(&shared).unwrap().last_active = at;
+        };
+
+        let (mut ours, theirs) = diverged();
+        assert_eq!(ours.get_reputation_points(&shared), 50);
+        assert_eq!(theirs.get_reputation_points(&shared), 120);
+        ours.merge_from(&theirs, MergeStrategy::KeepHigher);
+        let merged = ours.reputation(&shared).unwrap();
+        assert_eq!(merged.reputation_points, 120);
+        assert_eq!(merged.reputation, 0.6);
+        assert_eq!(merged.stake, 3000);
+        // Local observations are kept
+        assert_eq!(merged.history.dropped_connections, 2);
+        assert_eq!(ours.get_reputation_points(&only_theirs), 110);
+        assert_eq!(ours.node_count(), 2);
+
+        // The peer's record is newer, so it wins outright
+        let (mut ours, mut theirs) = diverged();
+        set_last_active(&mut ours, 1_000);
+        set_last_active(&mut theirs, 2_000);
+        ours.merge_from(&theirs, MergeStrategy::KeepNewer);
+        let merged = ours.reputation(&shared).unwrap();
+        assert_eq!(merged.reputation_points, 120);
+        assert_eq!(merged.stake, 3000);
+        assert_eq!(merged.history.dropped_connections, 0);
+        assert_eq!(merged.history.quality_bonuses, 1);
+
+        // Ours is newer, so nothing changes
+        let (mut ours, mut theirs) = diverged();
+        set_last_active(&mut ours, 2_000);
+        set_last_active(&mut theirs, 1_000);
+        ours.merge_from(&theirs, MergeStrategy::KeepNewer);
+        let merged = ours.reputation(&shared).unwrap();
+        assert_eq!(merged.reputation_points, 50);
+        assert_eq!(merged.stake, 1000);
+        assert!(ours.reputation(&only_theirs).is_some());
+
+        let (mut ours, mut theirs) = diverged();
+        set_last_active(&mut ours, 1_000);
+        set_last_active(&mut theirs, 2_000);
+        ours.merge_from(&theirs, MergeStrategy::Average);
+        let merged = ours.reputation(&shared).unwrap();
+        assert_eq!(merged.reputation_points, 85);
+        assert_eq!(merged.stake, 2000);
+        assert_eq!(merged.last_active, 2_000);
+        assert_eq!(merged.history.dropped_connections, 2);
+
+        // Merging is bounded by our policy and reported like any change
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let (ours, theirs) = diverged();
+        let mut strict = ours.with_policy(ReputationPolicy {
+            max_points: 100,
+            ..Default::default()
+        });
+        let sink = Arc::clone(&changes);
+        strict.on_reputation_change(Box::new(move |change| sink.lock().unwrap().push(change)));
+        strict.merge_from(&theirs, MergeStrategy::KeepHigher);
+        assert_eq!(strict.get_reputation_points(&shared), 100);
+        assert_eq!(strict.get_reputation_points(&only_theirs), 100);
+        let changes = changes.lock().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].old_points, changes[0].new_points), (50, 100));
+    }
+
+    #[test]
+    fn test_merge_caps_future_last_active() {
+        let relay: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let now = 1_000_000;
+        let mut ours = ReputationManager::new();
+        ours.add_node(relay, 1000);
+        ours.reputations.get_mut(&relay).unwrap().last_active = now - 10;
+
+        // A peer claims activity far in the future to pin its view
+        let mut theirs = ReputationManager::new();
+        theirs.add_node(relay, 3000);
+        theirs.update_reputation(&relay, ReputationAction::HighQualityService).unwrap();
+        theirs.reputations.get_mut(&relay).unwrap().last_active = u64::MAX;
+        ours.merge_from_at(&theirs, MergeStrategy::KeepNewer, now);
+        let merged = ours.reputation(&relay).unwrap();
+        assert_eq!(merged.reputation_points, 120);
+        assert_eq!(merged.last_active, now + MAX_MERGE_CLOCK_SKEW_SECS);
+
+        // Later genuine activity reported by another peer still wins
+        let mut honest = ReputationManager::new();
+        honest.add_node(relay, 2000);
+        honest.reputations.get_mut(&relay).unwrap().last_active = now + 3500;
+        ours.merge_from_at(&honest, MergeStrategy::KeepNewer, now + 3600);
+        assert_eq!(ours.get_reputation_points(&relay), 100);
+        assert_eq!(ours.reputation(&relay).unwrap().stake, 2000);
+
+        // The other strategies take the capped time as well
+        ours.merge_from_at(&theirs, MergeStrategy::KeepHigher, now + 3600);
+        let capped = now + 3600 + MAX_MERGE_CLOCK_SKEW_SECS;
+        assert_eq!(ours.reputation(&relay).unwrap().last_active, capped);
+    }
+
+    #[test]
+    fn test_oversized_gossip_rejected_early() {
+        let mut manager = ReputationManager::new();
+        for i in 0..20u16 {
+            let addr: SocketAddr = format!("10.1.0.{}:9000", i).parse().unwrap();
+            manager.add_node(addr, 1000);
+        }
+        let payload = manager.to_gossip().unwrap();
+
+        let received =
+            ReputationManager::from_gossip(&payload, &ReputationGossipLimits::default()).unwrap();
+        assert_eq!(received.node_count(), 20);
+
+        // Too many bytes: refused without looking at the contents
+        let limits = ReputationGossipLimits {
+            max_bytes: 1024,
+            ..Default::default()
+        };
+        let junk = vec![b'{'; 4096];
+        let err = ReputationManager::from_gossip(&junk, &limits).unwrap_err();
+        assert!(err.contains("byte limit"), "{}", err);
+
+        // Too many entries: decoding stops at the first extra entry, before
+        // reaching the malformed tail
+        let limits = ReputationGossipLimits {
+            max_entries: 10,
+            ..Default::default()
+        };
+        let mut truncated = payload[..payload.len() - 1].to_vec();
+        truncated.extend_from_slice(b",\"oops\":");
+        let err = ReputationManager::from_gossip(&truncated, &limits).unwrap_err();
+        assert!(err.contains("more than 10 entries"), "{}", err);
+    }
+
+    #[test]
+    fn test_reputation_change_callback() {
+        use std::sync::{Arc, Mutex};
+
+        let mut manager = ReputationManager::new();
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        manager.add_node(addr, 1000);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        manager.on_reputation_change(Box::new(move |change| {
+            sink.lock().unwrap().push(change);
+        }));
+        manager.set_change_threshold(10);
+
+        // +5 is below the threshold
+        manager.update_reputation(&addr, ReputationAction::UptimeMilestone).unwrap();
+        assert!(events.lock().unwrap().is_empty());
+
+        // -25 is significant
+        manager.update_reputation(&addr, ReputationAction::DroppedConnection).unwrap();
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].node, addr);
+        assert_eq!(events[0].old_points, 105);
+        assert_eq!(events[0].new_points, 80);
+        assert_eq!(events[0].delta(), -25);
+    }
 }
@@ -4,7 +4,7 @@
 //! and migration guides.
 
 use super::protocol_version::ProtocolVersion;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Protocol version metadata
@@ -79,6 +79,7 @@ impl VersionMetadata {
 /// Protocol version registry
 pub struct VersionRegistry {
     versions: Vec<VersionMetadata>,
+    timelines: Vec<DeprecationTimeline>,
 }
 
 impl VersionRegistry {
@@ -86,6 +87,7 @@ impl VersionRegistry {
     pub fn new() -> Self {
         let mut registry = Self {
             versions: Vec::new(),
+            timelines: Vec::new(),
         };
 
         // Register all versions
@@ -115,6 +117,7 @@ impl VersionRegistry {
         );
 
         self.versions.push(metadata);
+        self.timelines.push(DeprecationTimeline::v1_0_0());
     }
 
     /// Register v1.1.0 (batch processing)
@@ -191,6 +194,11 @@ New nodes should start directly with v1.2.0.",
             .unwrap_or(false)
     }
 
+    /// Get all deprecation timelines
+    pub fn deprecation_timelines(&self) -> &[DeprecationTimeline] {
+        &self.timelines
+    }
+
     /// Get upgrade path from one version to another
     pub fn upgrade_path(
         &self,
@@ -209,7 +217,7 @@ New nodes should start directly with v1.2.0.",
             }
         }
 
-        path.sort_by(|a, b| a.version.cmp(&b.version));
+        path.sort_by_key(|a| a.version);
         Some(path)
     }
 }
@@ -272,6 +280,98 @@ impl DeprecationTimeline {
         }
         None
     }
+
+    /// Parsed end of life date
+    pub fn eol_datetime(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.eol)
+            .ok()
+            .map(|eol| eol.with_timezone(&Utc))
+    }
+}
+
+/// Deprecation state of a peer's version at a point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeprecationStatus {
+    /// Not deprecated
+    Supported,
+    /// Deprecated but still before the cutoff (EOL plus grace)
+    Deprecated {
+        /// Date after which the version is refused
+        cutoff: DateTime<Utc>,
+        /// Recommended upgrade version
+        upgrade_to: ProtocolVersion,
+    },
+    /// Past the cutoff
+    Expired {
+        /// Date after which the version is refused
+        cutoff: DateTime<Utc>,
+        /// Recommended upgrade version
+        upgrade_to: ProtocolVersion,
+    },
+}
+
+/// Handshake enforcement of deprecation timelines
+#[derive(Debug, Clone)]
+pub struct DeprecationPolicy {
+    /// Timelines to enforce
+    pub timelines: Vec<DeprecationTimeline>,
+    /// Extra time after EOL before a version counts as expired
+    pub grace_period: Duration,
+    /// Refuse expired versions (otherwise only warn)
+    pub refuse_expired: bool,
+}
+
+impl DeprecationPolicy {
+    /// Enforce the registry's timelines
+    pub fn from_registry(registry: &VersionRegistry) -> Self {
+        Self {
+            timelines: registry.deprecation_timelines().to_vec(),
+            grace_period: Duration::zero(),
+            refuse_expired: false,
+        }
+    }
+
+    /// Set the grace period after EOL
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Refuse or merely warn about expired versions
+    pub fn refuse_expired(mut self, refuse: bool) -> Self {
+        self.refuse_expired = refuse;
+        self
+    }
+
+    /// Deprecation status of `version` at `now`
+    pub fn status(&self, version: &ProtocolVersion, now: DateTime<Utc>) -> DeprecationStatus {
+        let timeline = match self.timelines.iter().find(|t| t.version == *version) {
+            Some(timeline) => timeline,
+            None => return DeprecationStatus::Supported,
+        };
+        let cutoff = match timeline.eol_datetime() {
+            Some(eol) => eol + self.grace_period,
+            None => return DeprecationStatus::Supported,
+        };
+
+        if now < cutoff {
+            DeprecationStatus::Deprecated {
+                cutoff,
+                upgrade_to: timeline.upgrade_to,
+            }
+        } else {
+            DeprecationStatus::Expired {
+                cutoff,
+                upgrade_to: timeline.upgrade_to,
+            }
+        }
+    }
+}
+
+impl Default for DeprecationPolicy {
+    fn default() -> Self {
+        Self::from_registry(&VersionRegistry::new())
+    }
 }
 
 #[cfg(test)]
@@ -331,4 +431,32 @@ mod tests {
         assert_eq!(timeline.version, ProtocolVersion::new(1, 0, 0));
         assert_eq!(timeline.upgrade_to, ProtocolVersion::V1_2_0);
     }
+
+    #[test]
+    fn test_deprecation_policy_status() {
+        let v1_0 = ProtocolVersion::new(1, 0, 0);
+        let eol = DeprecationTimeline::v1_0_0().eol_datetime().unwrap();
+        let policy = DeprecationPolicy::default().with_grace_period(Duration::days(30));
+
+        assert_eq!(
+            policy.status(&ProtocolVersion::V1_2_0, eol),
+            DeprecationStatus::Supported
+        );
+        assert!(matches!(
+            policy.status(&v1_0, eol - Duration::days(1)),
+            DeprecationStatus::Deprecated { .. }
+        ));
+        // Inside the grace window
+        assert!(matches!(
+            policy.status(&v1_0, eol + Duration::days(29)),
+            DeprecationStatus::Deprecated { .. }
+        ));
+        assert_eq!(
+            policy.status(&v1_0, eol + Duration::days(30)),
+            DeprecationStatus::Expired {
+                cutoff: eol + Duration::days(30),
+                upgrade_to: ProtocolVersion::V1_2_0,
+            }
+        );
+    }
 }
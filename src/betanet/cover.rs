@@ -14,6 +14,11 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tracing::debug;
 
+#[cfg(feature = "vrf")]
+use crate::utils::epoch::EpochClock;
+#[cfg(feature = "vrf")]
+use crate::vrf::vrf_delay::{verify_with_public_key, VrfKeyPair, VrfProof};
+
 /// Cover traffic generation mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CoverTrafficMode {
@@ -23,6 +28,9 @@ pub enum CoverTrafficMode {
     Adaptive,
     /// Burst (send in bursts to match real traffic patterns)
     Burst,
+    /// Intervals derived from a VRF over the epoch, so the schedule can be
+    /// proven (constant rate until a schedule key is set)
+    VrfScheduled,
 }
 
 /// Cover traffic configuration
@@ -44,6 +52,14 @@ pub struct CoverTrafficConfig {
     pub max_bandwidth_overhead: f64,
     /// Indistinguishability threshold (0.0-1.0, higher = more similar to real traffic)
     pub indistinguishability_threshold: f64,
+    /// Window over which cover traffic tapers to nothing on shutdown
+    /// (zero stops it at once)
+    #[serde(default = "default_shutdown_ramp_down")]
+    pub shutdown_ramp_down: Duration,
+}
+
+fn default_shutdown_ramp_down() -> Duration {
+    Duration::from_secs(30)
 }
 
 impl Default for CoverTrafficConfig {
@@ -57,6 +73,7 @@ impl Default for CoverTrafficConfig {
             min_real_traffic_rate: 5.0,
             max_bandwidth_overhead: 0.05, // 5% maximum overhead
             indistinguishability_threshold: 0.95, // 95% similarity to real traffic
+            shutdown_ramp_down: default_shutdown_ramp_down(),
         }
     }
 }
@@ -122,7 +139,116 @@ impl Default for TrafficStatistics {
     }
 }
 
+/// Cover send schedule derived from a VRF over the epoch
+///
+/// Intervals jitter ±50% around `1 / target_rate` like burst mode, but they
+/// are a pure function of the VRF output. Anyone holding the node's public
+/// key and the schedule [`proof`](Self::proof) can rebuild the schedule and
+/// check the node really sent cover when it was due. Intervals are whole
+/// milliseconds so prover and verifier agree exactly.
+#[cfg(feature = "vrf")]
+#[derive(Clone)]
+pub struct VrfCoverSchedule {
+    epoch: u64,
+    base_interval_ms: u64,
+    seed: [u8; 32],
+    proof: VrfProof,
+}
+
+#[cfg(feature = "vrf")]
+impl VrfCoverSchedule {
+    /// Derive the schedule for `epoch` with `keypair`
+    pub fn generate(keypair: &VrfKeyPair, epoch: u64, target_rate: f64) -> crate::Result<Self> {
+        let proof = keypair.prove(&Self::message(epoch))?;
+        Ok(Self {
+            epoch,
+            base_interval_ms: Self::base_interval_ms(target_rate),
+            seed: proof.io.make_bytes(b"cover-schedule"),
+            proof,
+        })
+    }
+
+    /// Rebuild another node's schedule from its public key and proof
+    ///
+    /// Fails when the proof was not made by `public_key` for `epoch`.
+    pub fn verify(
+        public_key: &[u8; 32],
+        epoch: u64,
+        target_rate: f64,
+        proof: VrfProof,
+    ) -> crate::Result<Self> {
+        let io = verify_with_public_key(public_key, &Self::message(epoch), &proof).ok_or_else(|| {
+            crate::MixnodeError::Vrf(format!("Cover schedule proof for epoch {} is invalid", epoch))
+        })?;
+        Ok(Self {
+            epoch,
+            base_interval_ms: Self::base_interval_ms(target_rate),
+            seed: io.make_bytes(b"cover-schedule"),
+            proof,
+        })
+    }
+
+    /// Epoch the schedule covers
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Proof to hand to auditors
+    pub fn proof(&self) -> &VrfProof {
+        &self.proof
+    }
+
+    /// Wait before the `index`th cover packet of the epoch
+    pub fn interval(&self, index: u64) -> Duration {
+        let hash = blake3::keyed_hash(&self.seed, &index.to_be_bytes());
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&hash.as_bytes()[..8]);
+        let jitter = u64::from_be_bytes(word) % (self.base_interval_ms + 1);
+        Duration::from_millis((self.base_interval_ms / 2 + jitter).max(1))
+    }
+
+    /// Offsets from the epoch start at which cover is due within `epoch_length`
+    pub fn send_offsets(&self, epoch_length: Duration) -> Vec<Duration> {
+        let mut offsets = Vec::new();
+        let mut at = Duration::ZERO;
+        for index in 0.. {
+            at += self.interval(index);
+            if at >= epoch_length {
+                break;
+            }
+            offsets.push(at);
+        }
+        offsets
+    }
+
+    fn message(epoch: u64) -> Vec<u8> {
+        let mut message = b"betanet-cover-schedule".to_vec();
+        message.extend_from_slice(&epoch.to_be_bytes());
+        message
+    }
+
+    fn base_interval_ms(target_rate: f64) -> u64 {
+        if target_rate > 0.0 {
+            ((1000.0 / target_rate) as u64).max(1)
+        } else {
+            1000
+        }
+    }
+}
+
+/// Schedule key plus the position reached in the current epoch's schedule
+#[cfg(feature = "vrf")]
+struct VrfScheduleCursor {
+    keypair: Arc<VrfKeyPair>,
+    clock: EpochClock,
+    schedule: Option<VrfCoverSchedule>,
+    next_index: u64,
+}
+
 /// Advanced cover traffic generator with traffic shaping and indistinguishability
+///
+/// All mutable state is atomic or mutex-guarded, so the generator is
+/// `Send + Sync` and can be driven from several tasks via `Arc`.
 pub struct AdvancedCoverTrafficGenerator {
     config: CoverTrafficConfig,
     packets_sent: AtomicU64,
@@ -131,6 +257,9 @@ pub struct AdvancedCoverTrafficGenerator {
     cover_traffic_stats: Arc<Mutex<TrafficStatistics>>,
     last_packet_time: Arc<Mutex<Option<Instant>>>,
     rng: Arc<Mutex<StdRng>>,
+    ramp_down_started: Arc<Mutex<Option<Instant>>>,
+    #[cfg(feature = "vrf")]
+    vrf_schedule: Arc<Mutex<Option<VrfScheduleCursor>>>,
 }
 
 impl AdvancedCoverTrafficGenerator {
@@ -144,6 +273,74 @@ impl AdvancedCoverTrafficGenerator {
             cover_traffic_stats: Arc::new(Mutex::new(TrafficStatistics::new())),
             last_packet_time: Arc::new(Mutex::new(None)),
             rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+            ramp_down_started: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "vrf")]
+            vrf_schedule: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Derive `VrfScheduled` intervals from `keypair` over epochs of `clock`
+    #[cfg(feature = "vrf")]
+    pub fn with_vrf_schedule(self, keypair: Arc<VrfKeyPair>, clock: EpochClock) -> Self {
+        Self {
+            vrf_schedule: Arc::new(Mutex::new(Some(VrfScheduleCursor {
+                keypair,
+                clock,
+                schedule: None,
+                next_index: 0,
+            }))),
+            ..self
+        }
+    }
+
+    /// Schedule the generator is following this epoch, with its proof
+    #[cfg(feature = "vrf")]
+    pub async fn vrf_cover_schedule(&self) -> Option<VrfCoverSchedule> {
+        let mut cursor = self.vrf_schedule.lock().await;
+        let cursor = cursor.as_mut()?;
+        self.roll_schedule(cursor).ok()?;
+        cursor.schedule.clone()
+    }
+
+    /// Make sure the cursor follows the clock's current epoch
+    #[cfg(feature = "vrf")]
+    fn roll_schedule(&self, cursor: &mut VrfScheduleCursor) -> crate::Result<()> {
+        let epoch = cursor.clock.current_epoch();
+        if cursor.schedule.as_ref().map(VrfCoverSchedule::epoch) != Some(epoch) {
+            cursor.schedule = Some(VrfCoverSchedule::generate(
+                &cursor.keypair,
+                epoch,
+                self.config.target_rate,
+            )?);
+            cursor.next_index = 0;
+        }
+        Ok(())
+    }
+
+    /// Next interval of the VRF schedule, if a schedule key is set
+    #[cfg(feature = "vrf")]
+    async fn next_vrf_interval(&self) -> Option<Duration> {
+        let mut cursor = self.vrf_schedule.lock().await;
+        let cursor = cursor.as_mut()?;
+        self.roll_schedule(cursor).ok()?;
+        let interval = cursor.schedule.as_ref()?.interval(cursor.next_index);
+        cursor.next_index += 1;
+        Some(interval)
+    }
+
+    #[cfg(not(feature = "vrf"))]
+    async fn next_vrf_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Create generator with a fixed RNG seed
+    ///
+    /// Cover sizes and burst intervals become reproducible, which is what
+    /// indistinguishability tests need. Never use a fixed seed in production.
+    pub fn with_seed(config: CoverTrafficConfig, seed: u64) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+            ..Self::new(config)
         }
     }
 
@@ -190,7 +387,7 @@ impl AdvancedCoverTrafficGenerator {
 
     /// Generate cover packet with indistinguishability from real traffic
     pub async fn generate_cover_packet(&self) -> Option<Vec<u8>> {
-        if !self.config.enabled {
+        if !self.config.enabled || self.is_ramped_down_at(Instant::now()).await {
             return None;
         }
 
@@ -212,9 +409,9 @@ impl AdvancedCoverTrafficGenerator {
         // Update cover traffic statistics
         self.update_cover_stats(packet_size).await;
 
-        // Update counters
-        self.packets_sent.fetch_add(1, Ordering::Relaxed);
-        self.bytes_sent.fetch_add(packet_size as u64, Ordering::Relaxed);
+        // Update counters (saturating so a long-lived node never wraps)
+        saturating_add(&self.packets_sent, 1);
+        saturating_add(&self.bytes_sent, packet_size as u64);
 
         Some(packet)
     }
@@ -261,6 +458,9 @@ impl AdvancedCoverTrafficGenerator {
     }
 
     /// Calculate current bandwidth overhead (cover/real ratio)
+    ///
+    /// Always finite and non-negative, including after `reset_stats` or with
+    /// saturated counters.
     async fn calculate_bandwidth_overhead(&self) -> f64 {
         let cover_bytes = self.bytes_sent.load(Ordering::Relaxed) as f64;
         let real_stats = self.real_traffic_stats.lock().await;
@@ -270,22 +470,82 @@ impl AdvancedCoverTrafficGenerator {
         }
 
         let real_bytes = real_stats.avg_packet_size * real_stats.packet_count as f64;
-        if real_bytes == 0.0 {
+        if !real_bytes.is_finite() || real_bytes <= 0.0 {
             return 0.0;
         }
 
-        cover_bytes / real_bytes
+        let overhead = cover_bytes / real_bytes;
+        if overhead.is_finite() {
+            overhead.max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Start tapering cover traffic for shutdown
+    ///
+    /// Over `shutdown_ramp_down` the cover rate falls linearly to zero, so
+    /// the node's output doesn't change abruptly when it stops. Calling it
+    /// again while a ramp-down is under way has no effect.
+    pub async fn begin_ramp_down(&self) {
+        self.begin_ramp_down_at(Instant::now()).await;
+    }
+
+    /// Start tapering cover traffic as of `at`
+    pub async fn begin_ramp_down_at(&self, at: Instant) {
+        self.ramp_down_started.lock().await.get_or_insert(at);
+    }
+
+    /// Fraction of the normal cover rate in effect at `now` (1.0 until a
+    /// ramp-down begins, 0.0 once it completes)
+    pub async fn rate_factor_at(&self, now: Instant) -> f64 {
+        let Some(started) = *self.ramp_down_started.lock().await else {
+            return 1.0;
+        };
+        let window = self.config.shutdown_ramp_down.as_secs_f64();
+        if window <= 0.0 {
+            return 0.0;
+        }
+        let elapsed = now.saturating_duration_since(started).as_secs_f64();
+        (1.0 - elapsed / window).clamp(0.0, 1.0)
+    }
+
+    /// Check whether a ramp-down has finished and cover traffic has stopped
+    pub async fn is_ramped_down_at(&self, now: Instant) -> bool {
+        self.rate_factor_at(now).await <= 0.0
     }
 
     /// Get interval between cover packets based on mode
     pub async fn cover_interval(&self) -> Duration {
+        self.cover_interval_at(Instant::now()).await
+    }
+
+    /// Interval between cover packets at `now`, stretched during ramp-down
+    pub async fn cover_interval_at(&self, now: Instant) -> Duration {
+        let interval = self.base_cover_interval().await;
+        let factor = self.rate_factor_at(now).await;
+        if factor > 0.0 && factor < 1.0 {
+            interval.div_f64(factor)
+        } else {
+            interval
+        }
+    }
+
+    /// Interval between cover packets at the full rate for the mode
+    async fn base_cover_interval(&self) -> Duration {
+        let constant_rate = || {
+            if self.config.target_rate > 0.0 {
+                Duration::from_secs_f64(1.0 / self.config.target_rate)
+            } else {
+                Duration::from_secs(1)
+            }
+        };
+
         match self.config.mode {
-            CoverTrafficMode::ConstantRate => {
-                if self.config.target_rate > 0.0 {
-                    Duration::from_secs_f64(1.0 / self.config.target_rate)
-                } else {
-                    Duration::from_secs(1)
-                }
+            CoverTrafficMode::ConstantRate => constant_rate(),
+
+            CoverTrafficMode::VrfScheduled => {
+                self.next_vrf_interval().await.unwrap_or_else(constant_rate)
             }
 
             CoverTrafficMode::Adaptive => {
@@ -354,6 +614,13 @@ impl AdvancedCoverTrafficGenerator {
     }
 }
 
+/// Add to a counter, saturating at `u64::MAX` instead of wrapping
+fn saturating_add(counter: &AtomicU64, value: u64) {
+    let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+        Some(current.saturating_add(value))
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -376,7 +643,7 @@ mod tests {
         assert!(packet.is_some());
         // Packet size varies slightly due to randomization
         let len = packet.unwrap().len();
-        assert!(len >= 512 && len <= 2048, "Packet size {} out of expected range", len);
+        assert!((512..=2048).contains(&len), "Packet size {} out of expected range", len);
         assert_eq!(generator.packets_sent(), 1);
     }
 
@@ -391,4 +658,156 @@ mod tests {
         let interval = generator.cover_interval().await;
         assert_eq!(interval.as_millis(), 100); // 1/10 second
     }
+
+    #[tokio::test]
+    async fn test_counters_saturate_near_max() {
+        let generator = AdvancedCoverTrafficGenerator::new(CoverTrafficConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        generator.packets_sent.store(u64::MAX, Ordering::Relaxed);
+        generator.bytes_sent.store(u64::MAX - 10, Ordering::Relaxed);
+
+        // No real traffic yet, so the overhead limit doesn't block generation
+        assert!(generator.generate_cover_packet().await.is_some());
+        assert_eq!(generator.packets_sent(), u64::MAX);
+        assert_eq!(generator.bytes_sent(), u64::MAX);
+
+        generator.update_real_traffic_stats(1024).await;
+        let overhead = generator.calculate_bandwidth_overhead().await;
+        assert!(overhead.is_finite());
+        assert!(overhead >= 0.0);
+
+        generator.reset_stats().await;
+        assert_eq!(generator.calculate_bandwidth_overhead().await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_ramp_down_tapers_rate() {
+        let config = CoverTrafficConfig {
+            enabled: true,
+            mode: CoverTrafficMode::ConstantRate,
+            target_rate: 10.0,
+            shutdown_ramp_down: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let generator = AdvancedCoverTrafficGenerator::new(config.clone());
+        let start = Instant::now();
+        let rate_at = |secs: f64| {
+            let generator = &generator;
+            async move {
+                let interval = generator
+                    .cover_interval_at(start + Duration::from_secs_f64(secs))
+                    .await;
+                1.0 / interval.as_secs_f64()
+            }
+        };
+
+        assert!((rate_at(0.0).await - 10.0).abs() < 1e-9);
+        generator.begin_ramp_down_at(start).await;
+
+        // Rate falls step by step instead of dropping to zero
+        let rates = [
+            rate_at(1.0).await,
+            rate_at(2.5).await,
+            rate_at(5.0).await,
+            rate_at(7.5).await,
+        ];
+        assert!((rates[0] - 9.0).abs() < 1e-6);
+        assert!(rates.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!((rates[2] - 5.0).abs() < 1e-6);
+        assert!(generator.generate_cover_packet().await.is_some());
+
+        // Until the window has passed
+        assert!(generator.is_ramped_down_at(start + Duration::from_secs(10)).await);
+        let finished = AdvancedCoverTrafficGenerator::new(config);
+        finished
+            .begin_ramp_down_at(Instant::now() - Duration::from_secs(11))
+            .await;
+        assert!(finished.generate_cover_packet().await.is_none());
+    }
+
+    #[cfg(feature = "vrf")]
+    #[tokio::test]
+    async fn test_vrf_cover_schedule_verifies() {
+        use crate::utils::epoch::ManualTimeSource;
+
+        let keypair = Arc::new(VrfKeyPair::from_seed([7u8; 32]).unwrap());
+        let source = Arc::new(ManualTimeSource::new(10_000));
+        let clock = EpochClock::with_source(0, Duration::from_secs(60), source.clone());
+        let config = CoverTrafficConfig {
+            enabled: true,
+            mode: CoverTrafficMode::VrfScheduled,
+            target_rate: 10.0,
+            ..Default::default()
+        };
+        let generator = AdvancedCoverTrafficGenerator::new(config)
+            .with_vrf_schedule(keypair.clone(), clock.clone());
+
+        let mut intervals = Vec::new();
+        for _ in 0..50 {
+            intervals.push(generator.cover_interval().await);
+        }
+        assert!(intervals.iter().all(|i| (50..=150).contains(&i.as_millis())));
+        assert!(intervals.windows(2).any(|pair| pair[0] != pair[1]));
+
+        // An auditor rebuilds the same intervals from the public key and proof
+        let schedule = generator.vrf_cover_schedule().await.unwrap();
+        assert_eq!(schedule.epoch(), clock.current_epoch());
+        let rebuilt = VrfCoverSchedule::verify(
+            &keypair.public_key(),
+            schedule.epoch(),
+            10.0,
+            schedule.proof().clone(),
+        )
+        .unwrap();
+        let claimed: Vec<Duration> = (0..50).map(|i| rebuilt.interval(i)).collect();
+        assert_eq!(claimed, intervals);
+        let offsets = rebuilt.send_offsets(clock.epoch_length());
+        assert_eq!(offsets, schedule.send_offsets(clock.epoch_length()));
+        assert!((500..=700).contains(&offsets.len()), "{}", offsets.len());
+
+        // The proof is bound to the epoch and the key
+        let proof = schedule.proof().clone();
+        assert!(
+            VrfCoverSchedule::verify(&keypair.public_key(), schedule.epoch() + 1, 10.0, proof.clone())
+                .is_err()
+        );
+        let other = VrfKeyPair::from_seed([8u8; 32]).unwrap();
+        assert!(VrfCoverSchedule::verify(&other.public_key(), schedule.epoch(), 10.0, proof).is_err());
+
+        // A new epoch brings a new schedule
+        source.advance(Duration::from_secs(60));
+        let next = generator.vrf_cover_schedule().await.unwrap();
+        assert_eq!(next.epoch(), schedule.epoch() + 1);
+        let first = |s: &VrfCoverSchedule| (0..10).map(|i| s.interval(i)).collect::<Vec<_>>();
+        assert_ne!(first(&next), first(&schedule));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_generators_are_reproducible() {
+        let config = CoverTrafficConfig {
+            enabled: true,
+            mode: CoverTrafficMode::Burst,
+            max_bandwidth_overhead: f64::MAX,
+            ..Default::default()
+        };
+
+        async fn sequence(generator: &AdvancedCoverTrafficGenerator) -> Vec<(usize, Duration)> {
+            let mut out = Vec::new();
+            for _ in 0..20 {
+                let size = generator.generate_cover_packet().await.unwrap().len();
+                out.push((size, generator.cover_interval().await));
+            }
+            out
+        }
+
+        let a = AdvancedCoverTrafficGenerator::with_seed(config.clone(), 42);
+        let b = AdvancedCoverTrafficGenerator::with_seed(config.clone(), 42);
+        let c = AdvancedCoverTrafficGenerator::with_seed(config, 7);
+
+        let seq_a = sequence(&a).await;
+        assert_eq!(seq_a, sequence(&b).await);
+        assert_ne!(seq_a, sequence(&c).await);
+    }
 }
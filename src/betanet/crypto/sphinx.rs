@@ -7,6 +7,7 @@
 //! - High-performance batch processing
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -26,10 +27,14 @@ use crate::{
 pub const SPHINX_HEADER_SIZE: usize = 176; // 16 + 32 + 128
 /// Sphinx payload size
 pub const SPHINX_PAYLOAD_SIZE: usize = 1024;
+/// Encoded Sphinx packet size, identical at every hop
+pub const SPHINX_PACKET_SIZE: usize = SPHINX_HEADER_SIZE + SPHINX_PAYLOAD_SIZE;
 /// Maximum number of hops
 pub const MAX_HOPS: usize = 5;
 /// Replay window size (in seconds)
 pub const REPLAY_WINDOW: u64 = 3600; // 1 hour
+/// Default number of packets decrypted concurrently within a batch
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 1;
 
 /// Sphinx routing header
 #[derive(Debug, Clone, PartialEq)]
@@ -121,9 +126,14 @@ impl SphinxPacket {
         Ok(Self { header, payload })
     }
 
+    /// Size of the packet as encoded by [`to_bytes`](Self::to_bytes)
+    pub fn wire_size(&self) -> usize {
+        self.header.to_bytes().len() + self.payload.len()
+    }
+
     /// Convert to bytes
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = Vec::with_capacity(SPHINX_HEADER_SIZE + SPHINX_PAYLOAD_SIZE);
+        let mut bytes = Vec::with_capacity(SPHINX_PACKET_SIZE);
         bytes.extend_from_slice(&self.header.to_bytes());
         bytes.extend_from_slice(&self.payload);
         bytes
@@ -222,7 +232,7 @@ impl ReplayProtection {
         seen.insert(packet_hash, now);
 
         // Periodic cleanup
-        if seen.len() % 1000 == 0 {
+        if seen.len().is_multiple_of(1000) {
             seen.retain(|_, &mut timestamp| now - timestamp < REPLAY_WINDOW);
         }
 
@@ -313,6 +323,9 @@ impl RoutingInfo {
 }
 
 /// High-performance Sphinx processor
+///
+/// `Send + Sync`: replay state and statistics sit behind internal locks, so a
+/// single processor can be shared through an `Arc` by all pipeline workers.
 pub struct SphinxProcessor {
     /// Private key for this node
     private_key: StaticSecret,
@@ -322,6 +335,8 @@ pub struct SphinxProcessor {
     replay_protection: ReplayProtection,
     /// Processing statistics
     stats: Arc<RwLock<SphinxStats>>,
+    /// Packets decrypted concurrently within a batch
+    batch_concurrency: AtomicUsize,
 }
 
 /// Sphinx processing statistics
@@ -355,6 +370,7 @@ impl SphinxProcessor {
             public_key,
             replay_protection: ReplayProtection::new(),
             stats: Arc::new(RwLock::new(SphinxStats::default())),
+            batch_concurrency: AtomicUsize::new(DEFAULT_BATCH_CONCURRENCY),
         }
     }
 
@@ -368,17 +384,44 @@ impl SphinxProcessor {
             public_key,
             replay_protection: ReplayProtection::new(),
             stats: Arc::new(RwLock::new(SphinxStats::default())),
+            batch_concurrency: AtomicUsize::new(DEFAULT_BATCH_CONCURRENCY),
         }
     }
 
+    /// Set batch crypto concurrency (builder style)
+    pub fn with_batch_concurrency(self, concurrency: usize) -> Self {
+        self.set_batch_concurrency(concurrency);
+        self
+    }
+
+    /// Set how many packets of a batch are decrypted concurrently
+    ///
+    /// Independent of batch size and pipeline worker count; values below 1
+    /// are treated as 1.
+    pub fn set_batch_concurrency(&self, concurrency: usize) {
+        self.batch_concurrency
+            .store(concurrency.max(1), Ordering::Relaxed);
+    }
+
+    /// Get batch crypto concurrency
+    pub fn batch_concurrency(&self) -> usize {
+        self.batch_concurrency.load(Ordering::Relaxed)
+    }
+
     /// Get public key
     pub fn public_key(&self) -> &PublicKey {
         &self.public_key
     }
 
     /// Process Sphinx packet with high performance
-    pub async fn process_packet(&self, mut packet: SphinxPacket) -> Result<Option<SphinxPacket>> {
+    pub async fn process_packet(&self, packet: SphinxPacket) -> Result<Option<SphinxPacket>> {
+        self.process_packet_sync(packet)
+    }
+
+    /// Synchronous packet processing shared by single and batch paths
+    pub(crate) fn process_packet_sync(&self, mut packet: SphinxPacket) -> Result<Option<SphinxPacket>> {
         let start_time = std::time::Instant::now();
+        let input_size = packet.wire_size();
 
         // Calculate packet hash for replay protection
         let packet_hash = self.calculate_packet_hash(&packet);
@@ -405,6 +448,11 @@ impl SphinxProcessor {
         // Update header for next hop
         self.update_header(&mut packet.header, &shared_secret.to_bytes())?;
 
+        // A forwarded packet whose size changed is linkable to its input
+        if !routing_info.is_final {
+            Self::check_size_preserved(input_size, &packet)?;
+        }
+
         // Update statistics
         let processing_time = start_time.elapsed().as_nanos() as u64;
         let mut stats = self.stats.write().unwrap();
@@ -424,25 +472,46 @@ impl SphinxProcessor {
     }
 
     /// Process batch of packets for higher throughput
+    ///
+    /// The crypto runs on the blocking pool so the calling runtime worker
+    /// stays free; with `batch_concurrency > 1` it is further split across
+    /// that many threads. One result per packet, always in input order, so a
+    /// packet that fails to decrypt doesn't cost the rest of the batch.
     pub async fn process_batch(
-        &self,
+        self: &Arc<Self>,
         packets: Vec<SphinxPacket>,
-    ) -> Result<Vec<Option<SphinxPacket>>> {
-        let mut results = Vec::with_capacity(packets.len());
-
-        // Process packets in parallel batches
-        for chunk in packets.chunks(128) {
-            // Process 128 packets per batch
-            let mut batch_results = Vec::with_capacity(chunk.len());
+    ) -> Result<Vec<Result<Option<SphinxPacket>>>> {
+        let processor = Arc::clone(self);
+        tokio::task::spawn_blocking(move || processor.process_batch_sync(packets))
+            .await
+            .map_err(|e| MixnodeError::Crypto(format!("Sphinx batch task failed: {}", e)))
+    }
+
+    /// Synchronous batch processing behind [`process_batch`](Self::process_batch)
+    fn process_batch_sync(&self, packets: Vec<SphinxPacket>) -> Vec<Result<Option<SphinxPacket>>> {
+        let concurrency = self.batch_concurrency().min(packets.len());
+        if concurrency > 1 {
+            return process_in_order(packets, concurrency, |packet| {
+                self.process_packet_sync(packet)
+            });
+        }
 
-            for packet in chunk {
-                batch_results.push(self.process_packet(packet.clone()).await?);
-            }
+        packets
+            .into_iter()
+            .map(|packet| self.process_packet_sync(packet))
+            .collect()
+    }
 
-            results.extend(batch_results);
+    /// Check that peeling a layer left the wire size unchanged
+    fn check_size_preserved(input_size: usize, output: &SphinxPacket) -> Result<()> {
+        let output_size = output.wire_size();
+        if output_size != input_size || output_size != SPHINX_PACKET_SIZE {
+            return Err(MixnodeError::Crypto(format!(
+                "Processed packet is {} bytes, expected {} (input {})",
+                output_size, SPHINX_PACKET_SIZE, input_size
+            )));
         }
-
-        Ok(results)
+        Ok(())
     }
 
     /// Calculate packet hash for replay protection
@@ -541,6 +610,35 @@ impl Default for SphinxProcessor {
     }
 }
 
+/// Map `f` over `items` using up to `concurrency` scoped threads,
+/// preserving input order in the output
+fn process_in_order<T, R, F>(items: Vec<T>, concurrency: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let chunk_size = items.len().div_ceil(concurrency.max(1)).max(1);
+    let mut chunks: Vec<Vec<T>> = Vec::with_capacity(concurrency);
+    let mut items = items.into_iter().peekable();
+    while items.peek().is_some() {
+        chunks.push(items.by_ref().take(chunk_size).collect());
+    }
+
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || chunk.into_iter().map(f).collect::<Vec<R>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("sphinx batch thread panicked"))
+            .collect()
+    })
+}
+
 /// Process Sphinx packet (high-level interface)
 pub async fn process_sphinx_packet(packet: &Packet) -> Result<Option<Vec<u8>>> {
     if packet.is_cover_traffic() {
@@ -607,4 +705,93 @@ mod tests {
         assert_eq!(parsed.header, final_packet.header);
         assert_eq!(parsed.payload, final_packet.payload);
     }
+
+    #[test]
+    fn test_forwarded_packets_preserve_wire_size() {
+        let processor = SphinxProcessor::new();
+        let mut packet = SphinxPacket::new();
+        packet.header.routing_info = RoutingInfo::new([7u8; 16], 9000, 10, false).to_bytes();
+        let input = packet.to_bytes();
+        assert_eq!(input.len(), SPHINX_PACKET_SIZE);
+
+        // Blinding and shifting the routing info is what changes an
+        // intermediate hop's header; the padded tail keeps the size fixed
+        processor.update_header(&mut packet.header, &[3u8; 32]).unwrap();
+        let output = packet.to_bytes();
+        assert_eq!(output.len(), input.len());
+        assert_ne!(output, input);
+        assert_eq!(SphinxPacket::from_bytes(&output).unwrap().wire_size(), input.len());
+
+        // A size change is rejected rather than forwarded
+        let packet = SphinxPacket::new();
+        assert!(SphinxProcessor::check_size_preserved(SPHINX_PACKET_SIZE, &packet).is_ok());
+        let err = SphinxProcessor::check_size_preserved(SPHINX_PACKET_SIZE + 16, &packet)
+            .unwrap_err();
+        assert!(err.to_string().contains("expected"), "{}", err);
+    }
+
+    #[test]
+    fn test_process_in_order_preserves_order() {
+        let items: Vec<u32> = (0..1000).collect();
+        let results = process_in_order(items, 4, |x| x * 2);
+        let expected: Vec<u32> = (0..1000).map(|x| x * 2).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_batch_processing() {
+        let processor = Arc::new(SphinxProcessor::new().with_batch_concurrency(4));
+        assert_eq!(processor.batch_concurrency(), 4);
+
+        // Distinct packets; every third is recorded up front so it replays,
+        // and the rest reach decryption, where random bytes fail
+        let packets: Vec<SphinxPacket> = (0..300u32)
+            .map(|i| {
+                let mut packet = SphinxPacket::new();
+                packet.header.ephemeral_key[..4].copy_from_slice(&i.to_be_bytes());
+                packet.payload[..4].copy_from_slice(&i.to_be_bytes());
+                packet
+            })
+            .collect();
+        let replayed = |i: usize| i.is_multiple_of(3);
+        for (i, packet) in packets.iter().enumerate() {
+            if replayed(i) {
+                let hash = processor.calculate_packet_hash(packet);
+                assert!(processor.replay_protection.check_and_record(hash));
+            }
+        }
+
+        let results = processor.process_batch(packets).await.unwrap();
+        assert_eq!(results.len(), 300);
+        for (i, result) in results.iter().enumerate() {
+            if replayed(i) {
+                assert!(matches!(result, Ok(None)), "packet {}: {:?}", i, result);
+            } else {
+                assert!(result.is_err(), "packet {}: {:?}", i, result);
+            }
+        }
+        assert_eq!(processor.stats().packets_dropped_replay, 100);
+    }
+
+    #[tokio::test]
+    async fn test_batch_processing_leaves_runtime_free() {
+        let processor = Arc::new(SphinxProcessor::new().with_batch_concurrency(2));
+        let packets: Vec<SphinxPacket> = (0..2000u32)
+            .map(|i| {
+                let mut packet = SphinxPacket::new();
+                packet.payload[..4].copy_from_slice(&i.to_be_bytes());
+                packet
+            })
+            .collect();
+
+        // The only runtime thread keeps ticking while the batch decrypts
+        let ticker = tokio::spawn(async {
+            for _ in 0..1_000 {
+                tokio::task::yield_now().await;
+            }
+        });
+        let results = processor.process_batch(packets).await.unwrap();
+        assert!(ticker.is_finished());
+        assert_eq!(results.len(), 2000);
+    }
 }
@@ -43,6 +43,7 @@
 #![deny(clippy::all)]
 #![allow(missing_docs)]
 
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -53,8 +54,12 @@ use tokio::sync::RwLock;
 
 // Core modules
 pub mod core {
+    pub mod blocklist;
+    pub mod circuits;
     pub mod config;
+    pub mod connections;
     pub mod mixnode;
+    pub mod probe;
     pub mod protocol_version;
     pub mod relay_lottery;
     pub mod routing;
@@ -80,6 +85,9 @@ pub mod vrf {
 // Utility modules
 pub mod utils {
     pub mod delay;
+    pub mod entropy;
+    pub mod epoch;
+    pub mod mtu;
     pub mod packet;
     pub mod rate;
     pub mod timing_defense;
@@ -89,6 +97,10 @@ pub mod utils {
 #[cfg(feature = "cover-traffic")]
 pub mod cover;
 
+// Plaintext echo bypasses Sphinx entirely; never let it ship
+#[cfg(all(feature = "debug-echo", not(debug_assertions)))]
+compile_error!("the `debug-echo` feature is for debug builds only; disable it for release");
+
 // High-performance pipeline (primary implementation)
 pub mod pipeline;
 
@@ -96,7 +108,11 @@ pub mod pipeline;
 pub use core::config::MixnodeConfig;
 pub use core::mixnode::StandardMixnode;
 pub use crypto::sphinx::{SphinxPacket, SphinxProcessor};
-pub use pipeline::{PacketPipeline, PipelineBenchmark, PipelinePacket};
+pub use pipeline::{
+    BatchingConfig, BenchmarkReport, HealthMonitor, HealthStatus, LaneConfig, PacketPipeline,
+    PipelineBenchmark, PipelinePacket, PipelineStatsSnapshot, QueueStrategy, SourcePolicy,
+    TargetMiss, WarmupConfig,
+};
 pub use utils::packet::Packet;
 
 /// Mixnode protocol version
@@ -159,6 +175,9 @@ pub struct MixnodeStats {
     pub avg_processing_time_us: f64,
     /// Uptime in seconds
     pub uptime_secs: u64,
+    /// Dropped packets broken down by reason
+    #[serde(default)]
+    pub drop_reasons: BTreeMap<String, u64>,
 }
 
 impl MixnodeStats {
@@ -186,12 +205,55 @@ impl MixnodeStats {
         self.packets_dropped += 1;
     }
 
+    /// Record dropped packet with the reason it was dropped
+    pub fn record_dropped_with_reason(&mut self, reason: &str) {
+        self.record_dropped();
+        *self.drop_reasons.entry(reason.to_string()).or_insert(0) += 1;
+    }
+
     /// Record cover traffic
     pub fn record_cover_traffic(&mut self) {
         self.cover_traffic_sent += 1;
     }
 }
 
+/// Destination for the metrics a mixnode records
+///
+/// [`MixnodeStats`] is the built-in sink. Hand a custom one (e.g. one that
+/// pushes straight to statsd) to [`StandardMixnode::with_metrics_sink`] to
+/// record somewhere else without scraping the stats.
+pub trait MetricsSink: Send + Sync {
+    /// Packet processed in `processing_time`
+    fn record_processed(&mut self, processing_time: Duration);
+
+    /// Packet forwarded to its next hop
+    fn record_forwarded(&mut self);
+
+    /// Packet dropped for `reason`
+    fn record_dropped(&mut self, reason: &str);
+
+    /// Cover packet sent
+    fn record_cover_traffic(&mut self);
+}
+
+impl MetricsSink for MixnodeStats {
+    fn record_processed(&mut self, processing_time: Duration) {
+        MixnodeStats::record_processed(self, processing_time);
+    }
+
+    fn record_forwarded(&mut self) {
+        MixnodeStats::record_forwarded(self);
+    }
+
+    fn record_dropped(&mut self, reason: &str) {
+        self.record_dropped_with_reason(reason);
+    }
+
+    fn record_cover_traffic(&mut self) {
+        MixnodeStats::record_cover_traffic(self);
+    }
+}
+
 /// Mixnode trait for different implementations
 #[async_trait::async_trait]
 pub trait MixnodeTrait: Send + Sync {
@@ -212,6 +274,7 @@ pub trait MixnodeTrait: Send + Sync {
 }
 
 /// Performance targets for the pipeline
+#[derive(Debug, Clone)]
 pub struct PerformanceTargets {
     /// Target throughput (packets per second)
     pub target_throughput_pps: f64,
@@ -256,6 +319,29 @@ mod unit_tests {
         assert_eq!(stats.packets_forwarded, 1);
     }
 
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_shared_types_are_send_sync() {
+        assert_send_sync::<MixnodeStats>();
+        assert_send_sync::<StandardMixnode>();
+        assert_send_sync::<PacketPipeline>();
+        assert_send_sync::<SphinxProcessor>();
+        assert_send_sync::<core::relay_lottery::RelayLottery>();
+        assert_send_sync::<core::relay_lottery::SharedRelayLottery>();
+        assert_send_sync::<core::reputation::ReputationManager>();
+        assert_send_sync::<core::routing::RoutingTable>();
+        assert_send_sync::<vrf::poisson_delay::PoissonDelayGenerator>();
+        #[cfg(feature = "vrf")]
+        assert_send_sync::<vrf::vrf_delay::VrfKeyPair>();
+        assert_send_sync::<utils::delay::DelayQueue>();
+        assert_send_sync::<utils::rate::RateLimitedTrafficShaper>();
+        assert_send_sync::<utils::timing_defense::TimingDefenseManager>();
+        assert_send_sync::<server::tcp::TcpServer>();
+        #[cfg(feature = "cover-traffic")]
+        assert_send_sync::<cover::AdvancedCoverTrafficGenerator>();
+    }
+
     #[test]
     fn test_performance_targets() {
         let targets = PerformanceTargets::default();
@@ -7,16 +7,18 @@
 //! - SIMD optimizations for crypto operations
 //! - Lock-free data structures where possible
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use bytes::{Bytes, BytesMut};
+use crossbeam_queue::ArrayQueue;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tokio::sync::{broadcast, Semaphore};
 use tokio::time::sleep;
 
-use crate::{MixnodeError, Result};
+use crate::{MixnodeError, PerformanceTargets, Result};
 
 #[cfg(feature = "cover-traffic")]
 use crate::cover::{AdvancedCoverTrafficGenerator, CoverTrafficConfig};
@@ -35,8 +37,383 @@ pub const BATCH_SIZE: usize = 128;
 pub const POOL_SIZE: usize = 1024;
 /// Maximum queue depth before backpressure
 pub const MAX_QUEUE_DEPTH: usize = 10000;
+/// Batches between drop-rate feedback updates to the ingress limiter
+pub const DROP_FEEDBACK_INTERVAL_BATCHES: u64 = 100;
+/// Batches processed by [`PacketPipeline::next_batch`] between cooperative yields
+pub const DEFAULT_YIELD_EVERY_BATCHES: usize = 8;
+/// Blocking threads timed processing may occupy, counting packets abandoned
+/// past the processing timeout that are still running
+pub const MAX_TIMED_PROCESSING_THREADS: usize = 16;
+
+/// Per-packet processing deadline and the blocking threads it may occupy
+///
+/// Abandoned packets keep their thread until they finish, so they keep
+/// holding a permit; once every permit is held new packets are shed.
+#[derive(Debug, Clone)]
+struct ProcessingTimeout {
+    timeout: Duration,
+    threads: Arc<Semaphore>,
+}
+
+impl ProcessingTimeout {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            threads: Arc::new(Semaphore::new(MAX_TIMED_PROCESSING_THREADS)),
+        }
+    }
+}
+
+/// What happens to `PipelinePacket::source` once a packet has been processed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SourcePolicy {
+    /// Clear the upstream address before the packet leaves the pipeline
+    #[default]
+    Strip,
+    /// Keep the upstream address (diagnostics only; leaks the previous hop)
+    Retain,
+}
+
+impl SourcePolicy {
+    /// Apply the policy to processed packets
+    pub fn apply(self, packets: &mut [PipelinePacket]) {
+        if self == SourcePolicy::Strip {
+            for packet in packets.iter_mut() {
+                packet.strip_source();
+            }
+        }
+    }
+
+    /// Debug check that forwarded packets carry no upstream source
+    fn debug_assert_applied(self, packets: &[PipelinePacket]) {
+        debug_assert!(
+            self == SourcePolicy::Retain || packets.iter().all(|p| p.source.is_none()),
+            "forwarded packet still carries its upstream source address"
+        );
+    }
+}
+
+/// When a worker hands its accumulated packets to processing
+///
+/// The default flushes every non-empty poll, i.e. no accumulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchingConfig {
+    /// Packets to accumulate before flushing (capped at [`BATCH_SIZE`])
+    pub min_batch_size: usize,
+    /// Longest a partial batch waits for more packets
+    pub min_batch_delay: Duration,
+    /// Flush a partial batch as soon as the input queue drains, so packets
+    /// arriving during a lull don't wait out `min_batch_delay`
+    pub flush_on_idle: bool,
+    /// Distinct packet sources a batch needs before it is forwarded
+    /// (threshold mix; 0 or 1 disables). Bounded in practice by
+    /// [`BATCH_SIZE`], since a held batch doesn't grow past it.
+    pub min_anonymity_set: usize,
+    /// Longest a batch is held waiting for `min_anonymity_set` sources
+    pub anonymity_timeout: Duration,
+    /// Payload bytes at which a batch stops taking packets, even short of
+    /// [`BATCH_SIZE`]. The packet that crosses the budget stays in, so a
+    /// batch overshoots it by less than one packet. `None` limits by count
+    /// alone.
+    pub max_batch_bytes: Option<usize>,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            min_batch_size: 1,
+            min_batch_delay: Duration::ZERO,
+            flush_on_idle: false,
+            min_anonymity_set: 0,
+            anonymity_timeout: Duration::from_secs(1),
+            max_batch_bytes: None,
+        }
+    }
+}
+
+impl BatchingConfig {
+    /// Decide whether a pending batch of `len` packets, the oldest collected
+    /// `waited` ago, should be processed now
+    pub fn should_flush(&self, len: usize, waited: Duration, queue_idle: bool) -> bool {
+        len > 0
+            && (len >= self.min_batch_size.clamp(1, BATCH_SIZE)
+                || waited >= self.min_batch_delay
+                || (self.flush_on_idle && queue_idle))
+    }
+
+    /// Check if `batch` has used up the byte budget
+    pub fn reached_byte_budget(&self, batch: &[PipelinePacket]) -> bool {
+        self.max_batch_bytes.is_some_and(|max| batch_bytes(batch) >= max)
+    }
+
+    /// Decide whether a batch with `distinct_sources` sources, the oldest
+    /// packet collected `waited` ago, must keep waiting for more sources
+    pub fn holds_for_anonymity(&self, distinct_sources: usize, waited: Duration) -> bool {
+        self.min_anonymity_set > 1
+            && distinct_sources < self.min_anonymity_set
+            && waited < self.anonymity_timeout
+    }
+
+    /// Number of distinct upstream sources in `batch`
+    ///
+    /// Packets without a recorded source don't count toward the set.
+    pub fn distinct_sources(batch: &[PipelinePacket]) -> usize {
+        batch
+            .iter()
+            .filter_map(|packet| packet.source)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Payload bytes held by `batch`
+fn batch_bytes(batch: &[PipelinePacket]) -> usize {
+    batch.iter().map(|packet| packet.data.len()).sum()
+}
+
+/// Split of the input queue into a high-priority and a normal lane
+///
+/// While both lanes hold packets they are served in a weighted round of
+/// `high_weight` high packets to `normal_weight` normal ones, and a normal
+/// packet that has waited `max_normal_wait` is served next regardless, so
+/// sustained high-priority load can't starve normal traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneConfig {
+    /// Packets whose `priority` is at most this go to the high lane
+    pub high_priority_max: u8,
+    /// High packets per round while both lanes are busy (min 1)
+    pub high_weight: u32,
+    /// Normal packets per round while both lanes are busy (min 1)
+    pub normal_weight: u32,
+    /// Age at which a waiting normal packet is promoted ahead of the round
+    pub max_normal_wait: Duration,
+}
+
+impl Default for LaneConfig {
+    fn default() -> Self {
+        Self {
+            high_priority_max: 0,
+            high_weight: 7,
+            normal_weight: 3,
+            max_normal_wait: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Input queue, optionally split into weighted priority lanes
+///
+/// Without a [`LaneConfig`] every packet shares one FIFO lane.
+#[derive(Debug, Default)]
+pub struct PriorityLanes {
+    config: Option<LaneConfig>,
+    high: VecDeque<PipelinePacket>,
+    normal: VecDeque<PipelinePacket>,
+    served_high: u32,
+    served_normal: u32,
+    promoted: u64,
+}
+
+impl PriorityLanes {
+    /// Create queue with the given lane split
+    pub fn new(config: Option<LaneConfig>) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Lane split, if any
+    pub fn config(&self) -> Option<&LaneConfig> {
+        self.config.as_ref()
+    }
+
+    /// Queue a packet in its lane
+    pub fn push(&mut self, packet: PipelinePacket) {
+        match self.config {
+            Some(config) if packet.priority <= config.high_priority_max => {
+                self.high.push_back(packet)
+            }
+            _ => self.normal.push_back(packet),
+        }
+    }
+
+    /// Take the next packet to process
+    pub fn pop(&mut self) -> Option<PipelinePacket> {
+        self.pop_at(Instant::now())
+    }
+
+    /// Take the next packet to process as of `now`
+    pub fn pop_at(&mut self, now: Instant) -> Option<PipelinePacket> {
+        let Some(config) = self.config else {
+            return self.normal.pop_front();
+        };
+        if self.high.is_empty() || self.normal.is_empty() {
+            return self.high.pop_front().or_else(|| self.normal.pop_front());
+        }
+
+        let oldest_normal = self.normal.front().map(|p| p.arrival_time)?;
+        if now.saturating_duration_since(oldest_normal) >= config.max_normal_wait {
+            self.promoted += 1;
+            return self.normal.pop_front();
+        }
+
+        if self.served_high >= config.high_weight.max(1)
+            && self.served_normal >= config.normal_weight.max(1)
+        {
+            self.served_high = 0;
+            self.served_normal = 0;
+        }
+        if self.served_high < config.high_weight.max(1) {
+            self.served_high += 1;
+            self.high.pop_front()
+        } else {
+            self.served_normal += 1;
+            self.normal.pop_front()
+        }
+    }
+
+    /// Packets queued across both lanes
+    pub fn len(&self) -> usize {
+        self.high.len() + self.normal.len()
+    }
+
+    /// Check whether both lanes are empty
+    pub fn is_empty(&self) -> bool {
+        self.high.is_empty() && self.normal.is_empty()
+    }
+
+    /// Packets queued in the (high, normal) lanes
+    pub fn lane_depths(&self) -> (usize, usize) {
+        (self.high.len(), self.normal.len())
+    }
+
+    /// Normal packets served early because they aged past `max_normal_wait`
+    pub fn promoted(&self) -> u64 {
+        self.promoted
+    }
+}
+
+/// How the input queue is synchronized between submitters and workers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueueStrategy {
+    /// Mutex-guarded queue, supporting priority lanes
+    #[default]
+    Balanced,
+    /// Bounded lock-free MPMC ring of [`MAX_QUEUE_DEPTH`] slots
+    ///
+    /// Concurrent producers don't serialize on a lock. The ring is a single
+    /// FIFO lane, so a pipeline configured with [`LaneConfig`] keeps the
+    /// mutex-guarded queue.
+    MaxThroughput,
+}
+
+/// Input queue storage chosen by [`QueueStrategy`]
+#[derive(Debug)]
+enum InputQueue {
+    Locked(Mutex<PriorityLanes>),
+    Ring(Box<ArrayQueue<PipelinePacket>>),
+}
+
+impl InputQueue {
+    fn new(strategy: QueueStrategy, lanes: Option<LaneConfig>) -> Self {
+        match (strategy, lanes) {
+            (QueueStrategy::MaxThroughput, None) => {
+                Self::Ring(Box::new(ArrayQueue::new(MAX_QUEUE_DEPTH)))
+            }
+            _ => Self::Locked(Mutex::new(PriorityLanes::new(lanes))),
+        }
+    }
+
+    /// Queue a packet, handing it back if the queue is full
+    fn push(&self, packet: PipelinePacket) -> std::result::Result<(), PipelinePacket> {
+        match self {
+            Self::Locked(lanes) => {
+                let mut lanes = lanes.lock().unwrap();
+                if lanes.len() >= MAX_QUEUE_DEPTH {
+                    return Err(packet);
+                }
+                lanes.push(packet);
+                Ok(())
+            }
+            Self::Ring(ring) => ring.push(packet),
+        }
+    }
+
+    /// Take a packet in arrival order, without lane scheduling
+    fn pop(&self) -> Option<PipelinePacket> {
+        match self {
+            Self::Locked(lanes) => lanes.lock().unwrap().pop(),
+            Self::Ring(ring) => ring.pop(),
+        }
+    }
+
+    /// Top `batch` up to `BATCH_SIZE` live packets, or until it holds
+    /// `max_bytes` payload bytes, returning how many expired packets were
+    /// discarded along the way
+    fn fill(
+        &self,
+        batch: &mut Vec<PipelinePacket>,
+        now: Instant,
+        max_bytes: Option<usize>,
+    ) -> usize {
+        match self {
+            Self::Locked(lanes) => {
+                let mut lanes = lanes.lock().unwrap();
+                Self::fill_from(batch, now, max_bytes, || lanes.pop_at(now))
+            }
+            Self::Ring(ring) => Self::fill_from(batch, now, max_bytes, || ring.pop()),
+        }
+    }
+
+    fn fill_from(
+        batch: &mut Vec<PipelinePacket>,
+        now: Instant,
+        max_bytes: Option<usize>,
+        mut next: impl FnMut() -> Option<PipelinePacket>,
+    ) -> usize {
+        let max_bytes = max_bytes.unwrap_or(usize::MAX);
+        let mut bytes = batch_bytes(batch);
+        let mut expired = 0;
+        while batch.len() < BATCH_SIZE && bytes < max_bytes {
+            let Some(packet) = next() else {
+                break;
+            };
+            if packet.is_expired_at(now) {
+                expired += 1;
+            } else {
+                bytes += packet.data.len();
+                batch.push(packet);
+            }
+        }
+        expired
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Locked(lanes) => lanes.lock().unwrap().len(),
+            Self::Ring(ring) => ring.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Locked(lanes) => lanes.lock().unwrap().is_empty(),
+            Self::Ring(ring) => ring.is_empty(),
+        }
+    }
+
+    fn lanes(&self) -> Option<LaneConfig> {
+        match self {
+            Self::Locked(lanes) => lanes.lock().unwrap().config().copied(),
+            Self::Ring(_) => None,
+        }
+    }
+}
 
 /// High-performance packet processing pipeline
+///
+/// `Send + Sync`. `submit_packet` and `get_processed_packets` take `&self`,
+/// so a started pipeline is normally shared as `Arc<PacketPipeline>`.
 pub struct PacketPipeline {
     /// Packet buffer memory pool
     memory_pool: Arc<MemoryPool>,
@@ -44,7 +421,9 @@ pub struct PacketPipeline {
     #[cfg(feature = "sphinx")]
     sphinx_processor: Arc<SphinxProcessor>,
     /// Input packet queue
-    input_queue: Arc<Mutex<VecDeque<PipelinePacket>>>,
+    input_queue: Arc<InputQueue>,
+    /// Input queue synchronization
+    queue_strategy: QueueStrategy,
     /// Output packet queue
     output_queue: Arc<Mutex<VecDeque<PipelinePacket>>>,
     /// Processing semaphore for backpressure
@@ -60,6 +439,16 @@ pub struct PacketPipeline {
     /// Cover traffic generator (if enabled)
     #[cfg(feature = "cover-traffic")]
     cover_traffic: Arc<Mutex<AdvancedCoverTrafficGenerator>>,
+    /// Yield to the runtime every N calls to `next_batch`
+    yield_every: AtomicUsize,
+    /// `next_batch` calls since the last yield
+    batches_since_yield: AtomicUsize,
+    /// Upstream address handling after processing
+    source_policy: SourcePolicy,
+    /// Worker batch accumulation
+    batching: BatchingConfig,
+    /// Longest a single packet may spend in processing
+    processing_timeout: Option<ProcessingTimeout>,
 }
 
 /// Pipeline packet with metadata
@@ -73,6 +462,8 @@ pub struct PipelinePacket {
     pub priority: u8,
     /// Source address for routing decisions
     pub source: Option<std::net::SocketAddr>,
+    /// Latest time the packet is still worth processing
+    pub deadline: Option<Instant>,
 }
 
 impl PipelinePacket {
@@ -83,6 +474,15 @@ impl PipelinePacket {
             arrival_time: Instant::now(),
             priority: 0,
             source: None,
+            deadline: None,
+        }
+    }
+
+    /// Create a packet that is discarded unprocessed once `deadline` passes
+    pub fn new_with_deadline(data: Bytes, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Self::new(data)
         }
     }
 
@@ -93,6 +493,7 @@ impl PipelinePacket {
             arrival_time: Instant::now(),
             priority,
             source: None,
+            deadline: None,
         }
     }
 
@@ -100,6 +501,16 @@ impl PipelinePacket {
     pub fn age(&self) -> Duration {
         self.arrival_time.elapsed()
     }
+
+    /// Check whether the deadline has passed as of `now`
+    pub fn is_expired_at(&self, now: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| now > deadline)
+    }
+
+    /// Forget the upstream address once routing no longer needs it
+    pub fn strip_source(&mut self) {
+        self.source = None;
+    }
 }
 
 /// Memory pool for packet buffers
@@ -179,6 +590,15 @@ pub struct PipelineStats {
     pub packets_processed: AtomicU64,
     /// Total packets dropped (overflow/errors)
     pub packets_dropped: AtomicU64,
+    /// Packets discarded at batching because their deadline had passed
+    /// (also counted in `packets_dropped`)
+    pub packets_expired: AtomicU64,
+    /// Packets abandoned because processing outran the processing timeout
+    /// (also counted in `packets_dropped`)
+    pub packets_timed_out: AtomicU64,
+    /// Packets refused processing because abandoned packets held every
+    /// timed processing thread (also counted in `packets_dropped`)
+    pub packets_shed: AtomicU64,
     /// Total processing time (nanoseconds)
     pub total_processing_time_ns: AtomicU64,
     /// Batch processing efficiency
@@ -187,6 +607,136 @@ pub struct PipelineStats {
     pub avg_queue_depth: AtomicU64,
     /// Memory pool efficiency
     pub pool_hit_rate: AtomicU64,
+    /// Buffer requests the memory pool served from reuse
+    pub pool_hits: AtomicU64,
+    /// Buffer requests the memory pool had to allocate for
+    pub pool_misses: AtomicU64,
+    /// Distribution of batch sizes
+    pub batch_sizes: BatchSizeHistogram,
+    /// Distribution of arrival-to-processed packet latency
+    pub packet_latency: LatencyHistogram,
+}
+
+/// Number of power-of-two buckets needed to cover sizes up to [`BATCH_SIZE`]
+pub const BATCH_SIZE_BUCKETS: usize = BATCH_SIZE.ilog2() as usize + 1;
+
+/// Fixed-size histogram of batch sizes
+///
+/// Bucket `i` counts batches of `2^(i-1) + 1 ..= 2^i` packets (bucket 0 is
+/// single-packet batches), so memory stays constant however long the
+/// pipeline runs and a batcher swinging between tiny and full batches shows
+/// up as weight at both ends.
+#[derive(Debug, Default)]
+pub struct BatchSizeHistogram {
+    buckets: [AtomicU64; BATCH_SIZE_BUCKETS],
+}
+
+impl BatchSizeHistogram {
+    /// Record one batch
+    pub fn record(&self, batch_size: u64) {
+        let index = (batch_size.max(1).next_power_of_two().ilog2() as usize)
+            .min(BATCH_SIZE_BUCKETS - 1);
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Largest batch size counted by each bucket
+    pub fn upper_bounds() -> [u64; BATCH_SIZE_BUCKETS] {
+        std::array::from_fn(|i| 1u64 << i)
+    }
+
+    /// Batch count per bucket
+    pub fn counts(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Upper bound of the bucket holding the `p`-th percentile (0.0-1.0)
+    /// batch, or `None` before any batch is recorded
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let counts = self.counts();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let rank = ((p.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bound, count) in Self::upper_bounds().into_iter().zip(counts) {
+            seen += count;
+            if seen >= rank {
+                return Some(bound);
+            }
+        }
+        Some(BATCH_SIZE as u64)
+    }
+
+    fn from_counts(counts: &[u64]) -> Self {
+        let histogram = Self::default();
+        for (bucket, &count) in histogram.buckets.iter().zip(counts) {
+            bucket.store(count, Ordering::Relaxed);
+        }
+        histogram
+    }
+}
+
+/// Number of power-of-two microsecond buckets in [`LatencyHistogram`]
+/// (the last one collects everything from about 4 seconds up)
+pub const LATENCY_BUCKETS: usize = 24;
+
+/// Fixed-size histogram of packet latencies
+///
+/// Bucket `i` counts latencies of `2^(i-1) + 1 ..= 2^i` microseconds, like
+/// [`BatchSizeHistogram`] does for batch sizes.
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+}
+
+impl LatencyHistogram {
+    /// Record one packet latency
+    pub fn record(&self, latency: Duration) {
+        let micros = (latency.as_micros() as u64).max(1);
+        let index = (micros.next_power_of_two().ilog2() as usize).min(LATENCY_BUCKETS - 1);
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Packet count per bucket
+    pub fn counts(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Upper bound of the bucket holding the `p`-th percentile (0.0-1.0)
+    /// latency, or `None` before any packet is recorded
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        let counts = self.counts();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let rank = ((p.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in counts.into_iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_micros(1 << i));
+            }
+        }
+        Some(Duration::from_micros(1 << (LATENCY_BUCKETS - 1)))
+    }
+
+    fn from_counts(counts: &[u64]) -> Self {
+        let histogram = Self::default();
+        for (bucket, &count) in histogram.buckets.iter().zip(counts) {
+            bucket.store(count, Ordering::Relaxed);
+        }
+        histogram
+    }
 }
 
 impl PipelineStats {
@@ -195,10 +745,17 @@ impl PipelineStats {
         Self {
             packets_processed: AtomicU64::new(0),
             packets_dropped: AtomicU64::new(0),
+            packets_expired: AtomicU64::new(0),
+            packets_timed_out: AtomicU64::new(0),
+            packets_shed: AtomicU64::new(0),
             total_processing_time_ns: AtomicU64::new(0),
             batches_processed: AtomicU64::new(0),
             avg_queue_depth: AtomicU64::new(0),
             pool_hit_rate: AtomicU64::new(0),
+            pool_hits: AtomicU64::new(0),
+            pool_misses: AtomicU64::new(0),
+            batch_sizes: BatchSizeHistogram::default(),
+            packet_latency: LatencyHistogram::default(),
         }
     }
 
@@ -210,8 +767,9 @@ impl PipelineStats {
     }
 
     /// Record batch processing
-    pub fn record_batch(&self, _batch_size: u64) {
+    pub fn record_batch(&self, batch_size: u64) {
         self.batches_processed.fetch_add(1, Ordering::Relaxed);
+        self.batch_sizes.record(batch_size);
     }
 
     /// Update memory pool hit rate
@@ -221,6 +779,12 @@ impl PipelineStats {
         self.pool_hit_rate.store(hit_rate_fp, Ordering::Relaxed);
     }
 
+    /// Record the memory pool's cumulative (allocated, reused) counts
+    pub fn record_pool_usage(&self, allocated: usize, reused: usize) {
+        self.pool_misses.store(allocated as u64, Ordering::Relaxed);
+        self.pool_hits.store(reused as u64, Ordering::Relaxed);
+    }
+
     /// Get memory pool hit rate as percentage
     pub fn get_pool_hit_rate(&self) -> f64 {
         let hit_rate_fp = self.pool_hit_rate.load(Ordering::Relaxed);
@@ -230,11 +794,10 @@ impl PipelineStats {
     /// Get average processing time per packet (nanoseconds)
     pub fn avg_processing_time_ns(&self) -> u64 {
         let processed = self.packets_processed.load(Ordering::Relaxed);
-        if processed > 0 {
-            self.total_processing_time_ns.load(Ordering::Relaxed) / processed
-        } else {
-            0
-        }
+        self.total_processing_time_ns
+            .load(Ordering::Relaxed)
+            .checked_div(processed)
+            .unwrap_or(0)
     }
 
     /// Get throughput (packets per second)
@@ -242,6 +805,128 @@ impl PipelineStats {
         let processed = self.packets_processed.load(Ordering::Relaxed) as f64;
         processed / duration.as_secs_f64()
     }
+
+    /// Copy the counters into plain values
+    pub fn snapshot(&self) -> PipelineStatsSnapshot {
+        let latency_counts = self.packet_latency.counts();
+        let latency_us =
+            |p| self.packet_latency.percentile(p).map_or(0, |d| d.as_micros() as u64);
+        PipelineStatsSnapshot {
+            packets_processed: self.packets_processed.load(Ordering::Relaxed),
+            packets_dropped: self.packets_dropped.load(Ordering::Relaxed),
+            packets_expired: self.packets_expired.load(Ordering::Relaxed),
+            packets_timed_out: self.packets_timed_out.load(Ordering::Relaxed),
+            packets_shed: self.packets_shed.load(Ordering::Relaxed),
+            total_processing_time_ns: self.total_processing_time_ns.load(Ordering::Relaxed),
+            batches_processed: self.batches_processed.load(Ordering::Relaxed),
+            avg_queue_depth: self.avg_queue_depth.load(Ordering::Relaxed),
+            pool_hit_rate_pct: self.get_pool_hit_rate(),
+            pool_hits: self.pool_hits.load(Ordering::Relaxed),
+            pool_misses: self.pool_misses.load(Ordering::Relaxed),
+            avg_processing_time_ns: self.avg_processing_time_ns(),
+            batch_size_histogram: self.batch_sizes.counts(),
+            batch_size_p50: self.batch_sizes.percentile(0.50).unwrap_or(0),
+            batch_size_p99: self.batch_sizes.percentile(0.99).unwrap_or(0),
+            packet_latency_count: latency_counts.iter().sum(),
+            packet_latency_histogram: latency_counts,
+            packet_latency_p50_us: latency_us(0.50),
+            packet_latency_p95_us: latency_us(0.95),
+            packet_latency_p99_us: latency_us(0.99),
+        }
+    }
+}
+
+impl From<PipelineStatsSnapshot> for PipelineStats {
+    fn from(snapshot: PipelineStatsSnapshot) -> Self {
+        let stats = Self {
+            packets_processed: AtomicU64::new(snapshot.packets_processed),
+            packets_dropped: AtomicU64::new(snapshot.packets_dropped),
+            packets_expired: AtomicU64::new(snapshot.packets_expired),
+            packets_timed_out: AtomicU64::new(snapshot.packets_timed_out),
+            packets_shed: AtomicU64::new(snapshot.packets_shed),
+            total_processing_time_ns: AtomicU64::new(snapshot.total_processing_time_ns),
+            batches_processed: AtomicU64::new(snapshot.batches_processed),
+            avg_queue_depth: AtomicU64::new(snapshot.avg_queue_depth),
+            pool_hit_rate: AtomicU64::new(0),
+            pool_hits: AtomicU64::new(snapshot.pool_hits),
+            pool_misses: AtomicU64::new(snapshot.pool_misses),
+            batch_sizes: BatchSizeHistogram::from_counts(&snapshot.batch_size_histogram),
+            packet_latency: LatencyHistogram::from_counts(&snapshot.packet_latency_histogram),
+        };
+        stats.update_pool_hit_rate(snapshot.pool_hit_rate_pct);
+        stats
+    }
+}
+
+impl Serialize for PipelineStats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PipelineStats {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        PipelineStatsSnapshot::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Point-in-time copy of [`PipelineStats`] for shipping to a collector
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStatsSnapshot {
+    /// Total packets processed
+    pub packets_processed: u64,
+    /// Total packets dropped (overflow/errors)
+    pub packets_dropped: u64,
+    /// Packets discarded past their deadline (subset of `packets_dropped`)
+    #[serde(default)]
+    pub packets_expired: u64,
+    /// Packets abandoned past the processing timeout (subset of `packets_dropped`)
+    #[serde(default)]
+    pub packets_timed_out: u64,
+    /// Packets shed while timed processing was saturated (subset of `packets_dropped`)
+    #[serde(default)]
+    pub packets_shed: u64,
+    /// Total processing time (nanoseconds)
+    pub total_processing_time_ns: u64,
+    /// Batches processed
+    pub batches_processed: u64,
+    /// Queue depth sample
+    pub avg_queue_depth: u64,
+    /// Memory pool hit rate (percentage)
+    pub pool_hit_rate_pct: f64,
+    /// Buffer requests served from the memory pool
+    #[serde(default)]
+    pub pool_hits: u64,
+    /// Buffer requests the memory pool allocated for
+    #[serde(default)]
+    pub pool_misses: u64,
+    /// Average processing time per packet (nanoseconds, derived)
+    #[serde(default)]
+    pub avg_processing_time_ns: u64,
+    /// Batch counts per [`BatchSizeHistogram`] bucket
+    #[serde(default)]
+    pub batch_size_histogram: Vec<u64>,
+    /// Median batch size, as a bucket upper bound (derived)
+    #[serde(default)]
+    pub batch_size_p50: u64,
+    /// 99th percentile batch size, as a bucket upper bound (derived)
+    #[serde(default)]
+    pub batch_size_p99: u64,
+    /// Packet counts per [`LatencyHistogram`] bucket
+    #[serde(default)]
+    pub packet_latency_histogram: Vec<u64>,
+    /// Packets with a recorded latency (derived)
+    #[serde(default)]
+    pub packet_latency_count: u64,
+    /// Median packet latency in microseconds, as a bucket upper bound (derived)
+    #[serde(default)]
+    pub packet_latency_p50_us: u64,
+    /// 95th percentile packet latency in microseconds (derived)
+    #[serde(default)]
+    pub packet_latency_p95_us: u64,
+    /// 99th percentile packet latency in microseconds (derived)
+    #[serde(default)]
+    pub packet_latency_p99_us: u64,
 }
 
 impl Default for PipelineStats {
@@ -250,6 +935,150 @@ impl Default for PipelineStats {
     }
 }
 
+/// Warmup before pipeline health is judged against targets
+///
+/// Both thresholds must be reached before warmup ends.
+#[derive(Debug, Clone)]
+pub struct WarmupConfig {
+    /// Packets processed before targets apply
+    pub min_packets: u64,
+    /// Time since start before targets apply
+    pub min_duration: Duration,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            min_packets: 1000,
+            min_duration: Duration::from_secs(5),
+        }
+    }
+}
+
+/// A performance target the pipeline is currently missing
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetMiss {
+    /// Average per-packet processing latency above the maximum
+    Latency { avg_ms: f64, max_ms: f64 },
+    /// Memory pool hit rate below the minimum
+    PoolHitRate { pct: f64, min_pct: f64 },
+    /// Drop rate above the maximum
+    DropRate { pct: f64, max_pct: f64 },
+}
+
+/// Pipeline health against [`PerformanceTargets`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthStatus {
+    /// Meeting targets, or still warming up
+    Healthy,
+    /// Missing one or more targets
+    Degraded(Vec<TargetMiss>),
+}
+
+impl HealthStatus {
+    /// Check if the pipeline is healthy
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+}
+
+/// Judges pipeline statistics against targets once warmup has passed
+///
+/// Cold-start packets are excluded: after warmup, latency, drop rate and pool
+/// hit rate are computed only over activity since warmup ended.
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    targets: PerformanceTargets,
+    warmup: WarmupConfig,
+    started: Instant,
+    /// Stats at the end of warmup
+    baseline: Option<PipelineStatsSnapshot>,
+}
+
+impl HealthMonitor {
+    /// Start monitoring now
+    pub fn new(targets: PerformanceTargets, warmup: WarmupConfig) -> Self {
+        Self::new_at(targets, warmup, Instant::now())
+    }
+
+    /// Start monitoring from `started`
+    pub fn new_at(targets: PerformanceTargets, warmup: WarmupConfig, started: Instant) -> Self {
+        Self {
+            targets,
+            warmup,
+            started,
+            baseline: None,
+        }
+    }
+
+    /// Check if health evaluation is still suppressed
+    pub fn is_warming_up(&self) -> bool {
+        self.baseline.is_none()
+    }
+
+    /// Evaluate a stats snapshot taken now
+    pub fn evaluate(&mut self, snapshot: &PipelineStatsSnapshot) -> HealthStatus {
+        self.evaluate_at(snapshot, Instant::now())
+    }
+
+    /// Evaluate a stats snapshot taken at `now`
+    pub fn evaluate_at(&mut self, snapshot: &PipelineStatsSnapshot, now: Instant) -> HealthStatus {
+        let Some(baseline) = &self.baseline else {
+            let warmed = snapshot.packets_processed >= self.warmup.min_packets
+                && now.saturating_duration_since(self.started) >= self.warmup.min_duration;
+            if warmed {
+                self.baseline = Some(snapshot.clone());
+            }
+            return HealthStatus::Healthy;
+        };
+
+        let processed = snapshot
+            .packets_processed
+            .saturating_sub(baseline.packets_processed);
+        let dropped = snapshot.packets_dropped.saturating_sub(baseline.packets_dropped);
+        let processing_ns = snapshot
+            .total_processing_time_ns
+            .saturating_sub(baseline.total_processing_time_ns);
+
+        let mut misses = Vec::new();
+        if processed > 0 {
+            let avg_ms = processing_ns as f64 / processed as f64 / 1_000_000.0;
+            if avg_ms > self.targets.max_avg_latency_ms {
+                misses.push(TargetMiss::Latency {
+                    avg_ms,
+                    max_ms: self.targets.max_avg_latency_ms,
+                });
+            }
+        }
+        if processed + dropped > 0 {
+            let pct = dropped as f64 / (processed + dropped) as f64 * 100.0;
+            if pct > self.targets.max_drop_rate_pct {
+                misses.push(TargetMiss::DropRate {
+                    pct,
+                    max_pct: self.targets.max_drop_rate_pct,
+                });
+            }
+        }
+        let hits = snapshot.pool_hits.saturating_sub(baseline.pool_hits);
+        let requests = hits + snapshot.pool_misses.saturating_sub(baseline.pool_misses);
+        if requests > 0 {
+            let pct = hits as f64 / requests as f64 * 100.0;
+            if pct < self.targets.min_pool_hit_rate_pct {
+                misses.push(TargetMiss::PoolHitRate {
+                    pct,
+                    min_pct: self.targets.min_pool_hit_rate_pct,
+                });
+            }
+        }
+
+        if misses.is_empty() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Degraded(misses)
+        }
+    }
+}
+
 impl PacketPipeline {
     /// Create new packet pipeline
     pub fn new(num_workers: usize) -> Self {
@@ -284,7 +1113,8 @@ impl PacketPipeline {
             memory_pool,
             #[cfg(feature = "sphinx")]
             sphinx_processor,
-            input_queue: Arc::new(Mutex::new(VecDeque::new())),
+            input_queue: Arc::new(InputQueue::new(QueueStrategy::default(), None)),
+            queue_strategy: QueueStrategy::default(),
             output_queue: Arc::new(Mutex::new(VecDeque::new())),
             processing_semaphore,
             stats,
@@ -293,6 +1123,11 @@ impl PacketPipeline {
             rate_limiter,
             #[cfg(feature = "cover-traffic")]
             cover_traffic,
+            yield_every: AtomicUsize::new(DEFAULT_YIELD_EVERY_BATCHES),
+            batches_since_yield: AtomicUsize::new(0),
+            source_policy: SourcePolicy::default(),
+            batching: BatchingConfig::default(),
+            processing_timeout: None,
         }
     }
 
@@ -310,13 +1145,19 @@ impl PacketPipeline {
             memory_pool,
             #[cfg(feature = "sphinx")]
             sphinx_processor,
-            input_queue: Arc::new(Mutex::new(VecDeque::new())),
+            input_queue: Arc::new(InputQueue::new(QueueStrategy::default(), None)),
+            queue_strategy: QueueStrategy::default(),
             output_queue: Arc::new(Mutex::new(VecDeque::new())),
             processing_semaphore,
             stats,
             workers: Vec::with_capacity(num_workers),
             shutdown_tx: None,
             rate_limiter,
+            yield_every: AtomicUsize::new(DEFAULT_YIELD_EVERY_BATCHES),
+            batches_since_yield: AtomicUsize::new(0),
+            source_policy: SourcePolicy::default(),
+            batching: BatchingConfig::default(),
+            processing_timeout: None,
         }
     }
 
@@ -335,10 +1176,15 @@ impl PacketPipeline {
             let sphinx_processor = Arc::clone(&self.sphinx_processor);
             let processing_semaphore = Arc::clone(&self.processing_semaphore);
             let stats = Arc::clone(&self.stats);
+            let rate_limiter = Arc::clone(&self.rate_limiter);
+            let source_policy = self.source_policy;
+            let batching = self.batching;
+            let processing_timeout = self.processing_timeout.clone();
             let mut shutdown_rx = shutdown_tx.subscribe();
 
             let worker = tokio::spawn(async move {
                 let mut batch_buffer = Vec::with_capacity(BATCH_SIZE);
+                let mut pending_since: Option<Instant> = None;
 
                 loop {
                     tokio::select! {
@@ -348,32 +1194,43 @@ impl PacketPipeline {
                         }
                         _ = sleep(Duration::from_micros(50)) => {
                             // Process available packets in batches (faster polling)
-                            batch_buffer.clear();
-
-                            // Collect batch with guaranteed processing
-                            {
-                                let mut queue = input_queue.lock().unwrap();
-                                while batch_buffer.len() < BATCH_SIZE && !queue.is_empty() {
-                                    if let Some(packet) = queue.pop_front() {
-                                        batch_buffer.push(packet);
-                                    }
+                            Self::fill_batch(
+                                &input_queue,
+                                &mut batch_buffer,
+                                batching.max_batch_bytes,
+                                &stats,
+                                &processing_semaphore,
+                            );
+
+                            let waited = match (batch_buffer.is_empty(), pending_since) {
+                                (true, _) => Duration::ZERO,
+                                (false, Some(since)) => since.elapsed(),
+                                (false, None) => {
+                                    pending_since = Some(Instant::now());
+                                    Duration::ZERO
                                 }
-                            }
-
-                            if !batch_buffer.is_empty() {
-                                let start_time = Instant::now();
-
-                                // Process batch
-                                #[cfg(feature = "sphinx")]
-                                let processed = Self::process_batch(
+                            };
+                            let queue_idle = input_queue.is_empty();
+
+                            let held = batching.holds_for_anonymity(
+                                BatchingConfig::distinct_sources(&batch_buffer),
+                                waited,
+                            );
+
+                            let flush = batching.should_flush(batch_buffer.len(), waited, queue_idle)
+                                || batching.reached_byte_budget(&batch_buffer);
+                            if !held && flush {
+                                let processed = Self::run_batch(
                                     &batch_buffer,
+                                    #[cfg(feature = "sphinx")]
                                     &sphinx_processor,
                                     &memory_pool,
+                                    &stats,
+                                    &processing_semaphore,
+                                    source_policy,
+                                    processing_timeout.as_ref(),
                                 ).await;
 
-                                #[cfg(not(feature = "sphinx"))]
-                                let processed = Self::process_batch_simple(&batch_buffer, &memory_pool).await;
-
                                 // Output processed packets (ensure packets reach output)
                                 if !processed.is_empty() {
                                     let mut output = output_queue.lock().unwrap();
@@ -382,19 +1239,17 @@ impl PacketPipeline {
                                     }
                                 }
 
-                                // Update statistics
-                                let processing_time = start_time.elapsed().as_nanos() as u64;
-                                stats.record_processed(batch_buffer.len() as u64, processing_time);
-                                stats.record_batch(batch_buffer.len() as u64);
-
-                                // Update memory pool hit rate periodically
-                                if stats.batches_processed.load(Ordering::Relaxed) % 100 == 0 {
-                                    let hit_rate = memory_pool.hit_rate_percent();
-                                    stats.update_pool_hit_rate(hit_rate);
+                                // Shed load at ingress when downstream drops climb
+                                if stats
+                                    .batches_processed
+                                    .load(Ordering::Relaxed)
+                                    .is_multiple_of(DROP_FEEDBACK_INTERVAL_BATCHES)
+                                {
+                                    Self::drop_rate_feedback(&stats, &rate_limiter);
                                 }
 
-                                // Release semaphore permits
-                                processing_semaphore.add_permits(batch_buffer.len());
+                                batch_buffer.clear();
+                                pending_since = None;
                             }
                         }
                     }
@@ -433,13 +1288,9 @@ impl PacketPipeline {
             .map_err(|_| MixnodeError::Network("Pipeline semaphore closed".to_string()))?;
 
         // Add to input queue
-        {
-            let mut queue = self.input_queue.lock().unwrap();
-            if queue.len() >= MAX_QUEUE_DEPTH {
-                self.stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
-                return Err(MixnodeError::Network("Pipeline queue full".to_string()));
-            }
-            queue.push_back(packet);
+        if self.input_queue.push(packet).is_err() {
+            self.stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(MixnodeError::Network("Pipeline queue full".to_string()));
         }
 
         // Don't release permit - will be released after processing
@@ -463,11 +1314,225 @@ impl PacketPipeline {
         result
     }
 
+    /// Process the next batch from the input queue on the caller's task
+    ///
+    /// Returns the processed packets directly instead of routing them to the
+    /// output queue, for callers that drive the pipeline themselves rather
+    /// than via [`start`](Self::start). Sphinx work doesn't suspend, so a
+    /// tight `next_batch` loop would otherwise never hand the thread back to
+    /// the runtime; every [`yield_every`](Self::yield_every) calls (empty
+    /// batches included) this yields with `tokio::task::yield_now`, bounding
+    /// how long other tasks on the same worker thread can be starved.
+    pub async fn next_batch(&self) -> Vec<PipelinePacket> {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        Self::collect_batch(
+            &self.input_queue,
+            &mut batch,
+            self.batching.max_batch_bytes,
+            &self.stats,
+            &self.processing_semaphore,
+        );
+
+        let processed = if batch.is_empty() {
+            Vec::new()
+        } else {
+            Self::run_batch(
+                &batch,
+                #[cfg(feature = "sphinx")]
+                &self.sphinx_processor,
+                &self.memory_pool,
+                &self.stats,
+                &self.processing_semaphore,
+                self.source_policy,
+                self.processing_timeout.as_ref(),
+            )
+            .await
+        };
+
+        let since_yield = self.batches_since_yield.fetch_add(1, Ordering::Relaxed) + 1;
+        if since_yield >= self.yield_every() {
+            self.batches_since_yield.store(0, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+        }
+
+        processed
+    }
+
+    /// Choose how upstream source addresses are handled after processing
+    ///
+    /// Takes effect for workers spawned by a later [`start`](Self::start).
+    pub fn with_source_policy(mut self, policy: SourcePolicy) -> Self {
+        self.source_policy = policy;
+        self
+    }
+
+    /// Choose how workers accumulate packets into batches
+    ///
+    /// Takes effect for workers spawned by a later [`start`](Self::start).
+    pub fn with_batching(mut self, batching: BatchingConfig) -> Self {
+        self.batching = batching;
+        self
+    }
+
+    /// Split the input queue into weighted high-priority and normal lanes
+    ///
+    /// Packets already queued are re-sorted into the new lanes. Lanes need
+    /// the mutex-guarded queue, so they override
+    /// [`QueueStrategy::MaxThroughput`].
+    pub fn with_lanes(mut self, lanes: LaneConfig) -> Self {
+        self.rebuild_input_queue(Some(lanes));
+        self
+    }
+
+    /// Choose how the input queue is synchronized
+    ///
+    /// Packets already queued are carried over.
+    pub fn with_queue_strategy(mut self, strategy: QueueStrategy) -> Self {
+        self.queue_strategy = strategy;
+        let lanes = self.input_queue.lanes();
+        self.rebuild_input_queue(lanes);
+        self
+    }
+
+    /// Input queue synchronization in use
+    ///
+    /// Reports [`QueueStrategy::Balanced`] when lanes overrode the ring.
+    pub fn queue_strategy(&self) -> QueueStrategy {
+        match *self.input_queue {
+            InputQueue::Locked(_) => QueueStrategy::Balanced,
+            InputQueue::Ring(_) => QueueStrategy::MaxThroughput,
+        }
+    }
+
+    fn rebuild_input_queue(&mut self, lanes: Option<LaneConfig>) {
+        let queue = InputQueue::new(self.queue_strategy, lanes);
+        while let Some(packet) = self.input_queue.pop() {
+            // Both queues hold MAX_QUEUE_DEPTH packets, so this cannot overflow
+            let _ = queue.push(packet);
+        }
+        self.input_queue = Arc::new(queue);
+    }
+
+    /// Bound how long any one packet may spend in processing
+    ///
+    /// Each packet is then processed on a blocking thread and abandoned if
+    /// it outruns `timeout`, so a pathological packet costs the worker one
+    /// timeout instead of hanging it. Abandoned packets count as dropped and
+    /// timed out; while [`MAX_TIMED_PROCESSING_THREADS`] are busy, further
+    /// packets are shed instead of queueing more blocking work. This gives
+    /// up batch-level Sphinx concurrency, so it is off by default. Takes
+    /// effect for workers spawned by a later [`start`](Self::start) and for
+    /// [`next_batch`](Self::next_batch).
+    pub fn with_processing_timeout(mut self, timeout: Duration) -> Self {
+        self.processing_timeout = Some(ProcessingTimeout::new(timeout));
+        self
+    }
+
+    /// Set how many `next_batch` calls run between cooperative yields (min 1)
+    pub fn set_yield_every(&self, batches: usize) {
+        self.yield_every.store(batches.max(1), Ordering::Relaxed);
+    }
+
+    /// Current yield cadence in batches
+    pub fn yield_every(&self) -> usize {
+        self.yield_every.load(Ordering::Relaxed)
+    }
+
+    /// Move up to `BATCH_SIZE` packets from the input queue into `batch`
+    ///
+    /// Packets already past their deadline are discarded here rather than
+    /// batched, releasing their permits, so a backed-up pipeline doesn't
+    /// spend work on traffic nobody is waiting for.
+    fn collect_batch(
+        input_queue: &InputQueue,
+        batch: &mut Vec<PipelinePacket>,
+        max_bytes: Option<usize>,
+        stats: &PipelineStats,
+        processing_semaphore: &Semaphore,
+    ) {
+        batch.clear();
+        Self::fill_batch(input_queue, batch, max_bytes, stats, processing_semaphore);
+    }
+
+    /// Top `batch` up to `BATCH_SIZE` packets, or `max_bytes` payload
+    /// bytes, from the input queue
+    fn fill_batch(
+        input_queue: &InputQueue,
+        batch: &mut Vec<PipelinePacket>,
+        max_bytes: Option<usize>,
+        stats: &PipelineStats,
+        processing_semaphore: &Semaphore,
+    ) {
+        let expired = input_queue.fill(batch, Instant::now(), max_bytes);
+
+        if expired > 0 {
+            stats.packets_expired.fetch_add(expired as u64, Ordering::Relaxed);
+            stats.packets_dropped.fetch_add(expired as u64, Ordering::Relaxed);
+            processing_semaphore.add_permits(expired);
+        }
+    }
+
+    /// Process a collected batch, record statistics and release permits
+    async fn run_batch(
+        batch: &[PipelinePacket],
+        #[cfg(feature = "sphinx")] sphinx_processor: &Arc<SphinxProcessor>,
+        memory_pool: &MemoryPool,
+        stats: &PipelineStats,
+        processing_semaphore: &Semaphore,
+        source_policy: SourcePolicy,
+        processing_timeout: Option<&ProcessingTimeout>,
+    ) -> Vec<PipelinePacket> {
+        let start_time = Instant::now();
+
+        let mut processed = if let Some(timeout) = processing_timeout {
+            #[cfg(feature = "sphinx")]
+            let process = {
+                let sphinx_processor = Arc::clone(sphinx_processor);
+                move |packet| Self::process_one(&sphinx_processor, packet)
+            };
+            #[cfg(not(feature = "sphinx"))]
+            let process = Some;
+            Self::process_batch_timed(batch, timeout, stats, process).await
+        } else {
+            #[cfg(feature = "sphinx")]
+            let processed = Self::process_batch(batch, sphinx_processor, memory_pool).await;
+
+            #[cfg(not(feature = "sphinx"))]
+            let processed = Self::process_batch_simple(batch, memory_pool).await;
+
+            processed
+        };
+
+        // Routing is decided; the previous hop must not travel further
+        source_policy.apply(&mut processed);
+        source_policy.debug_assert_applied(&processed);
+
+        let processing_time = start_time.elapsed().as_nanos() as u64;
+        stats.record_processed(batch.len() as u64, processing_time);
+        let finished = Instant::now();
+        for packet in batch {
+            stats
+                .packet_latency
+                .record(finished.saturating_duration_since(packet.arrival_time));
+        }
+        stats.record_batch(batch.len() as u64);
+        let (allocated, reused) = memory_pool.stats();
+        stats.record_pool_usage(allocated, reused);
+
+        // Update memory pool hit rate periodically
+        if stats.batches_processed.load(Ordering::Relaxed).is_multiple_of(100) {
+            stats.update_pool_hit_rate(memory_pool.hit_rate_percent());
+        }
+
+        processing_semaphore.add_permits(batch.len());
+        processed
+    }
+
     /// Process a batch of packets with Sphinx
     #[cfg(feature = "sphinx")]
     async fn process_batch(
         batch: &[PipelinePacket],
-        sphinx_processor: &SphinxProcessor,
+        sphinx_processor: &Arc<SphinxProcessor>,
         _memory_pool: &MemoryPool,
     ) -> Vec<PipelinePacket> {
         let mut processed = Vec::with_capacity(batch.len());
@@ -490,7 +1555,7 @@ impl PacketPipeline {
         if !sphinx_packets.is_empty() {
             if let Ok(results) = sphinx_processor.process_batch(sphinx_packets).await {
                 for (result_idx, result) in results.into_iter().enumerate() {
-                    if let Some(processed_sphinx) = result {
+                    if let Ok(Some(processed_sphinx)) = result {
                         let original_idx = packet_indices[result_idx];
                         let original_packet = &batch[original_idx];
 
@@ -501,6 +1566,7 @@ impl PacketPipeline {
                             arrival_time: original_packet.arrival_time,
                             priority: original_packet.priority,
                             source: original_packet.source,
+                            deadline: original_packet.deadline,
                         };
 
                         processed.push(processed_packet);
@@ -512,6 +1578,72 @@ impl PacketPipeline {
         processed
     }
 
+    /// Process one packet with Sphinx, outside of batch processing
+    #[cfg(feature = "sphinx")]
+    fn process_one(
+        sphinx_processor: &SphinxProcessor,
+        pipeline_packet: PipelinePacket,
+    ) -> Option<PipelinePacket> {
+        let packet = Packet::parse(&pipeline_packet.data).ok()?;
+        let sphinx_packet = SphinxPacket::from_bytes(&packet.payload).ok()?;
+        let processed_sphinx = sphinx_processor.process_packet_sync(sphinx_packet).ok()??;
+        Some(PipelinePacket {
+            data: Bytes::from(processed_sphinx.to_bytes()),
+            ..pipeline_packet
+        })
+    }
+
+    /// Process packets one at a time, abandoning any that outrun the timeout
+    ///
+    /// `process` runs on the blocking pool because Sphinx work never yields,
+    /// so an async timeout alone couldn't interrupt it. An abandoned packet's
+    /// thread is left to finish on its own, holding its permit meanwhile;
+    /// the batch moves on, shedding packets while no permit is free.
+    async fn process_batch_timed<F>(
+        batch: &[PipelinePacket],
+        limit: &ProcessingTimeout,
+        stats: &PipelineStats,
+        process: F,
+    ) -> Vec<PipelinePacket>
+    where
+        F: Fn(PipelinePacket) -> Option<PipelinePacket> + Send + Sync + 'static,
+    {
+        let process = Arc::new(process);
+        let mut processed = Vec::with_capacity(batch.len());
+
+        for packet in batch {
+            let Ok(permit) = Arc::clone(&limit.threads).try_acquire_owned() else {
+                tracing::warn!("Abandoned packets hold every processing thread; shedding packet");
+                stats.packets_shed.fetch_add(1, Ordering::Relaxed);
+                stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            let process = Arc::clone(&process);
+            let packet = packet.clone();
+            let work = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                process(packet)
+            });
+
+            let timeout = limit.timeout;
+            match tokio::time::timeout(timeout, work).await {
+                Ok(Ok(Some(packet))) => processed.push(packet),
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => {
+                    tracing::warn!("Packet processing failed: {}", e);
+                    stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(_) => {
+                    tracing::warn!("Packet processing exceeded {:?}; dropping packet", timeout);
+                    stats.packets_timed_out.fetch_add(1, Ordering::Relaxed);
+                    stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        processed
+    }
+
     /// Simple batch processing without Sphinx (optimized)
     #[cfg(not(feature = "sphinx"))]
     async fn process_batch_simple(
@@ -522,14 +1654,30 @@ impl PacketPipeline {
         batch.to_vec()
     }
 
+    /// Set how many packets per batch are decrypted concurrently
+    #[cfg(feature = "sphinx")]
+    pub fn set_sphinx_concurrency(&self, concurrency: usize) {
+        self.sphinx_processor.set_batch_concurrency(concurrency);
+    }
+
     /// Get pipeline statistics
     pub fn stats(&self) -> &PipelineStats {
         &self.stats
     }
 
+    /// Serializable copy of the pipeline statistics
+    pub fn stats_snapshot(&self) -> PipelineStatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// Evaluate current statistics with a health monitor
+    pub fn health(&self, monitor: &mut HealthMonitor) -> HealthStatus {
+        monitor.evaluate(&self.stats.snapshot())
+    }
+
     /// Get current queue depths
     pub fn queue_depths(&self) -> (usize, usize) {
-        let input_depth = self.input_queue.lock().unwrap().len();
+        let input_depth = self.input_queue.len();
         let output_depth = self.output_queue.lock().unwrap().len();
         (input_depth, output_depth)
     }
@@ -544,6 +1692,26 @@ impl PacketPipeline {
         self.memory_pool.hit_rate_percent()
     }
 
+    /// Retune the ingress rate limiter from the current drop rate
+    ///
+    /// Workers call this every `DROP_FEEDBACK_INTERVAL_BATCHES` batches.
+    /// Returns the effective ingress rate.
+    pub fn apply_drop_rate_feedback(&self) -> f64 {
+        Self::drop_rate_feedback(&self.stats, &self.rate_limiter)
+    }
+
+    fn drop_rate_feedback(stats: &PipelineStats, rate_limiter: &RateLimitedTrafficShaper) -> f64 {
+        rate_limiter.observe_drop_rate(
+            stats.packets_processed.load(Ordering::Relaxed),
+            stats.packets_dropped.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Effective ingress rate after drop-rate throttling
+    pub fn ingress_rate(&self) -> f64 {
+        self.rate_limiter.ingress_rate()
+    }
+
     /// Get rate limiter queue length
     pub async fn rate_limiter_queue_length(&self) -> usize {
         self.rate_limiter.queue_length().await
@@ -610,7 +1778,7 @@ impl PipelineBenchmark {
                         packets_sent += 1;
 
                         // Small delay to prevent overwhelming
-                        if packets_sent % 1000 == 0 {
+                        if packets_sent.is_multiple_of(1000) {
                             tokio::task::yield_now().await;
                         }
                     }
@@ -632,6 +1800,10 @@ impl PipelineBenchmark {
         let avg_processing_time_ns = stats.avg_processing_time_ns();
         let throughput_pps = stats.throughput_pps(elapsed);
         let memory_pool_hit_rate = self.pipeline.memory_pool_hit_rate();
+        let latency_us =
+            |p| stats.packet_latency.percentile(p).map_or(0, |d| d.as_micros() as u64);
+        let (latency_p50_us, latency_p95_us, latency_p99_us) =
+            (latency_us(0.50), latency_us(0.95), latency_us(0.99));
 
         self.pipeline.stop().await?;
 
@@ -643,6 +1815,9 @@ impl PipelineBenchmark {
             throughput_pps,
             avg_processing_time_ns,
             memory_pool_hit_rate,
+            latency_p50_us,
+            latency_p95_us,
+            latency_p99_us,
         })
     }
 }
@@ -664,6 +1839,12 @@ pub struct BenchmarkResults {
     pub avg_processing_time_ns: u64,
     /// Memory pool hit rate (0.0 to 1.0)
     pub memory_pool_hit_rate: f64,
+    /// Median arrival-to-processed latency (microseconds, bucket upper bound)
+    pub latency_p50_us: u64,
+    /// 95th percentile latency (microseconds, bucket upper bound)
+    pub latency_p95_us: u64,
+    /// 99th percentile latency (microseconds, bucket upper bound)
+    pub latency_p99_us: u64,
 }
 
 impl BenchmarkResults {
@@ -672,6 +1853,44 @@ impl BenchmarkResults {
         self.throughput_pps >= target_pps
     }
 
+    /// Percentage of sent packets that were dropped
+    pub fn drop_rate_pct(&self) -> f64 {
+        if self.packets_sent == 0 {
+            return 0.0;
+        }
+        self.packets_dropped as f64 / self.packets_sent as f64 * 100.0
+    }
+
+    /// Build a machine-readable report judged against `targets`
+    pub fn report(&self, targets: &PerformanceTargets) -> BenchmarkReport {
+        let avg_latency_ms = self.avg_processing_time_ns as f64 / 1_000_000.0;
+        let pool_hit_rate_pct = self.memory_pool_hit_rate;
+        let drop_rate_pct = self.drop_rate_pct();
+
+        let checks = BenchmarkTargetChecks {
+            throughput: self.meets_target(targets.target_throughput_pps),
+            latency: avg_latency_ms <= targets.max_avg_latency_ms,
+            pool_hit_rate: pool_hit_rate_pct >= targets.min_pool_hit_rate_pct,
+            drop_rate: drop_rate_pct <= targets.max_drop_rate_pct,
+        };
+
+        BenchmarkReport {
+            packets_sent: self.packets_sent,
+            packets_processed: self.packets_processed,
+            packets_dropped: self.packets_dropped,
+            elapsed_secs: self.elapsed_secs,
+            throughput_pps: self.throughput_pps,
+            avg_latency_us: self.avg_processing_time_ns as f64 / 1000.0,
+            latency_p50_us: self.latency_p50_us,
+            latency_p95_us: self.latency_p95_us,
+            latency_p99_us: self.latency_p99_us,
+            pool_hit_rate_pct,
+            drop_rate_pct,
+            passed: checks.all_passed(),
+            targets: checks,
+        }
+    }
+
     /// Print results
     pub fn print_results(&self) {
         println!("🚀 Pipeline Benchmark Results:");
@@ -691,10 +1910,105 @@ impl BenchmarkResults {
     }
 }
 
+/// Pass/fail of each [`PerformanceTargets`] entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchmarkTargetChecks {
+    /// Throughput reached `target_throughput_pps`
+    pub throughput: bool,
+    /// Average processing latency within `max_avg_latency_ms`
+    pub latency: bool,
+    /// Pool hit rate reached `min_pool_hit_rate_pct`
+    pub pool_hit_rate: bool,
+    /// Drop rate within `max_drop_rate_pct`
+    pub drop_rate: bool,
+}
+
+impl BenchmarkTargetChecks {
+    /// Check if every target was met
+    pub fn all_passed(&self) -> bool {
+        self.throughput && self.latency && self.pool_hit_rate && self.drop_rate
+    }
+}
+
+/// Benchmark outcome for CI trend tracking
+///
+/// Field names are part of the JSON format; add fields rather than
+/// renaming them so older artifacts stay comparable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Total packets sent during test
+    pub packets_sent: u64,
+    /// Total packets successfully processed
+    pub packets_processed: u64,
+    /// Total packets dropped
+    pub packets_dropped: u64,
+    /// Test duration in seconds
+    pub elapsed_secs: f64,
+    /// Achieved throughput in packets per second
+    pub throughput_pps: f64,
+    /// Average processing time per packet (microseconds)
+    pub avg_latency_us: f64,
+    /// Median latency (microseconds)
+    pub latency_p50_us: u64,
+    /// 95th percentile latency (microseconds)
+    pub latency_p95_us: u64,
+    /// 99th percentile latency (microseconds)
+    pub latency_p99_us: u64,
+    /// Memory pool hit rate (percentage)
+    pub pool_hit_rate_pct: f64,
+    /// Dropped share of sent packets (percentage)
+    pub drop_rate_pct: f64,
+    /// Per-target pass/fail
+    pub targets: BenchmarkTargetChecks,
+    /// Whether every target passed
+    pub passed: bool,
+}
+
+impl BenchmarkReport {
+    /// Serialize the report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| MixnodeError::Config(format!("Failed to serialize report: {}", e)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_benchmark_report_json_fields() {
+        let mut bench = PipelineBenchmark::new(2, 500);
+        let results = bench.run_throughput_test(1).await.unwrap();
+        assert!(results.packets_processed > 0);
+
+        let report = results.report(&PerformanceTargets::default());
+        assert!(report.latency_p50_us <= report.latency_p99_us);
+        assert!(report.latency_p50_us > 0);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        for field in [
+            "packets_sent",
+            "packets_processed",
+            "packets_dropped",
+            "elapsed_secs",
+            "throughput_pps",
+            "avg_latency_us",
+            "latency_p50_us",
+            "latency_p95_us",
+            "latency_p99_us",
+            "pool_hit_rate_pct",
+            "drop_rate_pct",
+            "passed",
+        ] {
+            assert!(json.get(field).is_some(), "missing {}", field);
+        }
+        for target in ["throughput", "latency", "pool_hit_rate", "drop_rate"] {
+            assert!(json["targets"][target].is_boolean(), "missing target {}", target);
+        }
+        assert_eq!(json["passed"], report.targets.all_passed());
+    }
+
     #[tokio::test]
     async fn test_pipeline_basic_processing() {
         let mut pipeline = PacketPipeline::new(2);
@@ -723,6 +2037,606 @@ mod tests {
         pipeline.stop().await.unwrap();
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_lock_free_queue_with_many_producers() {
+        const PRODUCERS: usize = 16;
+        const PER_PRODUCER: usize = 500;
+
+        let pipeline =
+            Arc::new(PacketPipeline::new(1).with_queue_strategy(QueueStrategy::MaxThroughput));
+        assert_eq!(pipeline.queue_strategy(), QueueStrategy::MaxThroughput);
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let pipeline = Arc::clone(&pipeline);
+                tokio::spawn(async move {
+                    for seq in 0..PER_PRODUCER {
+                        let mut data = vec![producer as u8];
+                        data.extend_from_slice(&(seq as u32).to_le_bytes());
+                        pipeline
+                            .submit_packet(PipelinePacket::new(Bytes::from(data)))
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.await.unwrap();
+        }
+        assert_eq!(pipeline.queue_depths().0, PRODUCERS * PER_PRODUCER);
+
+        // Every packet arrives exactly once, in order per producer
+        let mut next_seq = [0u32; PRODUCERS];
+        let mut batch = Vec::new();
+        loop {
+            PacketPipeline::collect_batch(
+                &pipeline.input_queue,
+                &mut batch,
+                None,
+                &pipeline.stats,
+                &pipeline.processing_semaphore,
+            );
+            if batch.is_empty() {
+                break;
+            }
+            for packet in &batch {
+                let producer = packet.data[0] as usize;
+                let seq = u32::from_le_bytes(packet.data[1..5].try_into().unwrap());
+                assert_eq!(seq, next_seq[producer]);
+                next_seq[producer] += 1;
+            }
+        }
+        assert!(next_seq.iter().all(|&n| n as usize == PER_PRODUCER));
+
+        // Lanes need the locked queue
+        let laned = PacketPipeline::new(1)
+            .with_queue_strategy(QueueStrategy::MaxThroughput)
+            .with_lanes(LaneConfig::default());
+        assert_eq!(laned.queue_strategy(), QueueStrategy::Balanced);
+    }
+
+    #[tokio::test]
+    async fn test_next_batch_yields_to_other_tasks() {
+        // Single-threaded runtime: the other task only runs when we yield
+        let pipeline = PacketPipeline::new(1);
+        pipeline.set_yield_every(4);
+
+        for _ in 0..(BATCH_SIZE * 20) {
+            pipeline
+                .submit_packet(PipelinePacket::new(Bytes::from(vec![0u8; 256])))
+                .await
+                .unwrap();
+        }
+
+        let ran = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = Arc::clone(&ran);
+        let started = Instant::now();
+        let low_priority = tokio::spawn(async move {
+            flag.store(true, Ordering::Relaxed);
+        });
+
+        let mut calls = 0;
+        while !ran.load(Ordering::Relaxed) && calls < 1000 {
+            pipeline.next_batch().await;
+            calls += 1;
+        }
+
+        assert!(ran.load(Ordering::Relaxed));
+        assert!(calls <= pipeline.yield_every());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        low_priority.await.unwrap();
+    }
+
+    #[test]
+    fn test_weighted_lanes_keep_normal_traffic_moving() {
+        let config = LaneConfig {
+            max_normal_wait: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let mut lanes = PriorityLanes::new(Some(config));
+        let now = Instant::now();
+        let packet = |priority: u8| {
+            let mut packet = PipelinePacket::with_priority(Bytes::from_static(b"x"), priority);
+            packet.arrival_time = now;
+            packet
+        };
+
+        // Saturate the high lane, refilling it as it drains
+        for _ in 0..100 {
+            lanes.push(packet(0));
+        }
+        for _ in 0..10 {
+            lanes.push(packet(5));
+        }
+        let mut normal_at = Vec::new();
+        for served in 0..100 {
+            let next = lanes.pop_at(now).unwrap();
+            if next.priority == 0 {
+                lanes.push(packet(0));
+            } else {
+                normal_at.push(served);
+            }
+        }
+
+        // 70/30 split: three normal packets per ten served, never more than
+        // 7 high packets in a row
+        assert_eq!(normal_at.len(), 10);
+        assert_eq!(normal_at[..3], [7, 8, 9]);
+        assert!(*normal_at.last().unwrap() < 40);
+        let mut previous = None;
+        for &at in &normal_at {
+            let gap = previous.map_or(at, |p: usize| at - p - 1);
+            assert!(gap <= 7, "normal packet waited behind {} high packets", gap);
+            previous = Some(at);
+        }
+        assert_eq!(lanes.promoted(), 0);
+    }
+
+    #[test]
+    fn test_aged_normal_packets_promoted() {
+        let config = LaneConfig {
+            high_weight: 1000,
+            normal_weight: 1,
+            max_normal_wait: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let mut lanes = PriorityLanes::new(Some(config));
+        let now = Instant::now();
+        for _ in 0..500 {
+            lanes.push(PipelinePacket::with_priority(Bytes::from_static(b"h"), 0));
+        }
+        let mut old = PipelinePacket::with_priority(Bytes::from_static(b"n"), 1);
+        old.arrival_time = now - Duration::from_millis(80);
+        lanes.push(old);
+        assert_eq!(lanes.lane_depths(), (500, 1));
+
+        // The weights alone would serve it after 1000 high packets
+        assert_eq!(lanes.pop_at(now).unwrap().priority, 1);
+        assert_eq!(lanes.promoted(), 1);
+
+        // Without lanes everything is FIFO
+        let mut fifo = PriorityLanes::new(None);
+        fifo.push(PipelinePacket::with_priority(Bytes::from_static(b"a"), 9));
+        fifo.push(PipelinePacket::with_priority(Bytes::from_static(b"b"), 0));
+        assert_eq!(fifo.pop().unwrap().priority, 9);
+    }
+
+    #[tokio::test]
+    async fn test_expired_packets_skipped_at_batching() {
+        let pipeline = PacketPipeline::new(1);
+        let now = Instant::now();
+        let past = now - Duration::from_millis(10);
+        let future = now + Duration::from_secs(60);
+
+        // Interleave live and expired packets in one burst
+        for i in 0..20u8 {
+            let data = Bytes::from(vec![i; 64]);
+            let packet = match i % 4 {
+                0 => PipelinePacket::new(data),
+                1 | 2 => PipelinePacket::new_with_deadline(data, past),
+                _ => PipelinePacket::new_with_deadline(data, future),
+            };
+            pipeline.submit_packet(packet).await.unwrap();
+        }
+
+        let _processed = pipeline.next_batch().await;
+        let snapshot = pipeline.stats_snapshot();
+        assert_eq!(snapshot.packets_processed, 10);
+        assert_eq!(snapshot.packets_expired, 10);
+        assert_eq!(snapshot.packets_dropped, 10);
+        assert_eq!(pipeline.queue_depths().0, 0);
+
+        #[cfg(not(feature = "sphinx"))]
+        {
+            assert_eq!(_processed.len(), 10);
+            assert!(_processed.iter().all(|p| !p.is_expired_at(now)));
+            assert!(_processed.iter().all(|p| matches!(p.data[0] % 4, 0 | 3)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_byte_budget_caps_batch_before_count() {
+        let pipeline = PacketPipeline::new(1).with_batching(BatchingConfig {
+            max_batch_bytes: Some(16 * 1024),
+            ..Default::default()
+        });
+        for _ in 0..40 {
+            let packet = PipelinePacket::new(Bytes::from(vec![0u8; 2048]));
+            pipeline.submit_packet(packet).await.unwrap();
+        }
+
+        // 8 x 2KB fills the budget long before BATCH_SIZE packets
+        let _processed = pipeline.next_batch().await;
+        assert_eq!(pipeline.stats_snapshot().packets_processed, 8);
+        assert_eq!(pipeline.queue_depths().0, 32);
+        #[cfg(not(feature = "sphinx"))]
+        assert_eq!(_processed.len(), 8);
+
+        // The packet crossing the budget is the last one in
+        let mut batch = Vec::new();
+        let expired = pipeline.input_queue.fill(&mut batch, Instant::now(), Some(5000));
+        assert_eq!((batch.len(), expired), (3, 0));
+
+        // Workers flush a batch that hit the budget without waiting for
+        // the count or the batch delay
+        let mut pipeline = PacketPipeline::new(1).with_batching(BatchingConfig {
+            min_batch_size: BATCH_SIZE,
+            min_batch_delay: Duration::from_secs(5),
+            max_batch_bytes: Some(8 * 1024),
+            ..Default::default()
+        });
+        for _ in 0..20 {
+            let packet = PipelinePacket::new(Bytes::from(vec![0u8; 2048]));
+            pipeline.submit_packet(packet).await.unwrap();
+        }
+        pipeline.start().await.unwrap();
+        let started = Instant::now();
+        while pipeline.stats().packets_processed.load(Ordering::Relaxed) < 20 {
+            assert!(started.elapsed() < Duration::from_secs(1), "batches waited out the delay");
+            sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(pipeline.stats().batches_processed.load(Ordering::Relaxed), 5);
+        pipeline.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_flush_on_idle_processes_trickle_promptly() {
+        let batching = BatchingConfig {
+            min_batch_size: BATCH_SIZE,
+            min_batch_delay: Duration::from_secs(2),
+            flush_on_idle: true,
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1).with_batching(batching);
+        pipeline.start().await.unwrap();
+
+        for sent in 1..=5u64 {
+            let submitted = Instant::now();
+            pipeline
+                .submit_packet(PipelinePacket::new(Bytes::from(vec![0u8; 64])))
+                .await
+                .unwrap();
+            while pipeline.stats().packets_processed.load(Ordering::Relaxed) < sent {
+                assert!(
+                    submitted.elapsed() < Duration::from_millis(500),
+                    "packet {} waited for the batch delay",
+                    sent
+                );
+                sleep(Duration::from_millis(1)).await;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        pipeline.stop().await.unwrap();
+
+        // Without the idle flush the same trickle sits in a partial batch
+        let mut pipeline = PacketPipeline::new(1).with_batching(BatchingConfig {
+            flush_on_idle: false,
+            ..batching
+        });
+        pipeline.start().await.unwrap();
+        pipeline
+            .submit_packet(PipelinePacket::new(Bytes::from(vec![0u8; 64])))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(pipeline.stats().packets_processed.load(Ordering::Relaxed), 0);
+        pipeline.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_held_until_anonymity_set() {
+        let batching = BatchingConfig {
+            min_anonymity_set: 3,
+            anonymity_timeout: Duration::from_millis(400),
+            ..Default::default()
+        };
+        assert!(batching.holds_for_anonymity(2, Duration::from_millis(10)));
+        assert!(!batching.holds_for_anonymity(3, Duration::ZERO));
+        assert!(!batching.holds_for_anonymity(2, Duration::from_millis(400)));
+
+        let from = |port: u16| {
+            let mut packet = PipelinePacket::new(Bytes::from(vec![0u8; 64]));
+            packet.source = Some(format!("10.0.0.1:{}", port).parse().unwrap());
+            packet
+        };
+        let processed = |pipeline: &PacketPipeline| {
+            pipeline.stats().packets_processed.load(Ordering::Relaxed)
+        };
+
+        // Two sources are held; the third releases the batch
+        let mut pipeline = PacketPipeline::new(1).with_batching(BatchingConfig {
+            anonymity_timeout: Duration::from_secs(30),
+            ..batching
+        });
+        pipeline.start().await.unwrap();
+        for port in [1, 1, 2, 2] {
+            pipeline.submit_packet(from(port)).await.unwrap();
+        }
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(processed(&pipeline), 0);
+
+        pipeline.submit_packet(from(3)).await.unwrap();
+        let released = Instant::now();
+        while processed(&pipeline) < 5 {
+            assert!(released.elapsed() < Duration::from_secs(2), "batch never released");
+            sleep(Duration::from_millis(1)).await;
+        }
+        pipeline.stop().await.unwrap();
+
+        // Too few sources are forwarded once the timeout elapses
+        let mut pipeline = PacketPipeline::new(1).with_batching(batching);
+        pipeline.start().await.unwrap();
+        let submitted = Instant::now();
+        pipeline.submit_packet(from(1)).await.unwrap();
+        pipeline.submit_packet(from(2)).await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(processed(&pipeline), 0);
+        while processed(&pipeline) < 2 {
+            assert!(submitted.elapsed() < Duration::from_secs(2), "timeout never fired");
+            sleep(Duration::from_millis(5)).await;
+        }
+        assert!(submitted.elapsed() >= Duration::from_millis(400));
+        pipeline.stop().await.unwrap();
+    }
+
+    #[test]
+    fn test_default_batching_flushes_every_poll() {
+        let batching = BatchingConfig::default();
+        assert!(!batching.should_flush(0, Duration::ZERO, true));
+        assert!(batching.should_flush(1, Duration::ZERO, false));
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_histogram_captures_spread() {
+        let pipeline = PacketPipeline::new(1);
+        assert_eq!(pipeline.stats().batch_sizes.percentile(0.5), None);
+
+        // Oscillating load: mostly trickles, with occasional full bursts
+        for round in 0..20 {
+            let burst = if round % 4 == 3 { BATCH_SIZE } else { 2 };
+            for _ in 0..burst {
+                pipeline
+                    .submit_packet(PipelinePacket::new(Bytes::from(vec![0u8; 64])))
+                    .await
+                    .unwrap();
+            }
+            pipeline.next_batch().await;
+        }
+
+        let histogram = &pipeline.stats().batch_sizes;
+        let counts = histogram.counts();
+        assert_eq!(counts.len(), BATCH_SIZE_BUCKETS);
+        assert_eq!(counts[1], 15);
+        assert_eq!(counts[BATCH_SIZE_BUCKETS - 1], 5);
+        assert_eq!(counts.iter().sum::<u64>(), 20);
+
+        // Percentiles expose both ends, which an average (~33) would hide
+        assert_eq!(histogram.percentile(0.5), Some(2));
+        assert_eq!(histogram.percentile(0.99), Some(BATCH_SIZE as u64));
+
+        let snapshot = pipeline.stats_snapshot();
+        assert_eq!(snapshot.batch_size_histogram, counts);
+        assert_eq!((snapshot.batch_size_p50, snapshot.batch_size_p99), (2, BATCH_SIZE as u64));
+    }
+
+    #[tokio::test]
+    async fn test_processing_timeout_drops_stuck_packet() {
+        let stats = PipelineStats::new();
+        let packet = |tag: u8| PipelinePacket::new(Bytes::from(vec![tag; 16]));
+        let batch = vec![packet(1), packet(0xFF), packet(2)];
+
+        // Stub that blocks its thread on one packet and passes the rest through
+        let stub = |packet: PipelinePacket| {
+            if packet.data[0] == 0xFF {
+                std::thread::sleep(Duration::from_millis(300));
+            }
+            Some(packet)
+        };
+
+        let started = Instant::now();
+        let limit = ProcessingTimeout::new(Duration::from_millis(50));
+        let processed = PacketPipeline::process_batch_timed(&batch, &limit, &stats, stub).await;
+        assert!(started.elapsed() < Duration::from_millis(300));
+
+        let tags: Vec<u8> = processed.iter().map(|p| p.data[0]).collect();
+        assert_eq!(tags, vec![1, 2]);
+        assert_eq!(stats.packets_timed_out.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.packets_dropped.load(Ordering::Relaxed), 1);
+
+        // The pipeline keeps processing with the timeout in place
+        let pipeline = PacketPipeline::new(1).with_processing_timeout(Duration::from_secs(1));
+        for _ in 0..4 {
+            pipeline.submit_packet(packet(7)).await.unwrap();
+        }
+        pipeline.next_batch().await;
+        let snapshot = pipeline.stats_snapshot();
+        assert_eq!(snapshot.packets_processed, 4);
+        assert_eq!(snapshot.packets_timed_out, 0);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_packets_cannot_exhaust_blocking_pool() {
+        let stats = PipelineStats::new();
+        let batch: Vec<PipelinePacket> = (0..MAX_TIMED_PROCESSING_THREADS + 4)
+            .map(|_| PipelinePacket::new(Bytes::from(vec![0xFF; 16])))
+            .collect();
+
+        // Pathological packets spin their thread well past the deadline
+        let spin = |packet: PipelinePacket| {
+            let until = Instant::now() + Duration::from_millis(600);
+            while Instant::now() < until {
+                std::thread::yield_now();
+            }
+            Some(packet)
+        };
+
+        let limit = ProcessingTimeout::new(Duration::from_millis(5));
+        let processed = PacketPipeline::process_batch_timed(&batch, &limit, &stats, spin).await;
+        assert!(processed.is_empty());
+        assert_eq!(
+            stats.packets_timed_out.load(Ordering::Relaxed),
+            MAX_TIMED_PROCESSING_THREADS as u64
+        );
+        assert_eq!(stats.packets_shed.load(Ordering::Relaxed), 4);
+        assert_eq!(
+            stats.packets_dropped.load(Ordering::Relaxed),
+            MAX_TIMED_PROCESSING_THREADS as u64 + 4
+        );
+        assert_eq!(limit.threads.available_permits(), 0);
+
+        // Threads come back once the abandoned work finishes
+        let released = async {
+            while limit.threads.available_permits() < MAX_TIMED_PROCESSING_THREADS {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), released).await.unwrap();
+        let packet = PipelinePacket::new(Bytes::from(vec![1; 16]));
+        let processed = PacketPipeline::process_batch_timed(&[packet], &limit, &stats, Some).await;
+        assert_eq!(processed.len(), 1);
+        assert_eq!(stats.snapshot().packets_shed, 4);
+    }
+
+    #[tokio::test]
+    async fn test_stats_snapshot_serializes_counters() {
+        let pipeline = PacketPipeline::new(1);
+        for _ in 0..10 {
+            pipeline
+                .submit_packet(PipelinePacket::new(Bytes::from(vec![0u8; 128])))
+                .await
+                .unwrap();
+        }
+        pipeline.next_batch().await;
+        pipeline.stats().packets_dropped.fetch_add(2, Ordering::Relaxed);
+
+        let snapshot = pipeline.stats_snapshot();
+        assert_eq!(snapshot.packets_processed, 10);
+        assert_eq!(snapshot.batches_processed, 1);
+        assert_eq!(snapshot.packet_latency_count, 10);
+        assert!(snapshot.packet_latency_p50_us > 0);
+        assert!(snapshot.packet_latency_p50_us <= snapshot.packet_latency_p95_us);
+        assert!(snapshot.packet_latency_p95_us <= snapshot.packet_latency_p99_us);
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["packets_processed"], 10);
+        assert_eq!(json["packets_dropped"], 2);
+        assert_eq!(json["batches_processed"], 1);
+        assert_eq!(json["packet_latency_count"], 10);
+        assert_eq!(json["packet_latency_p99_us"], snapshot.packet_latency_p99_us);
+        assert_eq!(json["packet_latency_histogram"].as_array().unwrap().len(), LATENCY_BUCKETS);
+
+        // PipelineStats itself serializes through the snapshot
+        assert_eq!(serde_json::to_value(pipeline.stats()).unwrap(), json);
+        let restored: PipelineStats = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn test_healthy_during_warmup_despite_slow_start() {
+        let start = Instant::now();
+        let warmup = WarmupConfig {
+            min_packets: 100,
+            min_duration: Duration::from_secs(10),
+        };
+        let mut monitor = HealthMonitor::new_at(PerformanceTargets::default(), warmup, start);
+        let snapshot = |processed: u64, avg_ms: u64| PipelineStatsSnapshot {
+            packets_processed: processed,
+            total_processing_time_ns: processed * avg_ms * 1_000_000,
+            pool_hit_rate_pct: 95.0,
+            ..Default::default()
+        };
+
+        // Cold start: 20ms per packet against a 1ms target
+        let cold = snapshot(100, 20);
+        assert!(monitor
+            .evaluate_at(&cold, start + Duration::from_secs(1))
+            .is_healthy());
+        assert!(monitor.is_warming_up());
+        assert!(monitor
+            .evaluate_at(&cold, start + Duration::from_secs(10))
+            .is_healthy());
+        assert!(!monitor.is_warming_up());
+
+        // Fast packets after warmup aren't penalized for the slow start
+        let mut warm = cold.clone();
+        warm.packets_processed += 1000;
+        warm.total_processing_time_ns += 1000 * 500_000;
+        assert!(monitor.evaluate_at(&warm, start + Duration::from_secs(11)).is_healthy());
+
+        // Slow packets after warmup are reported
+        let mut slow = warm.clone();
+        slow.packets_processed += 1000;
+        slow.total_processing_time_ns += 1000 * 5_000_000;
+        match monitor.evaluate_at(&slow, start + Duration::from_secs(12)) {
+            HealthStatus::Degraded(misses) => {
+                assert!(matches!(misses[0], TargetMiss::Latency { .. }));
+            }
+            HealthStatus::Healthy => panic!("expected degraded health"),
+        }
+    }
+
+    #[test]
+    fn test_pool_hit_rate_judged_on_requests_since_warmup() {
+        let start = Instant::now();
+        let warmup = WarmupConfig {
+            min_packets: 100,
+            min_duration: Duration::from_secs(1),
+        };
+        let mut monitor = HealthMonitor::new_at(PerformanceTargets::default(), warmup, start);
+        let mut snapshot = PipelineStatsSnapshot {
+            packets_processed: 100,
+            pool_misses: 100,
+            ..Default::default()
+        };
+        assert!(monitor.evaluate_at(&snapshot, start + Duration::from_secs(1)).is_healthy());
+
+        // The periodic rate hasn't been sampled yet and no buffers were requested
+        snapshot.packets_processed += 10;
+        assert!(monitor.evaluate_at(&snapshot, start + Duration::from_secs(2)).is_healthy());
+
+        // A warm pool isn't blamed for cold-start allocations
+        snapshot.pool_hits += 990;
+        snapshot.pool_misses += 10;
+        assert!(monitor.evaluate_at(&snapshot, start + Duration::from_secs(3)).is_healthy());
+
+        snapshot.pool_misses += 1000;
+        match monitor.evaluate_at(&snapshot, start + Duration::from_secs(4)) {
+            HealthStatus::Degraded(misses) => {
+                assert!(matches!(misses[0], TargetMiss::PoolHitRate { .. }));
+            }
+            HealthStatus::Healthy => panic!("expected degraded health"),
+        }
+    }
+
+    #[test]
+    fn test_forwarded_packets_lose_source() {
+        let upstream: std::net::SocketAddr = "127.0.0.1:9400".parse().unwrap();
+        let make = || {
+            let mut packet = PipelinePacket::new(Bytes::from_static(b"payload"));
+            packet.source = Some(upstream);
+            vec![packet.clone(), packet]
+        };
+
+        let mut stripped = make();
+        SourcePolicy::default().apply(&mut stripped);
+        assert!(stripped.iter().all(|p| p.source.is_none()));
+        SourcePolicy::Strip.debug_assert_applied(&stripped);
+
+        let mut retained = make();
+        SourcePolicy::Retain.apply(&mut retained);
+        assert!(retained.iter().all(|p| p.source == Some(upstream)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "upstream source")]
+    fn test_source_leak_trips_debug_assertion() {
+        let mut packet = PipelinePacket::new(Bytes::from_static(b"payload"));
+        packet.source = Some("127.0.0.1:9401".parse().unwrap());
+        SourcePolicy::Strip.debug_assert_applied(&[packet]);
+    }
+
     #[tokio::test]
     async fn test_memory_pool() {
         let pool = MemoryPool::new(10, 1024);
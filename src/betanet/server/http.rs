@@ -2,11 +2,15 @@
 // Exposes mixnode statistics and deployment endpoints
 
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 
+use crate::core::reputation::ReputationManager;
+use crate::utils::rate::RateLimitedTrafficShaper;
+
 // Statistics structure
 #[derive(Serialize, Clone)]
 struct BetanetStatus {
@@ -64,6 +68,8 @@ struct MixnodeInfoResponse {
 struct AppState {
     mixnodes: Arc<Mutex<Vec<MixnodeInfo>>>,
     start_time: Instant,
+    rate_limiter: Option<Arc<RateLimitedTrafficShaper>>,
+    reputation: Option<Arc<RwLock<ReputationManager>>>,
 }
 
 impl AppState {
@@ -71,9 +77,23 @@ impl AppState {
         Self {
             mixnodes: Arc::new(Mutex::new(Vec::new())),
             start_time: Instant::now(),
+            rate_limiter: None,
+            reputation: None,
         }
     }
 
+    /// Export limiter counters from `shaper` alongside node metrics
+    fn with_rate_limiter(mut self, shaper: Arc<RateLimitedTrafficShaper>) -> Self {
+        self.rate_limiter = Some(shaper);
+        self
+    }
+
+    /// Serve per-relay reputation from `reputation`
+    fn with_reputation(mut self, reputation: Arc<RwLock<ReputationManager>>) -> Self {
+        self.reputation = Some(reputation);
+        self
+    }
+
     /// Record packet processing for a mixnode
     #[allow(dead_code)]
     fn record_packet(&self, node_id: &str, processing_time_us: u64, forwarded: bool) {
@@ -212,7 +232,7 @@ async fn handle_get_metrics(state: &AppState) -> String {
     let mixnodes = state.mixnodes.lock().unwrap();
     let active_count = mixnodes.iter().filter(|n| n.status == "active").count();
 
-    format!(
+    let mut body = format!(
         "# HELP betanet_nodes_total Total number of betanet mixnodes\n\
          # TYPE betanet_nodes_total gauge\n\
          betanet_nodes_total {}\n\
@@ -241,6 +261,44 @@ async fn handle_get_metrics(state: &AppState) -> String {
         mixnodes.iter().map(|n| n.packets_dropped).sum::<u64>(),
         state.avg_latency_ms(),
         state.start_time.elapsed().as_secs()
+    );
+
+    if let Some(shaper) = &state.rate_limiter {
+        body.push_str(&rate_limit_metrics(shaper));
+    }
+    body
+}
+
+// Per-relay reputation for the monitoring exporter; empty without a manager
+async fn handle_get_relay_reputation(state: &AppState) -> String {
+    let metrics = state
+        .reputation
+        .as_ref()
+        .map(|manager| manager.read().unwrap().relay_metrics())
+        .unwrap_or_default();
+    serde_json::to_string(&metrics).unwrap()
+}
+
+// Prometheus text for rate limiter decisions
+fn rate_limit_metrics(shaper: &RateLimitedTrafficShaper) -> String {
+    let stats = shaper.rate_limit_stats();
+    format!(
+        "# HELP betanet_rate_limit_admitted_total Packets admitted by the rate limiter\n\
+         # TYPE betanet_rate_limit_admitted_total counter\n\
+         betanet_rate_limit_admitted_total {}\n\
+         # HELP betanet_rate_limit_delayed_total Admitted packets delayed by traffic shaping\n\
+         # TYPE betanet_rate_limit_delayed_total counter\n\
+         betanet_rate_limit_delayed_total {}\n\
+         # HELP betanet_rate_limit_dropped_total Packets dropped by the rate limiter\n\
+         # TYPE betanet_rate_limit_dropped_total counter\n\
+         betanet_rate_limit_dropped_total {}\n\
+         # HELP betanet_rate_limit_throttle_active Whether drop-rate feedback is throttling ingress\n\
+         # TYPE betanet_rate_limit_throttle_active gauge\n\
+         betanet_rate_limit_throttle_active {}\n",
+        stats.packets_admitted.load(Ordering::Relaxed),
+        stats.packets_delayed.load(Ordering::Relaxed),
+        stats.packets_dropped.load(Ordering::Relaxed),
+        stats.throttle_active.load(Ordering::Relaxed) as u8,
     )
 }
 
@@ -286,6 +344,10 @@ async fn handle_request(mut stream: TcpStream, state: AppState) {
                     let body = handle_get_metrics(&state).await;
                     ("200 OK", "text/plain; version=0.0.4", body)
                 }
+                ("GET", "/api/v1/relays/reputation") => {
+                    let body = handle_get_relay_reputation(&state).await;
+                    ("200 OK", "application/json", body)
+                }
                 _ => {
                     let body = r#"{"error":"Not Found"}"#.to_string();
                     ("404 Not Found", "application/json", body)
@@ -308,10 +370,54 @@ async fn handle_request(mut stream: TcpStream, state: AppState) {
 }
 
 pub async fn run_server() -> std::io::Result<()> {
+    HttpServer::new().run().await
+}
+
+/// HTTP server with optional shared sources for its endpoints
+///
+/// Without them `/metrics` carries node metrics only and
+/// `/api/v1/relays/reputation` serves an empty list.
+#[derive(Clone)]
+pub struct HttpServer {
+    state: AppState,
+}
+
+impl HttpServer {
+    /// Create a server with no shared sources attached
+    pub fn new() -> Self {
+        Self {
+            state: AppState::new(),
+        }
+    }
+
+    /// Also export `shaper`'s limiter counters on `/metrics`
+    pub fn with_rate_limiter(mut self, shaper: Arc<RateLimitedTrafficShaper>) -> Self {
+        self.state = self.state.with_rate_limiter(shaper);
+        self
+    }
+
+    /// Also serve `reputation` on `/api/v1/relays/reputation`
+    pub fn with_reputation(mut self, reputation: Arc<RwLock<ReputationManager>>) -> Self {
+        self.state = self.state.with_reputation(reputation);
+        self
+    }
+
+    /// Serve on 0.0.0.0:9000 until the listener fails
+    pub async fn run(self) -> std::io::Result<()> {
+        serve(self.state).await
+    }
+}
+
+impl Default for HttpServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn serve(state: AppState) -> std::io::Result<()> {
     println!("🚀 Starting Betanet HTTP Server on 0.0.0.0:9000");
 
     let listener = TcpListener::bind("0.0.0.0:9000").await?;
-    let state = AppState::new();
 
     println!("✓ Server ready - listening for connections");
 
@@ -329,3 +435,30 @@ pub async fn run_server() -> std::io::Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::reputation::RelayReputationMetric;
+
+    #[tokio::test]
+    async fn test_relay_reputation_endpoint_body() {
+        let state = AppState::new();
+        assert_eq!(handle_get_relay_reputation(&state).await, "[]");
+
+        let mut manager = ReputationManager::new();
+        let relay = "10.0.0.1:9000".parse().unwrap();
+        manager.add_node(relay, 1000);
+        let shaper = Arc::new(RateLimitedTrafficShaper::new(Default::default()));
+        let state = HttpServer::new()
+            .with_rate_limiter(shaper)
+            .with_reputation(Arc::new(RwLock::new(manager)))
+            .state;
+        assert!(state.rate_limiter.is_some());
+
+        let body = handle_get_relay_reputation(&state).await;
+        let samples: Vec<RelayReputationMetric> = serde_json::from_str(&body).unwrap();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].relay, "10.0.0.1:9000");
+    }
+}
@@ -1,3 +1,4 @@
 // Server module
 pub mod http;
+pub mod source_validation;
 pub mod tcp;
@@ -0,0 +1,292 @@
+//! Source-address validation for connectionless transports
+//!
+//! A TCP peer proves its address by completing the handshake, but a datagram
+//! source address is whatever the sender wrote into it. Before accepting
+//! packets from a new datagram source the transport hands it a cookie, a
+//! keyed MAC over the source address and issue time, and only accepts the
+//! source once it echoes a valid cookie back. Cookies are stateless for the
+//! server; only sources that have proven themselves are remembered.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tracing::debug;
+
+/// Size of an encoded cookie: issue time (8 bytes) + truncated MAC (16 bytes)
+pub const COOKIE_LEN: usize = 24;
+
+const MAC_LEN: usize = COOKIE_LEN - 8;
+
+/// Source validation settings
+#[derive(Debug, Clone)]
+pub struct SourceValidationConfig {
+    /// How long an issued cookie may be echoed back
+    pub cookie_lifetime: Duration,
+    /// How long a validated source is accepted without a new challenge
+    pub validation_ttl: Duration,
+    /// Maximum number of validated sources remembered at once
+    pub max_validated: usize,
+}
+
+impl Default for SourceValidationConfig {
+    fn default() -> Self {
+        Self {
+            cookie_lifetime: Duration::from_secs(30),
+            validation_ttl: Duration::from_secs(300),
+            max_validated: 65_536,
+        }
+    }
+}
+
+/// What the transport should do with a packet from a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceVerdict {
+    /// Source is validated; process the packet
+    Accept,
+    /// Unvalidated source; drop the packet and send it this cookie
+    Challenge([u8; COOKIE_LEN]),
+    /// Invalid or stale cookie; drop the packet silently
+    Drop,
+}
+
+/// Cookie-based validator for datagram sources
+pub struct SourceValidator {
+    config: SourceValidationConfig,
+    secret: [u8; 32],
+    previous_secret: Option<[u8; 32]>,
+    validated: HashMap<SocketAddr, Instant>,
+}
+
+impl std::fmt::Debug for SourceValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceValidator")
+            .field("config", &self.config)
+            .field("validated", &self.validated.len())
+            .finish()
+    }
+}
+
+impl SourceValidator {
+    /// Create validator with a fresh random secret
+    pub fn new(config: SourceValidationConfig) -> Self {
+        Self {
+            config,
+            secret: rand::random(),
+            previous_secret: None,
+            validated: HashMap::new(),
+        }
+    }
+
+    /// Validator settings
+    pub fn config(&self) -> &SourceValidationConfig {
+        &self.config
+    }
+
+    /// Replace the cookie secret, still honoring cookies issued under the
+    /// previous one until they expire
+    pub fn rotate_secret(&mut self) {
+        self.previous_secret = Some(std::mem::replace(&mut self.secret, rand::random()));
+    }
+
+    /// Judge a packet from `source`, optionally carrying an echoed cookie
+    pub fn check(&mut self, source: SocketAddr, cookie: Option<&[u8]>) -> SourceVerdict {
+        self.check_at(source, cookie, Instant::now(), unix_now())
+    }
+
+    /// Judge a packet as of `now` (monotonic) and `unix_secs` (cookie clock)
+    pub fn check_at(
+        &mut self,
+        source: SocketAddr,
+        cookie: Option<&[u8]>,
+        now: Instant,
+        unix_secs: u64,
+    ) -> SourceVerdict {
+        if self.is_validated_at(&source, now) {
+            return SourceVerdict::Accept;
+        }
+
+        match cookie {
+            None => SourceVerdict::Challenge(self.issue_cookie_at(source, unix_secs)),
+            Some(cookie) if self.verify_cookie_at(source, cookie, unix_secs) => {
+                self.mark_validated(source, now);
+                SourceVerdict::Accept
+            }
+            Some(_) => {
+                debug!("Dropping packet from {} with invalid cookie", source);
+                SourceVerdict::Drop
+            }
+        }
+    }
+
+    /// Check whether `source` has proven its address and not expired
+    pub fn is_validated_at(&self, source: &SocketAddr, now: Instant) -> bool {
+        self.validated
+            .get(source)
+            .is_some_and(|at| now.saturating_duration_since(*at) < self.config.validation_ttl)
+    }
+
+    /// Number of remembered validated sources
+    pub fn validated_count(&self) -> usize {
+        self.validated.len()
+    }
+
+    /// Forget validated sources past the validation TTL
+    pub fn prune_at(&mut self, now: Instant) {
+        let ttl = self.config.validation_ttl;
+        self.validated
+            .retain(|_, at| now.saturating_duration_since(*at) < ttl);
+    }
+
+    /// Cookie for `source` issued at `unix_secs`
+    pub fn issue_cookie_at(&self, source: SocketAddr, unix_secs: u64) -> [u8; COOKIE_LEN] {
+        let mut cookie = [0u8; COOKIE_LEN];
+        cookie[..8].copy_from_slice(&unix_secs.to_be_bytes());
+        cookie[8..].copy_from_slice(&Self::mac(&self.secret, source, unix_secs));
+        cookie
+    }
+
+    fn verify_cookie_at(&self, source: SocketAddr, cookie: &[u8], unix_secs: u64) -> bool {
+        let Ok(cookie) = <[u8; COOKIE_LEN]>::try_from(cookie) else {
+            return false;
+        };
+        let issued = u64::from_be_bytes(cookie[..8].try_into().unwrap());
+        let fresh = issued <= unix_secs
+            && unix_secs - issued <= self.config.cookie_lifetime.as_secs();
+        if !fresh {
+            return false;
+        }
+
+        let mac = &cookie[8..];
+        std::iter::once(&self.secret)
+            .chain(self.previous_secret.as_ref())
+            .any(|secret| constant_time_eq(&Self::mac(secret, source, issued), mac))
+    }
+
+    fn mark_validated(&mut self, source: SocketAddr, now: Instant) {
+        if self.validated.len() >= self.config.max_validated {
+            self.prune_at(now);
+        }
+        if self.validated.len() >= self.config.max_validated {
+            // Still full of live entries: evict the oldest
+            if let Some(oldest) = self
+                .validated
+                .iter()
+                .min_by_key(|(_, at)| **at)
+                .map(|(addr, _)| *addr)
+            {
+                self.validated.remove(&oldest);
+            }
+        }
+        self.validated.insert(source, now);
+    }
+
+    fn mac(secret: &[u8; 32], source: SocketAddr, issued: u64) -> [u8; MAC_LEN] {
+        let mut hasher = blake3::Hasher::new_keyed(secret);
+        match source.ip() {
+            IpAddr::V4(ip) => hasher.update(&ip.octets()),
+            IpAddr::V6(ip) => hasher.update(&ip.octets()),
+        };
+        hasher.update(&source.port().to_be_bytes());
+        hasher.update(&issued.to_be_bytes());
+
+        let mut mac = [0u8; MAC_LEN];
+        mac.copy_from_slice(&hasher.finalize().as_bytes()[..MAC_LEN]);
+        mac
+    }
+}
+
+impl Default for SourceValidator {
+    fn default() -> Self {
+        Self::new(SourceValidationConfig::default())
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unvalidated_source_challenged_then_accepted() {
+        let mut validator = SourceValidator::default();
+        let now = Instant::now();
+        let unix = 1_700_000_000;
+        let honest: SocketAddr = "203.0.113.5:4000".parse().unwrap();
+        let spoofer: SocketAddr = "198.51.100.9:4000".parse().unwrap();
+
+        // A new source is challenged, not accepted
+        let cookie = match validator.check_at(honest, None, now, unix) {
+            SourceVerdict::Challenge(cookie) => cookie,
+            other => panic!("expected challenge, got {:?}", other),
+        };
+        assert!(!validator.is_validated_at(&honest, now));
+
+        // A cookie is bound to the address it was sent to
+        assert_eq!(
+            validator.check_at(spoofer, Some(&cookie), now, unix + 1),
+            SourceVerdict::Drop
+        );
+        assert_eq!(
+            validator.check_at(honest, Some(&cookie[..10]), now, unix + 1),
+            SourceVerdict::Drop
+        );
+
+        // Echoing it back validates the source for later packets
+        assert_eq!(
+            validator.check_at(honest, Some(&cookie), now, unix + 1),
+            SourceVerdict::Accept
+        );
+        assert_eq!(validator.check_at(honest, None, now, unix + 2), SourceVerdict::Accept);
+        assert!(matches!(
+            validator.check_at(spoofer, None, now, unix + 2),
+            SourceVerdict::Challenge(_)
+        ));
+        assert_eq!(validator.validated_count(), 1);
+    }
+
+    #[test]
+    fn test_stale_cookies_and_expired_validation() {
+        let config = SourceValidationConfig {
+            cookie_lifetime: Duration::from_secs(10),
+            validation_ttl: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let mut validator = SourceValidator::new(config);
+        let now = Instant::now();
+        let unix = 1_700_000_000;
+        let source: SocketAddr = "[2001:db8::7]:5000".parse().unwrap();
+
+        let cookie = validator.issue_cookie_at(source, unix);
+        assert_eq!(
+            validator.check_at(source, Some(&cookie), now, unix + 11),
+            SourceVerdict::Drop
+        );
+
+        // Cookies issued before a rotation still validate
+        validator.rotate_secret();
+        assert_eq!(
+            validator.check_at(source, Some(&cookie), now, unix + 5),
+            SourceVerdict::Accept
+        );
+
+        // Validation lapses after its TTL and the source is challenged again
+        let later = now + Duration::from_secs(61);
+        assert!(matches!(
+            validator.check_at(source, None, later, unix + 61),
+            SourceVerdict::Challenge(_)
+        ));
+        validator.prune_at(later);
+        assert_eq!(validator.validated_count(), 0);
+    }
+}
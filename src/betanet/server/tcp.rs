@@ -5,27 +5,295 @@
 //! batch processing.
 
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
 use tracing::{debug, error, info, warn};
 
 use crate::{
     core::{
+        blocklist::SharedBlocklist,
         config::MixnodeConfig,
-        protocol_version::{ProtocolAdvertisement, ProtocolVersion},
+        connections::ConnectionRegistry,
+        protocol_version::{
+            ConnectionRole, FeatureFlags, NegotiatedProtocol, ProtocolAdvertisement, ProtocolVersion,
+            MAX_ADVERTISEMENT_CAP,
+        },
+        reputation::ReputationManager,
+        versions::{DeprecationPolicy, DeprecationStatus},
     },
     pipeline::{PacketPipeline, PipelinePacket},
     MixnodeError, Result,
 };
 
-#[cfg(test)]
+#[cfg(any(test, feature = "debug-echo"))]
 use crate::utils::packet::Packet;
-#[cfg(test)]
-use bytes::Bytes;
+
+/// Length-prefix value announcing a retry-after frame instead of an
+/// advertisement
+///
+/// It is followed by the suggested backoff as a big-endian `u32` of
+/// milliseconds. Peers that predate the hint read it as an oversized
+/// advertisement length and fail the handshake, which is what they would
+/// have done on a bare close anyway.
+pub const RETRY_AFTER_MARKER: u32 = u32::MAX;
+
+/// Default backoff suggested to peers rejected at the connection limit
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Length-prefix value announcing a proof-of-work challenge ahead of the
+/// server's advertisement
+///
+/// It is followed by the difficulty byte and a random nonce. The peer must
+/// answer with a solution before the server reads its advertisement.
+pub const CHALLENGE_MARKER: u32 = u32::MAX - 1;
+
+/// Length of the challenge nonce
+pub const CHALLENGE_NONCE_LEN: usize = 16;
+
+/// Length of a challenge solution (big-endian `u64`)
+pub const CHALLENGE_SOLUTION_LEN: usize = 8;
+
+/// Hardest challenge a [`TcpClient`] will solve (about 16M hashes)
+pub const MAX_CLIENT_CHALLENGE_DIFFICULTY: u8 = 24;
+
+/// Default cap on bytes read from a peer during the handshake: a challenge
+/// solution, the largest advertisement plus its length prefix, and the
+/// confirmation byte
+pub const DEFAULT_HANDSHAKE_BYTE_BUDGET: usize =
+    CHALLENGE_SOLUTION_LEN + MAX_ADVERTISEMENT_CAP + 4 + 1;
+
+/// Largest legitimate frame: a `MAX_PACKET_SIZE` packet and its 4-byte
+/// length prefix
+pub const MAX_FRAME_LEN: usize = crate::MAX_PACKET_SIZE + 4;
+
+/// Largest frame an adaptive read buffer reserves for before its bytes arrive
+pub const MAX_FRAME_RESERVE: usize = 1024 * 1024;
+
+/// How a connection's read buffer is sized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferStrategy {
+    /// Allocate `buffer_size` up front and keep whatever it grows to
+    #[default]
+    Fixed,
+    /// Start at `baseline` bytes, reserve a whole frame at once when a large
+    /// one arrives, and shrink back to `baseline` once the buffer drains
+    Adaptive {
+        /// Capacity of an idle connection's buffer
+        baseline: usize,
+    },
+}
+
+/// Connection read buffer sized by a [`BufferStrategy`]
+///
+/// Frames are split off the front as they complete, which hides how much
+/// the buffer has allocated, so the largest capacity seen since the last
+/// shrink is tracked alongside it.
+#[derive(Debug)]
+pub struct ConnectionBuffer {
+    bytes: BytesMut,
+    strategy: BufferStrategy,
+    peak_capacity: usize,
+}
+
+impl ConnectionBuffer {
+    /// Buffer for a newly established connection
+    pub fn new(strategy: BufferStrategy, buffer_size: usize) -> Self {
+        let capacity = match strategy {
+            BufferStrategy::Fixed => buffer_size,
+            BufferStrategy::Adaptive { baseline } => baseline,
+        };
+        let bytes = BytesMut::with_capacity(capacity);
+        Self {
+            peak_capacity: bytes.capacity(),
+            bytes,
+            strategy,
+        }
+    }
+
+    /// Read whatever the stream has into the buffer
+    pub async fn read_from(&mut self, stream: &mut TcpStream) -> std::io::Result<usize> {
+        let n = stream.read_buf(&mut self.bytes).await?;
+        self.note_capacity();
+        Ok(n)
+    }
+
+    /// Buffered bytes not yet consumed
+    pub fn bytes_mut(&mut self) -> &mut BytesMut {
+        &mut self.bytes
+    }
+
+    /// Largest capacity reached since the buffer was last shrunk
+    pub fn peak_capacity(&self) -> usize {
+        self.peak_capacity
+    }
+
+    /// Make room for a frame of `frame_len` bytes (prefix included) whose
+    /// start is already buffered
+    ///
+    /// The length comes from the peer, so at most [`MAX_FRAME_RESERVE`]
+    /// bytes are reserved ahead of the data actually arriving. Fixed
+    /// buffers grow only as far as [`MAX_FRAME_LEN`], so a max-size packet
+    /// always fits even when `buffer_size` is smaller.
+    pub fn reserve_frame(&mut self, frame_len: usize) {
+        let cap = match self.strategy {
+            BufferStrategy::Fixed => MAX_FRAME_LEN,
+            BufferStrategy::Adaptive { .. } => MAX_FRAME_RESERVE,
+        };
+        // One allocation instead of doubling through every read
+        let target = frame_len.min(cap);
+        self.bytes.reserve(target.saturating_sub(self.bytes.len()));
+        self.note_capacity();
+    }
+
+    /// Give back memory once every complete frame has been consumed
+    pub fn shrink_if_idle(&mut self) {
+        if let BufferStrategy::Adaptive { baseline } = self.strategy {
+            // Hysteresis so a buffer hovering near baseline isn't churned
+            if self.bytes.is_empty() && self.peak_capacity > baseline.saturating_mul(2) {
+                self.bytes = BytesMut::with_capacity(baseline);
+                self.peak_capacity = self.bytes.capacity();
+            }
+        }
+    }
+
+    fn note_capacity(&mut self) {
+        self.peak_capacity = self.peak_capacity.max(self.bytes.capacity());
+    }
+}
+
+/// Check that `solution` gives `blake3(nonce || solution)` at least
+/// `difficulty` leading zero bits
+pub fn verify_challenge(nonce: &[u8; CHALLENGE_NONCE_LEN], difficulty: u8, solution: u64) -> bool {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(nonce);
+    hasher.update(&solution.to_be_bytes());
+
+    let mut zeros = 0u32;
+    for byte in hasher.finalize().as_bytes() {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    zeros >= difficulty as u32
+}
+
+/// Find the smallest solution to a handshake challenge
+///
+/// Expected work is `2^difficulty` hashes.
+pub fn solve_challenge(nonce: &[u8; CHALLENGE_NONCE_LEN], difficulty: u8) -> u64 {
+    (0..=u64::MAX)
+        .find(|&solution| verify_challenge(nonce, difficulty, solution))
+        .expect("challenge difficulty above 64 bits")
+}
+
+/// Compress a frame payload for a control connection
+///
+/// The uncompressed length is prepended (4 bytes, little-endian) so the
+/// receiver can bound the allocation before decompressing.
+pub fn compress_frame(payload: &[u8]) -> Vec<u8> {
+    lz4_flex::block::compress_prepend_size(payload)
+}
+
+/// Undo [`compress_frame`], refusing payloads that would expand past `max_len`
+pub fn decompress_frame(frame: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    if frame.len() < 4 {
+        return Err(MixnodeError::Packet("Compressed frame too short".to_string()));
+    }
+    let len = u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]) as usize;
+    if len > max_len {
+        return Err(MixnodeError::Packet(format!(
+            "Compressed frame expands to {} bytes, over the {}-byte limit",
+            len, max_len
+        )));
+    }
+    let payload = lz4_flex::block::decompress(&frame[4..], len)
+        .map_err(|e| MixnodeError::Packet(format!("Failed to decompress frame: {}", e)))?;
+    if payload.len() != len {
+        return Err(MixnodeError::Packet(format!(
+            "Compressed frame declared {} bytes but held {}",
+            len,
+            payload.len()
+        )));
+    }
+    Ok(payload)
+}
+
+/// Wait out the idle deadline, or forever when there is none
+async fn sleep_until_idle(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Negotiated state of one live connection, for interop troubleshooting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionDiagnostic {
+    /// Remote address
+    pub peer: SocketAddr,
+    /// Version agreed in the handshake
+    pub version: ProtocolVersion,
+    /// Features both sides advertised
+    pub features: FeatureFlags,
+    /// Agreed connection role
+    pub role: ConnectionRole,
+    /// Whether frames are compressed
+    pub compression: bool,
+    /// Read-buffer strategy; frames are always a 4-byte big-endian length
+    /// followed by the payload
+    pub framing: BufferStrategy,
+    /// Time since the connection was established (or last marked unhealthy)
+    pub connected_for: Duration,
+}
+
+/// Per-connection inputs to the version handshake
+#[derive(Clone)]
+struct HandshakeContext {
+    our_version: ProtocolVersion,
+    node_id: String,
+    deprecation_policy: Arc<DeprecationPolicy>,
+    byte_budget: usize,
+    challenge_difficulty: Option<u8>,
+    role: ConnectionRole,
+}
+
+/// Running total of bytes a peer may still send during the handshake
+struct HandshakeBudget {
+    limit: usize,
+    remaining: usize,
+}
+
+impl HandshakeBudget {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            remaining: limit,
+        }
+    }
+
+    /// Read exactly `buf.len()` bytes, refusing before the read if that
+    /// would take the connection over budget
+    async fn read_exact(&mut self, stream: &mut TcpStream, buf: &mut [u8]) -> Result<()> {
+        if buf.len() > self.remaining {
+            return Err(MixnodeError::Protocol(format!(
+                "Handshake exceeded {}-byte budget",
+                self.limit
+            )));
+        }
+        stream.read_exact(buf).await.map_err(MixnodeError::Io)?;
+        self.remaining -= buf.len();
+        Ok(())
+    }
+}
 
 /// TCP server for handling mixnode network I/O
 pub struct TcpServer {
@@ -34,6 +302,19 @@ pub struct TcpServer {
     shutdown_tx: Option<broadcast::Sender<()>>,
     protocol_version: ProtocolVersion,
     node_id: String,
+    blocklist: Option<SharedBlocklist>,
+    reputation: Option<Arc<RwLock<ReputationManager>>>,
+    deprecation_policy: Arc<DeprecationPolicy>,
+    connections: Arc<Mutex<ConnectionRegistry>>,
+    local_addr: watch::Sender<Option<SocketAddr>>,
+    max_connections: Option<usize>,
+    retry_after: Duration,
+    active_connections: Arc<AtomicUsize>,
+    handshake_byte_budget: usize,
+    challenge_difficulty: Option<u8>,
+    connection_role: ConnectionRole,
+    #[cfg(feature = "debug-echo")]
+    debug_echo: bool,
 }
 
 impl TcpServer {
@@ -55,9 +336,189 @@ impl TcpServer {
             shutdown_tx: None,
             protocol_version,
             node_id,
+            blocklist: None,
+            reputation: None,
+            deprecation_policy: Arc::new(DeprecationPolicy::default()),
+            connections: Arc::new(Mutex::new(ConnectionRegistry::default())),
+            local_addr: watch::channel(None).0,
+            max_connections: None,
+            retry_after: DEFAULT_RETRY_AFTER,
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            handshake_byte_budget: DEFAULT_HANDSHAKE_BYTE_BUDGET,
+            challenge_difficulty: None,
+            connection_role: ConnectionRole::Forwarding,
+            #[cfg(feature = "debug-echo")]
+            debug_echo: false,
         }
     }
 
+    /// Refuse connections from blocked address ranges
+    pub fn with_blocklist(mut self, blocklist: SharedBlocklist) -> Self {
+        self.blocklist = Some(blocklist);
+        self
+    }
+
+    /// Refuse connections from hosts below the manager's admission threshold
+    ///
+    /// Only the admission threshold applies here; the selection threshold is
+    /// for the relay lottery.
+    pub fn with_reputation(mut self, reputation: Arc<RwLock<ReputationManager>>) -> Self {
+        self.reputation = Some(reputation);
+        self
+    }
+
+    /// Enforce version deprecation timelines during the handshake
+    pub fn with_deprecation_policy(mut self, policy: DeprecationPolicy) -> Self {
+        self.deprecation_policy = Arc::new(policy);
+        self
+    }
+
+    /// Reject peers beyond `max` concurrent connections
+    ///
+    /// Rejected peers are sent a retry-after frame carrying `retry_after`
+    /// before the socket is closed, so well-behaved clients back off instead
+    /// of reconnecting immediately.
+    pub fn with_connection_limit(mut self, max: usize, retry_after: Duration) -> Self {
+        self.max_connections = Some(max);
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Close connections whose peer sends more than `bytes` in total
+    /// before the handshake completes
+    pub fn with_handshake_byte_budget(mut self, bytes: usize) -> Self {
+        self.handshake_byte_budget = bytes;
+        self
+    }
+
+    /// Require peers to solve a proof-of-work challenge of `difficulty`
+    /// leading zero bits before their advertisement is parsed
+    ///
+    /// Peers must understand [`CHALLENGE_MARKER`] (v1.3+); older peers read
+    /// it as an oversized advertisement and fail the handshake.
+    pub fn with_handshake_challenge(mut self, difficulty: u8) -> Self {
+        self.challenge_difficulty = Some(difficulty.min(64));
+        self
+    }
+
+    /// Declare the role of connections accepted by this listener
+    ///
+    /// Control listeners compress frames with peers that also declare a
+    /// control connection; forwarding listeners never compress.
+    pub fn with_connection_role(mut self, role: ConnectionRole) -> Self {
+        self.connection_role = role;
+        self
+    }
+
+    /// Size connection read buffers with `strategy`
+    pub fn with_buffer_strategy(mut self, strategy: BufferStrategy) -> Self {
+        self.config.buffer_strategy = strategy;
+        self
+    }
+
+    /// Echo decoded packets straight back instead of mixing them
+    ///
+    /// For validating client tooling without a Sphinx stack. Packets are
+    /// answered in plaintext and never reach the pipeline, so this offers
+    /// no privacy at all; the `debug-echo` feature only builds in debug
+    /// profiles.
+    #[cfg(feature = "debug-echo")]
+    pub fn with_debug_echo(mut self) -> Self {
+        warn!("Debug echo enabled: packets bypass Sphinx and are echoed in plaintext");
+        self.debug_echo = true;
+        self
+    }
+
+    /// Number of connections currently being handled
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::Acquire)
+    }
+
+    /// Registry of peers with an established connection
+    ///
+    /// Callers holding a `ReputationManager` periodically call
+    /// `reward_milestones` on it to reward long-lived connections.
+    pub fn connection_registry(&self) -> Arc<Mutex<ConnectionRegistry>> {
+        Arc::clone(&self.connections)
+    }
+
+    /// Negotiated state of every connection past its handshake, by peer
+    pub fn connection_diagnostics(&self) -> Vec<ConnectionDiagnostic> {
+        let registry = self.connections.lock().unwrap();
+        let mut diagnostics: Vec<ConnectionDiagnostic> = registry
+            .negotiated_peers()
+            .map(|(peer, negotiated)| ConnectionDiagnostic {
+                peer,
+                version: negotiated.version,
+                features: negotiated.features.clone(),
+                role: negotiated.role,
+                compression: negotiated.compression,
+                framing: self.config.buffer_strategy,
+                connected_for: registry.connection_age(&peer).unwrap_or_default(),
+            })
+            .collect();
+        diagnostics.sort_by_key(|diagnostic| diagnostic.peer);
+        diagnostics
+    }
+
+    /// Address the listener is bound to while `run` is accepting
+    ///
+    /// Resolves the OS-assigned port when `listen_addr` uses port 0.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        *self.local_addr.borrow()
+    }
+
+    /// Watch the bound address, e.g. to learn it after moving the server
+    /// into a task running `run`
+    pub fn local_addr_watch(&self) -> watch::Receiver<Option<SocketAddr>> {
+        self.local_addr.subscribe()
+    }
+
+    fn handshake_context(&self) -> HandshakeContext {
+        HandshakeContext {
+            our_version: self.protocol_version,
+            node_id: self.node_id.clone(),
+            deprecation_policy: Arc::clone(&self.deprecation_policy),
+            byte_budget: self.handshake_byte_budget,
+            challenge_difficulty: self.challenge_difficulty,
+            role: self.connection_role,
+        }
+    }
+
+    /// Check whether a newly accepted peer may proceed
+    fn is_admitted(&self, peer_addr: &SocketAddr) -> bool {
+        let unblocked = match &self.blocklist {
+            Some(blocklist) => !blocklist.is_blocked(&peer_addr.ip()),
+            None => true,
+        };
+        unblocked
+            && match &self.reputation {
+                Some(reputation) => reputation.read().unwrap().admits_ip(&peer_addr.ip()),
+                None => true,
+            }
+    }
+
+    /// Check whether another connection fits under the connection limit
+    fn has_capacity(&self) -> bool {
+        match self.max_connections {
+            Some(max) => self.active_connections.load(Ordering::Acquire) < max,
+            None => true,
+        }
+    }
+
+    /// Tell a rejected peer how long to wait, then close the socket
+    async fn send_retry_after(mut stream: TcpStream, retry_after: Duration) {
+        let millis = retry_after.as_millis().min(u32::MAX as u128) as u32;
+        let mut frame = [0u8; 8];
+        frame[..4].copy_from_slice(&RETRY_AFTER_MARKER.to_be_bytes());
+        frame[4..].copy_from_slice(&millis.to_be_bytes());
+        if let Err(e) = stream.write_all(&frame).await {
+            debug!("Failed to send retry-after hint: {}", e);
+            return;
+        }
+        let _ = stream.shutdown().await;
+    }
+
     /// Start the TCP server
     pub async fn run(&mut self) -> Result<()> {
         info!(
@@ -68,11 +529,13 @@ impl TcpServer {
         let listener = TcpListener::bind(self.config.listen_addr)
             .await
             .map_err(MixnodeError::Io)?;
+        let local_addr = listener.local_addr().map_err(MixnodeError::Io)?;
 
         let (shutdown_tx, _) = broadcast::channel(1);
         self.shutdown_tx = Some(shutdown_tx.clone());
+        self.local_addr.send_replace(Some(local_addr));
 
-        info!("✓ TCP server listening on {}", self.config.listen_addr);
+        info!("✓ TCP server listening on {}", local_addr);
 
         // Subscribe before loop to avoid temporary value issue
         let mut shutdown_rx_main = shutdown_tx.subscribe();
@@ -83,13 +546,49 @@ impl TcpServer {
                 result = listener.accept() => {
                     match result {
                         Ok((stream, peer_addr)) => {
+                            if !self.is_admitted(&peer_addr) {
+                                warn!("Refusing connection from unadmitted peer {}", peer_addr);
+                                drop(stream);
+                                continue;
+                            }
+
+                            if !self.has_capacity() {
+                                warn!(
+                                    "Connection limit reached, asking {} to retry after {:?}",
+                                    peer_addr, self.retry_after
+                                );
+                                tokio::spawn(Self::send_retry_after(stream, self.retry_after));
+                                continue;
+                            }
+
                             debug!("Accepted connection from {}", peer_addr);
 
                             let pipeline = Arc::clone(&self.pipeline);
                             let config = self.config.clone();
                             let shutdown_rx = shutdown_tx.subscribe();
-                            let protocol_version = self.protocol_version;
-                            let node_id = self.node_id.clone();
+                            let handshake = self.handshake_context();
+                            let connections = Arc::clone(&self.connections);
+                            let active = Arc::clone(&self.active_connections);
+                            active.fetch_add(1, Ordering::AcqRel);
+
+                            #[cfg(feature = "debug-echo")]
+                            if self.debug_echo {
+                                tokio::spawn(async move {
+                                    if let Err(e) = Self::echo_connection(
+                                        stream,
+                                        peer_addr,
+                                        config,
+                                        shutdown_rx,
+                                        handshake,
+                                    )
+                                    .await
+                                    {
+                                        error!("Echo connection error for {}: {}", peer_addr, e);
+                                    }
+                                    active.fetch_sub(1, Ordering::AcqRel);
+                                });
+                                continue;
+                            }
 
                             // Spawn connection handler
                             tokio::spawn(async move {
@@ -99,13 +598,15 @@ impl TcpServer {
                                     pipeline,
                                     config,
                                     shutdown_rx,
-                                    protocol_version,
-                                    node_id,
+                                    handshake,
+                                    Arc::clone(&connections),
                                 )
                                 .await
                                 {
                                     error!("Connection error for {}: {}", peer_addr, e);
                                 }
+                                connections.lock().unwrap().remove(&peer_addr);
+                                active.fetch_sub(1, Ordering::AcqRel);
                             });
                         }
                         Err(e) => {
@@ -120,6 +621,7 @@ impl TcpServer {
             }
         }
 
+        self.local_addr.send_replace(None);
         Ok(())
     }
 
@@ -138,33 +640,45 @@ impl TcpServer {
         pipeline: Arc<PacketPipeline>,
         config: MixnodeConfig,
         mut shutdown_rx: broadcast::Receiver<()>,
-        protocol_version: ProtocolVersion,
-        node_id: String,
+        handshake: HandshakeContext,
+        connections: Arc<Mutex<ConnectionRegistry>>,
     ) -> Result<()> {
         debug!("Handling connection from {}", peer_addr);
 
         // Perform version negotiation handshake
-        match Self::version_handshake(&mut stream, protocol_version, node_id).await {
-            Ok(negotiated_version) => {
+        let compression = match Self::version_handshake(&mut stream, &handshake).await {
+            Ok(negotiated) => {
                 info!(
-                    "Version negotiation successful with {}: {}",
-                    peer_addr, negotiated_version
+                    "Version negotiation successful with {}: {} ({:?}, {:?}, compression {})",
+                    peer_addr,
+                    negotiated.version,
+                    negotiated.features,
+                    negotiated.role,
+                    negotiated.compression
                 );
+                let compression = negotiated.compression;
+                connections
+                    .lock()
+                    .unwrap()
+                    .register_negotiated(peer_addr, negotiated);
+                compression
             }
             Err(e) => {
                 error!("Version negotiation failed with {}: {}", peer_addr, e);
                 return Err(e);
             }
-        }
+        };
 
-        let mut buffer = BytesMut::with_capacity(config.buffer_size);
+        let mut read_buffer = ConnectionBuffer::new(config.buffer_strategy, config.buffer_size);
+        let mut last_packet = tokio::time::Instant::now();
 
         loop {
+            let idle_deadline = config.idle_timeout.map(|timeout| last_packet + timeout);
             tokio::select! {
                 // Read from stream with timeout
                 result = tokio::time::timeout(
                     config.connection_timeout,
-                    stream.read_buf(&mut buffer)
+                    read_buffer.read_from(&mut stream)
                 ) => {
                     match result {
                         Ok(Ok(0)) => {
@@ -173,6 +687,7 @@ impl TcpServer {
                         }
                         Ok(Ok(n)) => {
                             debug!("Received {} bytes from {}", n, peer_addr);
+                            let buffer = read_buffer.bytes_mut();
 
                             // Process complete packets (length-prefixed)
                             while buffer.len() >= 4 {
@@ -187,16 +702,33 @@ impl TcpServer {
                                 // Check if we have the complete packet
                                 if buffer.len() < 4 + length {
                                     // Wait for more data
+                                    read_buffer.reserve_frame(4 + length);
                                     break;
                                 }
 
                                 // Extract packet data (skip length prefix)
                                 let packet_data = buffer.split_to(4 + length).split_off(4);
-                                let packet_bytes = packet_data.freeze();
+                                let packet_bytes = if compression {
+                                    match decompress_frame(&packet_data, crate::MAX_PACKET_SIZE) {
+                                        Ok(payload) => Bytes::from(payload),
+                                        Err(e) => {
+                                            warn!("Dropping frame from {}: {}", peer_addr, e);
+                                            continue;
+                                        }
+                                    }
+                                } else {
+                                    packet_data.freeze()
+                                };
+
+                                // Empty frames are keep-alives and don't count as activity
+                                if packet_bytes.is_empty() {
+                                    continue;
+                                }
 
                                 // Submit to pipeline for processing
                                 let mut pipeline_packet = PipelinePacket::new(packet_bytes);
                                 pipeline_packet.source = Some(peer_addr);
+                                last_packet = tokio::time::Instant::now();
 
                                 match pipeline.submit_packet(pipeline_packet).await {
                                     Ok(_) => {
@@ -207,6 +739,7 @@ impl TcpServer {
                                     }
                                 }
                             }
+                            read_buffer.shrink_if_idle();
 
                             // Send back processed packets
                             let processed = pipeline.get_processed_packets(10);
@@ -214,11 +747,17 @@ impl TcpServer {
                                 debug!("Sending {} processed packets", processed.len());
 
                                 for packet in processed {
+                                    let data = if compression {
+                                        Bytes::from(compress_frame(&packet.data))
+                                    } else {
+                                        packet.data
+                                    };
+
                                     // Write length prefix + packet data
-                                    let length = packet.data.len() as u32;
-                                    let mut response = BytesMut::with_capacity(4 + packet.data.len());
+                                    let length = data.len() as u32;
+                                    let mut response = BytesMut::with_capacity(4 + data.len());
                                     response.extend_from_slice(&length.to_be_bytes());
-                                    response.extend_from_slice(&packet.data);
+                                    response.extend_from_slice(&data);
 
                                     if let Err(e) = stream.write_all(&response).await {
                                         error!("Failed to write response: {}", e);
@@ -242,6 +781,10 @@ impl TcpServer {
                         }
                     }
                 }
+                _ = sleep_until_idle(idle_deadline) => {
+                    info!("Closing idle connection from {}", peer_addr);
+                    break;
+                }
                 _ = shutdown_rx.recv() => {
                     debug!("Shutdown signal received for connection {}", peer_addr);
                     break;
@@ -252,6 +795,69 @@ impl TcpServer {
         Ok(())
     }
 
+    /// Handshake, then answer each length-prefixed `Packet` with its
+    /// re-encoded copy
+    #[cfg(feature = "debug-echo")]
+    async fn echo_connection(
+        mut stream: TcpStream,
+        peer_addr: SocketAddr,
+        config: MixnodeConfig,
+        mut shutdown_rx: broadcast::Receiver<()>,
+        handshake: HandshakeContext,
+    ) -> Result<()> {
+        Self::version_handshake(&mut stream, &handshake).await?;
+
+        loop {
+            let mut length_buf = [0u8; 4];
+            tokio::select! {
+                result = tokio::time::timeout(
+                    config.connection_timeout,
+                    stream.read_exact(&mut length_buf)
+                ) => {
+                    match result {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                        Ok(Err(e)) => return Err(MixnodeError::Io(e)),
+                        Err(_) => {
+                            warn!("Connection timeout for {}", peer_addr);
+                            break;
+                        }
+                    }
+                }
+                _ = shutdown_rx.recv() => break,
+            }
+
+            let length = u32::from_be_bytes(length_buf) as usize;
+            if length > config.buffer_size {
+                return Err(MixnodeError::Packet(format!(
+                    "Echo frame of {} bytes exceeds buffer size",
+                    length
+                )));
+            }
+            let mut frame = vec![0u8; length];
+            stream.read_exact(&mut frame).await.map_err(MixnodeError::Io)?;
+
+            let packet = match Packet::parse(&frame) {
+                Ok(packet) => packet,
+                Err(e) => {
+                    warn!("Not echoing malformed packet from {}: {}", peer_addr, e);
+                    continue;
+                }
+            };
+            debug!("Echoing {}-byte payload to {}", packet.payload.len(), peer_addr);
+
+            let echoed = packet.encode()?;
+            stream
+                .write_all(&(echoed.len() as u32).to_be_bytes())
+                .await
+                .map_err(MixnodeError::Io)?;
+            stream.write_all(&echoed).await.map_err(MixnodeError::Io)?;
+            stream.flush().await.map_err(MixnodeError::Io)?;
+        }
+
+        Ok(())
+    }
+
     /// Get pipeline statistics
     pub fn pipeline_stats(&self) -> &crate::pipeline::PipelineStats {
         self.pipeline.stats()
@@ -260,11 +866,20 @@ impl TcpServer {
     /// Perform version negotiation handshake
     async fn version_handshake(
         stream: &mut TcpStream,
-        our_version: ProtocolVersion,
-        node_id: String,
-    ) -> Result<ProtocolVersion> {
+        handshake: &HandshakeContext,
+    ) -> Result<NegotiatedProtocol> {
+        let our_version = handshake.our_version;
+        let deprecation_policy = &handshake.deprecation_policy;
+        let mut budget = HandshakeBudget::new(handshake.byte_budget);
+
+        // Step 0: Make the peer pay before we parse anything it sends
+        if let Some(difficulty) = handshake.challenge_difficulty {
+            Self::challenge_peer(stream, &mut budget, difficulty).await?;
+        }
+
         // Step 1: Send our advertisement
-        let our_ad = ProtocolAdvertisement::new(our_version, node_id);
+        let our_ad = ProtocolAdvertisement::new(our_version, handshake.node_id.clone())
+            .with_role(handshake.role);
         let our_ad_bytes = our_ad
             .encode()
             .map_err(|e| MixnodeError::Protocol(format!("Failed to encode advertisement: {}", e)))?;
@@ -282,21 +897,19 @@ impl TcpServer {
 
         // Step 2: Receive their advertisement
         let mut length_buf = [0u8; 4];
-        stream
-            .read_exact(&mut length_buf)
-            .await
-            .map_err(MixnodeError::Io)?;
+        budget.read_exact(stream, &mut length_buf).await?;
 
+        // Peers can't be newer than us, so our own cap bounds the read
         let ad_length = u32::from_be_bytes(length_buf) as usize;
-        if ad_length > 4096 {
-            // Sanity check
-            return Err(MixnodeError::Protocol(
-                "Advertisement too large".to_string(),
-            ));
+        if ad_length > our_version.max_advertisement_size() {
+            return Err(MixnodeError::Protocol(format!(
+                "Advertisement too large: {} bytes",
+                ad_length
+            )));
         }
 
         let mut ad_buf = vec![0u8; ad_length];
-        stream.read_exact(&mut ad_buf).await.map_err(MixnodeError::Io)?;
+        budget.read_exact(stream, &mut ad_buf).await?;
 
         let their_ad = ProtocolAdvertisement::decode(&ad_buf).map_err(|e| {
             MixnodeError::Protocol(format!("Failed to decode peer advertisement: {}", e))
@@ -304,6 +917,15 @@ impl TcpServer {
 
         debug!("Received protocol advertisement: {}", their_ad.version);
 
+        // Older versions are held to their own, tighter cap
+        let their_cap = their_ad.version.max_advertisement_size();
+        if ad_length > their_cap {
+            return Err(MixnodeError::Protocol(format!(
+                "Advertisement of {} bytes exceeds {}-byte cap for v{}",
+                ad_length, their_cap, their_ad.version
+            )));
+        }
+
         // Step 3: Check compatibility
         if !our_ad.is_compatible_with(&their_ad) {
             return Err(MixnodeError::Protocol(format!(
@@ -319,6 +941,29 @@ impl TcpServer {
             their_ad.version
         };
 
+        // Step 4b: Enforce deprecation before committing to the version
+        match deprecation_policy.status(&negotiated, chrono::Utc::now()) {
+            DeprecationStatus::Supported => {}
+            DeprecationStatus::Deprecated { cutoff, upgrade_to } => {
+                warn!(
+                    "Peer negotiated deprecated version {} (refused after {}, upgrade to {})",
+                    negotiated, cutoff, upgrade_to
+                );
+            }
+            DeprecationStatus::Expired { cutoff, upgrade_to } => {
+                if deprecation_policy.refuse_expired {
+                    return Err(MixnodeError::Protocol(format!(
+                        "Version {} is past its {} cutoff; upgrade to {}",
+                        negotiated, cutoff, upgrade_to
+                    )));
+                }
+                warn!(
+                    "Peer negotiated expired version {} (cutoff {}, upgrade to {})",
+                    negotiated, cutoff, upgrade_to
+                );
+            }
+        }
+
         // Step 5: Send negotiation result (1 byte: version encoding)
         let negotiated_byte = negotiated.encode_byte();
         stream
@@ -329,10 +974,7 @@ impl TcpServer {
 
         // Step 6: Receive their confirmation
         let mut confirm_buf = [0u8; 1];
-        stream
-            .read_exact(&mut confirm_buf)
-            .await
-            .map_err(MixnodeError::Io)?;
+        budget.read_exact(stream, &mut confirm_buf).await?;
 
         let their_negotiated = ProtocolVersion::decode_byte(confirm_buf[0]).ok_or_else(|| {
             MixnodeError::Protocol(format!("Invalid version byte: {}", confirm_buf[0]))
@@ -345,24 +987,97 @@ impl TcpServer {
             )));
         }
 
+        // Step 7: Only use features both sides advertised, whatever the
+        // agreed version would imply
+        let features = our_ad.features.intersect(&their_ad.features);
+        if !features.supports(&FeatureFlags::for_version(&negotiated)) {
+            warn!(
+                "Peer advertised {} without all of its features: {:?}",
+                their_ad.version, their_ad.features
+            );
+        }
+
+        // Step 8: Compression is for control traffic only
+        let (role, compression) = our_ad.negotiate_role(&their_ad);
+
         info!("Protocol version negotiated: {}", negotiated);
-        Ok(negotiated)
+        Ok(NegotiatedProtocol {
+            version: negotiated,
+            features,
+            role,
+            compression,
+        })
+    }
+
+    /// Send a proof-of-work challenge and check the peer's solution
+    async fn challenge_peer(
+        stream: &mut TcpStream,
+        budget: &mut HandshakeBudget,
+        difficulty: u8,
+    ) -> Result<()> {
+        let nonce: [u8; CHALLENGE_NONCE_LEN] = rand::random();
+        let mut frame = [0u8; 4 + 1 + CHALLENGE_NONCE_LEN];
+        frame[..4].copy_from_slice(&CHALLENGE_MARKER.to_be_bytes());
+        frame[4] = difficulty;
+        frame[5..].copy_from_slice(&nonce);
+        stream.write_all(&frame).await.map_err(MixnodeError::Io)?;
+        stream.flush().await.map_err(MixnodeError::Io)?;
+
+        let mut solution = [0u8; CHALLENGE_SOLUTION_LEN];
+        budget.read_exact(stream, &mut solution).await?;
+        if !verify_challenge(&nonce, difficulty, u64::from_be_bytes(solution)) {
+            return Err(MixnodeError::Protocol(format!(
+                "Peer failed {}-bit handshake challenge",
+                difficulty
+            )));
+        }
+
+        debug!("Peer solved {}-bit handshake challenge", difficulty);
+        Ok(())
     }
 }
 
 /// TCP client for connecting to other mixnodes
 pub struct TcpClient {
     next_hop: SocketAddr,
+    retry_not_before: Mutex<Option<Instant>>,
 }
 
 impl TcpClient {
     /// Create new TCP client
     pub fn new(next_hop: SocketAddr) -> Self {
-        Self { next_hop }
+        Self {
+            next_hop,
+            retry_not_before: Mutex::new(None),
+        }
+    }
+
+    /// Time left before the next hop asked us to reconnect
+    ///
+    /// `None` once the hint has elapsed or if none was ever received.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let mut deadline = self.retry_not_before.lock().unwrap();
+        let remaining = deadline
+            .and_then(|at| at.checked_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero());
+        if remaining.is_none() {
+            *deadline = None;
+        }
+        remaining
     }
 
     /// Send packet to next hop
+    ///
+    /// Fails without connecting while a retry-after hint from the next hop
+    /// is still in effect.
     pub async fn send_packet(&self, packet: &[u8]) -> Result<Vec<u8>> {
+        if let Some(remaining) = self.retry_after() {
+            return Err(MixnodeError::Network(format!(
+                "Backing off from {}: retry after {:?}",
+                self.next_hop, remaining
+            )));
+        }
+
         debug!("Connecting to {}", self.next_hop);
 
         let mut stream = TcpStream::connect(self.next_hop)
@@ -383,14 +1098,41 @@ impl TcpClient {
 
         debug!("Sent {} bytes to {}", packet.len(), self.next_hop);
 
-        // Read response (length-prefixed)
-        let mut length_buf = [0u8; 4];
-        stream
-            .read_exact(&mut length_buf)
-            .await
-            .map_err(MixnodeError::Io)?;
+        // Read response (length-prefixed), answering a challenge first if asked
+        let response_length = loop {
+            let mut length_buf = [0u8; 4];
+            stream
+                .read_exact(&mut length_buf)
+                .await
+                .map_err(MixnodeError::Io)?;
+
+            match u32::from_be_bytes(length_buf) {
+                CHALLENGE_MARKER => self.answer_challenge(&mut stream).await?,
+                RETRY_AFTER_MARKER => {
+                    let mut millis_buf = [0u8; 4];
+                    stream
+                        .read_exact(&mut millis_buf)
+                        .await
+                        .map_err(MixnodeError::Io)?;
+                    let retry_after =
+                        Duration::from_millis(u32::from_be_bytes(millis_buf) as u64);
+                    *self.retry_not_before.lock().unwrap() = Some(Instant::now() + retry_after);
+                    warn!("{} rejected connection, retry after {:?}", self.next_hop, retry_after);
+                    return Err(MixnodeError::Network(format!(
+                        "Connection rejected by {}: retry after {:?}",
+                        self.next_hop, retry_after
+                    )));
+                }
+                length => break length as usize,
+            }
+        };
 
-        let response_length = u32::from_be_bytes(length_buf) as usize;
+        if response_length > MAX_FRAME_LEN {
+            return Err(MixnodeError::Protocol(format!(
+                "Response of {} bytes from {} exceeds the {}-byte frame limit",
+                response_length, self.next_hop, MAX_FRAME_LEN
+            )));
+        }
         let mut response = vec![0u8; response_length];
 
         stream
@@ -403,6 +1145,40 @@ impl TcpClient {
         Ok(response)
     }
 
+    /// Solve a handshake challenge from the next hop on the blocking pool
+    ///
+    /// Challenges above [`MAX_CLIENT_CHALLENGE_DIFFICULTY`] are refused
+    /// rather than tying up a blocking thread.
+    async fn answer_challenge(&self, stream: &mut TcpStream) -> Result<()> {
+        let mut challenge = [0u8; 1 + CHALLENGE_NONCE_LEN];
+        stream
+            .read_exact(&mut challenge)
+            .await
+            .map_err(MixnodeError::Io)?;
+
+        let difficulty = challenge[0];
+        if difficulty > MAX_CLIENT_CHALLENGE_DIFFICULTY {
+            return Err(MixnodeError::Protocol(format!(
+                "{} demanded a {}-bit handshake challenge, above the {}-bit cap",
+                self.next_hop, difficulty, MAX_CLIENT_CHALLENGE_DIFFICULTY
+            )));
+        }
+
+        let mut nonce = [0u8; CHALLENGE_NONCE_LEN];
+        nonce.copy_from_slice(&challenge[1..]);
+        let solution = tokio::task::spawn_blocking(move || solve_challenge(&nonce, difficulty))
+            .await
+            .map_err(|e| MixnodeError::Protocol(format!("Challenge solver failed: {}", e)))?;
+
+        stream
+            .write_all(&solution.to_be_bytes())
+            .await
+            .map_err(MixnodeError::Io)?;
+        stream.flush().await.map_err(MixnodeError::Io)?;
+        debug!("Solved {}-bit handshake challenge from {}", difficulty, self.next_hop);
+        Ok(())
+    }
+
     /// Send packet with timeout
     pub async fn send_packet_with_timeout(
         &self,
@@ -418,6 +1194,7 @@ impl TcpClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::compatibility::Feature;
     use crate::core::config::MixnodeConfig;
     use crate::pipeline::PacketPipeline;
 
@@ -432,6 +1209,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::field_reassign_with_default)]
     async fn test_tcp_client_send() {
         // Start a test server
         let mut config = MixnodeConfig::default();
@@ -468,4 +1246,789 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_blocked_peer_refused_at_accept() {
+        use crate::core::blocklist::{Blocklist, SharedBlocklist};
+
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+
+        let blocklist = SharedBlocklist::new(Blocklist::parse("127.0.0.0/8").unwrap());
+        let mut server = TcpServer::new(config, pipeline).with_blocklist(blocklist);
+        let mut bound = server.local_addr_watch();
+        tokio::spawn(async move {
+            server.run().await.ok();
+        });
+        let addr = bound.wait_for(|addr| addr.is_some()).await.unwrap().unwrap();
+
+        // A blocked peer is closed before the server sends its advertisement
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let mut buf = [0u8; 4];
+        let read = tokio::time::timeout(std::time::Duration::from_secs(2), stream.read(&mut buf))
+            .await
+            .unwrap();
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+
+    #[tokio::test]
+    async fn test_reputation_gates_admission_not_selection() {
+        use crate::core::reputation::{ReputationAction, ReputationManager};
+
+        // Probe whether the server sends its advertisement to a fresh connection
+        async fn admitted(manager: ReputationManager) -> bool {
+            let config = MixnodeConfig {
+                listen_addr: "127.0.0.1:0".parse().unwrap(),
+                ..Default::default()
+            };
+            let mut pipeline = PacketPipeline::new(1);
+            pipeline.start().await.unwrap();
+
+            let reputation = Arc::new(RwLock::new(manager));
+            let mut server = TcpServer::new(config, pipeline).with_reputation(reputation);
+            let mut bound = server.local_addr_watch();
+            tokio::spawn(async move {
+                server.run().await.ok();
+            });
+            let addr = bound.wait_for(|addr| addr.is_some()).await.unwrap().unwrap();
+
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let mut buf = [0u8; 4];
+            let read =
+                tokio::time::timeout(std::time::Duration::from_secs(2), stream.read(&mut buf))
+                    .await
+                    .unwrap();
+            matches!(read, Ok(n) if n > 0)
+        }
+
+        // The local host is tracked with 75 points after one dropped connection
+        let peer: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let tracked = |admission, selection| {
+            let mut manager = ReputationManager::new()
+                .with_admission_threshold(admission)
+                .with_selection_threshold(selection);
+            manager.add_node(peer, 1000);
+            manager
+                .update_reputation(&peer, ReputationAction::DroppedConnection)
+                .unwrap();
+            manager
+        };
+
+        // Admitted even though it is too low to be picked for circuits
+        let manager = tracked(50, 150);
+        assert!(!manager.is_selectable(&peer));
+        assert!(admitted(manager).await);
+
+        // Selectable, but refused at accept
+        let manager = tracked(150, 50);
+        assert!(manager.is_selectable(&peer));
+        assert!(!admitted(manager).await);
+    }
+
+    #[tokio::test]
+    async fn test_local_addr_resolves_ephemeral_port() {
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+
+        let mut server = TcpServer::new(config, pipeline);
+        assert_eq!(server.local_addr(), None);
+
+        let mut bound = server.local_addr_watch();
+        tokio::spawn(async move {
+            server.run().await.ok();
+        });
+
+        let addr = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            bound.wait_for(|addr| addr.is_some()),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+        assert_ne!(addr.port(), 0);
+        assert!(TcpStream::connect(addr).await.is_ok());
+    }
+
+    #[cfg(feature = "debug-echo")]
+    #[tokio::test]
+    async fn test_debug_echo_returns_payload() {
+        // Release builds refuse the feature at compile time (see lib.rs)
+        const { assert!(cfg!(debug_assertions)) };
+
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+        let mut server = TcpServer::new(config, pipeline).with_debug_echo();
+        let mut bound = server.local_addr_watch();
+        tokio::spawn(async move {
+            server.run().await.ok();
+        });
+        let addr = bound.wait_for(|addr| addr.is_some()).await.unwrap().unwrap();
+
+        // Play the client side of the handshake
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).await.unwrap();
+        let mut ad = vec![0u8; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut ad).await.unwrap();
+        let our_ad = ProtocolAdvertisement::new(ProtocolVersion::default(), "client".to_string());
+        let bytes = our_ad.encode().unwrap();
+        stream.write_all(&(bytes.len() as u32).to_be_bytes()).await.unwrap();
+        stream.write_all(&bytes).await.unwrap();
+        let mut negotiated = [0u8; 1];
+        stream.read_exact(&mut negotiated).await.unwrap();
+        stream.write_all(&negotiated).await.unwrap();
+
+        for payload in [&b"hello"[..], &[7u8; 300][..]] {
+            let packet = Packet::data(Bytes::copy_from_slice(payload), 0).encode().unwrap();
+            stream.write_all(&(packet.len() as u32).to_be_bytes()).await.unwrap();
+            stream.write_all(&packet).await.unwrap();
+
+            stream.read_exact(&mut len).await.unwrap();
+            let mut reply = vec![0u8; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut reply).await.unwrap();
+            let echoed = Packet::parse(&reply).unwrap();
+            assert_eq!(&echoed.payload[..], payload);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_diagnostics_reflect_handshake() {
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+        let server = TcpServer::new(config.clone(), pipeline);
+        assert!(server.connection_diagnostics().is_empty());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let handler = tokio::spawn(TcpServer::handle_connection(
+            stream,
+            peer_addr,
+            Arc::clone(&server.pipeline),
+            config,
+            shutdown_tx.subscribe(),
+            server.handshake_context(),
+            server.connection_registry(),
+        ));
+
+        // v1.1 client without batch processing
+        let mut our_ad = ProtocolAdvertisement::new(ProtocolVersion::V1_1_0, "client".to_string());
+        our_ad.features.batch_processing = false;
+        client_handshake(&mut client, our_ad).await;
+
+        let diagnostics = loop {
+            let diagnostics = server.connection_diagnostics();
+            if !diagnostics.is_empty() {
+                break diagnostics;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        };
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.peer, peer_addr);
+        assert_eq!(diagnostic.version, ProtocolVersion::V1_1_0);
+        assert_eq!(
+            diagnostic.features,
+            FeatureFlags {
+                batch_processing: false,
+                ..FeatureFlags::v1_1_0()
+            }
+        );
+        assert_eq!(diagnostic.role, ConnectionRole::Forwarding);
+        assert!(!diagnostic.compression);
+        assert_eq!(diagnostic.framing, BufferStrategy::default());
+
+        let _ = shutdown_tx.send(());
+        handler.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_noise_is_idle_closed() {
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            idle_timeout: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+        let server = TcpServer::new(config.clone(), pipeline);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let handler = tokio::spawn(TcpServer::handle_connection(
+            stream,
+            peer_addr,
+            Arc::clone(&server.pipeline),
+            config,
+            shutdown_tx.subscribe(),
+            server.handshake_context(),
+            server.connection_registry(),
+        ));
+        client_handshake(
+            &mut client,
+            ProtocolAdvertisement::new(ProtocolVersion::V1_1_0, "client".to_string()),
+        )
+        .await;
+
+        // Empty frames, then a frame trickled a byte at a time that never
+        // completes; every read lands well inside the 30s read timeout
+        let started = tokio::time::Instant::now();
+        let trickle = tokio::spawn(async move {
+            let noise = std::iter::repeat_n([0u8; 4].as_slice(), 4)
+                .chain([[0u8, 0, 0, 200].as_slice()])
+                .chain(std::iter::repeat_n([0xAAu8].as_slice(), 30));
+            for chunk in noise {
+                if client.write_all(chunk).await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+
+        tokio::time::timeout(Duration::from_secs(2), handler)
+            .await
+            .expect("idle connection was not closed")
+            .unwrap()
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        trickle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_rejected_client_honors_retry_after() {
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+
+        let hint = Duration::from_millis(300);
+        let mut server = TcpServer::new(config, pipeline).with_connection_limit(1, hint);
+        let mut bound = server.local_addr_watch();
+        tokio::spawn(async move {
+            server.run().await.ok();
+        });
+        let addr = tokio::time::timeout(Duration::from_secs(2), bound.wait_for(|a| a.is_some()))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        // Occupy the only slot; the server's advertisement proves it was accepted
+        let mut holder = TcpStream::connect(addr).await.unwrap();
+        let mut len = [0u8; 4];
+        holder.read_exact(&mut len).await.unwrap();
+        assert_ne!(u32::from_be_bytes(len), RETRY_AFTER_MARKER);
+
+        let client = TcpClient::new(addr);
+        let packet = Packet::data(Bytes::from(vec![1, 2, 3, 4]), 0).encode().unwrap();
+        let err = client
+            .send_packet_with_timeout(&packet, Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("rejected"), "{}", err);
+        let remaining = client.retry_after().unwrap();
+        assert!(remaining <= hint);
+
+        // Within the hint the client does not even try to connect
+        let err = client.send_packet(&packet).await.unwrap_err();
+        assert!(err.to_string().contains("Backing off"), "{}", err);
+
+        drop(holder);
+        tokio::time::sleep(hint + Duration::from_millis(100)).await;
+        assert_eq!(client.retry_after(), None);
+
+        // After the hint the slot is free and the server answers normally
+        match client
+            .send_packet_with_timeout(&packet, Duration::from_secs(2))
+            .await
+        {
+            Ok(response) => assert!(!response.is_empty()),
+            Err(e) => {
+                let msg = e.to_string();
+                assert!(!msg.contains("rejected") && !msg.contains("Backing off"), "{}", msg);
+            }
+        }
+    }
+
+    /// Next hop that challenges at `difficulty`, then answers with
+    /// `response_length` and, if the solution checks out, an echo of the request
+    async fn challenging_peer(difficulty: u8, response_length: u32) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let nonce: [u8; CHALLENGE_NONCE_LEN] = rand::random();
+            let mut frame = vec![0u8; 5];
+            frame[..4].copy_from_slice(&CHALLENGE_MARKER.to_be_bytes());
+            frame[4] = difficulty;
+            frame.extend_from_slice(&nonce);
+            stream.write_all(&frame).await.unwrap();
+
+            let mut len = [0u8; 4];
+            stream.read_exact(&mut len).await.unwrap();
+            let mut request = vec![0u8; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut request).await.unwrap();
+
+            let mut solution = [0u8; CHALLENGE_SOLUTION_LEN];
+            if stream.read_exact(&mut solution).await.is_err() {
+                return;
+            }
+            assert!(verify_challenge(&nonce, difficulty, u64::from_be_bytes(solution)));
+            stream.write_all(&response_length.to_be_bytes()).await.unwrap();
+            stream.write_all(&request).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_client_solves_handshake_challenge() {
+        let packet = Packet::data(Bytes::from(vec![9; 32]), 0).encode().unwrap();
+        let addr = challenging_peer(8, packet.len() as u32).await;
+
+        let response = TcpClient::new(addr)
+            .send_packet_with_timeout(&packet, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(response, packet);
+    }
+
+    #[tokio::test]
+    async fn test_client_refuses_excessive_challenge_and_oversized_response() {
+        let packet = Packet::data(Bytes::from(vec![9; 32]), 0).encode().unwrap();
+
+        let addr = challenging_peer(MAX_CLIENT_CHALLENGE_DIFFICULTY + 1, 0).await;
+        let err = TcpClient::new(addr)
+            .send_packet_with_timeout(&packet, Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("cap"), "{}", err);
+
+        // A length the server could never legitimately send is refused before allocating
+        let addr = challenging_peer(1, MAX_FRAME_LEN as u32 + 1).await;
+        let err = TcpClient::new(addr)
+            .send_packet_with_timeout(&packet, Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("frame limit"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_buffer_grows_then_shrinks() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let frame_len = 4 + 64 * 1024;
+        let writer = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(&vec![7u8; frame_len]).await.unwrap();
+            stream
+        });
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let _writer = writer.await.unwrap();
+
+        let mut buffer = ConnectionBuffer::new(BufferStrategy::Adaptive { baseline: 1024 }, 8192);
+        assert!(buffer.peak_capacity() < 8192);
+
+        // A large frame is reserved in one go once its start arrives
+        buffer.read_from(&mut stream).await.unwrap();
+        buffer.reserve_frame(frame_len);
+        assert!(buffer.peak_capacity() >= frame_len);
+        while buffer.bytes_mut().len() < frame_len {
+            buffer.read_from(&mut stream).await.unwrap();
+        }
+
+        // Not shrunk while a frame is still buffered
+        let frame = buffer.bytes_mut().split_to(frame_len - 10);
+        buffer.shrink_if_idle();
+        assert!(buffer.peak_capacity() >= frame_len);
+        drop(frame);
+
+        // Drained: back to baseline
+        buffer.bytes_mut().clear();
+        buffer.shrink_if_idle();
+        assert!(buffer.peak_capacity() < 2 * 1024);
+        assert!(buffer.bytes_mut().capacity() < 2 * 1024);
+
+        // A forged length prefix doesn't reserve past the cap
+        buffer.bytes_mut().extend_from_slice(&[0u8; 4]);
+        buffer.reserve_frame(u32::MAX as usize);
+        assert!(buffer.peak_capacity() < 2 * MAX_FRAME_RESERVE);
+
+        // A fixed buffer smaller than a max-size frame grows to hold it
+        let mut small = ConnectionBuffer::new(BufferStrategy::Fixed, 64);
+        small.bytes_mut().extend_from_slice(&[0u8; 4]);
+        small.reserve_frame(MAX_FRAME_LEN);
+        assert!(small.bytes_mut().capacity() >= MAX_FRAME_LEN);
+        small.reserve_frame(u32::MAX as usize);
+        assert!(small.peak_capacity() < 2 * MAX_FRAME_LEN);
+
+        // Fixed buffers never shrink
+        let mut fixed = ConnectionBuffer::new(BufferStrategy::Fixed, 8192);
+        fixed.bytes_mut().extend_from_slice(&vec![0u8; 32 * 1024]);
+        fixed.note_capacity();
+        fixed.bytes_mut().clear();
+        fixed.shrink_if_idle();
+        assert!(fixed.bytes_mut().capacity() >= 32 * 1024);
+    }
+
+    /// Play the peer side of the handshake with a padded advertisement
+    async fn handshake_with_padded_ad(
+        our_version: ProtocolVersion,
+        peer_version: ProtocolVersion,
+        ad_size: usize,
+    ) -> Result<ProtocolVersion> {
+        handshake_with_policy(our_version, peer_version, ad_size, DeprecationPolicy::default()).await
+    }
+
+    async fn handshake_with_policy(
+        our_version: ProtocolVersion,
+        peer_version: ProtocolVersion,
+        ad_size: usize,
+        policy: DeprecationPolicy,
+    ) -> Result<ProtocolVersion> {
+        let mut peer_ad = ProtocolAdvertisement::new(peer_version, String::new());
+        let overhead = peer_ad.encode().unwrap().len();
+        peer_ad.node_id = "x".repeat(ad_size.saturating_sub(overhead));
+        assert_eq!(peer_ad.encode().unwrap().len(), ad_size);
+
+        handshake_with_ad(our_version, peer_ad, policy)
+            .await
+            .map(|negotiated| negotiated.version)
+    }
+
+    /// Answer the server's advertisement with `our_ad` and echo the
+    /// negotiated version back
+    async fn client_handshake(client: &mut TcpStream, our_ad: ProtocolAdvertisement) {
+        let mut len = [0u8; 4];
+        client.read_exact(&mut len).await.unwrap();
+        let mut ad = vec![0u8; u32::from_be_bytes(len) as usize];
+        client.read_exact(&mut ad).await.unwrap();
+        let bytes = our_ad.encode().unwrap();
+        client.write_all(&(bytes.len() as u32).to_be_bytes()).await.unwrap();
+        client.write_all(&bytes).await.unwrap();
+        let mut negotiated = [0u8; 1];
+        client.read_exact(&mut negotiated).await.unwrap();
+        client.write_all(&negotiated).await.unwrap();
+    }
+
+    /// Play the peer side of the handshake sending `peer_ad` verbatim
+    async fn handshake_with_ad(
+        our_version: ProtocolVersion,
+        peer_ad: ProtocolAdvertisement,
+        policy: DeprecationPolicy,
+    ) -> Result<NegotiatedProtocol> {
+        handshake_with_role(our_version, ConnectionRole::Forwarding, peer_ad, policy).await
+    }
+
+    async fn handshake_with_role(
+        our_version: ProtocolVersion,
+        role: ConnectionRole,
+        peer_ad: ProtocolAdvertisement,
+        policy: DeprecationPolicy,
+    ) -> Result<NegotiatedProtocol> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let peer = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            let mut len = [0u8; 4];
+            stream.read_exact(&mut len).await.unwrap();
+            let mut ad = vec![0u8; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut ad).await.unwrap();
+
+            let bytes = peer_ad.encode().unwrap();
+
+            let _ = stream.write_all(&(bytes.len() as u32).to_be_bytes()).await;
+            let _ = stream.write_all(&bytes).await;
+
+            let mut negotiated = [0u8; 1];
+            if stream.read_exact(&mut negotiated).await.is_ok() {
+                let _ = stream.write_all(&negotiated).await;
+            }
+        });
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let handshake = HandshakeContext {
+            our_version,
+            node_id: "node".to_string(),
+            deprecation_policy: Arc::new(policy),
+            byte_budget: DEFAULT_HANDSHAKE_BYTE_BUDGET,
+            challenge_difficulty: None,
+            role,
+        };
+        let result = TcpServer::version_handshake(&mut stream, &handshake).await;
+        drop(stream);
+        let _ = peer.await;
+        result
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_features_are_intersection() {
+        let v1_2 = ProtocolVersion::new(1, 2, 0);
+
+        // Peer claims v1.2 but leaves out two of its features
+        let mut peer_ad = ProtocolAdvertisement::new(v1_2, "peer".to_string());
+        peer_ad.features.vrf_delays = false;
+        peer_ad.features.cover_traffic = false;
+
+        let negotiated = handshake_with_ad(v1_2, peer_ad, DeprecationPolicy::default())
+            .await
+            .unwrap();
+        assert_eq!(negotiated.version, v1_2);
+        assert_eq!(
+            negotiated.features,
+            FeatureFlags {
+                vrf_delays: false,
+                cover_traffic: false,
+                ..FeatureFlags::v1_2_0()
+            }
+        );
+
+        let peer: SocketAddr = "127.0.0.1:9700".parse().unwrap();
+        let mut registry = ConnectionRegistry::new();
+        registry.register_with_features(peer, negotiated.features);
+        assert!(registry.peer_supports(&peer, Feature::RelayLottery));
+        assert!(registry.peer_supports(&peer, Feature::BatchProcessing));
+        assert!(!registry.peer_supports(&peer, Feature::VrfDelays));
+        assert!(!registry.peer_supports(&peer, Feature::CoverTraffic));
+    }
+
+    #[tokio::test]
+    async fn test_only_control_connections_compress() {
+        let version = ProtocolVersion::default();
+        let control_ad = || {
+            ProtocolAdvertisement::new(version, "peer".to_string()).with_role(ConnectionRole::Control)
+        };
+
+        // A forwarding listener never compresses, whatever the peer asks for
+        let forwarding =
+            handshake_with_role(version, ConnectionRole::Forwarding, control_ad(), Default::default())
+                .await
+                .unwrap();
+        assert_eq!(forwarding.role, ConnectionRole::Forwarding);
+        assert!(!forwarding.compression);
+
+        let control =
+            handshake_with_role(version, ConnectionRole::Control, control_ad(), Default::default())
+                .await
+                .unwrap();
+        assert_eq!(control.role, ConnectionRole::Control);
+        assert!(control.compression);
+
+        // Peers that predate compression omit the field and stay uncompressed
+        let mut legacy = serde_json::to_value(control_ad()).unwrap();
+        legacy.as_object_mut().unwrap().remove("compression");
+        let legacy: ProtocolAdvertisement = serde_json::from_value(legacy).unwrap();
+        let negotiated =
+            handshake_with_role(version, ConnectionRole::Control, legacy, Default::default())
+                .await
+                .unwrap();
+        assert_eq!(negotiated.role, ConnectionRole::Control);
+        assert!(!negotiated.compression);
+
+        // Gossip-like payloads shrink and round-trip; bombs are refused
+        let gossip = br#"{"relay":"10.0.0.1:9000","score":0.5}"#.repeat(40);
+        let frame = compress_frame(&gossip);
+        assert!(frame.len() < gossip.len() / 4);
+        assert_eq!(decompress_frame(&frame, crate::MAX_PACKET_SIZE).unwrap(), gossip);
+        let err = decompress_frame(&frame, 64).unwrap_err();
+        assert!(err.to_string().contains("over the 64-byte limit"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_advertisement_cap_depends_on_version() {
+        let v1_0 = ProtocolVersion::new(1, 0, 0);
+        let v1_3 = ProtocolVersion::new(1, 3, 0);
+
+        // 3000 bytes is over the v1.0 cap but within the v1.3 cap
+        let err = handshake_with_padded_ad(v1_3, v1_0, 3000).await.unwrap_err();
+        assert!(err.to_string().contains("cap for v1.0.0"), "{}", err);
+
+        let negotiated = handshake_with_padded_ad(v1_3, v1_3, 6000).await.unwrap();
+        assert_eq!(negotiated, v1_3);
+
+        // Small v1.0 advertisements are still fine
+        let negotiated = handshake_with_padded_ad(v1_3, v1_0, 512).await.unwrap();
+        assert_eq!(negotiated, v1_0);
+
+        // Nothing may exceed our own version's cap
+        assert!(handshake_with_padded_ad(v1_3, v1_3, 9000).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_over_budget_handshake_terminated() {
+        let config = MixnodeConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut pipeline = PacketPipeline::new(1);
+        pipeline.start().await.unwrap();
+
+        // Budget well under the advertisement cap
+        let mut server = TcpServer::new(config, pipeline).with_handshake_byte_budget(256);
+        let mut bound = server.local_addr_watch();
+        tokio::spawn(async move {
+            server.run().await.ok();
+        });
+        let addr = tokio::time::timeout(Duration::from_secs(2), bound.wait_for(|a| a.is_some()))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).await.unwrap();
+        let mut ad = vec![0u8; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut ad).await.unwrap();
+
+        // A valid advertisement within the version cap but over the byte budget
+        let mut peer_ad = ProtocolAdvertisement::new(ProtocolVersion::default(), String::new());
+        peer_ad.node_id = "x".repeat(1000);
+        let bytes = peer_ad.encode().unwrap();
+        assert!(bytes.len() <= ProtocolVersion::default().max_advertisement_size());
+        let _ = stream.write_all(&(bytes.len() as u32).to_be_bytes()).await;
+        let _ = stream.write_all(&bytes).await;
+
+        // The server closes instead of answering with a negotiated version
+        let mut buf = [0u8; 1];
+        let read = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+            .await
+            .expect("over-budget connection was left open");
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+
+    /// Play a peer that answers the challenge with `solve`, then completes
+    /// the handshake
+    async fn handshake_with_challenge(
+        difficulty: u8,
+        solve: fn(&[u8; CHALLENGE_NONCE_LEN], u8) -> u64,
+    ) -> Result<NegotiatedProtocol> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let peer = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            let mut frame = [0u8; 4 + 1 + CHALLENGE_NONCE_LEN];
+            stream.read_exact(&mut frame).await.unwrap();
+            assert_eq!(&frame[..4], &CHALLENGE_MARKER.to_be_bytes());
+            let nonce: [u8; CHALLENGE_NONCE_LEN] = frame[5..].try_into().unwrap();
+            let solution = solve(&nonce, frame[4]);
+            let _ = stream.write_all(&solution.to_be_bytes()).await;
+
+            let mut len = [0u8; 4];
+            if stream.read_exact(&mut len).await.is_err() {
+                return;
+            }
+            let mut ad = vec![0u8; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut ad).await.unwrap();
+
+            let bytes = ProtocolAdvertisement::new(ProtocolVersion::default(), "peer".to_string())
+                .encode()
+                .unwrap();
+            let _ = stream.write_all(&(bytes.len() as u32).to_be_bytes()).await;
+            let _ = stream.write_all(&bytes).await;
+
+            let mut negotiated = [0u8; 1];
+            if stream.read_exact(&mut negotiated).await.is_ok() {
+                let _ = stream.write_all(&negotiated).await;
+            }
+        });
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let handshake = HandshakeContext {
+            our_version: ProtocolVersion::default(),
+            node_id: "node".to_string(),
+            deprecation_policy: Arc::new(DeprecationPolicy::default()),
+            byte_budget: DEFAULT_HANDSHAKE_BYTE_BUDGET,
+            challenge_difficulty: Some(difficulty),
+            role: ConnectionRole::Forwarding,
+        };
+        let result = TcpServer::version_handshake(&mut stream, &handshake).await;
+        drop(stream);
+        let _ = peer.await;
+        result
+    }
+
+    #[tokio::test]
+    async fn test_handshake_challenge_gates_advertisement() {
+        let negotiated = handshake_with_challenge(12, solve_challenge).await.unwrap();
+        assert_eq!(negotiated.version, ProtocolVersion::default());
+
+        // A peer that doesn't do the work is dropped before its advertisement is read
+        fn wrong_answer(nonce: &[u8; CHALLENGE_NONCE_LEN], difficulty: u8) -> u64 {
+            (0..)
+                .find(|&guess| !verify_challenge(nonce, difficulty, guess))
+                .unwrap()
+        }
+        let err = handshake_with_challenge(12, wrong_answer).await.unwrap_err();
+        assert!(err.to_string().contains("failed 12-bit handshake challenge"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_peer_warned_then_refused() {
+        use crate::core::versions::DeprecationTimeline;
+        use chrono::{Duration, Utc};
+
+        let v1_0 = ProtocolVersion::new(1, 0, 0);
+        let policy_with_eol = |eol: chrono::DateTime<Utc>| DeprecationPolicy {
+            timelines: vec![DeprecationTimeline {
+                eol: eol.to_rfc3339(),
+                ..DeprecationTimeline::v1_0_0()
+            }],
+            grace_period: Duration::days(7),
+            refuse_expired: true,
+        };
+
+        // Before the cutoff: allowed with a warning
+        let pre_cutoff = policy_with_eol(Utc::now() + Duration::days(1));
+        let negotiated = handshake_with_policy(ProtocolVersion::V1_2_0, v1_0, 256, pre_cutoff)
+            .await
+            .unwrap();
+        assert_eq!(negotiated, v1_0);
+
+        // Past EOL plus grace: refused
+        let post_cutoff = policy_with_eol(Utc::now() - Duration::days(8));
+        let err = handshake_with_policy(ProtocolVersion::V1_2_0, v1_0, 256, post_cutoff)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("past its"), "{}", err);
+
+        // Current versions are unaffected by the policy
+        let post_cutoff = policy_with_eol(Utc::now() - Duration::days(8));
+        let negotiated = handshake_with_policy(
+            ProtocolVersion::V1_2_0,
+            ProtocolVersion::V1_2_0,
+            256,
+            post_cutoff,
+        )
+        .await
+        .unwrap();
+        assert_eq!(negotiated, ProtocolVersion::V1_2_0);
+    }
 }
@@ -0,0 +1,191 @@
+//! Deterministic fuzz corpus runner for packet and Sphinx decoding
+//!
+//! Feeds embedded seeds, every file under `tests/fuzz_corpus/`, and a fixed
+//! set of mutations of each through `Packet::parse`, `PacketHeader::decode`,
+//! `SphinxPacket::from_bytes` and `SphinxProcessor`. Inputs must never panic
+//! and must decode quickly without amplifying their size.
+//!
+//! Drop crashing inputs found by external fuzzers into `tests/fuzz_corpus/`
+//! to keep them as regressions.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use betanet::crypto::sphinx::{
+    SphinxPacket, SphinxProcessor, SPHINX_HEADER_SIZE, SPHINX_PAYLOAD_SIZE,
+};
+use betanet::utils::packet::{Packet, PacketHeader};
+use betanet::{MAX_PACKET_SIZE, MIXNODE_VERSION};
+use bytes::Bytes;
+
+/// Per-input time budget
+const INPUT_BUDGET: Duration = Duration::from_millis(250);
+
+/// Mutated variants generated per seed
+const MUTATIONS_PER_SEED: usize = 32;
+
+const SPHINX_PACKET_SIZE: usize = SPHINX_HEADER_SIZE + SPHINX_PAYLOAD_SIZE;
+
+fn header(packet_type: u8, length: u16) -> Vec<u8> {
+    let mut buf = vec![MIXNODE_VERSION, packet_type, 0, 0];
+    buf.extend_from_slice(&length.to_be_bytes());
+    buf.extend_from_slice(&[0, 0]);
+    buf
+}
+
+fn framed(payload: &[u8]) -> Vec<u8> {
+    let mut buf = header(0x01, payload.len() as u16);
+    buf.extend_from_slice(payload);
+    buf
+}
+
+fn embedded_seeds() -> Vec<(String, Vec<u8>)> {
+    let mut rng = XorShift::new(0x5eed);
+    let sphinx_noise: Vec<u8> = (0..SPHINX_PACKET_SIZE).map(|_| rng.next() as u8).collect();
+
+    vec![
+        ("zero_length".into(), Vec::new()),
+        ("single_byte".into(), vec![MIXNODE_VERSION]),
+        ("truncated_header".into(), header(0x01, 0)[..7].to_vec()),
+        ("bad_version".into(), {
+            let mut h = header(0x01, 0);
+            h[0] = 0xff;
+            h
+        }),
+        ("bad_type".into(), header(0x7f, 0)),
+        ("length_exceeds_payload".into(), header(0x01, u16::MAX)),
+        ("oversized".into(), vec![0xaa; MAX_PACKET_SIZE + 1]),
+        ("max_size".into(), framed(&vec![0x55; MAX_PACKET_SIZE - 8])),
+        ("truncated_sphinx".into(), framed(&sphinx_noise[..SPHINX_HEADER_SIZE + 1])),
+        ("sphinx_noise".into(), framed(&sphinx_noise)),
+        ("sphinx_zeroed".into(), framed(&[0u8; SPHINX_PACKET_SIZE])),
+    ]
+}
+
+fn corpus_files() -> Vec<(String, Vec<u8>)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fuzz_corpus");
+    let mut files: Vec<_> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let data = std::fs::read(&path).expect("corpus file readable");
+            (path.display().to_string(), data)
+        })
+        .collect()
+}
+
+/// Small deterministic PRNG so mutations are identical on every run
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn mutate(input: &[u8], rng: &mut XorShift) -> Vec<u8> {
+    let mut out = input.to_vec();
+    match rng.next() % 4 {
+        // Flip a few bytes
+        0 if !out.is_empty() => {
+            for _ in 0..1 + rng.next() % 4 {
+                let i = (rng.next() as usize) % out.len();
+                out[i] ^= rng.next() as u8;
+            }
+        }
+        // Truncate
+        1 if !out.is_empty() => {
+            let len = (rng.next() as usize) % out.len();
+            out.truncate(len);
+        }
+        // Append garbage
+        2 => {
+            let extra = (rng.next() % 64) as usize;
+            out.extend((0..extra).map(|_| rng.next() as u8));
+        }
+        // Rewrite the length field
+        _ if out.len() >= 6 => {
+            out[4..6].copy_from_slice(&(rng.next() as u16).to_be_bytes());
+        }
+        _ => out.push(rng.next() as u8),
+    }
+    out
+}
+
+/// Decode one input through every parser, checking output bounds
+fn exercise(processor: &SphinxProcessor, runtime: &tokio::runtime::Runtime, input: &[u8]) {
+    let _ = PacketHeader::decode(Bytes::copy_from_slice(input));
+
+    let payload = match Packet::parse(input) {
+        Ok(packet) => {
+            assert!(packet.payload.len() <= input.len());
+            packet.payload.to_vec()
+        }
+        Err(_) => input.to_vec(),
+    };
+
+    if let Ok(sphinx) = SphinxPacket::from_bytes(&payload) {
+        assert_eq!(sphinx.to_bytes().len(), SPHINX_PACKET_SIZE);
+        if let Ok(Some(processed)) = runtime.block_on(processor.process_packet(sphinx)) {
+            assert_eq!(processed.to_bytes().len(), SPHINX_PACKET_SIZE);
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_corpus_decodes_without_panic() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let processor = SphinxProcessor::new();
+    let mut rng = XorShift::new(0xf022);
+
+    let mut inputs = embedded_seeds();
+    inputs.extend(corpus_files());
+
+    let mut failures = Vec::new();
+    let mut total = 0;
+    for (name, seed) in &inputs {
+        let mut variants = vec![seed.clone()];
+        variants.extend((0..MUTATIONS_PER_SEED).map(|_| mutate(seed, &mut rng)));
+
+        for (i, input) in variants.iter().enumerate() {
+            total += 1;
+            let start = Instant::now();
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| exercise(&processor, &runtime, input)));
+            let elapsed = start.elapsed();
+
+            if result.is_err() {
+                failures.push(format!("{} (variant {}): panicked", name, i));
+            } else if elapsed > INPUT_BUDGET {
+                failures.push(format!("{} (variant {}): took {:?}", name, i, elapsed));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} inputs failed:\n{}",
+        failures.len(),
+        total,
+        failures.join("\n")
+    );
+}
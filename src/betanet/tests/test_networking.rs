@@ -2,6 +2,8 @@
 //!
 //! Tests TCP send/receive, multi-hop circuits, and throughput benchmarks
 
+#![allow(clippy::field_reassign_with_default)]
+
 use bytes::Bytes;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -1,13 +1,60 @@
 //! Delay queue implementation
 
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, VecDeque};
 use std::time::{Duration, Instant};
 
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, Span};
+
+/// Drop reason recorded when the delay queue refuses a packet
+pub const DELAY_QUEUE_FULL: &str = "delay_queue_full";
+
+/// What to do with a packet arriving while the delay queue is at its cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DelayOverflowPolicy {
+    /// Refuse the arriving packet
+    #[default]
+    Drop,
+    /// Queue the arriving packet and release the soonest-due one early
+    ReleaseSoonest,
+}
+
+/// How queued packets are mixed before release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MixStrategy {
+    /// Each packet waits out its own delay
+    #[default]
+    Continuous,
+    /// Packets arriving within a window are held, shuffled and released
+    /// together at the window boundary; per-packet delays are ignored
+    Timed {
+        /// Window length; boundaries fall every `window` from queue creation
+        window: Duration,
+    },
+}
+
+/// Packet pushed out of a full delay queue by [`DelayQueue::add_packet`]
+#[derive(Debug)]
+pub enum DelayOverflow {
+    /// Released ahead of its delay; forward it now under its queued span
+    ReleasedEarly(Vec<u8>, Span),
+    /// Arriving packet refused; record it as dropped with this reason
+    Dropped(&'static str),
+}
+
 /// Delayed packet entry
 #[derive(Debug)]
 struct DelayedPacket {
     packet: Vec<u8>,
     release_time: Instant,
+    /// When the packet reached the node
+    arrived_at: Instant,
+    /// Span current when the packet was queued
+    span: Span,
 }
 
 impl PartialEq for DelayedPacket {
@@ -34,33 +81,137 @@ impl Ord for DelayedPacket {
 /// Delay queue for packet processing
 pub struct DelayQueue {
     queue: BinaryHeap<DelayedPacket>,
+    max_packets: Option<usize>,
+    overflow_policy: DelayOverflowPolicy,
+    strategy: MixStrategy,
+    /// Start of the first timed-mix window
+    origin: Instant,
+    /// Packets collected in the current timed-mix window
+    window_pool: Vec<DelayedPacket>,
+    /// End of the window `window_pool` belongs to
+    window_end: Option<Instant>,
+    /// Shuffled packets of a closed window, waiting to be popped
+    released: VecDeque<DelayedPacket>,
 }
 
 impl DelayQueue {
-    /// Create new delay queue
+    /// Create new unbounded delay queue
     pub fn new() -> Self {
         Self {
             queue: BinaryHeap::new(),
+            max_packets: None,
+            overflow_policy: DelayOverflowPolicy::default(),
+            strategy: MixStrategy::default(),
+            origin: Instant::now(),
+            window_pool: Vec::new(),
+            window_end: None,
+            released: VecDeque::new(),
         }
     }
 
+    /// Mix queued packets with `strategy`
+    pub fn with_mix_strategy(mut self, strategy: MixStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Strategy used to mix queued packets
+    pub fn mix_strategy(&self) -> MixStrategy {
+        self.strategy
+    }
+
+    /// Hold at most `max_packets` delayed packets, applying `policy` beyond that
+    pub fn with_capacity_limit(mut self, max_packets: usize, policy: DelayOverflowPolicy) -> Self {
+        self.max_packets = Some(max_packets.max(1));
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Maximum number of delayed packets held at once (if capped)
+    pub fn max_packets(&self) -> Option<usize> {
+        self.max_packets
+    }
+
+    /// Policy applied once the cap is reached
+    pub fn overflow_policy(&self) -> DelayOverflowPolicy {
+        self.overflow_policy
+    }
+
     /// Add packet with delay
-    pub async fn add_packet(&mut self, packet: Vec<u8>, delay: Duration) {
+    ///
+    /// The current tracing span is kept with the packet so forwarding can
+    /// continue the same trace. With a capacity limit, a full queue either
+    /// refuses the packet or hands back the soonest-due one, per the policy.
+    pub async fn add_packet(&mut self, packet: Vec<u8>, delay: Duration) -> Option<DelayOverflow> {
+        self.add_packet_arrived(packet, delay, Instant::now()).await
+    }
+
+    /// Add packet with delay, recording that it reached the node at `arrived_at`
+    ///
+    /// The arrival time comes back out of [`DelayQueue::pop_ready_timed`], so
+    /// time spent before queueing counts towards the packet's time in the node.
+    pub async fn add_packet_arrived(
+        &mut self,
+        packet: Vec<u8>,
+        delay: Duration,
+        arrived_at: Instant,
+    ) -> Option<DelayOverflow> {
+        if let MixStrategy::Timed { window } = self.strategy {
+            return self.add_to_window(packet, window, Instant::now(), arrived_at);
+        }
+
+        let full = self.max_packets.is_some_and(|max| self.queue.len() >= max);
+        if full && self.overflow_policy == DelayOverflowPolicy::Drop {
+            debug!("Delay queue full ({} packets), dropping packet", self.queue.len());
+            return Some(DelayOverflow::Dropped(DELAY_QUEUE_FULL));
+        }
+
         let release_time = Instant::now() + delay;
         let delayed_packet = DelayedPacket {
             packet,
             release_time,
+            arrived_at,
+            span: Span::current(),
         };
         self.queue.push(delayed_packet);
+
+        if full {
+            // The arriving packet may itself be the soonest
+            let entry = self.queue.pop().unwrap();
+            debug!("Delay queue full, releasing soonest packet early");
+            return Some(DelayOverflow::ReleasedEarly(entry.packet, entry.span));
+        }
+        None
     }
 
     /// Pop ready packet
     pub async fn pop_ready(&mut self) -> Option<Vec<u8>> {
+        self.pop_ready_traced().await.map(|(packet, _)| packet)
+    }
+
+    /// Pop ready packet with the span it was queued under
+    pub async fn pop_ready_traced(&mut self) -> Option<(Vec<u8>, Span)> {
+        self.pop_ready_timed()
+            .await
+            .map(|(packet, span, _)| (packet, span))
+    }
+
+    /// Pop ready packet with its span and the time it reached the node
+    pub async fn pop_ready_timed(&mut self) -> Option<(Vec<u8>, Span, Instant)> {
         let now = Instant::now();
 
+        if let MixStrategy::Timed { .. } = self.strategy {
+            self.close_window_if_due(now);
+            return self
+                .released
+                .pop_front()
+                .map(|entry| (entry.packet, entry.span, entry.arrived_at));
+        }
+
         if let Some(top) = self.queue.peek() {
             if top.release_time <= now {
-                return Some(self.queue.pop().unwrap().packet);
+                let entry = self.queue.pop().unwrap();
+                return Some((entry.packet, entry.span, entry.arrived_at));
             }
         }
 
@@ -69,12 +220,69 @@ impl DelayQueue {
 
     /// Get queue size
     pub fn size(&self) -> usize {
-        self.queue.len()
+        self.queue.len() + self.window_pool.len() + self.released.len()
     }
 
     /// Check if queue is empty
     pub fn is_empty(&self) -> bool {
-        self.queue.is_empty()
+        self.size() == 0
+    }
+
+    /// Collect a packet into the timed-mix window open at `now`
+    fn add_to_window(
+        &mut self,
+        packet: Vec<u8>,
+        window: Duration,
+        now: Instant,
+        arrived_at: Instant,
+    ) -> Option<DelayOverflow> {
+        self.close_window_if_due(now);
+
+        let full = self.max_packets.is_some_and(|max| self.size() >= max);
+        if full && self.overflow_policy == DelayOverflowPolicy::Drop {
+            debug!("Delay queue full ({} packets), dropping packet", self.size());
+            return Some(DelayOverflow::Dropped(DELAY_QUEUE_FULL));
+        }
+
+        let window_end = *self.window_end.get_or_insert_with(|| {
+            let window = window.max(Duration::from_millis(1));
+            let elapsed = now.saturating_duration_since(self.origin).as_nanos();
+            let windows_passed = elapsed / window.as_nanos();
+            // Next boundary on the origin-aligned grid, or one window from now
+            // if that can't be represented
+            (windows_passed + 1)
+                .checked_mul(window.as_nanos())
+                .and_then(|nanos| u64::try_from(nanos).ok())
+                .and_then(|nanos| self.origin.checked_add(Duration::from_nanos(nanos)))
+                .unwrap_or_else(|| now + window)
+        });
+        self.window_pool.push(DelayedPacket {
+            packet,
+            release_time: window_end,
+            arrived_at,
+            span: Span::current(),
+        });
+
+        if full {
+            // Every pooled packet shares the boundary, so any of them is
+            // "soonest"; pick one at random so the release isn't linkable
+            let index = rand::thread_rng().gen_range(0..self.window_pool.len());
+            let entry = self.window_pool.swap_remove(index);
+            debug!("Delay queue full, releasing a pooled packet early");
+            return Some(DelayOverflow::ReleasedEarly(entry.packet, entry.span));
+        }
+        None
+    }
+
+    /// Shuffle the current window into the release queue once its boundary passes
+    fn close_window_if_due(&mut self, now: Instant) {
+        if self.window_end.is_some_and(|end| now >= end) {
+            let mut pool = std::mem::take(&mut self.window_pool);
+            pool.shuffle(&mut rand::thread_rng());
+            debug!("Timed mix window closed, releasing {} packets", pool.len());
+            self.released.extend(pool);
+            self.window_end = None;
+        }
     }
 }
 
@@ -108,4 +316,76 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(result.unwrap(), packet);
     }
+
+    #[tokio::test]
+    async fn test_timed_mix_releases_window_shuffled() {
+        let window = Duration::from_millis(100);
+        let mut queue = DelayQueue::new().with_mix_strategy(MixStrategy::Timed { window });
+
+        // Per-packet delays don't matter; the window boundary does
+        for i in 0..32u8 {
+            queue.add_packet(vec![i], Duration::ZERO).await;
+        }
+        assert!(queue.pop_ready().await.is_none());
+        assert_eq!(queue.size(), 32);
+
+        sleep(window + Duration::from_millis(10)).await;
+
+        // A late arrival opens the next window instead of joining this one
+        queue.add_packet(vec![100], Duration::ZERO).await;
+
+        let mut released = Vec::new();
+        while let Some(packet) = queue.pop_ready().await {
+            released.push(packet[0]);
+        }
+        assert_eq!(released.len(), 32);
+        assert_ne!(released, (0..32).collect::<Vec<u8>>());
+        released.sort_unstable();
+        assert_eq!(released, (0..32).collect::<Vec<u8>>());
+        assert_eq!(queue.size(), 1);
+
+        sleep(window).await;
+        assert_eq!(queue.pop_ready().await, Some(vec![100]));
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_capacity_limit_policies() {
+        let hour = Duration::from_secs(3600);
+
+        let mut dropping = DelayQueue::new().with_capacity_limit(4, DelayOverflowPolicy::Drop);
+        let mut dropped = 0;
+        for i in 0..100u8 {
+            if let Some(overflow) = dropping.add_packet(vec![i], hour).await {
+                assert!(matches!(overflow, DelayOverflow::Dropped(DELAY_QUEUE_FULL)));
+                dropped += 1;
+            }
+        }
+        assert_eq!(dropping.size(), 4);
+        assert_eq!(dropped, 96);
+
+        let mut releasing =
+            DelayQueue::new().with_capacity_limit(4, DelayOverflowPolicy::ReleaseSoonest);
+        for i in 0..4u8 {
+            let delay = hour + Duration::from_secs(i as u64);
+            assert!(releasing.add_packet(vec![i], delay).await.is_none());
+        }
+        // Flooding hands back the soonest-due packet each time
+        let mut released = Vec::new();
+        for i in 4..100u8 {
+            let delay = hour + Duration::from_secs(i as u64);
+            match releasing.add_packet(vec![i], delay).await {
+                Some(DelayOverflow::ReleasedEarly(packet, _)) => released.push(packet[0]),
+                other => panic!("expected early release, got {:?}", other),
+            }
+        }
+        assert_eq!(releasing.size(), 4);
+        assert_eq!(released, (0..96).collect::<Vec<u8>>());
+
+        // A packet due sooner than everything queued is released immediately
+        match releasing.add_packet(vec![200], Duration::ZERO).await {
+            Some(DelayOverflow::ReleasedEarly(packet, _)) => assert_eq!(packet, vec![200]),
+            other => panic!("expected early release, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,161 @@
+//! Network epoch clock
+//!
+//! Nodes agree on fixed-length epochs counted from a shared genesis time.
+//! Anything that must line up across nodes - proof timestamps, schedules,
+//! decay - reads the epoch from an [`EpochClock`] instead of raw wall-clock
+//! seconds, so a node whose clock drifts by less than an epoch still lands
+//! in the same window as its peers. The clock reads time through a
+//! [`TimeSource`], which tests replace with a [`ManualTimeSource`].
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default epoch length
+pub const DEFAULT_EPOCH_LENGTH: Duration = Duration::from_secs(3600);
+
+/// Source of Unix time in seconds
+pub trait TimeSource: Send + Sync + std::fmt::Debug {
+    /// Current Unix time in seconds
+    fn unix_secs(&self) -> u64;
+}
+
+/// Time source backed by the system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn unix_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// Time source that only moves when told to
+#[derive(Debug, Default)]
+pub struct ManualTimeSource {
+    secs: AtomicU64,
+}
+
+impl ManualTimeSource {
+    /// Create source reading `unix_secs`
+    pub fn new(unix_secs: u64) -> Self {
+        Self {
+            secs: AtomicU64::new(unix_secs),
+        }
+    }
+
+    /// Set the current time
+    pub fn set(&self, unix_secs: u64) {
+        self.secs.store(unix_secs, Ordering::Relaxed);
+    }
+
+    /// Move the current time forward
+    pub fn advance(&self, by: Duration) {
+        self.secs.fetch_add(by.as_secs(), Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn unix_secs(&self) -> u64 {
+        self.secs.load(Ordering::Relaxed)
+    }
+}
+
+/// Fixed-length epochs counted from a genesis time
+///
+/// Cloning is cheap and clones share the time source.
+#[derive(Debug, Clone)]
+pub struct EpochClock {
+    genesis_unix: u64,
+    epoch_secs: u64,
+    source: Arc<dyn TimeSource>,
+}
+
+impl EpochClock {
+    /// Create clock on the system time source
+    pub fn new(genesis_unix: u64, epoch_length: Duration) -> Self {
+        Self::with_source(genesis_unix, epoch_length, Arc::new(SystemTimeSource))
+    }
+
+    /// Create clock reading time from `source`
+    ///
+    /// Epoch lengths below one second are rounded up to one second.
+    pub fn with_source(
+        genesis_unix: u64,
+        epoch_length: Duration,
+        source: Arc<dyn TimeSource>,
+    ) -> Self {
+        Self {
+            genesis_unix,
+            epoch_secs: epoch_length.as_secs().max(1),
+            source,
+        }
+    }
+
+    /// Epoch length
+    pub fn epoch_length(&self) -> Duration {
+        Duration::from_secs(self.epoch_secs)
+    }
+
+    /// Current Unix time according to the time source
+    pub fn now_unix(&self) -> u64 {
+        self.source.unix_secs()
+    }
+
+    /// Current epoch
+    pub fn current_epoch(&self) -> u64 {
+        self.epoch_at(self.now_unix())
+    }
+
+    /// Epoch containing `unix_secs`; times before genesis fall in epoch 0
+    pub fn epoch_at(&self, unix_secs: u64) -> u64 {
+        unix_secs.saturating_sub(self.genesis_unix) / self.epoch_secs
+    }
+
+    /// Unix time at which `epoch` starts
+    pub fn epoch_start(&self, epoch: u64) -> u64 {
+        self.genesis_unix
+            .saturating_add(epoch.saturating_mul(self.epoch_secs))
+    }
+
+    /// Unix times belonging to `epoch`
+    pub fn epoch_window(&self, epoch: u64) -> Range<u64> {
+        self.epoch_start(epoch)..self.epoch_start(epoch.saturating_add(1))
+    }
+}
+
+impl Default for EpochClock {
+    fn default() -> Self {
+        Self::new(0, DEFAULT_EPOCH_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epochs_follow_the_time_source() {
+        let source = Arc::new(ManualTimeSource::new(1_000));
+        let clock = EpochClock::with_source(1_000, Duration::from_secs(60), source.clone());
+        assert_eq!(clock.current_epoch(), 0);
+
+        source.advance(Duration::from_secs(59));
+        assert_eq!(clock.current_epoch(), 0);
+        source.advance(Duration::from_secs(1));
+        assert_eq!(clock.current_epoch(), 1);
+
+        // Clones share the source
+        let shared = clock.clone();
+        source.set(1_000 + 60 * 5 + 30);
+        assert_eq!(shared.current_epoch(), 5);
+        assert_eq!(shared.epoch_window(5), 1_300..1_360);
+
+        // Before genesis clamps to the first epoch
+        assert_eq!(clock.epoch_at(10), 0);
+    }
+}
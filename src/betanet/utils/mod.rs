@@ -4,10 +4,18 @@
 
 pub mod rate;
 pub mod delay;
+pub mod entropy;
+pub mod epoch;
+pub mod mtu;
 pub mod packet;
 pub mod timing_defense;
 
-pub use rate::{RateLimitedTrafficShaper, RateLimitingConfig};
-pub use delay::{DelayScheduler, DelayConfig};
-pub use packet::{Packet, PacketHeader};
+pub use rate::{
+    DropRateController, DropRateControllerConfig, RateLimitStats, RateLimitedTrafficShaper,
+    RateLimitingConfig,
+};
+pub use delay::{DelayScheduler, DelayConfig, DelayOverflowPolicy, MixStrategy};
+pub use entropy::{EntropyAuditConfig, PayloadEntropyAuditor, PayloadKind};
+pub use epoch::{EpochClock, ManualTimeSource, SystemTimeSource, TimeSource};
+pub use packet::{Packet, PacketHeader, SequenceDetector, SequenceEvent};
 pub use timing_defense::{TimingDefenseManager, TimingDefenseConfig};
\ No newline at end of file
@@ -0,0 +1,438 @@
+//! Per-hop MTU cache and fragmentation
+//!
+//! Next hops may sit behind transports with a smaller effective MTU than
+//! our padded packets. The cache remembers the MTU each hop advertised and
+//! the forward path splits oversized packets into fragments that the hop
+//! reassembles with [`FragmentReassembler`].
+//!
+//! Each fragment travels as its own frame: a packet header with
+//! [`FLAG_FRAGMENT`] set, whose payload is the fragment header followed by
+//! one slice of the original encoded packet.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+
+use crate::utils::packet::{Packet, PacketHeader, PacketType, FLAG_FRAGMENT, HEADER_LEN};
+use crate::{MixnodeError, Result, MAX_PACKET_SIZE};
+
+/// Fragment header: fragment id (u32), index (u8), count (u8)
+pub const FRAGMENT_HEADER_SIZE: usize = 6;
+
+/// Smallest MTU a hop may advertise
+pub const MIN_HOP_MTU: usize = 64;
+
+/// Default lifetime of a probed MTU
+pub const DEFAULT_MTU_TTL: Duration = Duration::from_secs(600);
+
+/// Default cap on partially received packets per source
+pub const DEFAULT_MAX_PENDING_PER_SOURCE: usize = 16;
+
+/// Default cap on partially received packets across all sources
+pub const DEFAULT_MAX_PENDING_TOTAL: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct MtuEntry {
+    mtu: usize,
+    probed_at: Instant,
+}
+
+/// How a packet should be sent to a given hop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardPlan {
+    /// Packet fits the hop MTU as-is
+    Whole(Vec<u8>),
+    /// Packet was split into fragment frames, each within the hop MTU
+    Fragmented(Vec<Vec<u8>>),
+}
+
+impl ForwardPlan {
+    /// Number of frames that will be written to the hop
+    pub fn frame_count(&self) -> usize {
+        self.frames().len()
+    }
+
+    /// Frames to write to the hop, in order
+    pub fn frames(&self) -> &[Vec<u8>] {
+        match self {
+            ForwardPlan::Whole(packet) => std::slice::from_ref(packet),
+            ForwardPlan::Fragmented(fragments) => fragments,
+        }
+    }
+}
+
+/// Cache of per-hop MTUs
+#[derive(Debug)]
+pub struct MtuCache {
+    entries: HashMap<SocketAddr, MtuEntry>,
+    default_mtu: usize,
+    ttl: Duration,
+    next_fragment_id: u32,
+}
+
+impl MtuCache {
+    /// Create cache with default MTU and probe TTL
+    pub fn new(default_mtu: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            default_mtu: default_mtu.max(MIN_HOP_MTU),
+            ttl,
+            next_fragment_id: 0,
+        }
+    }
+
+    /// Record the MTU a hop advertised in response to a probe
+    pub fn record_probe(&mut self, hop: SocketAddr, mtu: usize) {
+        self.entries.insert(
+            hop,
+            MtuEntry {
+                mtu: mtu.max(MIN_HOP_MTU),
+                probed_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Effective MTU for a hop (default if unknown or expired)
+    pub fn mtu_for(&self, hop: &SocketAddr) -> usize {
+        match self.entries.get(hop) {
+            Some(entry) if entry.probed_at.elapsed() < self.ttl => entry.mtu,
+            _ => self.default_mtu,
+        }
+    }
+
+    /// Whether a hop has no fresh probe result
+    pub fn needs_probe(&self, hop: &SocketAddr) -> bool {
+        self.entries
+            .get(hop)
+            .map(|entry| entry.probed_at.elapsed() >= self.ttl)
+            .unwrap_or(true)
+    }
+
+    /// Forget a hop's MTU
+    pub fn invalidate(&mut self, hop: &SocketAddr) {
+        self.entries.remove(hop);
+    }
+
+    /// Number of cached hops
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Decide how to send a packet to a hop, fragmenting if needed
+    pub fn plan(&mut self, hop: &SocketAddr, packet: &[u8]) -> Result<ForwardPlan> {
+        let mtu = self.mtu_for(hop);
+        if packet.len() <= mtu {
+            return Ok(ForwardPlan::Whole(packet.to_vec()));
+        }
+
+        let chunk_size = mtu - HEADER_LEN - FRAGMENT_HEADER_SIZE;
+        let count = packet.len().div_ceil(chunk_size);
+        if count > u8::MAX as usize {
+            return Err(MixnodeError::Packet(format!(
+                "Packet of {} bytes needs {} fragments for MTU {}",
+                packet.len(),
+                count,
+                mtu
+            )));
+        }
+
+        let id = self.next_fragment_id;
+        self.next_fragment_id = self.next_fragment_id.wrapping_add(1);
+
+        let fragments = packet
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut fragment = Vec::with_capacity(FRAGMENT_HEADER_SIZE + chunk.len());
+                fragment.extend_from_slice(&id.to_be_bytes());
+                fragment.push(index as u8);
+                fragment.push(count as u8);
+                fragment.extend_from_slice(chunk);
+
+                let frame = Packet {
+                    header: PacketHeader {
+                        flags: FLAG_FRAGMENT,
+                        ..PacketHeader::new(PacketType::Data, fragment.len(), 0)
+                    },
+                    payload: Bytes::from(fragment),
+                };
+                frame.encode().map(|bytes| bytes.to_vec())
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(ForwardPlan::Fragmented(fragments))
+    }
+}
+
+impl Default for MtuCache {
+    fn default() -> Self {
+        Self::new(MAX_PACKET_SIZE, DEFAULT_MTU_TTL)
+    }
+}
+
+#[derive(Debug)]
+struct PartialPacket {
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+    bytes: usize,
+    first_seen: Instant,
+}
+
+/// Receiver-side fragment reassembly
+///
+/// Memory is bounded: incomplete packets expire after the timeout, each
+/// source keeps at most `max_per_source` of them (its oldest is evicted to
+/// make room) and new packets are refused once `max_total` are pending.
+/// No packet may reassemble past [`MAX_PACKET_SIZE`].
+#[derive(Debug)]
+pub struct FragmentReassembler {
+    pending: HashMap<(SocketAddr, u32), PartialPacket>,
+    timeout: Duration,
+    max_per_source: usize,
+    max_total: usize,
+}
+
+impl FragmentReassembler {
+    /// Create reassembler that discards incomplete packets after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            pending: HashMap::new(),
+            timeout,
+            max_per_source: DEFAULT_MAX_PENDING_PER_SOURCE,
+            max_total: DEFAULT_MAX_PENDING_TOTAL,
+        }
+    }
+
+    /// Cap incomplete packets per source and in total (each at least 1)
+    pub fn with_pending_limits(mut self, max_per_source: usize, max_total: usize) -> Self {
+        self.max_per_source = max_per_source.max(1);
+        self.max_total = max_total.max(1);
+        self
+    }
+
+    /// Add a fragment (the payload of a fragment frame), returning the full
+    /// packet once all fragments arrived
+    pub fn add_fragment(&mut self, source: SocketAddr, fragment: &[u8]) -> Result<Option<Vec<u8>>> {
+        if fragment.len() < FRAGMENT_HEADER_SIZE {
+            return Err(MixnodeError::Packet("Fragment too short".to_string()));
+        }
+
+        let id = u32::from_be_bytes([fragment[0], fragment[1], fragment[2], fragment[3]]);
+        let index = fragment[4] as usize;
+        let count = fragment[5] as usize;
+        if count == 0 || index >= count {
+            return Err(MixnodeError::Packet(format!(
+                "Invalid fragment {}/{}",
+                index, count
+            )));
+        }
+
+        // Every fragment but the last is a full chunk, so one of them bounds
+        // the packet from below
+        let chunk = &fragment[FRAGMENT_HEADER_SIZE..];
+        if index + 1 < count && (count - 1) * chunk.len() >= MAX_PACKET_SIZE {
+            return Err(MixnodeError::Packet(format!(
+                "{} fragments of {} bytes exceed the {}-byte packet limit",
+                count,
+                chunk.len(),
+                MAX_PACKET_SIZE
+            )));
+        }
+
+        self.expire();
+
+        let key = (source, id);
+        if !self.pending.contains_key(&key) {
+            self.make_room(source)?;
+        }
+        let partial = self.pending.entry(key).or_insert_with(|| PartialPacket {
+            fragments: vec![None; count],
+            received: 0,
+            bytes: 0,
+            first_seen: Instant::now(),
+        });
+
+        if partial.fragments.len() != count {
+            return Err(MixnodeError::Packet(
+                "Fragment count mismatch".to_string(),
+            ));
+        }
+
+        if partial.fragments[index].is_none() {
+            if partial.bytes + chunk.len() > MAX_PACKET_SIZE {
+                self.pending.remove(&key);
+                return Err(MixnodeError::Packet(format!(
+                    "Reassembled packet exceeds the {}-byte packet limit",
+                    MAX_PACKET_SIZE
+                )));
+            }
+            partial.fragments[index] = Some(chunk.to_vec());
+            partial.received += 1;
+            partial.bytes += chunk.len();
+        }
+
+        if partial.received < count {
+            return Ok(None);
+        }
+
+        let partial = self.pending.remove(&(source, id)).expect("entry exists");
+        Ok(Some(partial.fragments.into_iter().flatten().flatten().collect()))
+    }
+
+    /// Number of packets awaiting fragments
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn expire(&mut self) {
+        let timeout = self.timeout;
+        self.pending
+            .retain(|_, partial| partial.first_seen.elapsed() < timeout);
+    }
+
+    /// Free a slot for a new packet from `source`
+    fn make_room(&mut self, source: SocketAddr) -> Result<()> {
+        let from_source = self.pending.keys().filter(|(from, _)| *from == source).count();
+        if from_source >= self.max_per_source {
+            let oldest = self
+                .pending
+                .iter()
+                .filter(|((from, _), _)| *from == source)
+                .min_by_key(|(_, partial)| partial.first_seen)
+                .map(|(key, _)| *key);
+            if let Some(key) = oldest {
+                self.pending.remove(&key);
+            }
+        }
+
+        if self.pending.len() >= self.max_total {
+            return Err(MixnodeError::Packet(format!(
+                "{} packets already awaiting fragments",
+                self.pending.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for FragmentReassembler {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_packet_not_fragmented() {
+        let mut cache = MtuCache::default();
+        let hop: SocketAddr = "127.0.0.1:9100".parse().unwrap();
+
+        let plan = cache.plan(&hop, &[7u8; 512]).unwrap();
+        assert_eq!(plan, ForwardPlan::Whole(vec![7u8; 512]));
+    }
+
+    #[test]
+    fn test_small_mtu_hop_fragments_without_loss() {
+        let mut cache = MtuCache::default();
+        let hop: SocketAddr = "127.0.0.1:9101".parse().unwrap();
+        cache.record_probe(hop, 300);
+        assert!(!cache.needs_probe(&hop));
+
+        let packet: Vec<u8> = (0..1200).map(|i| (i % 251) as u8).collect();
+        let plan = cache.plan(&hop, &packet).unwrap();
+
+        let fragments = match plan {
+            ForwardPlan::Fragmented(fragments) => fragments,
+            other => panic!("expected fragmentation, got {:?}", other),
+        };
+        assert!(fragments.iter().all(|f| f.len() <= 300));
+
+        let source: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let mut reassembler = FragmentReassembler::default();
+        let mut result = None;
+        // Deliver out of order
+        for fragment in fragments.iter().rev() {
+            let frame = Packet::parse(fragment).unwrap();
+            assert!(frame.is_fragment());
+            result = reassembler.add_fragment(source, &frame.payload).unwrap();
+        }
+
+        assert_eq!(result, Some(packet));
+        assert_eq!(reassembler.pending_count(), 0);
+    }
+
+    /// Payload of fragment `index` of `count` for packet `id`
+    fn fragment(id: u32, index: u8, count: u8, chunk: &[u8]) -> Vec<u8> {
+        let mut fragment = id.to_be_bytes().to_vec();
+        fragment.extend_from_slice(&[index, count]);
+        fragment.extend_from_slice(chunk);
+        fragment
+    }
+
+    #[test]
+    fn test_reassembler_bounds_pending_packets() {
+        let a: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let b: SocketAddr = "127.0.0.2:9000".parse().unwrap();
+        let mut reassembler = FragmentReassembler::default().with_pending_limits(2, 3);
+
+        // A source opening fresh ids only displaces its own oldest partials
+        for id in 0..10 {
+            assert_eq!(reassembler.add_fragment(a, &fragment(id, 0, 2, &[1; 8])).unwrap(), None);
+        }
+        assert_eq!(reassembler.pending_count(), 2);
+        reassembler.add_fragment(b, &fragment(0, 0, 2, &[2; 8])).unwrap();
+        let done = reassembler.add_fragment(a, &fragment(9, 1, 2, &[3; 4])).unwrap();
+        assert_eq!(done.unwrap().len(), 12);
+
+        // Once the total cap is reached new packets are refused
+        reassembler.add_fragment(b, &fragment(1, 0, 2, &[2; 8])).unwrap();
+        let c: SocketAddr = "127.0.0.3:9000".parse().unwrap();
+        assert!(reassembler.add_fragment(c, &fragment(0, 0, 2, &[4; 8])).is_err());
+        assert_eq!(reassembler.pending_count(), 3);
+
+        // Stale partials age out and free their slots
+        let mut expiring = FragmentReassembler::new(Duration::from_millis(0));
+        expiring.add_fragment(a, &fragment(0, 0, 2, &[1; 8])).unwrap();
+        expiring.add_fragment(a, &fragment(1, 0, 2, &[1; 8])).unwrap();
+        assert_eq!(expiring.pending_count(), 1);
+    }
+
+    #[test]
+    fn test_reassembler_rejects_oversized_packets() {
+        let source: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let mut reassembler = FragmentReassembler::default();
+
+        // The declared set is too large from its first full chunk
+        let chunk = vec![0u8; MAX_PACKET_SIZE / 4];
+        assert!(reassembler.add_fragment(source, &fragment(1, 0, 5, &chunk)).is_err());
+        assert_eq!(reassembler.pending_count(), 0);
+
+        // A last fragment larger than its siblings overruns the total
+        let mut reassembler = FragmentReassembler::default();
+        let small = vec![0u8; 100];
+        reassembler.add_fragment(source, &fragment(2, 0, 2, &small)).unwrap();
+        let huge = vec![0u8; MAX_PACKET_SIZE];
+        assert!(reassembler.add_fragment(source, &fragment(2, 1, 2, &huge)).is_err());
+        assert_eq!(reassembler.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_expired_probe_falls_back_to_default() {
+        let mut cache = MtuCache::new(1500, Duration::from_millis(0));
+        let hop: SocketAddr = "127.0.0.1:9102".parse().unwrap();
+        cache.record_probe(hop, 200);
+
+        assert!(cache.needs_probe(&hop));
+        assert_eq!(cache.mtu_for(&hop), 1500);
+    }
+}
@@ -1,10 +1,52 @@
 //! Packet format and processing
 
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
 
 use crate::{MixnodeError, Result, MAX_PACKET_SIZE, MIXNODE_VERSION};
 
+/// Hops a packet may take when the sender sets no TTL
+pub const DEFAULT_HOP_TTL: u8 = 16;
+
+/// Header flag marking that the TTL byte is present
+///
+/// Headers from senders that predate the TTL leave the byte zeroed and this
+/// flag clear; they get [`DEFAULT_HOP_TTL`] instead of being dropped.
+pub const FLAG_HOP_TTL: u8 = 0x80;
+
+/// Header flag marking that a 4-byte sequence number follows the header
+pub const FLAG_SEQUENCE: u8 = 0x40;
+
+/// Header flag marking the payload as one fragment of a larger packet
+///
+/// See [`crate::utils::mtu`] for the fragment layout.
+pub const FLAG_FRAGMENT: u8 = 0x20;
+
+/// Encoded header size without extensions
+pub const HEADER_LEN: usize = 8;
+
+/// Size of the sequence number extension
+pub const SEQUENCE_LEN: usize = 4;
+
+fn default_hop_ttl() -> u8 {
+    DEFAULT_HOP_TTL
+}
+
+/// Anonymized id for correlating one packet's log events
+///
+/// A keyed hash of the raw bytes under a random per-process key: stable
+/// within this node, unlinkable across nodes, and reveals nothing about
+/// the payload.
+pub fn packet_trace_id(raw: &[u8]) -> u64 {
+    static TRACE_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+    let key = TRACE_KEY.get_or_init(rand::random);
+    let hash = blake3::keyed_hash(key, raw);
+    u64::from_be_bytes(hash.as_bytes()[..8].try_into().unwrap())
+}
+
 /// Packet type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
@@ -46,6 +88,12 @@ pub struct PacketHeader {
     pub length: u16,
     /// Layer number (for Sphinx)
     pub layer: u8,
+    /// Remaining mixnode hops before the packet is dropped
+    #[serde(default = "default_hop_ttl")]
+    pub ttl: u8,
+    /// Per-flow sequence number, for flows that want ordering hints
+    #[serde(default)]
+    pub sequence: Option<u32>,
     /// Checksum
     pub checksum: u32,
 }
@@ -59,25 +107,54 @@ impl PacketHeader {
             flags: 0,
             length: payload_len as u16,
             layer,
+            ttl: DEFAULT_HOP_TTL,
+            sequence: None,
             checksum: 0, // Will be calculated later
         }
     }
 
+    /// Consume one hop of TTL before forwarding
+    ///
+    /// Returns `false` once the TTL is exhausted and the packet must be
+    /// dropped.
+    pub fn take_hop(&mut self) -> bool {
+        match self.ttl.checked_sub(1) {
+            Some(ttl) => {
+                self.ttl = ttl;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Encoded size, including the sequence extension if present
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + if self.sequence.is_some() { SEQUENCE_LEN } else { 0 }
+    }
+
     /// Encode header to bytes
     pub fn encode(&self) -> Bytes {
-        let mut buf = BytesMut::with_capacity(8);
+        let mut buf = BytesMut::with_capacity(self.encoded_len());
+        let mut flags = self.flags | FLAG_HOP_TTL;
+        if self.sequence.is_some() {
+            flags |= FLAG_SEQUENCE;
+        }
         buf.put_u8(self.version);
         buf.put_u8(self.packet_type as u8);
-        buf.put_u8(self.flags);
+        buf.put_u8(flags);
         buf.put_u8(self.layer);
         buf.put_u16(self.length);
-        buf.put_u16(0); // Reserved
+        buf.put_u8(self.ttl);
+        buf.put_u8(0); // Reserved
+        if let Some(sequence) = self.sequence {
+            buf.put_u32(sequence);
+        }
         buf.freeze()
     }
 
     /// Decode header from bytes
     pub fn decode(mut buf: Bytes) -> Result<Self> {
-        if buf.len() < 8 {
+        if buf.len() < HEADER_LEN {
             return Err(MixnodeError::Packet("Header too short".to_string()));
         }
 
@@ -93,14 +170,30 @@ impl PacketHeader {
         let flags = buf.get_u8();
         let layer = buf.get_u8();
         let length = buf.get_u16();
-        let _reserved = buf.get_u16();
+        let ttl = buf.get_u8();
+        let _reserved = buf.get_u8();
+
+        let sequence = if flags & FLAG_SEQUENCE != 0 {
+            if buf.len() < SEQUENCE_LEN {
+                return Err(MixnodeError::Packet("Sequence number truncated".to_string()));
+            }
+            Some(buf.get_u32())
+        } else {
+            None
+        };
 
         Ok(Self {
             version,
             packet_type,
-            flags,
+            flags: flags & !(FLAG_HOP_TTL | FLAG_SEQUENCE),
             length,
             layer,
+            ttl: if flags & FLAG_HOP_TTL != 0 {
+                ttl
+            } else {
+                DEFAULT_HOP_TTL
+            },
+            sequence,
             checksum: 0,
         })
     }
@@ -132,6 +225,23 @@ impl Packet {
         Self::new(PacketType::Control, payload, 0)
     }
 
+    /// Create a control packet carrying a flow sequence number
+    pub fn control_with_sequence(payload: Bytes, sequence: u32) -> Self {
+        Self::control(payload).with_sequence(sequence)
+    }
+
+    /// Attach a flow sequence number
+    pub fn with_sequence(mut self, sequence: u32) -> Self {
+        self.header.sequence = Some(sequence);
+        self
+    }
+
+    /// Limit how many mixnodes may forward this packet
+    pub fn with_ttl(mut self, ttl: u8) -> Self {
+        self.header.ttl = ttl;
+        self
+    }
+
     /// Create a cover traffic packet
     pub fn cover_traffic(size: usize, layer: u8) -> Self {
         let payload = Bytes::from(vec![0u8; size]);
@@ -140,7 +250,7 @@ impl Packet {
 
     /// Parse packet from raw bytes
     pub fn parse(data: &[u8]) -> Result<Self> {
-        if data.len() < 8 {
+        if data.len() < HEADER_LEN {
             return Err(MixnodeError::Packet("Packet too short".to_string()));
         }
 
@@ -152,29 +262,57 @@ impl Packet {
             )));
         }
 
-        let header_bytes = Bytes::copy_from_slice(&data[..8]);
-        let header = PacketHeader::decode(header_bytes)?;
+        let header_end = data.len().min(HEADER_LEN + SEQUENCE_LEN);
+        let header = PacketHeader::decode(Bytes::copy_from_slice(&data[..header_end]))?;
+        let offset = header.encoded_len();
 
-        if data.len() < 8 + header.length as usize {
+        if data.len() < offset + header.length as usize {
             return Err(MixnodeError::Packet("Payload too short".to_string()));
         }
 
-        let payload = Bytes::copy_from_slice(&data[8..8 + header.length as usize]);
+        let payload = Bytes::copy_from_slice(&data[offset..offset + header.length as usize]);
 
         Ok(Self { header, payload })
     }
 
+    /// Length of the first frame in `data`, once all of it has arrived
+    ///
+    /// Returns `Ok(None)` while the header or payload is still incomplete and
+    /// fails as soon as the header is invalid, so a stream reader can split
+    /// back-to-back frames and keep a partial tail for the next read.
+    pub fn frame_len(data: &[u8]) -> Result<Option<usize>> {
+        if data.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let sequenced = data[2] & FLAG_SEQUENCE != 0;
+        let header_len = HEADER_LEN + if sequenced { SEQUENCE_LEN } else { 0 };
+        if data.len() < header_len {
+            return Ok(None);
+        }
+
+        let header = PacketHeader::decode(Bytes::copy_from_slice(&data[..header_len]))?;
+        let len = header_len + header.length as usize;
+        if len > MAX_PACKET_SIZE {
+            return Err(MixnodeError::Packet(format!(
+                "Packet too large: {} > {}",
+                len, MAX_PACKET_SIZE
+            )));
+        }
+        Ok((data.len() >= len).then_some(len))
+    }
+
     /// Encode packet to bytes
     pub fn encode(&self) -> Result<Bytes> {
-        if self.payload.len() > MAX_PACKET_SIZE - 8 {
+        let header_len = self.header.encoded_len();
+        if self.payload.len() > MAX_PACKET_SIZE - header_len {
             return Err(MixnodeError::Packet(format!(
                 "Payload too large: {} > {}",
                 self.payload.len(),
-                MAX_PACKET_SIZE - 8
+                MAX_PACKET_SIZE - header_len
             )));
         }
 
-        let mut buf = BytesMut::with_capacity(8 + self.payload.len());
+        let mut buf = BytesMut::with_capacity(header_len + self.payload.len());
         buf.put(self.header.encode());
         buf.put(self.payload.as_ref());
 
@@ -183,7 +321,7 @@ impl Packet {
 
     /// Get packet size
     pub fn size(&self) -> usize {
-        8 + self.payload.len()
+        self.header.encoded_len() + self.payload.len()
     }
 
     /// Check if packet is cover traffic
@@ -191,6 +329,11 @@ impl Packet {
         self.header.packet_type == PacketType::Cover
     }
 
+    /// Check if packet carries one fragment of a larger packet
+    pub fn is_fragment(&self) -> bool {
+        self.header.flags & FLAG_FRAGMENT != 0
+    }
+
     /// Get layer number
     pub fn layer(&self) -> u8 {
         self.header.layer
@@ -212,6 +355,106 @@ impl Packet {
     }
 }
 
+/// Sequence numbers remembered behind the highest one seen, for telling
+/// late packets from duplicates
+pub const SEQUENCE_WINDOW: usize = 64;
+
+/// How an observed sequence number relates to the flow so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceEvent {
+    /// Next expected sequence number
+    InOrder,
+    /// Ahead of the expected number; `missing` packets were skipped
+    Gap {
+        /// Sequence numbers skipped over
+        missing: u32,
+    },
+    /// Behind the highest number seen but not seen before
+    Reordered,
+    /// Already seen within the window
+    Duplicate,
+}
+
+/// Counters kept by a [`SequenceDetector`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SequenceStats {
+    /// Packets that arrived in order
+    pub in_order: u64,
+    /// Jumps ahead of the expected number
+    pub gaps: u64,
+    /// Sequence numbers skipped by those jumps
+    pub missing: u64,
+    /// Late packets behind the highest number seen
+    pub reordered: u64,
+    /// Repeated sequence numbers
+    pub duplicates: u64,
+}
+
+/// Receiver-side reorder and gap detection for one sequenced flow
+///
+/// Keep one detector per flow. Sequence numbers are not expected to wrap;
+/// a control channel reconnects long before `u32::MAX` packets.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceDetector {
+    highest: Option<u32>,
+    recent: VecDeque<u32>,
+    stats: SequenceStats,
+}
+
+impl SequenceDetector {
+    /// Create detector for a new flow
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify a received sequence number and update the counters
+    pub fn observe(&mut self, sequence: u32) -> SequenceEvent {
+        let event = match self.highest {
+            _ if self.recent.contains(&sequence) => SequenceEvent::Duplicate,
+            None => SequenceEvent::InOrder,
+            Some(highest) if sequence == highest.saturating_add(1) => SequenceEvent::InOrder,
+            Some(highest) if sequence > highest => SequenceEvent::Gap {
+                missing: sequence - highest - 1,
+            },
+            Some(_) => SequenceEvent::Reordered,
+        };
+
+        match event {
+            SequenceEvent::InOrder => self.stats.in_order += 1,
+            SequenceEvent::Gap { missing } => {
+                self.stats.gaps += 1;
+                self.stats.missing += missing as u64;
+            }
+            SequenceEvent::Reordered => self.stats.reordered += 1,
+            SequenceEvent::Duplicate => self.stats.duplicates += 1,
+        }
+
+        if event != SequenceEvent::Duplicate {
+            self.highest = Some(self.highest.map_or(sequence, |highest| highest.max(sequence)));
+            if self.recent.len() == SEQUENCE_WINDOW {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(sequence);
+        }
+        event
+    }
+
+    /// Observe a packet's sequence number, if it carries one
+    pub fn observe_packet(&mut self, packet: &Packet) -> Option<SequenceEvent> {
+        packet.header.sequence.map(|sequence| self.observe(sequence))
+    }
+
+    /// Highest sequence number seen
+    pub fn highest(&self) -> Option<u32> {
+        self.highest
+    }
+
+    /// Counters so far
+    pub fn stats(&self) -> &SequenceStats {
+        &self.stats
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +480,93 @@ mod tests {
         assert_eq!(original.payload, decoded.payload);
     }
 
+    #[test]
+    fn test_ttl_round_trip() {
+        let packet = Packet::data(Bytes::from("ttl"), 1).with_ttl(3);
+        let decoded = Packet::parse(&packet.encode().unwrap()).unwrap();
+        assert_eq!(decoded.header.ttl, 3);
+        assert_eq!(decoded.header.flags, 0);
+
+        // Legacy headers without the TTL flag get the default
+        let mut legacy = packet.encode().unwrap().to_vec();
+        legacy[2] = 0;
+        legacy[6] = 0;
+        assert_eq!(Packet::parse(&legacy).unwrap().header.ttl, DEFAULT_HOP_TTL);
+
+        let mut header = decoded.header;
+        assert!(header.take_hop() && header.take_hop() && header.take_hop());
+        assert!(!header.take_hop());
+        assert_eq!(header.ttl, 0);
+    }
+
+    #[test]
+    fn test_out_of_order_control_packets_flagged() {
+        let packet = Packet::control_with_sequence(Bytes::from("ctl"), 7);
+        assert_eq!(packet.size(), HEADER_LEN + SEQUENCE_LEN + 3);
+        let decoded = Packet::parse(&packet.encode().unwrap()).unwrap();
+        assert_eq!(decoded.header.sequence, Some(7));
+        assert_eq!(decoded.header.flags, 0);
+        assert_eq!(decoded.payload, Bytes::from("ctl"));
+        // Unsequenced packets keep the plain header
+        let plain = Packet::control(Bytes::from("ctl")).encode().unwrap();
+        assert_eq!(plain.len(), HEADER_LEN + 3);
+        assert_eq!(Packet::parse(&plain).unwrap().header.sequence, None);
+
+        // Delivered as 0, 1, 4, 2, 3, 3 after mixing
+        let mut detector = SequenceDetector::new();
+        let events: Vec<SequenceEvent> = [0, 1, 4, 2, 3, 3]
+            .into_iter()
+            .map(|sequence| {
+                let wire = Packet::control_with_sequence(Bytes::from("ctl"), sequence)
+                    .encode()
+                    .unwrap();
+                detector.observe_packet(&Packet::parse(&wire).unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                SequenceEvent::InOrder,
+                SequenceEvent::InOrder,
+                SequenceEvent::Gap { missing: 2 },
+                SequenceEvent::Reordered,
+                SequenceEvent::Reordered,
+                SequenceEvent::Duplicate,
+            ]
+        );
+        assert_eq!(
+            detector.stats(),
+            &SequenceStats {
+                in_order: 2,
+                gaps: 1,
+                missing: 2,
+                reordered: 2,
+                duplicates: 1,
+            }
+        );
+        assert_eq!(detector.highest(), Some(4));
+    }
+
+    #[test]
+    fn test_frame_len_splits_back_to_back_frames() {
+        let first = Packet::data(Bytes::from("first"), 1).encode().unwrap();
+        let second = Packet::control_with_sequence(Bytes::from("second"), 7)
+            .encode()
+            .unwrap();
+        let mut stream = [first.as_ref(), second.as_ref()].concat();
+
+        assert_eq!(Packet::frame_len(&stream).unwrap(), Some(first.len()));
+        stream.drain(..first.len());
+        // Partial headers and payloads wait for more bytes
+        assert_eq!(Packet::frame_len(&stream[..HEADER_LEN]).unwrap(), None);
+        assert_eq!(Packet::frame_len(&stream[..stream.len() - 1]).unwrap(), None);
+        assert_eq!(Packet::frame_len(&stream).unwrap(), Some(second.len()));
+
+        let mut garbage = stream.clone();
+        garbage[0] = 0xff;
+        assert!(Packet::frame_len(&garbage).is_err());
+    }
+
     #[test]
     fn test_cover_traffic() {
         let packet = Packet::cover_traffic(100, 3);
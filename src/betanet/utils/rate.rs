@@ -3,7 +3,7 @@
 //! Implements token bucket algorithm for rate limiting and traffic shaping
 //! to ensure constant-rate output and prevent traffic analysis.
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
 use std::time::{Duration, Instant};
 
@@ -12,7 +12,7 @@ use tokio::sync::{Mutex, Semaphore};
 use tokio::time::sleep;
 use tracing::{debug, warn};
 
-use crate::{MixnodeError, Result};
+use crate::{MixnodeError, PerformanceTargets, Result};
 
 /// High-performance token bucket rate limiter
 pub struct TokenBucket {
@@ -21,7 +21,7 @@ pub struct TokenBucket {
     /// Current number of tokens (fixed-point: actual_tokens = tokens / PRECISION)
     tokens: AtomicU64,
     /// Rate of token refill (tokens per second, fixed-point)
-    refill_rate_fp: u64,
+    refill_rate_fp: AtomicU64,
     /// Last refill timestamp
     last_refill: StdMutex<Instant>,
     /// Statistics
@@ -36,7 +36,7 @@ impl TokenBucket {
         Self {
             capacity,
             tokens: AtomicU64::new(capacity * TOKEN_PRECISION),
-            refill_rate_fp: (refill_rate * TOKEN_PRECISION as f64) as u64,
+            refill_rate_fp: AtomicU64::new((refill_rate * TOKEN_PRECISION as f64) as u64),
             last_refill: StdMutex::new(Instant::now()),
             stats: Arc::new(RateLimiterStats::new()),
         }
@@ -123,7 +123,8 @@ impl TokenBucket {
         };
 
         let elapsed_us = elapsed.as_micros() as u64;
-        let tokens_to_add_fp = (elapsed_us * self.refill_rate_fp) / 1_000_000;
+        let refill_rate_fp = self.refill_rate_fp.load(Ordering::Relaxed);
+        let tokens_to_add_fp = (elapsed_us * refill_rate_fp) / 1_000_000;
 
         if tokens_to_add_fp > 0 {
             let capacity_fp = self.capacity * TOKEN_PRECISION;
@@ -167,10 +168,23 @@ impl TokenBucket {
         &self.stats
     }
 
+    /// Current refill rate (tokens per second)
+    pub fn refill_rate(&self) -> f64 {
+        self.refill_rate_fp.load(Ordering::Relaxed) as f64 / TOKEN_PRECISION as f64
+    }
+
+    /// Change the refill rate without touching capacity or current tokens
+    pub fn set_refill_rate(&self, refill_rate: f64) {
+        self.refill_rate_fp.store(
+            (refill_rate.max(0.0) * TOKEN_PRECISION as f64) as u64,
+            Ordering::Relaxed,
+        );
+    }
+
     /// Update rate parameters
     pub fn update_rate(&mut self, capacity: u64, refill_rate: f64) {
         self.capacity = capacity;
-        self.refill_rate_fp = (refill_rate * TOKEN_PRECISION as f64) as u64;
+        self.set_refill_rate(refill_rate);
 
         // Adjust current tokens if new capacity is smaller
         let capacity_fp = capacity * TOKEN_PRECISION;
@@ -402,6 +416,49 @@ impl Default for TrafficShaperStats {
     }
 }
 
+/// Limiter decision counters for [`RateLimitedTrafficShaper`]
+#[derive(Debug)]
+pub struct RateLimitStats {
+    /// Packets accepted into the output shaper
+    pub packets_admitted: AtomicU64,
+    /// Admitted packets queued behind others awaiting shaped output
+    pub packets_delayed: AtomicU64,
+    /// Packets rejected by the input limiter or a full shaper queue
+    pub packets_dropped: AtomicU64,
+    /// Set while drop-rate feedback holds ingress below the configured rate
+    pub throttle_active: AtomicBool,
+}
+
+impl RateLimitStats {
+    /// Create new statistics
+    pub fn new() -> Self {
+        Self {
+            packets_admitted: AtomicU64::new(0),
+            packets_delayed: AtomicU64::new(0),
+            packets_dropped: AtomicU64::new(0),
+            throttle_active: AtomicBool::new(false),
+        }
+    }
+
+    /// Get drop rate (0.0 to 1.0) over all limiter decisions
+    pub fn drop_rate(&self) -> f64 {
+        let admitted = self.packets_admitted.load(Ordering::Relaxed) as f64;
+        let dropped = self.packets_dropped.load(Ordering::Relaxed) as f64;
+        let total = admitted + dropped;
+        if total > 0.0 {
+            dropped / total
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for RateLimitStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Combined rate limiting and traffic shaping configuration
 #[derive(Debug, Clone)]
 pub struct RateLimitingConfig {
@@ -429,6 +486,101 @@ impl Default for RateLimitingConfig {
     }
 }
 
+/// Drop-rate feedback controller configuration
+#[derive(Debug, Clone)]
+pub struct DropRateControllerConfig {
+    /// Drop rate above which ingress is throttled (percentage)
+    pub target_drop_rate_pct: f64,
+    /// Multiplier applied to the throttle on each over-target observation
+    pub decrease_factor: f64,
+    /// Throttle recovered per under-target observation (fraction of full rate)
+    pub recovery_step: f64,
+    /// Lowest fraction of the configured rate ingress is throttled to
+    pub min_rate_fraction: f64,
+}
+
+impl DropRateControllerConfig {
+    /// Use the drop-rate target from the pipeline performance targets
+    pub fn from_targets(targets: &PerformanceTargets) -> Self {
+        Self {
+            target_drop_rate_pct: targets.max_drop_rate_pct,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for DropRateControllerConfig {
+    fn default() -> Self {
+        Self {
+            target_drop_rate_pct: PerformanceTargets::default().max_drop_rate_pct,
+            decrease_factor: 0.5,
+            recovery_step: 0.1,
+            min_rate_fraction: 0.1,
+        }
+    }
+}
+
+/// AIMD controller turning measured drop rate into an ingress throttle
+///
+/// Each observation looks at drops since the previous one. Over target the
+/// throttle is cut multiplicatively; at or under target it recovers
+/// additively, so load is shed quickly and restored gradually.
+#[derive(Debug, Clone)]
+pub struct DropRateController {
+    config: DropRateControllerConfig,
+    /// Fraction of the configured ingress rate currently allowed (0.0-1.0)
+    throttle: f64,
+    last_processed: u64,
+    last_dropped: u64,
+}
+
+impl DropRateController {
+    /// Create unthrottled controller
+    pub fn new(config: DropRateControllerConfig) -> Self {
+        Self {
+            config,
+            throttle: 1.0,
+            last_processed: 0,
+            last_dropped: 0,
+        }
+    }
+
+    /// Feed cumulative processed/dropped counters, returning the new throttle
+    pub fn observe(&mut self, processed: u64, dropped: u64) -> f64 {
+        let processed_delta = processed.saturating_sub(self.last_processed);
+        let dropped_delta = dropped.saturating_sub(self.last_dropped);
+        self.last_processed = processed;
+        self.last_dropped = dropped;
+
+        let total = processed_delta + dropped_delta;
+        let drop_rate_pct = if total == 0 {
+            0.0
+        } else {
+            dropped_delta as f64 / total as f64 * 100.0
+        };
+
+        if drop_rate_pct > self.config.target_drop_rate_pct {
+            self.throttle = (self.throttle * self.config.decrease_factor)
+                .max(self.config.min_rate_fraction);
+        } else {
+            self.throttle = (self.throttle + self.config.recovery_step).min(1.0);
+        }
+
+        self.throttle
+    }
+
+    /// Current throttle (fraction of configured rate)
+    pub fn throttle(&self) -> f64 {
+        self.throttle
+    }
+}
+
+impl Default for DropRateController {
+    fn default() -> Self {
+        Self::new(DropRateControllerConfig::default())
+    }
+}
+
 /// Adaptive traffic estimation for zero-traffic scenarios
 #[derive(Debug, Clone)]
 pub struct TrafficEstimator {
@@ -547,6 +699,10 @@ pub struct RateLimitedTrafficShaper {
     output_shaper: TrafficShaper,
     /// Traffic estimator for adaptive epsilon
     traffic_estimator: Arc<Mutex<TrafficEstimator>>,
+    /// Drop-rate feedback for the input limiter
+    drop_controller: StdMutex<DropRateController>,
+    /// Limiter decision counters
+    stats: Arc<RateLimitStats>,
 }
 
 impl RateLimitedTrafficShaper {
@@ -561,9 +717,38 @@ impl RateLimitedTrafficShaper {
             input_limiter,
             output_shaper,
             traffic_estimator,
+            drop_controller: StdMutex::new(DropRateController::default()),
+            stats: Arc::new(RateLimitStats::new()),
         }
     }
 
+    /// Replace the drop-rate feedback controller
+    pub fn with_drop_rate_controller(self, controller: DropRateController) -> Self {
+        *self.drop_controller.lock().unwrap() = controller;
+        self
+    }
+
+    /// Feed downstream processed/dropped counters and retune ingress
+    ///
+    /// Returns the resulting effective ingress rate.
+    pub fn observe_drop_rate(&self, processed: u64, dropped: u64) -> f64 {
+        let throttle = self.drop_controller.lock().unwrap().observe(processed, dropped);
+        let rate = self.config.sustained_rate * throttle;
+        self.stats
+            .throttle_active
+            .store(throttle < 1.0, Ordering::Relaxed);
+        if throttle < 1.0 {
+            debug!("Ingress throttled to {:.0}% ({:.1}/s)", throttle * 100.0, rate);
+        }
+        self.input_limiter.set_refill_rate(rate);
+        rate
+    }
+
+    /// Effective ingress rate after drop-rate throttling
+    pub fn ingress_rate(&self) -> f64 {
+        self.input_limiter.refill_rate()
+    }
+
     /// Process packet through rate limiting and shaping
     pub async fn process_packet(&self, packet: Vec<u8>) -> Result<()> {
         // Record packet for traffic estimation
@@ -574,18 +759,37 @@ impl RateLimitedTrafficShaper {
 
         if !self.config.enabled {
             // Pass through without limiting
-            return self.output_shaper.submit_packet(packet).await;
+            return self.submit_shaped(packet).await;
         }
 
         // Rate limit input
         let packet_size = packet.len() as u64;
         if !self.input_limiter.try_consume(packet_size).await {
             debug!("Packet rate limited, size: {} bytes", packet_size);
+            self.stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
             return Err(MixnodeError::Network("Rate limited".to_string()));
         }
 
         // Submit to traffic shaper
-        self.output_shaper.submit_packet(packet).await
+        self.submit_shaped(packet).await
+    }
+
+    /// Hand a packet to the output shaper, counting the outcome
+    async fn submit_shaped(&self, packet: Vec<u8>) -> Result<()> {
+        let queued_behind = self.output_shaper.queue_length().await > 0;
+        match self.output_shaper.submit_packet(packet).await {
+            Ok(()) => {
+                self.stats.packets_admitted.fetch_add(1, Ordering::Relaxed);
+                if queued_behind {
+                    self.stats.packets_delayed.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.stats.packets_dropped.fetch_add(1, Ordering::Relaxed);
+                Err(e)
+            }
+        }
     }
 
     /// Get next shaped output packet
@@ -603,10 +807,19 @@ impl RateLimitedTrafficShaper {
         self.output_shaper.stats()
     }
 
+    /// Get limiter decision statistics
+    pub fn rate_limit_stats(&self) -> &RateLimitStats {
+        &self.stats
+    }
+
     /// Update configuration
     pub fn update_config(&mut self, config: RateLimitingConfig) {
+        let throttle = self.drop_controller.lock().unwrap().throttle();
+        self.stats
+            .throttle_active
+            .store(throttle < 1.0, Ordering::Relaxed);
         self.input_limiter
-            .update_rate(config.burst_capacity, config.sustained_rate);
+            .update_rate(config.burst_capacity, config.sustained_rate * throttle);
         self.output_shaper.update_rate(config.output_rate);
         self.config = config;
     }
@@ -744,6 +957,35 @@ mod tests {
         assert!(shaper.process_packet(large_packet).await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_stats_count_decisions() {
+        let config = RateLimitingConfig {
+            enabled: true,
+            burst_capacity: 100,
+            sustained_rate: 50.0,
+            shaping_buffer_size: 10,
+            output_rate: 1.0,
+        };
+        let shaper = RateLimitedTrafficShaper::new(config);
+
+        for i in 0..3 {
+            let packet = format!("packet_{}", i).into_bytes();
+            shaper.process_packet(packet).await.unwrap();
+        }
+        assert!(shaper.process_packet(vec![0u8; 200]).await.is_err());
+
+        let stats = shaper.rate_limit_stats();
+        assert_eq!(stats.packets_admitted.load(Ordering::Relaxed), 3);
+        // Only the first packet found the shaper queue empty
+        assert_eq!(stats.packets_delayed.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.packets_dropped.load(Ordering::Relaxed), 1);
+        assert!((stats.drop_rate() - 0.25).abs() < 1e-9);
+        assert!(!stats.throttle_active.load(Ordering::Relaxed));
+
+        shaper.observe_drop_rate(0, 100);
+        assert!(stats.throttle_active.load(Ordering::Relaxed));
+    }
+
     #[tokio::test]
     async fn test_zero_traffic_epsilon_estimation() {
         let config = RateLimitingConfig::default();
@@ -787,6 +1029,44 @@ mod tests {
         assert!((short_silence_epsilon - 0.1).abs() < 0.001); // Should be 0.1 for short silence
     }
 
+    #[test]
+    fn test_drop_rate_throttles_and_recovers_ingress() {
+        let config = RateLimitingConfig {
+            sustained_rate: 1000.0,
+            ..RateLimitingConfig::default()
+        };
+        let shaper = RateLimitedTrafficShaper::new(config).with_drop_rate_controller(
+            DropRateController::new(DropRateControllerConfig::from_targets(
+                &PerformanceTargets::default(),
+            )),
+        );
+        assert_eq!(shaper.ingress_rate(), 1000.0);
+
+        // 5% drops, well above the 0.1% target
+        let (mut processed, mut dropped) = (0u64, 0u64);
+        for _ in 0..3 {
+            processed += 950;
+            dropped += 50;
+            shaper.observe_drop_rate(processed, dropped);
+        }
+        assert!((shaper.ingress_rate() - 125.0).abs() < 1e-6);
+
+        // Floor holds under sustained overload
+        for _ in 0..10 {
+            processed += 950;
+            dropped += 50;
+            shaper.observe_drop_rate(processed, dropped);
+        }
+        assert!((shaper.ingress_rate() - 100.0).abs() < 1e-6);
+
+        // Clean intervals restore the configured rate
+        for _ in 0..10 {
+            processed += 1000;
+            shaper.observe_drop_rate(processed, dropped);
+        }
+        assert!((shaper.ingress_rate() - 1000.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_rate_limiting_config() {
         let config = RateLimitingConfig::default();
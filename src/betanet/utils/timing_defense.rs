@@ -24,6 +24,9 @@ pub struct TimingDefenseConfig {
     pub burst_threshold: f64,
     /// Maximum acceptable correlation coefficient
     pub max_correlation: f64,
+    /// Randomized delays never come out shorter than this, so jitter
+    /// cannot turn a delay into a near-instant forward
+    pub min_delay_floor: Duration,
 }
 
 impl Default for TimingDefenseConfig {
@@ -34,6 +37,7 @@ impl Default for TimingDefenseConfig {
             correlation_window_size: 100,
             burst_threshold: 100.0, // 100 packets/sec
             max_correlation: 0.3, // Maximum 0.3 correlation
+            min_delay_floor: Duration::from_millis(5),
         }
     }
 }
@@ -73,7 +77,8 @@ impl TimingDefenseManager {
     /// Apply timing randomization to a delay
     ///
     /// Adds random jitter to prevent correlation attacks.
-    /// Randomization is applied as: delay * (1 ± random * randomization_pct)
+    /// Randomization is applied as: delay * (1 ± random * randomization_pct),
+    /// then raised to `min_delay_floor` if it came out shorter.
     pub async fn randomize_delay(&self, delay: Duration) -> Duration {
         if !self.config.enabled {
             return delay;
@@ -86,8 +91,8 @@ impl TimingDefenseManager {
         let randomization = (rng.gen::<f64>() - 0.5) * 2.0 * self.config.randomization_pct;
         let randomized_ms = delay_ms * (1.0 + randomization);
 
-        // Ensure positive delay
-        Duration::from_millis(randomized_ms.max(0.0) as u64)
+        // Never fall below the floor
+        Duration::from_millis(randomized_ms.max(0.0) as u64).max(self.config.min_delay_floor)
     }
 
     /// Record packet timing for correlation analysis
@@ -394,6 +399,29 @@ mod tests {
         assert!(variance > 0.0); // Should have some variance
     }
 
+    #[tokio::test]
+    async fn test_randomized_delay_respects_floor() {
+        let floor = Duration::from_millis(20);
+        let manager = TimingDefenseManager::new(TimingDefenseConfig {
+            // Wide enough to push some samples to zero without the floor
+            randomization_pct: 1.5,
+            min_delay_floor: floor,
+            ..Default::default()
+        });
+
+        let mut at_floor = 0;
+        for base in [0, 1, 10, 25, 100] {
+            for _ in 0..2000 {
+                let delay = manager.randomize_delay(Duration::from_millis(base)).await;
+                assert!(delay >= floor, "{:?} below floor for base {}ms", delay, base);
+                if delay == floor {
+                    at_floor += 1;
+                }
+            }
+        }
+        assert!(at_floor > 0);
+    }
+
     #[tokio::test]
     async fn test_correlation_calculation() {
         let config = TimingDefenseConfig::default();
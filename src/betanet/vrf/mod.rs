@@ -8,4 +8,4 @@ pub mod poisson_delay;
 
 pub use vrf_delay::{VrfDelay, VrfDelayProof};
 pub use vrf_neighbor::{VrfNeighborSelector, NeighborProof};
-pub use poisson_delay::{PoissonDelayGenerator, calculate_vrf_poisson_delay};
\ No newline at end of file
+pub use poisson_delay::{ClampPolicy, PoissonDelayGenerator, calculate_vrf_poisson_delay};
\ No newline at end of file
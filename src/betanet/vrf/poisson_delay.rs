@@ -30,6 +30,20 @@ impl Exp {
     }
 }
 
+/// Maximum re-draws under [`ClampPolicy::Resample`] before falling back to clamping
+pub const MAX_RESAMPLE_ATTEMPTS: usize = 16;
+
+/// How samples outside [min_delay, max_delay] are brought into bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClampPolicy {
+    /// Snap to the nearest bound (piles probability mass onto min/max)
+    #[default]
+    Clamp,
+    /// Re-draw until in bounds (up to `MAX_RESAMPLE_ATTEMPTS`), approximating
+    /// the truncated distribution instead of adding spikes at the bounds
+    Resample,
+}
+
 /// Enhanced Poisson delay generator with adaptive lambda and per-circuit customization.
 ///
 /// Generates delays following a Poisson distribution (exponential inter-arrival times)
@@ -54,10 +68,12 @@ impl Exp {
 ///
 /// # Bounds
 ///
-/// To ensure practical operation, delays are clamped to [min_delay, max_delay]:
+/// To ensure practical operation, delays are kept within [min_delay, max_delay]:
 /// - **min_delay**: Prevents zero or near-zero delays that could leak information
 /// - **max_delay**: Prevents excessive delays that impact usability
 ///
+/// See [`ClampPolicy`] for how out-of-bounds samples are handled.
+///
 /// # Examples
 ///
 /// ```
@@ -76,6 +92,10 @@ impl Exp {
 /// let delay = generator.next_delay();
 /// assert!(delay >= min && delay <= max);
 /// ```
+///
+/// The generator is `Send + Sync`, but `adapt_to_network_load` and
+/// `set_circuit_multiplier` take `&mut self`; wrap it in a lock if tasks
+/// need to adjust it concurrently.
 pub struct PoissonDelayGenerator {
     /// Base mean delay in milliseconds (λ = 1/mean_delay_ms)
     base_mean_delay_ms: f64,
@@ -93,6 +113,8 @@ pub struct PoissonDelayGenerator {
     load_adaptation_factor: f64,
     /// Per-circuit delay multiplier (default 1.0)
     circuit_multiplier: f64,
+    /// Out-of-bounds sample handling
+    clamp_policy: ClampPolicy,
 }
 
 impl PoissonDelayGenerator {
@@ -121,6 +143,7 @@ impl PoissonDelayGenerator {
             jitter_pct: 0.1, // Default 10% jitter
             load_adaptation_factor: 0.0,
             circuit_multiplier: 1.0,
+            clamp_policy: ClampPolicy::default(),
         })
     }
 
@@ -130,6 +153,12 @@ impl PoissonDelayGenerator {
         self
     }
 
+    /// Create with a specific out-of-bounds policy
+    pub fn with_clamp_policy(mut self, policy: ClampPolicy) -> Self {
+        self.clamp_policy = policy;
+        self
+    }
+
     /// Adapt delay based on network load (0.0 = no load, 1.0 = max load)
     ///
     /// Higher load results in longer delays to maintain privacy under stress.
@@ -166,7 +195,8 @@ impl PoissonDelayGenerator {
     /// Generate the next delay using enhanced Poisson distribution with jitter.
     ///
     /// Samples from the exponential distribution, applies circuit multiplier,
-    /// adds jitter for unpredictability, and clamps to configured bounds.
+    /// adds jitter for unpredictability, and brings the result into the
+    /// configured bounds according to the [`ClampPolicy`].
     /// This method is thread-safe but uses thread-local RNG for performance.
     ///
     /// # Returns
@@ -197,9 +227,28 @@ impl PoissonDelayGenerator {
     /// ```
     pub fn next_delay(&self) -> Duration {
         let mut rng = thread_rng();
+        let min_ms = self.min_delay.as_secs_f64() * 1000.0;
+        let max_ms = self.max_delay.as_secs_f64() * 1000.0;
+
+        let mut delay_ms = self.sample_ms(&mut rng);
+        if self.clamp_policy == ClampPolicy::Resample {
+            let mut attempts = 1;
+            while (delay_ms < min_ms || delay_ms > max_ms) && attempts < MAX_RESAMPLE_ATTEMPTS {
+                delay_ms = self.sample_ms(&mut rng);
+                attempts += 1;
+            }
+        }
+
+        // Clamp to min/max bounds (final fallback under Resample)
+        let clamped_ms = delay_ms.max(min_ms).min(max_ms);
 
+        Duration::from_millis(clamped_ms as u64)
+    }
+
+    /// Draw one unbounded delay sample in milliseconds
+    fn sample_ms(&self, rng: &mut impl Rng) -> f64 {
         // Sample from exponential distribution
-        let base_delay_ms = self.exp_dist.sample(&mut rng);
+        let base_delay_ms = self.exp_dist.sample(rng);
 
         // Apply circuit multiplier
         let circuit_adjusted_ms = base_delay_ms * self.circuit_multiplier;
@@ -207,19 +256,12 @@ impl PoissonDelayGenerator {
         // Add jitter for unpredictability
         let jitter_factor = if self.jitter_pct > 0.0 {
             let jitter_range = 1.0 + (rng.gen::<f64>() - 0.5) * 2.0 * self.jitter_pct;
-            jitter_range.max(0.5).min(1.5) // Prevent extreme jitter
+            jitter_range.clamp(0.5, 1.5) // Prevent extreme jitter
         } else {
             1.0
         };
 
-        let final_delay_ms = circuit_adjusted_ms * jitter_factor;
-
-        // Clamp to min/max bounds for practical operation
-        let clamped_ms = final_delay_ms
-            .max(self.min_delay.as_secs_f64() * 1000.0)
-            .min(self.max_delay.as_secs_f64() * 1000.0);
-
-        Duration::from_millis(clamped_ms as u64)
+        circuit_adjusted_ms * jitter_factor
     }
 
     /// Generate multiple delays
@@ -260,7 +302,7 @@ impl PoissonDelayGenerator {
         // Convert chi-squared to approximate p-value (simplified)
         // For proper implementation, use statistical library
         let p_value = (-chi_squared / 2.0).exp();
-        p_value.max(0.0).min(1.0)
+        p_value.clamp(0.0, 1.0)
     }
 
     /// Calculate delay distribution entropy (higher = more unpredictable)
@@ -459,6 +501,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resample_avoids_mass_at_bounds() {
+        let mean = Duration::from_millis(500);
+        let min = Duration::from_millis(100);
+        let max = Duration::from_millis(2000);
+        let samples = 20_000;
+
+        let fraction_at = |generator: &PoissonDelayGenerator, bound: Duration| {
+            let hits = generator
+                .next_delays(samples)
+                .into_iter()
+                .filter(|d| *d == bound)
+                .count();
+            hits as f64 / samples as f64
+        };
+
+        let clamp = PoissonDelayGenerator::new(mean, min, max)
+            .unwrap()
+            .with_jitter(0.0);
+        let resample = PoissonDelayGenerator::new(mean, min, max)
+            .unwrap()
+            .with_jitter(0.0)
+            .with_clamp_policy(ClampPolicy::Resample);
+
+        // P(X < 100ms) ≈ 18% and P(X > 2000ms) ≈ 1.8% for a 500ms mean
+        assert!(fraction_at(&clamp, min) > 0.10);
+        assert!(fraction_at(&clamp, max) > 0.005);
+
+        // Resampling leaves only the ordinary density at the bounds
+        assert!(fraction_at(&resample, min) < 0.01);
+        assert!(fraction_at(&resample, max) < 0.001);
+
+        for delay in resample.next_delays(1000) {
+            assert!(delay >= min && delay <= max);
+        }
+    }
+
     #[test]
     fn test_invalid_config() {
         let mean = Duration::from_millis(500);
@@ -103,8 +103,58 @@ impl VrfKeyPair {
     }
 }
 
+/// Verify `proof` over `message` against a bare public key
+///
+/// Returns the VRF output recomputed from the checked pre-output, so values
+/// derived from it can't be swapped out by the prover.
+#[cfg(feature = "vrf")]
+pub fn verify_with_public_key(
+    public_key: &[u8; 32],
+    message: &[u8],
+    proof: &VrfProof,
+) -> Option<schnorrkel::vrf::VRFInOut> {
+    use schnorrkel::{signing_context, PublicKey};
+    let public = PublicKey::from_bytes(public_key).ok()?;
+    let ctx = signing_context(b"betanet-mixnode-vrf");
+    public
+        .vrf_verify(ctx.bytes(message), &proof.io.to_preout(), &proof.proof)
+        .ok()
+        .map(|(io, _)| io)
+}
+
+/// Length of a serialized [`VrfProof`]: pre-output then proof
+#[cfg(feature = "vrf")]
+pub const VRF_PROOF_BYTES: usize =
+    schnorrkel::vrf::VRF_PREOUT_LENGTH + schnorrkel::vrf::VRF_PROOF_LENGTH;
+
+/// Verify a proof serialized with [`VrfProof::to_bytes`]
+///
+/// Returns `None` when the bytes are malformed or don't verify.
+#[cfg(feature = "vrf")]
+pub fn verify_bytes_with_public_key(
+    public_key: &[u8; 32],
+    message: &[u8],
+    bytes: &[u8],
+) -> Option<schnorrkel::vrf::VRFInOut> {
+    use schnorrkel::vrf::{VRFPreOut, VRFProof, VRF_PREOUT_LENGTH};
+    use schnorrkel::{signing_context, PublicKey};
+    if bytes.len() != VRF_PROOF_BYTES {
+        return None;
+    }
+    let (preout, proof) = bytes.split_at(VRF_PREOUT_LENGTH);
+    let preout = VRFPreOut::from_bytes(preout).ok()?;
+    let proof = VRFProof::from_bytes(proof).ok()?;
+    let public = PublicKey::from_bytes(public_key).ok()?;
+    let ctx = signing_context(b"betanet-mixnode-vrf");
+    public
+        .vrf_verify(ctx.bytes(message), &preout, &proof)
+        .ok()
+        .map(|(io, _)| io)
+}
+
 /// VRF proof
 #[cfg(feature = "vrf")]
+#[derive(Clone)]
 pub struct VrfProof {
     /// Input/output pair from VRF
     pub io: schnorrkel::vrf::VRFInOut,
@@ -114,6 +164,14 @@ pub struct VrfProof {
 
 #[cfg(feature = "vrf")]
 impl VrfProof {
+    /// Serialize the pre-output and proof, [`VRF_PROOF_BYTES`] long
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(VRF_PROOF_BYTES);
+        bytes.extend_from_slice(&self.io.to_preout().to_bytes());
+        bytes.extend_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+
     /// Extract delay from VRF output
     pub fn extract_delay(&self, min_delay: Duration, max_delay: Duration) -> Duration {
         let bytes: [u8; 8] = self.io.make_bytes(b"delay");
@@ -300,7 +300,7 @@ impl VrfNeighborSelector {
         // Fallback: Secure HMAC-SHA256(private_key, seed)
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
-        hasher.update(&self.vrf_private_key);
+        hasher.update(self.vrf_private_key);
         hasher.update(seed);
         Ok(hasher.finalize().into())
     }